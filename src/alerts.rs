@@ -0,0 +1,247 @@
+use crate::app::Throughput;
+use crate::command::glob_match;
+use crate::diag::Ping;
+use crate::net::EthernetDevice;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// An `increasing` rule stays violated this long after the counter last went up.
+const INCREASE_HOLD: Duration = Duration::from_secs(60);
+
+/// What a rule can watch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// Only while the link has a carrier.
+    SpeedMbps,
+    RxErrors,
+    TxErrors,
+    RxDropped,
+    TxDropped,
+    RxCrcErrors,
+    RxMissedErrors,
+    Collisions,
+    /// Current throughput in Mb/s.
+    RxMbps,
+    TxMbps,
+    /// Rolling average and loss of a running ping (`P`) out of the interface.
+    LatencyMs,
+    LossPct,
+}
+
+impl AlertMetric {
+    fn name(self) -> &'static str {
+        match self {
+            AlertMetric::SpeedMbps => "speed_mbps",
+            AlertMetric::RxErrors => "rx_errors",
+            AlertMetric::TxErrors => "tx_errors",
+            AlertMetric::RxDropped => "rx_dropped",
+            AlertMetric::TxDropped => "tx_dropped",
+            AlertMetric::RxCrcErrors => "rx_crc_errors",
+            AlertMetric::RxMissedErrors => "rx_missed_errors",
+            AlertMetric::Collisions => "collisions",
+            AlertMetric::RxMbps => "rx_mbps",
+            AlertMetric::TxMbps => "tx_mbps",
+            AlertMetric::LatencyMs => "latency_ms",
+            AlertMetric::LossPct => "loss_pct",
+        }
+    }
+
+    fn is_counter(self) -> bool {
+        matches!(
+            self,
+            AlertMetric::RxErrors
+                | AlertMetric::TxErrors
+                | AlertMetric::RxDropped
+                | AlertMetric::TxDropped
+                | AlertMetric::RxCrcErrors
+                | AlertMetric::RxMissedErrors
+                | AlertMetric::Collisions
+        )
+    }
+
+    /// None when there's nothing to compare right now (no carrier, no ping running...).
+    pub fn value(
+        self,
+        d: &EthernetDevice,
+        throughput: Option<&Throughput>,
+        ping: Option<&Ping>,
+    ) -> Option<f64> {
+        let st = &d.stats;
+        let mbps = |bytes: u64| bytes as f64 * 8.0 / 1_000_000.0;
+        Some(match self {
+            AlertMetric::SpeedMbps if d.carrier == Some(true) => f64::from(d.speed_mbps?),
+            AlertMetric::SpeedMbps => return None,
+            AlertMetric::RxErrors => st.rx_errors as f64,
+            AlertMetric::TxErrors => st.tx_errors as f64,
+            AlertMetric::RxDropped => st.rx_dropped as f64,
+            AlertMetric::TxDropped => st.tx_dropped as f64,
+            AlertMetric::RxCrcErrors => st.rx_crc_errors as f64,
+            AlertMetric::RxMissedErrors => st.rx_missed_errors as f64,
+            AlertMetric::Collisions => st.collisions as f64,
+            AlertMetric::RxMbps => mbps(*throughput?.rx.back()?),
+            AlertMetric::TxMbps => mbps(*throughput?.tx.back()?),
+            AlertMetric::LatencyMs => ping?.rolling().avg_ms?,
+            AlertMetric::LossPct => {
+                let window = ping?.rolling();
+                (window.sent > 0).then_some(window.loss_pct)?
+            }
+        })
+    }
+}
+
+/// An `[[alerts]]` entry from the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// Shown with the alert; the condition itself otherwise.
+    pub name: Option<String>,
+    pub metric: AlertMetric,
+    /// A name or glob like `enp*`; every interface without one.
+    pub interface: Option<String>,
+    pub below: Option<f64>,
+    pub above: Option<f64>,
+    /// For counters: violated while the counter keeps going up.
+    #[serde(default)]
+    pub increasing: bool,
+    /// Also raise a desktop notification (when notifications are on).
+    #[serde(default)]
+    pub notify: bool,
+}
+
+impl AlertRule {
+    /// Exactly one condition, and `increasing` only on counters.
+    pub fn validate(&self) -> Result<(), String> {
+        let conditions = usize::from(self.below.is_some())
+            + usize::from(self.above.is_some())
+            + usize::from(self.increasing);
+        let metric = self.metric.name();
+        if conditions != 1 {
+            return Err(format!(
+                "the {metric} alert needs exactly one of below, above or increasing"
+            ));
+        }
+        if self.increasing && !self.metric.is_counter() {
+            return Err(format!(
+                "{metric} isn't a counter, so it can't be `increasing`"
+            ));
+        }
+        Ok(())
+    }
+
+    fn describe(&self, value: f64) -> String {
+        let metric = self.metric.name();
+        let condition = match (self.below, self.above) {
+            (Some(limit), _) => format!("{metric} {value} below {limit}"),
+            (_, Some(limit)) => format!("{metric} {value:.1} above {limit}"),
+            _ => format!("{metric} increasing (now {value})"),
+        };
+        match &self.name {
+            Some(name) => format!("{name}: {condition}"),
+            None => condition,
+        }
+    }
+}
+
+pub enum AlertChange {
+    Raised {
+        iface: String,
+        msg: String,
+        notify: bool,
+    },
+    Cleared {
+        iface: String,
+        msg: String,
+    },
+}
+
+/// The configured rules and which of them are currently violated, per interface.
+#[derive(Debug, Default)]
+pub struct Alerts {
+    rules: Vec<AlertRule>,
+    /// (rule, interface) → message, while violated.
+    active: HashMap<(usize, String), String>,
+    /// (rule, interface) → last counter value and when it last went up.
+    counters: HashMap<(usize, String), (f64, Option<Instant>)>,
+}
+
+impl Alerts {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            ..Self::default()
+        }
+    }
+
+    /// Re-evaluates every rule; returns only what started or stopped being violated.
+    /// Interfaces that went away drop their alerts silently.
+    pub fn check(
+        &mut self,
+        devices: &[EthernetDevice],
+        value: impl Fn(AlertMetric, &EthernetDevice) -> Option<f64>,
+    ) -> Vec<AlertChange> {
+        let mut changes = Vec::new();
+        let now = Instant::now();
+        for (i, rule) in self.rules.iter().enumerate() {
+            for d in devices {
+                if let Some(pattern) = &rule.interface
+                    && !glob_match(pattern, &d.name)
+                {
+                    continue;
+                }
+                let key = (i, d.name.clone());
+                let current = value(rule.metric, d);
+                let violated = current.is_some_and(|v| match (rule.below, rule.above) {
+                    (Some(limit), _) => v < limit,
+                    (_, Some(limit)) => v > limit,
+                    _ => {
+                        let (last, rose) = self.counters.entry(key.clone()).or_insert((v, None));
+                        if v > *last {
+                            *rose = Some(now);
+                        }
+                        *last = v;
+                        rose.is_some_and(|at| now.duration_since(at) < INCREASE_HOLD)
+                    }
+                });
+                match (violated, self.active.contains_key(&key)) {
+                    (true, false) => {
+                        let msg = rule.describe(current.unwrap_or_default());
+                        self.active.insert(key, msg.clone());
+                        changes.push(AlertChange::Raised {
+                            iface: d.name.clone(),
+                            msg,
+                            notify: rule.notify,
+                        });
+                    }
+                    (false, true) => {
+                        if let Some(msg) = self.active.remove(&key) {
+                            changes.push(AlertChange::Cleared {
+                                iface: d.name.clone(),
+                                msg,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.active
+            .retain(|(_, iface), _| devices.iter().any(|d| &d.name == iface));
+        self.counters
+            .retain(|(_, iface), _| devices.iter().any(|d| &d.name == iface));
+        changes
+    }
+
+    /// Messages of the alerts currently raised for `iface`.
+    pub fn active(&self, iface: &str) -> Vec<&str> {
+        let mut active: Vec<(usize, &str)> = self
+            .active
+            .iter()
+            .filter(|((_, name), _)| name == iface)
+            .map(|((rule, _), msg)| (*rule, msg.as_str()))
+            .collect();
+        active.sort_unstable();
+        active.into_iter().map(|(_, msg)| msg).collect()
+    }
+}
@@ -1,8 +1,56 @@
-use crate::net::{EthernetDevice, list_ethernet_devices};
+use crate::alerts::{AlertChange, Alerts};
+use crate::backend::{self, NetworkBackend};
+use crate::bpf::{self, BpfProgram};
+use crate::command::{CommandLine, glob_match};
+use crate::config::Config;
+use crate::diag::{
+    self, Connectivity, DEFAULT_DNS_TEST_HOST, DEFAULT_PING_WINDOW, DEFAULT_TRACE_TARGET,
+    DNS_BENCHMARK_QUERIES, DnsTest, GatewayReach, LatencyWindow, MdnsBrowser, Ping, Scan,
+    SpeedTest, Trace,
+};
+use crate::dns::{self, DnsChange};
+use crate::ethtool::{self, DriverStat, Hardware};
+use crate::events::{self, EventKind, EventLog, LinkEvent};
+use crate::export::{self, InterfaceSnapshot, Snapshot};
+use crate::firewall::{self, FirewallRule};
+use crate::history::History;
+use crate::hooks::HookEvent;
+use crate::ifupdown::{self, Interfaces};
+use crate::lldp::{self, LldpNeighbor};
+use crate::logfile;
+use crate::mqtt;
+use crate::net::{
+    EthernetDevice, IfaceStats, LinkDetails, Neighbor, NetEvent, Qdisc, Route, StaticIpv4Config,
+    default_gateway_v4, default_route_iface, hostname, iface_name_for_index, list_bridges,
+    list_ethernet_devices, list_neighbors, list_qdiscs, list_routes, load_oui_db, parse_cidr,
+    parse_mac, random_local_mac, read_bond, read_ethernet_device, read_iface_stats, read_mtu,
+    read_wifi_signal,
+};
+use crate::netplan::{self, Netplan, NetplanTry, TryAnswer};
+use crate::networkd::{self, LinkStatus, NetworkConfig, Units};
+use crate::notify::{Notifier, NotifyEvent};
+use crate::plugins::{self, Plugins};
+use crate::policy::{self, PolicyRouting};
+use crate::profiles::{Profile, SavedState};
+use crate::qr::QrCode;
+use crate::queues::{self, Queue, Queues, read_queues};
+use crate::remote::Remote;
+use crate::sockets::{Socket, list_sockets};
+use crate::sriov::{self, Sriov, VF_LINK_STATES, Vf};
+use crate::ui::{self, human_duration};
+use crate::wireguard::{self, WgInterface};
 use anyhow::Result;
+use ratatui::layout::Rect;
 use ratatui::widgets::TableState;
-use std::time::{Duration, Instant};
+use serde_json::json;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ToastKind {
@@ -28,6 +76,626 @@ pub struct CmdOutput {
     pub stderr: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormKind {
+    StaticIpv4,
+    AddAddress,
+    FlushAddresses,
+    Route,
+    DnsServers,
+    NetworkFile,
+    Netplan,
+    Profile,
+    LinkSettings,
+    Rings,
+    Channels,
+    Coalesce,
+    Fec,
+    IrqAffinity,
+    NumVfs,
+    VfSettings,
+    WakeOnLan,
+    MacAddress,
+    Ping,
+    Trace,
+    SpeedTest,
+    DnsTest,
+    Vlan,
+    Bridge,
+    ExportJson,
+    ExportCsv,
+}
+
+#[derive(Debug, Clone)]
+pub struct FormField {
+    pub label: &'static str,
+    pub hint: String,
+    pub value: String,
+}
+
+/// A small modal input form; `kind` decides what happens on submit.
+#[derive(Debug, Clone)]
+pub struct Form {
+    pub kind: FormKind,
+    pub title: String,
+    pub fields: Vec<FormField>,
+    pub focus: usize,
+}
+
+impl Form {
+    pub fn focus_next(&mut self) {
+        self.focus = (self.focus + 1) % self.fields.len();
+    }
+
+    pub fn focus_prev(&mut self) {
+        self.focus = (self.focus + self.fields.len() - 1) % self.fields.len();
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.fields[self.focus].value.push(c);
+    }
+
+    pub fn pop_char(&mut self) {
+        self.fields[self.focus].value.pop();
+    }
+
+    pub fn value(&self, i: usize) -> &str {
+        &self.fields[i].value
+    }
+}
+
+/// Which pane receives navigation keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Devices,
+    Details,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetailTab {
+    Overview,
+    Stats,
+    Hardware,
+    Offloads,
+    Coalescing,
+    Routes,
+    Neighbors,
+    Connections,
+    Qos,
+    Firewall,
+    History,
+    Queues,
+    Driver,
+    Sriov,
+    Config,
+}
+
+impl DetailTab {
+    /// New tabs go at the end, so the number keys of the first ten never move.
+    pub const ALL: [DetailTab; 15] = [
+        DetailTab::Overview,
+        DetailTab::Stats,
+        DetailTab::Hardware,
+        DetailTab::Offloads,
+        DetailTab::Coalescing,
+        DetailTab::Routes,
+        DetailTab::Neighbors,
+        DetailTab::Connections,
+        DetailTab::Qos,
+        DetailTab::Firewall,
+        DetailTab::History,
+        DetailTab::Queues,
+        DetailTab::Driver,
+        DetailTab::Sriov,
+        DetailTab::Config,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            DetailTab::Overview => "Overview",
+            DetailTab::Stats => "Stats",
+            DetailTab::Hardware => "Hardware",
+            DetailTab::Offloads => "Offloads",
+            DetailTab::Coalescing => "Coalescing",
+            DetailTab::Queues => "Queues",
+            DetailTab::Driver => "Driver",
+            DetailTab::Sriov => "SR-IOV",
+            DetailTab::Routes => "Routes",
+            DetailTab::Neighbors => "Neighbors",
+            DetailTab::Connections => "Connections",
+            DetailTab::Qos => "QoS",
+            DetailTab::Firewall => "Firewall",
+            DetailTab::History => "History",
+            DetailTab::Config => "Config",
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|t| *t == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        Self::ALL[(self.index() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// The tab for a number key: `1` is the first, `0` the tenth. Later ones are only reached
+    /// with `[`/`]`.
+    pub fn from_digit(c: char) -> Option<Self> {
+        let n = c.to_digit(10)? as usize;
+        Self::ALL.get((n + 9) % 10).copied()
+    }
+}
+
+/// Column the interface list is ordered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    State,
+    Speed,
+    Ipv4,
+}
+
+impl SortKey {
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Name => SortKey::State,
+            SortKey::State => SortKey::Speed,
+            SortKey::Speed => SortKey::Ipv4,
+            SortKey::Ipv4 => SortKey::Name,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::State => "state",
+            SortKey::Speed => "speed",
+            SortKey::Ipv4 => "IP",
+        }
+    }
+
+    /// Unknown speeds and interfaces without an address sort last either way; ties fall back
+    /// to the name so the order is stable between refreshes.
+    fn compare(self, a: &EthernetDevice, b: &EthernetDevice, descending: bool) -> Ordering {
+        let directed = |o: Ordering| if descending { o.reverse() } else { o };
+        fn known_first<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) if descending => b.cmp(&a),
+                (Some(a), Some(b)) => a.cmp(&b),
+                (a, b) => a.is_none().cmp(&b.is_none()),
+            }
+        }
+        let by_key = match self {
+            SortKey::Name => Ordering::Equal,
+            SortKey::State => directed(state_rank(&a.operstate).cmp(&state_rank(&b.operstate))),
+            SortKey::Speed => known_first(a.speed_mbps, b.speed_mbps, descending),
+            SortKey::Ipv4 => known_first(first_ipv4(a), first_ipv4(b), descending),
+        };
+        by_key.then_with(|| match self {
+            SortKey::Name => directed(a.name.cmp(&b.name)),
+            _ => a.name.cmp(&b.name),
+        })
+    }
+}
+
+/// Working links first, then the ones on their way up, then the dead ones.
+fn state_rank(operstate: &str) -> u8 {
+    match operstate {
+        "up" => 0,
+        "dormant" => 1,
+        "unknown" => 2,
+        "lowerlayerdown" => 3,
+        "down" => 4,
+        _ => 5,
+    }
+}
+
+fn first_ipv4(d: &EthernetDevice) -> Option<Ipv4Addr> {
+    d.ipv4
+        .iter()
+        .find_map(|a| a.split('/').next()?.parse().ok())
+}
+
+/// Number of throughput samples kept per interface (one per tick).
+pub const THROUGHPUT_SAMPLES: usize = 120;
+
+/// RX/TX rate history (bytes per second) for one interface.
+#[derive(Debug, Clone, Default)]
+pub struct Throughput {
+    last: Option<(Instant, IfaceStats)>,
+    pub rx: VecDeque<u64>,
+    pub tx: VecDeque<u64>,
+    /// When each rx/tx sample was taken.
+    pub at: VecDeque<SystemTime>,
+}
+
+impl Throughput {
+    fn sample(&mut self, now: Instant, stats: IfaceStats) {
+        if let Some((then, prev)) = self.last {
+            let secs = now.duration_since(then).as_secs_f64();
+            if secs > 0.0 {
+                // Counters can go backwards when a driver resets them; treat that as zero.
+                let rate = |cur: u64, old: u64| (cur.saturating_sub(old) as f64 / secs) as u64;
+                push_capped(&mut self.rx, rate(stats.rx_bytes, prev.rx_bytes));
+                push_capped(&mut self.tx, rate(stats.tx_bytes, prev.tx_bytes));
+                push_capped(&mut self.at, SystemTime::now());
+            }
+        }
+        self.last = Some((now, stats));
+    }
+
+    pub fn rx_rate(&self) -> u64 {
+        self.rx.back().copied().unwrap_or(0)
+    }
+
+    pub fn tx_rate(&self) -> u64 {
+        self.tx.back().copied().unwrap_or(0)
+    }
+}
+
+fn push_capped<T>(buf: &mut VecDeque<T>, v: T) {
+    if buf.len() == THROUGHPUT_SAMPLES {
+        buf.pop_front();
+    }
+    buf.push_back(v);
+}
+
+/// Runs a command that may need root, retrying through `sudo -n` when it's refused. Every run
+/// ends up in the log file, when there is one.
+pub async fn run_privileged_capture(program: &str, args: &[&str]) -> Result<CmdOutput> {
+    let res = run_privileged(program, args).await;
+    let fields = match &res {
+        Ok(out) => json!({
+            "program": program,
+            "args": args,
+            "sudo": out.used_sudo,
+            "status": out.status,
+            "stdout": out.stdout,
+            "stderr": out.stderr,
+        }),
+        Err(e) => json!({ "program": program, "args": args, "error": e.to_string() }),
+    };
+    logfile::write("command", fields);
+    res
+}
+
+async fn run_privileged(program: &str, args: &[&str]) -> Result<CmdOutput> {
+    let mk = |used_sudo: bool, status: i32, stdout: Vec<u8>, stderr: Vec<u8>| CmdOutput {
+        program: program.to_string(),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        used_sudo,
+        status,
+        stdout: String::from_utf8_lossy(&stdout).trim().to_string(),
+        stderr: String::from_utf8_lossy(&stderr).trim().to_string(),
+    };
+
+    // Try without sudo first (works if running as root or with capabilities/polkit).
+    if let Ok(out) = Command::new(program).args(args).output().await {
+        let code = out.status.code().unwrap_or(1);
+        if out.status.success() {
+            return Ok(mk(false, code, out.stdout, out.stderr));
+        }
+
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        if stderr.contains("Operation not permitted")
+            || stderr.contains("Permission denied")
+            || out.status.code() == Some(1)
+        {
+            let sudo_out = Command::new("sudo")
+                .arg("-n")
+                .arg(program)
+                .args(args)
+                .output()
+                .await?;
+            let code = sudo_out.status.code().unwrap_or(1);
+            if sudo_out.status.success() {
+                return Ok(mk(true, code, sudo_out.stdout, sudo_out.stderr));
+            }
+            let sudo_err = String::from_utf8_lossy(&sudo_out.stderr).trim().to_string();
+            return Err(std::io::Error::other(if sudo_err.is_empty() {
+                format!("{} failed (sudo)", program)
+            } else {
+                sudo_err
+            })
+            .into());
+        }
+
+        return Err(std::io::Error::other(if stderr.is_empty() {
+            format!("{} failed", program)
+        } else {
+            stderr
+        })
+        .into());
+    }
+
+    Err(std::io::Error::other("failed to spawn command").into())
+}
+
+/// Writes a root-owned config file by staging it in a temp dir and `install`ing it, so the
+/// privileged step goes through [`run_privileged_capture`] like every other command.
+pub async fn write_privileged_file(path: &str, contents: &str) -> Result<CmdOutput> {
+    use std::io::Write;
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    let tmp = std::env::temp_dir().join(format!("ethtui-{}-{nanos}.tmp", std::process::id()));
+    // create_new: never follow or reuse a file someone else planted at that path.
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp)?
+        .write_all(contents.as_bytes())?;
+    let tmp_str = tmp.to_string_lossy().to_string();
+    let res = run_privileged_capture("install", &["-D", "-m", "0644", &tmp_str, path]).await;
+    let _ = std::fs::remove_file(&tmp);
+    res
+}
+
+/// Writes a value to a `/proc` or `/sys` file that needs root. `install` can't replace those,
+/// so a shell redirect does the write, run through [`run_privileged_capture`].
+pub async fn write_kernel_file(path: &str, value: &str) -> Result<CmdOutput> {
+    run_privileged_capture("sh", &["-c", "echo \"$1\" > \"$2\"", "sh", value, path]).await
+}
+
+/// The backend managing an interface, and where its configuration lives.
+#[derive(Clone)]
+pub struct BackendInfo {
+    pub backend: &'static dyn NetworkBackend,
+    pub config_source: Option<String>,
+}
+
+/// A command running in the background; its result is shown as a toast when it finishes,
+/// or in a popup that stays until dismissed when `popup` is set.
+pub struct Job {
+    pub label: String,
+    popup: bool,
+    handle: JoinHandle<Result<String>>,
+}
+
+/// Actions that drop connectivity and so need a yes/no confirmation first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfirmAction {
+    ReleaseDhcp,
+    DeleteVlan,
+    LeaveBridge,
+    BondFailover,
+    LinkDown,
+    SetMac(String),
+    SetMtu(u32),
+    SetEee(bool),
+    SetFec(String),
+    /// `ethtool -L` keys and counts.
+    SetChannels(Vec<(&'static str, String)>),
+    SpreadIrqs,
+    SetNumVfs(u32),
+    SetDns(DnsChange),
+    WriteNetworkFile(NetworkConfig),
+    NetplanApply,
+    ApplyProfile(String),
+    DeleteAddress(String),
+    /// Restart DHCP afterwards.
+    FlushAddresses(bool),
+    AddRoute(Route),
+    DeleteRoute(Route),
+    Undo,
+}
+
+/// A change `U` can revert, with what it replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Undo {
+    Mtu {
+        iface: String,
+        mtu: u32,
+    },
+    Mac {
+        iface: String,
+        mac: String,
+    },
+    /// The static form flushed `addrs` for `added`; `gateway` is the default route it replaced
+    /// (Some(None) when there was none), or None when it left routes alone.
+    Ipv4 {
+        iface: String,
+        added: String,
+        addrs: Vec<String>,
+        gateway: Option<Option<String>>,
+    },
+    /// `cidr` was deleted from the interface.
+    Address {
+        iface: String,
+        cidr: String,
+    },
+    /// `route` was added to the interface, or deleted from it when `deleted`.
+    Route {
+        iface: String,
+        route: Route,
+        deleted: bool,
+    },
+    /// A `[[profiles]]` entry was applied over `previous`.
+    Profile {
+        iface: String,
+        name: String,
+        previous: SavedState,
+    },
+}
+
+impl Undo {
+    pub fn describe(&self) -> String {
+        match self {
+            Undo::Mtu { iface, mtu } => format!("{iface}: MTU back to {mtu}"),
+            Undo::Mac { iface, mac } => format!("{iface}: MAC back to {mac}"),
+            Undo::Ipv4 {
+                iface,
+                added,
+                addrs,
+                gateway,
+            } => {
+                let addrs = if addrs.is_empty() {
+                    "no address".to_string()
+                } else {
+                    addrs.join(", ")
+                };
+                let mut msg = format!("{iface}: {added} back to {addrs}");
+                match gateway {
+                    Some(Some(gw)) => msg.push_str(&format!(", gateway {gw}")),
+                    Some(None) => msg.push_str(", no default route"),
+                    None => {}
+                }
+                msg
+            }
+            Undo::Address { iface, cidr } => format!("{iface}: add {cidr} back"),
+            Undo::Route {
+                iface,
+                route,
+                deleted,
+            } => {
+                if *deleted {
+                    format!("{iface}: add route {} back", route.describe())
+                } else {
+                    format!("{iface}: delete route {}", route.describe())
+                }
+            }
+            Undo::Profile { iface, name, .. } => {
+                format!("{iface}: settings from before profile {name}")
+            }
+        }
+    }
+}
+
+/// How many changes `U` can walk back.
+const UNDO_LEN: usize = 10;
+
+/// A yes/no prompt; `y` runs `action`, any other key cancels.
+#[derive(Debug, Clone)]
+pub struct Confirm {
+    pub action: ConfirmAction,
+    pub prompt: String,
+}
+
+/// A result popup that stays on screen until a key is pressed.
+#[derive(Debug, Clone)]
+pub struct Popup {
+    pub title: String,
+    pub body: String,
+}
+
+/// One field of the interface before and after a DHCP renew.
+#[derive(Debug, Clone)]
+pub struct DiffRow {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+impl DiffRow {
+    pub fn changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+/// What a DHCP renew changed, shown side by side until a key is pressed.
+#[derive(Debug, Clone)]
+pub struct RenewDiff {
+    pub iface: String,
+    pub rows: Vec<DiffRow>,
+    /// Whatever the DHCP client printed.
+    pub output: String,
+}
+
+impl RenewDiff {
+    pub fn new(
+        iface: String,
+        before: &EthernetDevice,
+        after: &EthernetDevice,
+        output: String,
+    ) -> Self {
+        let rows = renew_fields(before)
+            .into_iter()
+            .zip(renew_fields(after))
+            .map(|((field, before), (_, after))| DiffRow {
+                field,
+                before,
+                after,
+            })
+            .collect();
+        Self {
+            iface,
+            rows,
+            output,
+        }
+    }
+
+    pub fn changed(&self) -> bool {
+        self.rows.iter().any(DiffRow::changed)
+    }
+}
+
+/// What a renew can change; a new lease shows up as a later `obtained` even when it handed
+/// out the same address.
+fn renew_fields(d: &EthernetDevice) -> [(&'static str, String); 8] {
+    let or_dash = |v: &[String]| {
+        if v.is_empty() {
+            "-".to_string()
+        } else {
+            v.join(", ")
+        }
+    };
+    let lease = d.lease.as_ref();
+    let obtained = lease
+        .and_then(|l| events::datetime(l.acquired?))
+        .unwrap_or_else(|| "-".into());
+    [
+        ("State", d.operstate.clone()),
+        (
+            "Carrier",
+            d.carrier
+                .map_or("?", |c| if c { "yes" } else { "no" })
+                .into(),
+        ),
+        ("IPv4", or_dash(&d.ipv4)),
+        (
+            "Gateway",
+            d.gateway_v4.clone().unwrap_or_else(|| "-".into()),
+        ),
+        ("DNS", or_dash(&d.dns)),
+        (
+            "DHCP server",
+            lease
+                .and_then(|l| l.server.clone())
+                .unwrap_or_else(|| "-".into()),
+        ),
+        (
+            "Lease time",
+            lease
+                .and_then(|l| l.lease_time)
+                .map_or("-".into(), |t| human_duration(t.as_secs())),
+        ),
+        ("Lease obtained", obtained),
+    ]
+}
+
+/// The `/` prompt; the list is filtered as it's typed.
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub input: String,
+    /// Filters the Driver tab's counters instead of the interface list.
+    pub counters: bool,
+    /// The filter in effect before `/`, put back when the prompt is cancelled.
+    previous: Option<String>,
+}
+
+/// A QR code popup, and the text it encodes.
+#[derive(Debug, Clone)]
+pub struct QrPopup {
+    pub text: String,
+    pub code: QrCode,
+}
+
 pub struct App {
     pub running: bool,
     pub devices: Vec<EthernetDevice>,
@@ -35,16 +703,231 @@ pub struct App {
     pub last_error: Option<String>,
     pub last_action: Option<String>,
     pub toast: Option<Toast>,
+    pub form: Option<Form>,
+    pub throughput: HashMap<u32, Throughput>,
+    pub hardware: HashMap<u32, Hardware>,
+    pub backends: HashMap<u32, BackendInfo>,
+    pub routes: HashMap<u32, Vec<Route>>,
+    /// Rules and other tables involving the interface, read along with its routes.
+    pub policy: HashMap<u32, Result<PolicyRouting, String>>,
+    pub neighbors: Vec<Neighbor>,
+    neighbors_fetched: Option<(u32, Instant)>,
+    pub connections: Vec<Socket>,
+    connections_fetched: Option<(u32, Instant)>,
+    /// Qdiscs of the selected interface, or why `tc` failed.
+    pub qdiscs: Result<Vec<Qdisc>, String>,
+    qdiscs_fetched: Option<(u32, Instant)>,
+    /// Per-queue counters and interrupts of the selected interface, with rates.
+    pub queues: Result<Option<Queues>, String>,
+    queues_fetched: Option<(u32, Instant)>,
+    pub queues_state: TableState,
+    /// `ethtool -S` of the selected interface with deltas since the previous read, or why it
+    /// failed.
+    pub driver_stats: Result<Vec<DriverStat>, String>,
+    driver_stats_fetched: Option<(u32, Instant)>,
+    pub driver_stats_state: TableState,
+    /// VFs of the selected interface; None when it isn't an SR-IOV physical function.
+    pub sriov: Result<Option<Sriov>, String>,
+    sriov_fetched: Option<(u32, Instant)>,
+    pub sriov_state: TableState,
+    /// The networkd units behind the selected interface, for the Config tab.
+    pub units: Option<Units>,
+    /// The netplan definitions behind it; None when the machine doesn't use netplan.
+    pub netplan: Option<Netplan>,
+    /// The ifupdown stanzas mentioning it; None without `/etc/network/interfaces`.
+    pub ifupdown: Option<Interfaces>,
+    units_fetched: Option<(u32, Instant)>,
+    /// Cursor over the IPv4 then IPv6 addresses of the focused Overview.
+    pub address_state: TableState,
+    pub routes_state: TableState,
+    /// The Driver tab's `/` filter.
+    pub stats_filter: Option<String>,
+    /// Rules mentioning the selected interface and the tool they came from, or why neither
+    /// nft nor iptables could be read.
+    pub firewall: Result<(&'static str, Vec<FirewallRule>), String>,
+    firewall_fetched: Option<(u32, Instant)>,
+    /// lldpd's view per ifindex (None when it can't be asked), and when it was fetched.
+    lldp: HashMap<u32, (Instant, Option<Vec<LldpNeighbor>>)>,
+    /// `wg show` per ifindex of WireGuard interfaces (or why it failed), and when it was read.
+    wireguard: HashMap<u32, (Instant, Result<WgInterface, String>)>,
+    /// XDP and tc programs per ifindex (or why they couldn't be listed), and when.
+    bpf: HashMap<u32, (Instant, Result<Vec<BpfProgram>, String>)>,
+    /// `networkctl status` per ifindex of networkd-managed interfaces (or why it failed), and
+    /// when it was read.
+    networkd_status: HashMap<u32, (Instant, Result<LinkStatus, String>)>,
+    /// Fastest supported speed per ifindex (None when ethtool can't tell), looked up once a
+    /// link comes up slow or half duplex.
+    max_link_speeds: HashMap<u32, Option<u32>>,
+    pub oui: Option<HashMap<String, String>>,
+    pub jobs: Vec<Job>,
+    pub popup: Option<Popup>,
+    pub renew_diff: Option<RenewDiff>,
+    pub qr: Option<QrPopup>,
+    /// Scroll offset of the help overlay, when it's open.
+    pub help_scroll: Option<u16>,
+    /// The `:` command line, while it's open.
+    pub command: Option<CommandLine>,
+    pub command_history: Vec<String>,
+    /// Pattern from `/` or `:filter`; only matching interfaces are listed.
+    pub filter: Option<String>,
+    pub search: Option<Search>,
+    /// Changes noticed on any interface since startup.
+    pub events: EventLog,
+    notifier: Notifier,
+    /// What plugins printed, collected on every tick.
+    plugin_replies: Option<mpsc::UnboundedReceiver<plugins::Reply>>,
+    /// Plugin notes per interface, as (plugin, text) in the order they first arrived.
+    pub annotations: HashMap<String, Vec<(String, String)>>,
+    /// Command lines from plugins, as (plugin, line), for the handler to run.
+    pub plugin_commands: Vec<(String, String)>,
+    /// `--remote`, or the `hosts` from the config, each polled on its own.
+    pub hosts: Vec<Remote>,
+    /// Which of `hosts` is shown; None for this machine.
+    pub host: Option<usize>,
+    /// This machine is one of the choices: always, except with `--remote`.
+    pub local: bool,
+    /// This machine's name, read once for the status bar and host list.
+    pub hostname: String,
+    /// Every frame is turned into plain ASCII before it's drawn.
+    pub ascii: bool,
+    /// Where the default route goes out, for the status bar.
+    pub default_iface: Option<String>,
+    /// Addresses, gateways and leases seen over time; None with `history = false`.
+    pub history: Option<History>,
+    pub history_state: TableState,
+    /// Threshold rules from the config and which interfaces currently break them.
+    pub alerts: Alerts,
+    /// State published to the configured MQTT broker, if any.
+    mqtt: Option<mqtt::Publisher>,
+    /// Scroll offset of the event log, when it's open.
+    pub events_scroll: Option<u16>,
+    /// `y` was pressed and the next key picks what to copy.
+    pub yank_pending: bool,
+    pub sort: SortKey,
+    pub sort_descending: bool,
+    /// Header cells of the interface list at the last draw, for sorting by click.
+    pub sort_headers: Vec<(Rect, SortKey)>,
+    pub confirm: Option<Confirm>,
+    /// Reversible changes, most recent last.
+    pub undo: Vec<Undo>,
+    pub ping: Option<Ping>,
+    /// Loss/jitter windows from earlier pings, per interface name, kept after the ping stops.
+    pub latency_history: HashMap<String, Vec<LatencyWindow>>,
+    pub trace: Option<Trace>,
+    pub netplan_try: Option<NetplanTry>,
+    pub speedtest: Option<SpeedTest>,
+    last_speedtest_target: String,
+    pub dns_test: Option<DnsTest>,
+    last_dns_test_host: String,
+    pub scan: Option<Scan>,
+    pub scan_state: TableState,
+    pub mdns: Option<MdnsBrowser>,
+    pub mdns_state: TableState,
+    pub connectivity: HashMap<u32, Connectivity>,
+    connectivity_checks: Vec<(u32, JoinHandle<Connectivity>)>,
+    /// Per-link DNS and wireless details, fetched in the background for local interfaces.
+    link_details: LinkDetails,
+    connectivity_checked: Option<Instant>,
+    /// Whether each interface's IPv4 gateway (the one checked) answers ARP.
+    pub gateway_reach: HashMap<u32, (String, GatewayReach)>,
+    gateway_checks: Vec<(u32, String, JoinHandle<GatewayReach>)>,
+    gateway_checked: Option<Instant>,
+    pub focus: Focus,
+    pub tab: DetailTab,
+    pub offloads_state: TableState,
+    /// First visible line of the text tabs (Overview, Stats, Hardware), which can be longer than
+    /// the pane.
+    pub details_scroll: u16,
+    /// Height of the text pane at the last draw, for paging.
+    pub details_height: u16,
+    /// Settings from the config file; `--all` is folded into `include_wifi`.
+    pub config: Config,
+    pub connections_state: TableState,
+    pub firewall_state: TableState,
+    /// Automatic refreshing is stopped: counters, periodic lookups and netlink updates are
+    /// ignored until resumed. Running tools and jobs still report.
+    pub paused: bool,
+    /// The interface the first `p` was for, and until when the second one counts.
+    promisc_armed: Option<(String, Instant)>,
+    last_full_refresh: Instant,
+}
+
+/// What `y s` copies and `ethtui show` prints: the selected interface's state in a form fit for
+/// pasting into a ticket.
+pub fn snapshot_text(d: &EthernetDevice) -> String {
+    let or_dash = |v: &[String]| {
+        if v.is_empty() {
+            "-".to_string()
+        } else {
+            v.join(", ")
+        }
+    };
+    let carrier = d.carrier.map_or("?", |c| if c { "yes" } else { "no" });
+    let speed = d.speed_mbps.map_or("-".into(), |s| format!("{s} Mb/s"));
+    [
+        format!("interface: {}", d.name),
+        format!("state: {}", d.operstate),
+        format!("carrier: {carrier}"),
+        format!("speed: {speed}"),
+        format!("mac: {}", d.mac.as_deref().unwrap_or("-")),
+        format!("ipv4: {}", or_dash(&d.ipv4)),
+        format!("ipv6: {}", or_dash(&d.ipv6)),
+        format!("gateway: {}", d.gateway_v4.as_deref().unwrap_or("-")),
+        format!("dns: {}", or_dash(&d.dns)),
+    ]
+    .join("\n")
 }
 
+/// Full rescans are only a safety net; rtnetlink events drive normal updates.
+const FULL_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often each connected interface is probed for Internet access.
+const CONNECTIVITY_INTERVAL: Duration = Duration::from_secs(60);
+/// How often every gateway is asked for its MAC.
+const GATEWAY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often lldpd is asked about the selected interface.
+const LLDP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often `wg show` is re-run for the selected WireGuard interface.
+const WIREGUARD_INTERVAL: Duration = Duration::from_secs(5);
+const BPF_INTERVAL: Duration = Duration::from_secs(10);
+/// How often networkd is asked about the selected interface it manages.
+const NETWORKD_STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
 impl App {
-    pub async fn new() -> Result<Self> {
-        let devices = list_ethernet_devices()?;
+    pub async fn new(config: Config) -> Result<Self> {
+        let notifier = Notifier::new(config.notifications, &config.notify);
+        let (plugins, plugin_replies) = config.plugins.then(Plugins::open).flatten().unzip();
+        let command = &config.remote_command;
+        let (hosts, host) = match &config.remote {
+            Some(host) => {
+                let remote =
+                    Remote::connect(host.clone(), command.clone(), config.include_wifi).await?;
+                (vec![remote], Some(0))
+            }
+            None => {
+                let hosts = config
+                    .hosts
+                    .iter()
+                    .map(|h| Remote::new(h.clone(), command.clone(), config.include_wifi))
+                    .collect();
+                (hosts, None)
+            }
+        };
+        let devices = match host {
+            Some(i) => hosts[i].devices.clone(),
+            None => list_ethernet_devices(config.include_wifi)?,
+        };
         let mut devices_state = TableState::default();
         if devices.is_empty() {
             devices_state.select(None);
         } else {
-            devices_state.select(Some(0));
+            let preferred = config
+                .default_interface
+                .as_deref()
+                .and_then(|name| devices.iter().position(|d| d.name == name));
+            devices_state.select(Some(preferred.unwrap_or(0)));
         }
 
         Ok(Self {
@@ -54,6 +937,109 @@ impl App {
             last_error: None,
             last_action: None,
             toast: None,
+            form: None,
+            throughput: HashMap::new(),
+            hardware: HashMap::new(),
+            backends: HashMap::new(),
+            routes: HashMap::new(),
+            policy: HashMap::new(),
+            neighbors: Vec::new(),
+            neighbors_fetched: None,
+            connections: Vec::new(),
+            connections_fetched: None,
+            qdiscs: Ok(Vec::new()),
+            qdiscs_fetched: None,
+            queues: Ok(None),
+            queues_fetched: None,
+            queues_state: TableState::default(),
+            driver_stats: Ok(Vec::new()),
+            driver_stats_fetched: None,
+            driver_stats_state: TableState::default(),
+            sriov: Ok(None),
+            sriov_fetched: None,
+            sriov_state: TableState::default(),
+            units: None,
+            netplan: None,
+            ifupdown: None,
+            units_fetched: None,
+            address_state: TableState::default(),
+            routes_state: TableState::default(),
+            stats_filter: None,
+            firewall: Ok(("", Vec::new())),
+            firewall_fetched: None,
+            lldp: HashMap::new(),
+            wireguard: HashMap::new(),
+            bpf: HashMap::new(),
+            networkd_status: HashMap::new(),
+            max_link_speeds: HashMap::new(),
+            oui: None,
+            jobs: Vec::new(),
+            popup: None,
+            renew_diff: None,
+            qr: None,
+            help_scroll: None,
+            command: None,
+            command_history: Vec::new(),
+            filter: None,
+            search: None,
+            events: EventLog::new(config.hooks.clone(), plugins, notifier.clone()),
+            notifier,
+            plugin_replies,
+            annotations: HashMap::new(),
+            plugin_commands: Vec::new(),
+            alerts: Alerts::new(config.alerts.clone()),
+            hosts,
+            host,
+            local: config.remote.is_none(),
+            hostname: hostname(),
+            ascii: config.ascii.unwrap_or_else(ui::plain_terminal),
+            default_iface: None,
+            // Another host's addresses don't belong in this machine's history.
+            history: (config.history && config.remote.is_none())
+                .then(History::open)
+                .flatten(),
+            history_state: TableState::default(),
+            mqtt: mqtt::Publisher::start(&config.mqtt),
+            events_scroll: None,
+            yank_pending: false,
+            sort: SortKey::Name,
+            sort_descending: false,
+            sort_headers: Vec::new(),
+            confirm: None,
+            undo: Vec::new(),
+            ping: None,
+            latency_history: HashMap::new(),
+            trace: None,
+            netplan_try: None,
+            speedtest: None,
+            dns_test: None,
+            last_dns_test_host: config
+                .dns_test_host
+                .clone()
+                .unwrap_or_else(|| DEFAULT_DNS_TEST_HOST.into()),
+            last_speedtest_target: config.speedtest_target.clone().unwrap_or_default(),
+            scan: None,
+            scan_state: TableState::default(),
+            mdns: None,
+            mdns_state: TableState::default(),
+            connectivity: HashMap::new(),
+            connectivity_checks: Vec::new(),
+            link_details: LinkDetails::default(),
+            connectivity_checked: None,
+            gateway_reach: HashMap::new(),
+            gateway_checks: Vec::new(),
+            gateway_checked: None,
+            focus: Focus::Devices,
+            tab: DetailTab::Overview,
+            offloads_state: TableState::default(),
+            details_scroll: 0,
+            details_height: 0,
+            config,
+            connections_state: TableState::default(),
+            firewall_state: TableState::default(),
+            paused: false,
+            promisc_armed: None,
+            last_full_refresh: Instant::now(),
         })
     }
 
@@ -64,181 +1050,4222 @@ impl App {
             self.toast = None;
         }
 
-        // Refresh state periodically so link/IP changes show up without restarting the TUI.
-        match list_ethernet_devices() {
-            Ok(devices) => {
-                let selected = self.devices_state.selected();
-                self.devices = devices;
-                if self.devices.is_empty() {
-                    self.devices_state.select(None);
-                } else if let Some(i) = selected {
-                    self.devices_state
-                        .select(Some(i.min(self.devices.len() - 1)));
-                } else {
-                    self.devices_state.select(Some(0));
-                }
-                self.last_error = None;
+        if !self.paused {
+            self.auto_refresh().await;
+        }
+        self.check_alerts();
+        if self.host.is_none()
+            && let Some(history) = &mut self.history
+        {
+            history.observe(&self.devices);
+        }
+        self.default_iface = match self.remote() {
+            // Only the gateways are known there, not the route metrics.
+            Some(remote) => remote
+                .devices
+                .iter()
+                .find(|d| d.gateway_v4.is_some())
+                .map(|d| d.name.clone()),
+            None => default_route_iface(),
+        };
+        // Cached once per interface, so these only fill in a newly selected one. Neither can be
+        // read from a remote host.
+        if self.host.is_none() {
+            self.load_selected_hardware().await;
+            let _ = self.selected_backend().await;
+            if let Some((index, name)) = self.default_device().map(|d| (d.ifindex, d.name.clone()))
+            {
+                self.backend_of(index, &name).await;
             }
-            Err(e) => {
-                self.last_error = Some(e.to_string());
+        }
+        if let Some(ping) = &mut self.ping {
+            ping.poll();
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.poll();
+        }
+        self.poll_netplan_try();
+        self.poll_speedtest().await;
+        self.poll_dns_test();
+        if let Some(scan) = &mut self.scan
+            && !scan.finished
+        {
+            scan.poll();
+            if scan.finished {
+                let hosts: Vec<_> = scan
+                    .hosts
+                    .iter()
+                    .map(|h| json!({ "ip": h.ip, "mac": h.mac, "hostname": h.hostname }))
+                    .collect();
+                let msg = format!("{} hosts found on {}", hosts.len(), scan.subnet);
+                let details = json!({ "subnet": scan.subnet, "hosts": hosts });
+                self.events
+                    .hook(HookEvent::ScanFinished, &scan.iface, &msg, details);
             }
         }
+        self.poll_plugins();
+        if let Some(mdns) = &mut self.mdns {
+            mdns.poll();
+        }
+        self.poll_jobs().await;
+        // The topics carry this machine's hostname, so another host's interfaces don't belong.
+        if let Some(mqtt) = &mut self.mqtt
+            && self.host.is_none()
+        {
+            mqtt.update(&self.devices);
+        }
 
         Ok(())
     }
 
-    pub fn quit(&mut self) {
-        self.running = false;
+    fn check_alerts(&mut self) {
+        let (throughput, ping) = (&self.throughput, &self.ping);
+        let changes = self.alerts.check(&self.devices, |metric, d| {
+            let ping = ping.as_ref().filter(|p| p.iface == d.name);
+            metric.value(d, throughput.get(&d.ifindex), ping)
+        });
+        for change in changes {
+            match change {
+                AlertChange::Raised { iface, msg, notify } => {
+                    self.set_toast(ToastKind::Error, format!("{iface}: {msg}"));
+                    if notify {
+                        self.notifier.alert(format!("{iface}: {msg}"));
+                    }
+                    self.events
+                        .push(&iface, EventKind::Down, format!("alert: {msg}"));
+                }
+                AlertChange::Cleared { iface, msg } => {
+                    self.events
+                        .push(&iface, EventKind::Up, format!("alert cleared: {msg}"));
+                }
+            }
+        }
     }
 
-    pub fn set_toast(&mut self, kind: ToastKind, msg: impl Into<String>) {
-        self.toast = Some(Toast {
-            kind,
-            msg: msg.into(),
-            until: Instant::now() + Duration::from_millis(2500),
-        });
+    /// Everything `tick` re-reads on its own; skipped while paused.
+    async fn auto_refresh(&mut self) {
+        let mut shown_changed = false;
+        for (i, remote) in self.hosts.iter_mut().enumerate() {
+            shown_changed |= remote.poll().await && self.host == Some(i);
+        }
+        if self.host.is_some() {
+            // Counters only move when a poll comes back, so that's when throughput is sampled.
+            if shown_changed {
+                self.rescan_devices();
+                self.sample_throughput();
+            }
+            return;
+        }
+        if self.last_full_refresh.elapsed() >= FULL_REFRESH_INTERVAL {
+            self.refresh();
+        } else {
+            // Counters change constantly and don't generate netlink events; neither do
+            // bond failure counts.
+            for d in &mut self.devices {
+                d.stats = read_iface_stats(&d.name);
+                if d.bond.is_some() {
+                    d.bond = read_bond(&d.name);
+                }
+                if let Some(wifi) = &mut d.wifi {
+                    wifi.signal_dbm = read_wifi_signal(&d.name).or(wifi.signal_dbm);
+                }
+            }
+        }
+        self.link_details.track(&self.devices);
+        if self.link_details.collect().await {
+            self.link_details.apply(&mut self.devices);
+        }
+        self.sample_throughput();
+        self.load_max_link_speeds().await;
+        self.load_selected_routes().await;
+        self.load_neighbors().await;
+        self.load_connections();
+        self.load_qdiscs().await;
+        self.load_queues().await;
+        self.load_driver_stats().await;
+        self.load_sriov().await;
+        self.load_units().await;
+        self.load_firewall().await;
+        self.load_selected_lldp().await;
+        self.load_selected_wireguard().await;
+        self.load_selected_bpf().await;
+        self.load_networkd_status().await;
+        self.check_connectivity().await;
+        self.check_gateways().await;
     }
 
-    pub fn clear_error(&mut self) {
-        self.last_error = None;
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        // Catch up on whatever was missed while paused.
+        if !self.paused {
+            self.refresh();
+        }
     }
 
-    pub async fn notify(&self, title: &str, body: &str) {
-        // Best-effort desktop notification (Omarchy uses mako). Ignore failures.
-        let _ = Command::new("notify-send")
-            .arg(title)
-            .arg(body)
-            .arg("-t")
-            .arg("2000")
-            .output()
-            .await;
+    /// The host shown, unless it's this machine.
+    pub fn remote(&self) -> Option<&Remote> {
+        self.hosts.get(self.host?)
     }
 
-    pub fn select_next(&mut self) {
-        if self.devices.is_empty() {
-            self.devices_state.select(None);
-            return;
-        }
+    /// Every host that can be shown, in list order; None stands for this machine.
+    pub fn host_choices(&self) -> Vec<Option<usize>> {
+        let local = self.local.then_some(None);
+        local
+            .into_iter()
+            .chain((0..self.hosts.len()).map(Some))
+            .collect()
+    }
 
-        let i = match self.devices_state.selected() {
-            Some(i) => (i + 1).min(self.devices.len() - 1),
-            None => 0,
+    /// Steps through `host_choices`, starting over from scratch on the new host: its interfaces
+    /// are listed without being compared to the old host's, and throughput and alerts restart.
+    pub fn cycle_host(&mut self, forward: bool) {
+        let choices = self.host_choices();
+        let Some(pos) = choices.iter().position(|h| *h == self.host) else {
+            return;
         };
-        self.devices_state.select(Some(i));
+        let next = if forward {
+            (pos + 1) % choices.len()
+        } else {
+            (pos + choices.len() - 1) % choices.len()
+        };
+        if choices[next] == self.host {
+            return;
+        }
+        self.host = choices[next];
+        self.devices.clear();
+        self.throughput.clear();
+        self.alerts = Alerts::new(self.config.alerts.clone());
+        self.devices_state.select(Some(0));
+        self.rescan_devices();
+        if let Some(i) = self.host {
+            self.hosts[i].poll_now();
+        }
+        let name = self.remote().map_or_else(hostname, |r| r.host.clone());
+        self.set_toast(ToastKind::Info, format!("Showing {name}"));
     }
 
-    pub fn select_prev(&mut self) {
-        if self.devices.is_empty() {
-            self.devices_state.select(None);
+    /// Rescans every interface from sysfs.
+    pub fn refresh(&mut self) {
+        if let Some(i) = self.host {
+            self.hosts[i].poll_now();
             return;
         }
+        self.last_full_refresh = Instant::now();
+        self.hardware.clear();
+        self.backends.clear();
+        self.routes.clear();
+        self.rescan_devices();
+    }
 
-        let i = match self.devices_state.selected() {
-            Some(i) => i.saturating_sub(1),
-            None => 0,
+    /// Re-lists interfaces without dropping anything cached about them.
+    fn rescan_devices(&mut self) {
+        let listed = match self.remote() {
+            Some(remote) => Ok(remote.devices.clone()),
+            None => list_ethernet_devices(self.config.include_wifi),
         };
-        self.devices_state.select(Some(i));
+        match listed {
+            Ok(mut devices) => {
+                let selected = self.selected_device().map(|d| d.ifindex);
+                devices.retain(|d| self.shows(&d.name));
+                if self.host.is_none() {
+                    self.link_details.apply(&mut devices);
+                }
+                // Interfaces coming and going here are usually just the filter changing; real
+                // arrivals and removals are logged from netlink events.
+                let mut links = Vec::new();
+                for d in &devices {
+                    if let Some(old) = self.devices.iter().find(|o| o.ifindex == d.ifindex) {
+                        links.extend(self.events.compare(old, d));
+                    }
+                }
+                for link in links {
+                    self.announce_link(&link);
+                }
+                self.devices = devices;
+                self.sort_devices(selected);
+                self.last_error = None;
+            }
+            Err(e) => {
+                self.last_error = Some(e.to_string());
+            }
+        }
     }
 
-    pub fn selected_device(&self) -> Option<&EthernetDevice> {
-        self.devices_state
-            .selected()
-            .and_then(|i| self.devices.get(i))
+    /// A pattern with `*` or `?` is a glob over the whole name; anything else matches as a
+    /// substring.
+    fn shows(&self, name: &str) -> bool {
+        self.filter
+            .as_deref()
+            .is_none_or(|pattern| filter_match(pattern, name))
     }
 
-    fn selected_iface(&self) -> Result<String> {
-        self.selected_device()
-            .map(|d| d.name.clone())
-            .ok_or_else(|| std::io::Error::other("no interface selected").into())
+    /// The driver counters matching the Driver tab's `/` filter.
+    pub fn shown_driver_stats(&self) -> Vec<&DriverStat> {
+        let Ok(stats) = &self.driver_stats else {
+            return Vec::new();
+        };
+        stats
+            .iter()
+            .filter(|s| {
+                self.stats_filter
+                    .as_deref()
+                    .is_none_or(|pattern| filter_match(pattern, &s.name))
+            })
+            .collect()
     }
 
-    async fn run_privileged_capture(&mut self, program: &str, args: &[&str]) -> Result<CmdOutput> {
-        let mk = |used_sudo: bool, status: i32, stdout: Vec<u8>, stderr: Vec<u8>| CmdOutput {
-            program: program.to_string(),
-            args: args.iter().map(|s| s.to_string()).collect(),
-            used_sudo,
-            status,
-            stdout: String::from_utf8_lossy(&stdout).trim().to_string(),
-            stderr: String::from_utf8_lossy(&stderr).trim().to_string(),
-        };
-
-        // Try without sudo first (works if running as root or with capabilities/polkit).
-        if let Ok(out) = Command::new(program).args(args).output().await {
-            let code = out.status.code().unwrap_or(1);
-            if out.status.success() {
-                return Ok(mk(false, code, out.stdout, out.stderr));
-            }
-
-            let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
-            if stderr.contains("Operation not permitted")
-                || stderr.contains("Permission denied")
-                || out.status.code() == Some(1)
-            {
-                let sudo_out = Command::new("sudo")
-                    .arg("-n")
-                    .arg(program)
-                    .args(args)
-                    .output()
-                    .await?;
-                let code = sudo_out.status.code().unwrap_or(1);
-                if sudo_out.status.success() {
-                    return Ok(mk(true, code, sudo_out.stdout, sudo_out.stderr));
-                }
-                let sudo_err = String::from_utf8_lossy(&sudo_out.stderr).trim().to_string();
-                return Err(std::io::Error::other(if sudo_err.is_empty() {
-                    format!("{} failed (sudo)", program)
-                } else {
-                    sudo_err
-                })
-                .into());
-            }
+    /// Lists only interfaces matching `pattern`, or all of them again with None.
+    pub fn set_filter(&mut self, pattern: Option<String>) {
+        self.filter = pattern.filter(|p| !p.is_empty());
+        self.rescan_devices();
+    }
 
-            return Err(std::io::Error::other(if stderr.is_empty() {
-                format!("{} failed", program)
-            } else {
-                stderr
-            })
+    /// On the focused Driver tab `/` filters its counters; everywhere else the interface list.
+    pub fn open_search(&mut self) {
+        let counters = self.focus == Focus::Details && self.tab == DetailTab::Driver;
+        let current = if counters {
+            self.stats_filter.clone()
+        } else {
+            self.filter.clone()
+        };
+        self.search = Some(Search {
+            input: current.clone().unwrap_or_default(),
+            counters,
+            previous: current,
+        });
+    }
+
+    /// Re-filters after the `/` input changed.
+    pub fn update_search(&mut self) {
+        let Some(search) = &self.search else {
+            return;
+        };
+        let (input, counters) = (search.input.clone(), search.counters);
+        self.apply_search(counters, Some(input));
+    }
+
+    /// Closes the `/` prompt, keeping the filter or, when `keep` is false, restoring the old one.
+    pub fn close_search(&mut self, keep: bool) {
+        if let Some(search) = self.search.take()
+            && !keep
+        {
+            self.apply_search(search.counters, search.previous);
+        }
+    }
+
+    fn apply_search(&mut self, counters: bool, pattern: Option<String>) {
+        if counters {
+            self.stats_filter = pattern.filter(|p| !p.is_empty());
+            self.driver_stats_state.select(None);
+        } else {
+            self.set_filter(pattern);
+        }
+    }
+
+    /// Sorts by `key`, or flips the direction when it's already the sort column.
+    pub fn sort_by(&mut self, key: SortKey) {
+        if self.sort == key {
+            self.sort_descending = !self.sort_descending;
+        } else {
+            self.sort = key;
+            self.sort_descending = false;
+        }
+        let selected = self.selected_device().map(|d| d.ifindex);
+        self.sort_devices(selected);
+    }
+
+    pub fn cycle_sort(&mut self) {
+        self.sort_by(self.sort.next());
+    }
+
+    /// Re-applies the sort order, keeping the interface with ifindex `selected` selected.
+    fn sort_devices(&mut self, selected: Option<u32>) {
+        let (key, descending) = (self.sort, self.sort_descending);
+        self.devices.sort_by(|a, b| key.compare(a, b, descending));
+        if let Some(idx) = selected
+            && let Some(i) = self.devices.iter().position(|d| d.ifindex == idx)
+        {
+            self.devices_state.select(Some(i));
+        }
+        self.fix_selection();
+    }
+
+    /// Applies a single rtnetlink notification, re-reading only what it touches.
+    pub fn apply_net_event(&mut self, ev: NetEvent) {
+        match ev {
+            NetEvent::Link(index) | NetEvent::Addr(index) => {
+                let selected = self.selected_device().map(|d| d.ifindex);
+                let fresh = iface_name_for_index(index)
+                    .filter(|n| self.shows(n))
+                    .and_then(|n| read_ethernet_device(&n, self.config.include_wifi))
+                    .map(|mut d| {
+                        // Shown with what's cached until the new fetch comes back.
+                        self.link_details.refresh(&d);
+                        self.link_details.apply(std::slice::from_mut(&mut d));
+                        d
+                    });
+                let pos = self.devices.iter().position(|d| d.ifindex == index);
+
+                match (pos, fresh) {
+                    (Some(i), Some(d)) => {
+                        if let Some(link) = self.events.compare(&self.devices[i], &d) {
+                            self.announce_link(&link);
+                        }
+                        self.devices[i] = d;
+                    }
+                    (Some(i), None) => {
+                        let gone = self.devices.remove(i);
+                        self.events
+                            .push(&gone.name, EventKind::Down, "interface removed");
+                    }
+                    (None, Some(d)) => {
+                        self.events
+                            .push(&d.name, EventKind::Up, "interface appeared");
+                        self.devices.push(d);
+                    }
+                    (None, None) => return,
+                }
+
+                self.sort_devices(selected);
+            }
+            NetEvent::Route => {
+                for d in &mut self.devices {
+                    let gateway = default_gateway_v4(&d.name);
+                    self.events.compare_gateway(
+                        &d.name,
+                        d.gateway_v4.as_deref(),
+                        gateway.as_deref(),
+                    );
+                    d.gateway_v4 = gateway;
+                }
+                self.routes.clear();
+            }
+        }
+    }
+
+    fn sample_throughput(&mut self) {
+        let now = Instant::now();
+        self.throughput
+            .retain(|idx, _| self.devices.iter().any(|d| d.ifindex == *idx));
+        for d in &self.devices {
+            self.throughput
+                .entry(d.ifindex)
+                .or_default()
+                .sample(now, d.stats);
+        }
+    }
+
+    /// Fetches ethtool data for the selected interface once; `refresh` drops the cache.
+    pub async fn load_selected_hardware(&mut self) {
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            return;
+        };
+        if self.hardware.contains_key(&index) {
+            return;
+        }
+        let hw = ethtool::load_hardware(&name).await;
+        self.hardware.insert(index, hw);
+    }
+
+    /// Route lists are fetched only while the Routes tab is shown and dropped on route events.
+    pub async fn load_selected_routes(&mut self) {
+        if self.tab != DetailTab::Routes {
+            return;
+        }
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            return;
+        };
+        if self.routes.contains_key(&index) {
+            return;
+        }
+        let addrs: Vec<String> = self
+            .selected_device()
+            .map(|d| d.ipv4.iter().chain(&d.ipv6).cloned().collect())
+            .unwrap_or_default();
+        let policy = policy::policy_for(&name, &addrs).await;
+        self.policy.insert(index, policy.map_err(|e| e.to_string()));
+        match list_routes(&name).await {
+            Ok(routes) => {
+                if self
+                    .routes_state
+                    .selected()
+                    .is_some_and(|i| i >= routes.len())
+                {
+                    self.routes_state.select(routes.len().checked_sub(1));
+                }
+                self.routes.insert(index, routes);
+            }
+            Err(e) => self.last_error = Some(e.to_string()),
+        }
+    }
+
+    /// Neighbor entries age quickly, so while the tab is shown they're re-read every second.
+    pub async fn load_neighbors(&mut self) {
+        if self.tab != DetailTab::Neighbors {
+            return;
+        }
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            self.neighbors.clear();
+            return;
+        };
+        let fresh = self
+            .neighbors_fetched
+            .is_some_and(|(i, at)| i == index && at.elapsed() < Duration::from_secs(1));
+        if fresh {
+            return;
+        }
+        if self.oui.is_none() {
+            self.oui = Some(load_oui_db());
+        }
+        self.neighbors = list_neighbors(&name).await;
+        self.neighbors_fetched = Some((index, Instant::now()));
+    }
+
+    /// Like neighbors, sockets come and go quickly; re-read every 2s while the tab is shown.
+    pub fn load_connections(&mut self) {
+        if self.tab != DetailTab::Connections {
+            return;
+        }
+        let Some(d) = self.selected_device() else {
+            self.connections.clear();
+            return;
+        };
+        let fresh = self
+            .connections_fetched
+            .is_some_and(|(i, at)| i == d.ifindex && at.elapsed() < Duration::from_secs(2));
+        if fresh {
+            return;
+        }
+        let index = d.ifindex;
+        let addrs: Vec<IpAddr> = d
+            .ipv4
+            .iter()
+            .chain(&d.ipv6)
+            .filter_map(|cidr| cidr.split('/').next()?.parse().ok())
+            .collect();
+        self.connections = list_sockets(&addrs);
+        self.connections_fetched = Some((index, Instant::now()));
+        let len = self.connections.len();
+        if self.connections_state.selected().is_some_and(|i| i >= len) {
+            self.connections_state.select(len.checked_sub(1));
+        }
+    }
+
+    /// Qdisc counters are re-read every second while the QoS tab is shown.
+    pub async fn load_qdiscs(&mut self) {
+        if self.tab != DetailTab::Qos {
+            return;
+        }
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            self.qdiscs = Ok(Vec::new());
+            return;
+        };
+        let fresh = self
+            .qdiscs_fetched
+            .is_some_and(|(i, at)| i == index && at.elapsed() < Duration::from_secs(1));
+        if fresh {
+            return;
+        }
+        self.qdiscs = list_qdiscs(&name).await.map_err(|e| e.to_string());
+        self.qdiscs_fetched = Some((index, Instant::now()));
+    }
+
+    /// Asks ethtool what a NIC supports the first time its link looks degraded; see
+    /// [`App::degraded_link`].
+    async fn load_max_link_speeds(&mut self) {
+        let suspect: Vec<(u32, String)> = self
+            .devices
+            .iter()
+            .filter(|d| d.carrier == Some(true) && !self.max_link_speeds.contains_key(&d.ifindex))
+            .filter(|d| {
+                d.speed_mbps.is_some_and(|s| s <= 100) || d.duplex.as_deref() == Some("half")
+            })
+            .map(|d| (d.ifindex, d.name.clone()))
+            .collect();
+        for (index, name) in suspect {
+            let max = ethtool::link_settings(&name)
+                .await
+                .ok()
+                .and_then(|ls| ls.max_supported_mbps());
+            self.max_link_speeds.insert(index, max);
+        }
+    }
+
+    /// Why a gigabit-capable NIC's link at 100 Mb/s or less, or at half duplex, is suspect;
+    /// None when it looks as fast as it should.
+    pub fn degraded_link(&self, d: &EthernetDevice) -> Option<String> {
+        if self.remote().is_some() {
+            return None;
+        }
+        let max = (*self.max_link_speeds.get(&d.ifindex)?)?;
+        let speed = d.speed_mbps?;
+        let half = d.duplex.as_deref() == Some("half");
+        if d.carrier != Some(true) || max < 1000 || (speed > 100 && !half) {
+            return None;
+        }
+        Some(if half {
+            format!(
+                "{speed} Mb/s half duplex on a {max} Mb/s NIC: usually one end has autonegotiation off (a duplex mismatch), or a bad cable"
+            )
+        } else {
+            format!(
+                "{speed} Mb/s on a {max} Mb/s NIC: usually a bad cable (gigabit over copper needs all four pairs) or a 100M switch port"
+            )
+        })
+    }
+
+    /// Re-read every second while the Queues tab is shown, so rates can be worked out.
+    pub async fn load_queues(&mut self) {
+        if self.tab != DetailTab::Queues {
+            return;
+        }
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            self.queues = Ok(None);
+            return;
+        };
+        let previous = self.queues_fetched.filter(|(i, _)| *i == index);
+        if previous.is_some_and(|(_, at)| at.elapsed() < Duration::from_secs(1)) {
+            return;
+        }
+        let mut queues = read_queues(&name).await.map_err(|e| e.to_string());
+        if let (Ok(queues), Ok(Some(prev)), Some((_, at))) = (&mut queues, &self.queues, previous) {
+            queues.rates_since(prev, at.elapsed());
+        }
+        self.queues = queues.map(Some);
+        self.queues_fetched = Some((index, Instant::now()));
+    }
+
+    /// Re-read every second while the Driver tab is shown, so counters that are still climbing
+    /// stand out from ones that went up once long ago.
+    pub async fn load_driver_stats(&mut self) {
+        if self.tab != DetailTab::Driver {
+            return;
+        }
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            self.driver_stats = Ok(Vec::new());
+            return;
+        };
+        let previous = self.driver_stats_fetched.filter(|(i, _)| *i == index);
+        if previous.is_some_and(|(_, at)| at.elapsed() < Duration::from_secs(1)) {
+            return;
+        }
+        let mut stats = ethtool::driver_stats(&name)
+            .await
+            .map_err(|e| e.to_string());
+        if let (Ok(stats), Ok(prev), Some(_)) = (&mut stats, &self.driver_stats, previous) {
+            ethtool::stats_deltas(stats, prev);
+        }
+        self.driver_stats = stats;
+        self.driver_stats_fetched = Some((index, Instant::now()));
+    }
+
+    /// VF settings only change through us or a hypervisor, so every 5s is plenty.
+    pub async fn load_sriov(&mut self) {
+        if self.tab != DetailTab::Sriov {
+            return;
+        }
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            self.sriov = Ok(None);
+            return;
+        };
+        let fresh = self
+            .sriov_fetched
+            .is_some_and(|(i, at)| i == index && at.elapsed() < Duration::from_secs(5));
+        if fresh {
+            return;
+        }
+        self.sriov = sriov::read_sriov(&name).await.map_err(|e| e.to_string());
+        self.sriov_fetched = Some((index, Instant::now()));
+    }
+
+    /// Unit files change rarely, but a reload can switch them, so they're re-read every 5s while
+    /// the Config tab is shown.
+    pub async fn load_units(&mut self) {
+        if self.tab != DetailTab::Config {
+            return;
+        }
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            self.units = None;
+            return;
+        };
+        let fresh = self
+            .units_fetched
+            .is_some_and(|(i, at)| i == index && at.elapsed() < Duration::from_secs(5));
+        if fresh {
+            return;
+        }
+        self.units = Some(networkd::units(&name, index).await);
+        let mac = self.selected_device().and_then(|d| d.mac.clone());
+        self.netplan = netplan::netplan_for(&name, mac.as_deref());
+        self.ifupdown = ifupdown::interfaces_for(&name);
+        self.units_fetched = Some((index, Instant::now()));
+    }
+
+    /// The ruleset is read through sudo, so it's refreshed only every 5s while the tab is shown.
+    pub async fn load_firewall(&mut self) {
+        if self.tab != DetailTab::Firewall {
+            return;
+        }
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            self.firewall = Ok(("", Vec::new()));
+            return;
+        };
+        let fresh = self
+            .firewall_fetched
+            .is_some_and(|(i, at)| i == index && at.elapsed() < Duration::from_secs(5));
+        if fresh {
+            return;
+        }
+        self.firewall = firewall::rules_for(&name).await.map_err(|e| e.to_string());
+        self.firewall_fetched = Some((index, Instant::now()));
+        let len = self.firewall.as_ref().map_or(0, |(_, rules)| rules.len());
+        if self.firewall_state.selected().is_some_and(|i| i >= len) {
+            self.firewall_state.select(len.checked_sub(1));
+        }
+    }
+
+    /// Switches advertise every 30s by default, so there's no point asking lldpd more often.
+    pub async fn load_selected_lldp(&mut self) {
+        if self.tab != DetailTab::Overview {
+            return;
+        }
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            return;
+        };
+        let fresh = self
+            .lldp
+            .get(&index)
+            .is_some_and(|(at, _)| at.elapsed() < LLDP_INTERVAL);
+        if fresh {
+            return;
+        }
+        let neighbors = lldp::query(&name).await;
+        self.lldp.insert(index, (Instant::now(), neighbors));
+    }
+
+    /// Handshakes and transfer counters move constantly, so while the Overview of a WireGuard
+    /// interface is shown it's re-read every few seconds.
+    pub async fn load_selected_wireguard(&mut self) {
+        if self.tab != DetailTab::Overview {
+            return;
+        }
+        let Some((index, name)) = self
+            .selected_device()
+            .filter(|d| d.wireguard)
+            .map(|d| (d.ifindex, d.name.clone()))
+        else {
+            return;
+        };
+        let fresh = self
+            .wireguard
+            .get(&index)
+            .is_some_and(|(at, _)| at.elapsed() < WIREGUARD_INTERVAL);
+        if fresh {
+            return;
+        }
+        let wg = wireguard::show(&name).await.map_err(|e| e.to_string());
+        self.wireguard.insert(index, (Instant::now(), wg));
+    }
+
+    /// The setup state and DHCP client move independently of the link, so while the Overview of
+    /// a networkd-managed interface is shown networkd is asked every few seconds.
+    pub async fn load_networkd_status(&mut self) {
+        if self.tab != DetailTab::Overview {
+            return;
+        }
+        let networkd = self
+            .selected_backend_info()
+            .is_some_and(|info| info.backend.name() == backend::Networkd.name());
+        let Some((index, name)) = self
+            .selected_device()
+            .filter(|_| networkd)
+            .map(|d| (d.ifindex, d.name.clone()))
+        else {
+            return;
+        };
+        let fresh = self
+            .networkd_status
+            .get(&index)
+            .is_some_and(|(at, _)| at.elapsed() < NETWORKD_STATUS_INTERVAL);
+        if fresh {
+            return;
+        }
+        let status = networkd::link_status(&name)
+            .await
+            .map_err(|e| e.to_string());
+        self.networkd_status.insert(index, (Instant::now(), status));
+    }
+
+    /// Programs come and go without any netlink event the device scan sees, so while the
+    /// Overview is shown they're re-listed every few seconds.
+    pub async fn load_selected_bpf(&mut self) {
+        if self.tab != DetailTab::Overview {
+            return;
+        }
+        let Some((index, name)) = self.selected_device().map(|d| (d.ifindex, d.name.clone()))
+        else {
+            return;
+        };
+        let fresh = self
+            .bpf
+            .get(&index)
+            .is_some_and(|(at, _)| at.elapsed() < BPF_INTERVAL);
+        if fresh {
+            return;
+        }
+        let programs = bpf::attached(&name).await.map_err(|e| e.to_string());
+        self.bpf.insert(index, (Instant::now(), programs));
+    }
+
+    /// Probes every interface with carrier and an IPv4 address in the background, once a
+    /// minute, and collects results from earlier probes.
+    pub async fn check_connectivity(&mut self) {
+        let (done, pending): (Vec<_>, Vec<_>) = self
+            .connectivity_checks
+            .drain(..)
+            .partition(|(_, h)| h.is_finished());
+        self.connectivity_checks = pending;
+        for (index, handle) in done {
+            if let Ok(status) = handle.await {
+                self.connectivity.insert(index, status);
+            }
+        }
+
+        let due = self
+            .connectivity_checked
+            .is_none_or(|at| at.elapsed() >= CONNECTIVITY_INTERVAL);
+        if !due || !self.connectivity_checks.is_empty() {
+            return;
+        }
+        self.connectivity_checked = Some(Instant::now());
+        self.connectivity
+            .retain(|idx, _| self.devices.iter().any(|d| d.ifindex == *idx));
+
+        let url = diag::probe_url(self.config.probe_url.as_deref());
+        for d in &self.devices {
+            if d.carrier != Some(true) || d.ipv4.is_empty() {
+                self.connectivity.remove(&d.ifindex);
+                continue;
+            }
+            let (iface, url) = (d.name.clone(), url.clone());
+            let handle = tokio::spawn(async move { diag::probe_connectivity(&iface, &url).await });
+            self.connectivity_checks.push((d.ifindex, handle));
+        }
+    }
+
+    /// ARPs the IPv4 gateway of every interface with carrier in the background, every 30s,
+    /// and logs it going quiet or answering again.
+    pub async fn check_gateways(&mut self) {
+        let (done, pending): (Vec<_>, Vec<_>) = self
+            .gateway_checks
+            .drain(..)
+            .partition(|(_, _, h)| h.is_finished());
+        self.gateway_checks = pending;
+        for (index, gateway, handle) in done {
+            let Ok(reach) = handle.await else {
+                continue;
+            };
+            let before = self.gateway_reach.insert(index, (gateway.clone(), reach));
+            let was_reachable =
+                before
+                    .filter(|(gw, _)| *gw == gateway)
+                    .and_then(|(_, r)| match r {
+                        GatewayReach::Reachable(_) => Some(true),
+                        GatewayReach::Unreachable => Some(false),
+                        GatewayReach::Unknown => None,
+                    });
+            let Some(name) = self
+                .devices
+                .iter()
+                .find(|d| d.ifindex == index)
+                .map(|d| d.name.clone())
+            else {
+                continue;
+            };
+            match (was_reachable, reach) {
+                (Some(true), GatewayReach::Unreachable) => self.events.push(
+                    &name,
+                    EventKind::Down,
+                    format!("gateway {gateway} doesn't answer ARP"),
+                ),
+                (Some(false), GatewayReach::Reachable(_)) => self.events.push(
+                    &name,
+                    EventKind::Up,
+                    format!("gateway {gateway} answers ARP again"),
+                ),
+                _ => {}
+            }
+        }
+
+        let due = self
+            .gateway_checked
+            .is_none_or(|at| at.elapsed() >= GATEWAY_INTERVAL);
+        if !due || !self.gateway_checks.is_empty() {
+            return;
+        }
+        self.gateway_checked = Some(Instant::now());
+        for d in &self.devices {
+            let Some(gateway) = d.gateway_v4.clone().filter(|_| d.carrier == Some(true)) else {
+                self.gateway_reach.remove(&d.ifindex);
+                continue;
+            };
+            let iface = d.name.clone();
+            let target = gateway.clone();
+            let handle = tokio::spawn(async move { diag::probe_gateway(&iface, &target).await });
+            self.gateway_checks.push((d.ifindex, gateway, handle));
+        }
+        self.gateway_reach
+            .retain(|idx, _| self.devices.iter().any(|d| d.ifindex == *idx));
+    }
+
+    /// Whether the selected interface's current gateway answered the last ARP check.
+    pub fn selected_gateway_reach(&self) -> Option<GatewayReach> {
+        let d = self.selected_device()?;
+        let (gateway, reach) = self.gateway_reach.get(&d.ifindex)?;
+        (d.gateway_v4.as_ref() == Some(gateway)).then_some(*reach)
+    }
+
+    pub fn selected_routes(&self) -> Option<&Vec<Route>> {
+        self.selected_device()
+            .and_then(|d| self.routes.get(&d.ifindex))
+    }
+
+    pub fn selected_policy(&self) -> Option<&Result<PolicyRouting, String>> {
+        self.selected_device()
+            .and_then(|d| self.policy.get(&d.ifindex))
+    }
+
+    /// Detects (once per interface until the next full refresh) which backend manages it.
+    pub async fn selected_backend(&mut self) -> Result<&'static dyn NetworkBackend> {
+        let (index, name) = self
+            .selected_device()
+            .map(|d| (d.ifindex, d.name.clone()))
+            .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+        Ok(self.backend_of(index, &name).await)
+    }
+
+    async fn backend_of(&mut self, index: u32, name: &str) -> &'static dyn NetworkBackend {
+        if let Some(info) = self.backends.get(&index) {
+            return info.backend;
+        }
+
+        let backend = backend::detect(name).await;
+        let config_source = backend.config_source(name).await.ok().flatten();
+        self.backends.insert(
+            index,
+            BackendInfo {
+                backend,
+                config_source,
+            },
+        );
+        backend
+    }
+
+    /// The listed interface the default route goes out of, if it's listed.
+    pub fn default_device(&self) -> Option<&EthernetDevice> {
+        let name = self.default_iface.as_deref()?;
+        self.devices.iter().find(|d| d.name == name)
+    }
+
+    pub fn selected_backend_info(&self) -> Option<&BackendInfo> {
+        self.selected_device()
+            .and_then(|d| self.backends.get(&d.ifindex))
+    }
+
+    pub fn selected_hardware(&self) -> Option<&Hardware> {
+        self.selected_device()
+            .and_then(|d| self.hardware.get(&d.ifindex))
+    }
+
+    /// What lldpd heard on the selected interface; empty when nothing or lldpd isn't available.
+    pub fn selected_lldp(&self) -> &[LldpNeighbor] {
+        self.selected_device()
+            .and_then(|d| self.lldp.get(&d.ifindex))
+            .and_then(|(_, n)| n.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Only for interfaces networkd manages.
+    pub fn selected_networkd_status(&self) -> Option<&Result<LinkStatus, String>> {
+        let networkd = self
+            .selected_backend_info()
+            .is_some_and(|info| info.backend.name() == backend::Networkd.name());
+        self.selected_device()
+            .filter(|_| networkd)
+            .and_then(|d| self.networkd_status.get(&d.ifindex))
+            .map(|(_, status)| status)
+    }
+
+    pub fn selected_wireguard(&self) -> Option<&Result<WgInterface, String>> {
+        self.selected_device()
+            .and_then(|d| self.wireguard.get(&d.ifindex))
+            .map(|(_, wg)| wg)
+    }
+
+    /// Empty until listed, and when listing failed.
+    pub fn selected_bpf(&self) -> &[BpfProgram] {
+        self.selected_device()
+            .and_then(|d| self.bpf.get(&d.ifindex))
+            .and_then(|(_, p)| p.as_deref().ok())
+            .unwrap_or_default()
+    }
+
+    /// When `iface` lost its link and got it back within `flap_window_minutes`.
+    pub fn recent_flaps(&self, iface: &str) -> Vec<SystemTime> {
+        let window = Duration::from_secs(self.config.flap_window_minutes * 60);
+        self.events.recent_flaps(iface, window)
+    }
+
+    pub fn selected_throughput(&self) -> Option<&Throughput> {
+        self.selected_device()
+            .and_then(|d| self.throughput.get(&d.ifindex))
+    }
+
+    fn fix_selection(&mut self) {
+        let selected = self.devices_state.selected();
+        if self.devices.is_empty() {
+            self.devices_state.select(None);
+        } else if let Some(i) = selected {
+            self.devices_state
+                .select(Some(i.min(self.devices.len() - 1)));
+        } else {
+            self.devices_state.select(Some(0));
+        }
+    }
+
+    pub fn spawn_job<F>(&mut self, label: impl Into<String>, fut: F)
+    where
+        F: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.jobs.push(Job {
+            label: label.into(),
+            popup: false,
+            handle: tokio::spawn(fut),
+        });
+    }
+
+    pub fn spawn_popup_job<F>(&mut self, label: impl Into<String>, fut: F)
+    where
+        F: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.jobs.push(Job {
+            label: label.into(),
+            popup: true,
+            handle: tokio::spawn(fut),
+        });
+    }
+
+    /// Takes in what plugins printed: annotations are kept, log lines go to the event log and
+    /// command lines wait for the handler.
+    fn poll_plugins(&mut self) {
+        let Some(rx) = &mut self.plugin_replies else {
+            return;
+        };
+        while let Ok(reply) = rx.try_recv() {
+            let iface = reply.interface.unwrap_or_default();
+            if let Some(text) = reply.annotation {
+                let notes = self.annotations.entry(iface.clone()).or_default();
+                match notes.iter().position(|(plugin, _)| *plugin == reply.plugin) {
+                    Some(i) if text.is_empty() => {
+                        notes.remove(i);
+                    }
+                    Some(i) => notes[i].1 = text,
+                    None if text.is_empty() => {}
+                    None => notes.push((reply.plugin.clone(), text)),
+                }
+            }
+            if let Some(log) = reply.log {
+                self.events
+                    .push(&iface, EventKind::Info, format!("{}: {log}", reply.plugin));
+            }
+            if let Some(line) = reply.command {
+                self.plugin_commands.push((reply.plugin, line));
+            }
+        }
+    }
+
+    async fn poll_jobs(&mut self) {
+        let (done, pending): (Vec<Job>, Vec<Job>) =
+            self.jobs.drain(..).partition(|j| j.handle.is_finished());
+        self.jobs = pending;
+
+        for job in done {
+            match job.handle.await {
+                Ok(Ok(body)) if job.popup => {
+                    self.popup = Some(Popup {
+                        title: format!(" {} ", job.label),
+                        body,
+                    })
+                }
+                Ok(Ok(msg)) => self.set_toast(ToastKind::Success, format!("{}: {msg}", job.label)),
+                Ok(Err(e)) => self.set_toast(ToastKind::Error, format!("{}: {e}", job.label)),
+                Err(e) => self.set_toast(ToastKind::Error, format!("{}: {e}", job.label)),
+            }
+        }
+    }
+
+    pub fn quit(&mut self) {
+        self.running = false;
+    }
+
+    /// A toast for the carrier of a listed interface going down, coming back or flapping.
+    fn announce_link(&mut self, link: &LinkEvent) {
+        let kind = match link.kind {
+            EventKind::Up => ToastKind::Success,
+            _ => ToastKind::Error,
+        };
+        self.set_toast(kind, link.describe());
+    }
+
+    pub fn set_toast(&mut self, kind: ToastKind, msg: impl Into<String>) {
+        self.toast = Some(Toast {
+            kind,
+            msg: msg.into(),
+            until: Instant::now() + Duration::from_millis(2500),
+        });
+    }
+
+    pub fn clear_error(&mut self) {
+        self.last_error = None;
+    }
+
+    /// A desktop notification, if `event` is one the config asks for.
+    pub fn notify(&self, event: NotifyEvent, body: impl Into<String>) {
+        self.notifier.send(event, body);
+    }
+
+    pub fn select_next(&mut self) {
+        self.details_scroll = 0;
+        self.promisc_armed = None;
+        if self.devices.is_empty() {
+            self.devices_state.select(None);
+            return;
+        }
+
+        let i = match self.devices_state.selected() {
+            Some(i) => (i + 1).min(self.devices.len() - 1),
+            None => 0,
+        };
+        self.devices_state.select(Some(i));
+    }
+
+    pub fn select_prev(&mut self) {
+        self.details_scroll = 0;
+        self.promisc_armed = None;
+        if self.devices.is_empty() {
+            self.devices_state.select(None);
+            return;
+        }
+
+        let i = match self.devices_state.selected() {
+            Some(i) => i.saturating_sub(1),
+            None => 0,
+        };
+        self.devices_state.select(Some(i));
+    }
+
+    /// Copies a value of the selected interface picked by `target` (the key pressed after `y`)
+    /// to the clipboard.
+    pub async fn yank(&mut self, target: char) -> Result<String> {
+        let d = self
+            .selected_device()
+            .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+        let missing = |what: &str| std::io::Error::other(format!("{}: no {what}", d.name));
+        let (what, text) = match target {
+            'i' => ("IP", self.selected_ipv4()?),
+            'm' => ("MAC", d.mac.clone().ok_or_else(|| missing("MAC address"))?),
+            'g' => (
+                "gateway",
+                d.gateway_v4.clone().ok_or_else(|| missing("gateway"))?,
+            ),
+            'd' if d.dns.is_empty() => return Err(missing("DNS servers").into()),
+            'd' => ("DNS", d.dns.join(" ")),
+            's' => ("snapshot", snapshot_text(d)),
+            _ => {
+                return Err(
+                    std::io::Error::other(format!("nothing to yank with `{target}`")).into(),
+                );
+            }
+        };
+        let shown = if target == 's' {
+            d.name.clone()
+        } else {
+            text.clone()
+        };
+        let via = crate::clipboard::copy(&text).await?;
+        Ok(format!("Copied {what} ({shown}) via {via}"))
+    }
+
+    /// Shows `text` as a QR code, or by default an `http://` URL of the selected interface's
+    /// IPv4 address.
+    pub fn show_qr(&mut self, text: Option<String>) -> Result<()> {
+        let text = match text {
+            Some(text) => text,
+            None => format!("http://{}", self.selected_ipv4()?),
+        };
+        let code = QrCode::encode(text.as_bytes()).ok_or_else(|| {
+            std::io::Error::other(format!(
+                "too long for a QR code here ({} bytes, at most 106)",
+                text.len()
+            ))
+        })?;
+        self.qr = Some(QrPopup { text, code });
+        Ok(())
+    }
+
+    /// The first IPv4 address of the selected interface, without the prefix length.
+    pub fn selected_ipv4(&self) -> Result<String> {
+        let d = self
+            .selected_device()
+            .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+        let ip = d
+            .ipv4
+            .first()
+            .ok_or_else(|| std::io::Error::other(format!("{}: no IPv4 address", d.name)))?;
+        Ok(ip.split('/').next().unwrap_or(ip).to_string())
+    }
+
+    pub fn selected_device(&self) -> Option<&EthernetDevice> {
+        self.devices_state
+            .selected()
+            .and_then(|i| self.devices.get(i))
+    }
+
+    pub fn select_iface(&mut self, name: &str) -> Result<()> {
+        let i = self
+            .devices
+            .iter()
+            .position(|d| d.name == name)
+            .ok_or_else(|| std::io::Error::other(format!("{name}: no such interface listed")))?;
+        self.devices_state.select(Some(i));
+        self.details_scroll = 0;
+        Ok(())
+    }
+
+    fn selected_iface(&self) -> Result<String> {
+        self.selected_device()
+            .map(|d| d.name.clone())
+            .ok_or_else(|| std::io::Error::other("no interface selected").into())
+    }
+
+    async fn run_privileged_capture(&mut self, program: &str, args: &[&str]) -> Result<CmdOutput> {
+        run_privileged_capture(program, args).await
+    }
+
+    pub async fn renew_dhcp(&mut self) -> Result<CmdOutput> {
+        let iface = self.selected_iface()?;
+        let backend = self.selected_backend().await?;
+        let cap = match backend.renew(&iface).await {
+            Ok(cap) => cap,
+            Err(e) => {
+                let msg = format!("DHCP renew failed: {e}");
+                self.events.push(&iface, EventKind::Down, msg);
+                return Err(e);
+            }
+        };
+        let msg = format!("DHCP renew requested ({})", backend.name());
+        self.events.push(&iface, EventKind::Info, msg);
+        self.last_action = Some(format!("Renewed DHCP on {iface} ({})", backend.name()));
+        Ok(cap)
+    }
+
+    pub async fn confirm_release_dhcp(&mut self) {
+        // Fails only with nothing selected.
+        let Ok(backend) = self.selected_backend().await else {
+            return;
+        };
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let address = d
+            .ipv4
+            .first()
+            .cloned()
+            .unwrap_or_else(|| "its address".into());
+        let warning = backend.release_warning(&d.name, &address);
+        self.ask(
+            ConfirmAction::ReleaseDhcp,
+            format!("Release the DHCP lease on {}?\n\n{warning}", d.name),
+        );
+    }
+
+    /// Asks before running `action`. With `confirm = false` in the config the handler runs it
+    /// right away instead of showing `prompt`.
+    pub fn ask(&mut self, action: ConfirmAction, prompt: String) {
+        self.confirm = Some(Confirm { action, prompt });
+    }
+
+    /// Runs the action the user just confirmed.
+    pub async fn run_confirmed(&mut self, action: ConfirmAction) -> Result<String> {
+        match action {
+            ConfirmAction::ReleaseDhcp => self.release_dhcp().await,
+            ConfirmAction::DeleteVlan => self.delete_vlan().await,
+            ConfirmAction::LeaveBridge => self.leave_bridge().await,
+            ConfirmAction::BondFailover => self.bond_failover().await,
+            ConfirmAction::LinkDown => {
+                let iface = self.selected_iface()?;
+                let out = self.set_link_down().await?;
+                let sudo = if out.used_sudo { " (sudo)" } else { "" };
+                Ok(format!("{iface}: link set down{sudo}"))
+            }
+            ConfirmAction::SetMac(mac) => self.set_mac_address(&mac).await,
+            ConfirmAction::SetMtu(mtu) => self.set_mtu(mtu).await,
+            ConfirmAction::SetEee(on) => self.set_eee(on).await,
+            ConfirmAction::SetFec(encoding) => self.set_fec(&encoding).await,
+            ConfirmAction::SetChannels(counts) => self.apply_channels(&counts).await,
+            ConfirmAction::SpreadIrqs => self.spread_irqs().await,
+            ConfirmAction::SetNumVfs(n) => self.set_num_vfs(n).await,
+            ConfirmAction::SetDns(change) => self.set_dns(&change).await,
+            ConfirmAction::WriteNetworkFile(config) => self.write_network_file(&config).await,
+            ConfirmAction::NetplanApply => self.netplan_apply().await,
+            ConfirmAction::ApplyProfile(name) => self.apply_profile(&name).await,
+            ConfirmAction::DeleteAddress(cidr) => self.delete_address(&cidr).await,
+            ConfirmAction::FlushAddresses(dhcp) => self.flush_addresses(dhcp).await,
+            ConfirmAction::AddRoute(route) => self.change_route("add", &route).await,
+            ConfirmAction::DeleteRoute(route) => self.change_route("del", &route).await,
+            ConfirmAction::Undo => self.undo_last().await,
+        }
+    }
+
+    fn push_undo(&mut self, undo: Undo) {
+        self.undo.push(undo);
+        if self.undo.len() > UNDO_LEN {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn confirm_undo(&mut self) {
+        let Some(undo) = self.undo.last() else {
+            self.set_toast(ToastKind::Info, "Nothing to undo");
+            return;
+        };
+        let prompt = format!("Revert the last change?\n\n{}", undo.describe());
+        self.ask(ConfirmAction::Undo, prompt);
+    }
+
+    /// Reverts the most recent change with the inverse commands; it stays on the stack when
+    /// they fail, so it can be retried.
+    pub async fn undo_last(&mut self) -> Result<String> {
+        let Some(undo) = self.undo.pop() else {
+            return Err(std::io::Error::other("nothing to undo").into());
+        };
+        let res = self.revert(&undo).await;
+        if res.is_err() {
+            self.undo.push(undo.clone());
+        }
+        res?;
+
+        let msg = undo.describe();
+        self.last_action = Some(format!("Undo: {msg}"));
+        self.refresh();
+        Ok(msg)
+    }
+
+    async fn revert(&mut self, undo: &Undo) -> Result<()> {
+        match undo {
+            Undo::Mtu { iface, mtu } => {
+                let mtu = mtu.to_string();
+                self.run_privileged_capture("ip", &["link", "set", "dev", iface, "mtu", &mtu])
+                    .await?;
+            }
+            Undo::Mac { iface, mac } => {
+                self.run_privileged_capture("ip", &["link", "set", "dev", iface, "address", mac])
+                    .await?;
+            }
+            Undo::Ipv4 {
+                iface,
+                addrs,
+                gateway,
+                ..
+            } => {
+                self.run_privileged_capture("ip", &["-4", "addr", "flush", "dev", iface])
+                    .await?;
+                for addr in addrs {
+                    self.run_privileged_capture("ip", &["addr", "add", addr, "dev", iface])
+                        .await?;
+                }
+                match gateway {
+                    Some(Some(gw)) => {
+                        self.run_privileged_capture(
+                            "ip",
+                            &["route", "replace", "default", "via", gw, "dev", iface],
+                        )
+                        .await?;
+                    }
+                    // Flushing the address usually took the added route with it.
+                    Some(None) => {
+                        let _ = self
+                            .run_privileged_capture(
+                                "ip",
+                                &["route", "del", "default", "dev", iface],
+                            )
+                            .await;
+                    }
+                    None => {}
+                }
+            }
+            Undo::Address { iface, cidr } => {
+                self.run_privileged_capture("ip", &["addr", "add", cidr, "dev", iface])
+                    .await?;
+            }
+            Undo::Route {
+                iface,
+                route,
+                deleted,
+            } => {
+                let args = route.ip_args(if *deleted { "add" } else { "del" }, iface);
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                self.run_privileged_capture("ip", &args).await?;
+            }
+            Undo::Profile {
+                iface, previous, ..
+            } => {
+                if let Some(mtu) = previous.mtu {
+                    let mtu = mtu.to_string();
+                    self.run_privileged_capture("ip", &["link", "set", "dev", iface, "mtu", &mtu])
+                        .await?;
+                }
+                if let Some((addrs, gateway)) = &previous.ipv4 {
+                    self.run_privileged_capture("ip", &["-4", "addr", "flush", "dev", iface])
+                        .await?;
+                    for addr in addrs {
+                        self.run_privileged_capture("ip", &["addr", "add", addr, "dev", iface])
+                            .await?;
+                    }
+                    match gateway {
+                        Some(gw) => {
+                            self.run_privileged_capture(
+                                "ip",
+                                &["route", "replace", "default", "via", gw, "dev", iface],
+                            )
+                            .await?;
+                        }
+                        None => {
+                            let _ = self
+                                .run_privileged_capture(
+                                    "ip",
+                                    &["route", "del", "default", "dev", iface],
+                                )
+                                .await;
+                        }
+                    }
+                    // Back on its lease, so it keeps being renewed.
+                    if previous.dhcp {
+                        backend::detect(iface).await.renew(iface).await?;
+                    }
+                }
+                if let Some(dns) = &previous.dns {
+                    let mut args = vec!["dns", iface.as_str()];
+                    if dns.is_empty() {
+                        args.push("");
+                    }
+                    args.extend(dns.iter().map(String::as_str));
+                    self.run_privileged_capture("resolvectl", &args).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn confirm_link_down(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let prompt = format!(
+            "Take {} down?\n\nEverything using it loses connectivity until it's brought back up (u).",
+            d.name
+        );
+        self.ask(ConfirmAction::LinkDown, prompt);
+    }
+
+    pub fn confirm_mtu(&mut self, mtu: u32) -> Result<()> {
+        let iface = self.selected_iface()?;
+        // 68 is the IPv4 minimum; drivers enforce their own maximum.
+        if !(68..=65535).contains(&mtu) {
+            return Err(std::io::Error::other("MTU must be between 68 and 65535").into());
+        }
+        let prompt = format!(
+            "Set the MTU of {iface} to {mtu}?\n\nSome drivers reset the link to apply it, and an MTU the rest of the path doesn't match can break large transfers."
+        );
+        self.ask(ConfirmAction::SetMtu(mtu), prompt);
+        Ok(())
+    }
+
+    pub async fn release_dhcp(&mut self) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let backend = self.selected_backend().await?;
+        let cap = backend.release(&iface).await?;
+        self.events
+            .push(&iface, EventKind::Down, "DHCP lease released");
+
+        let mut msg = format!("{iface}: DHCP lease released ({})", backend.name());
+        if cap.used_sudo {
+            msg.push_str(" (sudo)");
+        }
+        self.last_action = Some(format!("Released DHCP on {iface} ({})", backend.name()));
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    /// VLANs are created on the selected NIC, or on the parent of a selected VLAN.
+    fn vlan_parent(&self) -> Result<String> {
+        let d = self
+            .selected_device()
+            .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+        Ok(d.vlan
+            .as_ref()
+            .map_or_else(|| d.name.clone(), |v| v.parent.clone()))
+    }
+
+    pub fn open_vlan_form(&mut self) {
+        let Ok(parent) = self.vlan_parent() else {
+            return;
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::Vlan,
+            title: format!(" Add VLAN ({parent}) "),
+            fields: vec![
+                FormField {
+                    label: "VLAN ID",
+                    hint: "802.1Q VID, 1-4094".into(),
+                    value: String::new(),
+                },
+                FormField {
+                    label: "Name",
+                    hint: format!("leave empty for {parent}.<id>"),
+                    value: String::new(),
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    /// Adds an 802.1Q subinterface; it shows up in the list through the netlink event.
+    pub async fn add_vlan(&mut self, id: u16, name: &str) -> Result<String> {
+        let parent = self.vlan_parent()?;
+        let name = if name.is_empty() {
+            format!("{parent}.{id}")
+        } else {
+            name.to_string()
+        };
+        if name.len() >= libc::IF_NAMESIZE {
+            return Err(std::io::Error::other(format!(
+                "{name}: interface names are limited to {} characters",
+                libc::IF_NAMESIZE - 1
+            ))
+            .into());
+        }
+        let id = id.to_string();
+        self.run_privileged_capture(
+            "ip",
+            &[
+                "link", "add", "link", &parent, "name", &name, "type", "vlan", "id", &id,
+            ],
+        )
+        .await?;
+
+        let msg = format!("{parent}: added VLAN {id} as {name} (u to bring it up)");
+        self.last_action = Some(msg.clone());
+        Ok(msg)
+    }
+
+    pub fn confirm_delete_vlan(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let Some(vlan) = &d.vlan else {
+            self.set_toast(
+                ToastKind::Info,
+                format!("{} is not a VLAN interface", d.name),
+            );
+            return;
+        };
+        self.ask(
+            ConfirmAction::DeleteVlan,
+            format!(
+                "Delete VLAN interface {} (VID {} on {})?\n\nIts addresses and routes go with it.",
+                d.name, vlan.id, vlan.parent
+            ),
+        );
+    }
+
+    pub async fn delete_vlan(&mut self) -> Result<String> {
+        let iface = self.selected_iface()?;
+        self.run_privileged_capture("ip", &["link", "delete", "dev", &iface])
+            .await?;
+
+        let msg = format!("{iface}: VLAN deleted");
+        self.last_action = Some(msg.clone());
+        Ok(msg)
+    }
+
+    /// `b` joins a bridge, or, for a NIC that's already a bridge port, leaves it.
+    pub fn bridge_action(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        if let Some(port) = &d.bridge {
+            self.ask(
+                ConfirmAction::LeaveBridge,
+                format!(
+                    "Remove {} from bridge {}?\n\nAnything reached through the bridge over this port loses connectivity.",
+                    d.name, port.bridge
+                ),
+            );
+            return;
+        }
+
+        let bridges = list_bridges();
+        let hint = if bridges.is_empty() {
+            "no bridges found (create one first, e.g. ip link add br0 type bridge)".to_string()
+        } else {
+            format!("existing: {}", bridges.join(", "))
+        };
+        self.form = Some(Form {
+            kind: FormKind::Bridge,
+            title: format!(" Add {} to bridge ", d.name),
+            fields: vec![FormField {
+                label: "Bridge",
+                hint,
+                value: bridges.first().cloned().unwrap_or_default(),
+            }],
+            focus: 0,
+        });
+    }
+
+    pub async fn join_bridge(&mut self, bridge: &str) -> Result<String> {
+        let iface = self.selected_iface()?;
+        self.run_privileged_capture("ip", &["link", "set", "dev", &iface, "master", bridge])
+            .await?;
+
+        let msg = format!("{iface}: added to bridge {bridge}");
+        self.last_action = Some(msg.clone());
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    pub async fn leave_bridge(&mut self) -> Result<String> {
+        let iface = self.selected_iface()?;
+        self.run_privileged_capture("ip", &["link", "set", "dev", &iface, "nomaster"])
+            .await?;
+
+        let msg = format!("{iface}: removed from its bridge");
+        self.last_action = Some(msg.clone());
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    /// The slave a manual failover switches to: the selected NIC, or when it's already the
+    /// active one, the first other slave whose link is up.
+    fn failover_target(&self) -> Result<(String, String)> {
+        let d = self
+            .selected_device()
+            .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+        let bond = d
+            .bond
+            .as_ref()
+            .ok_or_else(|| std::io::Error::other(format!("{} is not in a bond", d.name)))?;
+        let Some(active) = &bond.active_slave else {
+            return Err(std::io::Error::other(format!(
+                "{}: mode {} has no active slave to fail over",
+                bond.name, bond.mode
+            ))
             .into());
+        };
+        let target = if *active != d.name {
+            d.name.clone()
+        } else {
+            bond.slaves
+                .iter()
+                .find(|s| s.name != d.name && s.mii_status == "up")
+                .map(|s| s.name.clone())
+                .ok_or_else(|| {
+                    std::io::Error::other(format!("{}: no other slave is up", bond.name))
+                })?
+        };
+        Ok((bond.name.clone(), target))
+    }
+
+    pub fn confirm_bond_failover(&mut self) {
+        match self.failover_target() {
+            Ok((bond, target)) => {
+                self.ask(
+                    ConfirmAction::BondFailover,
+                    format!(
+                        "Make {target} the active slave of {bond}?\n\nTraffic on the bond may pause briefly while it switches over."
+                    ),
+                );
+            }
+            Err(e) => self.last_error = Some(e.to_string()),
+        }
+    }
+
+    pub async fn bond_failover(&mut self) -> Result<String> {
+        let (bond, target) = self.failover_target()?;
+        self.run_privileged_capture(
+            "ip",
+            &[
+                "link",
+                "set",
+                "dev",
+                &bond,
+                "type",
+                "bond",
+                "active_slave",
+                &target,
+            ],
+        )
+        .await?;
+
+        let msg = format!("{bond}: active slave is now {target}");
+        self.last_action = Some(msg.clone());
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    pub async fn set_link_up(&mut self) -> Result<CmdOutput> {
+        self.set_link_state("up").await
+    }
+
+    pub async fn set_link_down(&mut self) -> Result<CmdOutput> {
+        self.set_link_state("down").await
+    }
+
+    async fn set_link_state(&mut self, state: &str) -> Result<CmdOutput> {
+        let iface = self.selected_iface()?;
+        let backend = self.selected_backend().await?;
+        let cap = backend.set_link(&iface, state == "up").await?;
+        self.last_action = Some(format!("Set {iface} {state}"));
+        // Don't wait for the netlink notification; show the new operstate right away.
+        self.refresh_selected();
+        Ok(cap)
+    }
+
+    /// Re-reads only the selected interface from sysfs.
+    pub fn refresh_selected(&mut self) {
+        if let Some(index) = self.selected_device().map(|d| d.ifindex) {
+            self.apply_net_event(NetEvent::Link(index));
+        }
+    }
+
+    pub fn open_static_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+
+        let address = d
+            .ipv4
+            .first()
+            .cloned()
+            .unwrap_or_else(|| "192.168.1.10/24".into());
+        let gateway = d.gateway_v4.clone().unwrap_or_default();
+        let dns = d.dns.join(", ");
+
+        self.form = Some(Form {
+            kind: FormKind::StaticIpv4,
+            title: format!(" Static IPv4 ({}) ", d.name),
+            fields: vec![
+                FormField {
+                    label: "Address",
+                    hint: "a.b.c.d/prefix".into(),
+                    value: address,
+                },
+                FormField {
+                    label: "Gateway",
+                    hint: "optional".into(),
+                    value: gateway,
+                },
+                FormField {
+                    label: "DNS",
+                    hint: "optional, comma separated".into(),
+                    value: dns,
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    /// Applies the open form. The form stays open on validation errors so input isn't lost.
+    pub async fn submit_form(&mut self) -> Result<String> {
+        let Some(form) = self.form.clone() else {
+            return Err(std::io::Error::other("no form open").into());
+        };
+
+        let msg = match form.kind {
+            FormKind::StaticIpv4 => {
+                let cfg = StaticIpv4Config::parse(form.value(0), form.value(1), form.value(2))?;
+                self.form = None;
+                self.apply_static_ipv4(&cfg).await?
+            }
+            FormKind::LinkSettings => {
+                let autoneg = match form.value(0).trim() {
+                    "on" => true,
+                    "off" => false,
+                    v => {
+                        return Err(
+                            std::io::Error::other(format!("autoneg must be on/off: {v}")).into(),
+                        );
+                    }
+                };
+                let speed = form.value(1).trim().to_string();
+                let duplex = form.value(2).trim().to_lowercase();
+                if !autoneg {
+                    if speed.parse::<u32>().is_err() {
+                        return Err(std::io::Error::other(format!("invalid speed: {speed}")).into());
+                    }
+                    if duplex != "half" && duplex != "full" {
+                        return Err(std::io::Error::other(format!(
+                            "duplex must be half/full: {duplex}"
+                        ))
+                        .into());
+                    }
+                }
+                self.form = None;
+                self.apply_link_settings(autoneg, &speed, &duplex).await?
+            }
+            FormKind::Rings => {
+                let parse = |v: &str, what: &str| -> Result<u32> {
+                    v.trim().parse::<u32>().map_err(|_| {
+                        std::io::Error::other(format!("invalid {what} ring size: {v}")).into()
+                    })
+                };
+                let rx = parse(form.value(0), "RX")?;
+                let tx = parse(form.value(1), "TX")?;
+                self.form = None;
+                self.apply_rings(rx, tx).await?
+            }
+            FormKind::Channels => {
+                let max = self
+                    .selected_hardware()
+                    .and_then(|hw| hw.channels)
+                    .map(|c| c.max)
+                    .unwrap_or_default();
+                let mut args: Vec<(&'static str, String)> = Vec::new();
+                for (i, (key, max)) in [("combined", max.combined), ("rx", max.rx), ("tx", max.tx)]
+                    .into_iter()
+                    .enumerate()
+                {
+                    let v = form.value(i).trim();
+                    if v.is_empty() {
+                        continue;
+                    }
+                    let n: u32 = v.parse().map_err(|_| {
+                        std::io::Error::other(format!("invalid {key} channel count: {v}"))
+                    })?;
+                    match max {
+                        Some(max) if n <= max => args.push((key, n.to_string())),
+                        Some(max) => {
+                            return Err(std::io::Error::other(format!(
+                                "{key} channels can be at most {max}"
+                            ))
+                            .into());
+                        }
+                        None => {
+                            return Err(std::io::Error::other(format!(
+                                "this NIC has no {key} channels"
+                            ))
+                            .into());
+                        }
+                    }
+                }
+                self.form = None;
+                if self.channels_unchanged(&args) {
+                    return self.apply_channels(&args).await;
+                }
+                let iface = self.selected_iface()?;
+                let summary: Vec<String> = args.iter().map(|(k, v)| format!("{k} {v}")).collect();
+                let prompt = format!(
+                    "Set channels on {iface} to {}?\n\nMost drivers reset the NIC to apply this, so the link drops for a few seconds.",
+                    summary.join(", ")
+                );
+                self.ask(ConfirmAction::SetChannels(args), prompt);
+                // Nothing to report until it's confirmed.
+                String::new()
+            }
+            FormKind::Coalesce => {
+                let mut args: Vec<(&str, String)> = Vec::new();
+                for (i, key) in ["adaptive-rx", "adaptive-tx"].into_iter().enumerate() {
+                    match form.value(i).trim() {
+                        "" => {}
+                        v @ ("on" | "off") => args.push((key, v.to_string())),
+                        v => {
+                            return Err(std::io::Error::other(format!(
+                                "{key} must be on/off: {v}"
+                            ))
+                            .into());
+                        }
+                    }
+                }
+                for (i, key) in ["rx-usecs", "tx-usecs"].into_iter().enumerate() {
+                    match form.value(i + 2).trim() {
+                        "" => {}
+                        v if v.parse::<u32>().is_ok() => args.push((key, v.to_string())),
+                        v => {
+                            return Err(std::io::Error::other(format!("invalid {key}: {v}")).into());
+                        }
+                    }
+                }
+                self.form = None;
+                self.apply_coalesce(&args).await?
+            }
+            FormKind::Fec => {
+                let encoding = form.value(0).trim().to_lowercase();
+                let words: Vec<&str> = encoding
+                    .split([' ', ','])
+                    .filter(|w| !w.is_empty())
+                    .collect();
+                if let Some(bad) = words.iter().find(|w| !ethtool::FEC_ENCODINGS.contains(w)) {
+                    return Err(std::io::Error::other(format!(
+                        "unknown FEC encoding {bad:?} (use {})",
+                        ethtool::FEC_ENCODINGS.join(", ")
+                    ))
+                    .into());
+                }
+                if words.is_empty() {
+                    return Err(std::io::Error::other("no FEC encoding given").into());
+                }
+                self.form = None;
+                let iface = self.selected_iface()?;
+                let encoding = words.join(" ");
+                let prompt = format!(
+                    "Set FEC on {iface} to {encoding}?\n\nBoth ends of the link have to agree on FEC; a mismatch keeps the link down until one side is changed."
+                );
+                self.ask(ConfirmAction::SetFec(encoding), prompt);
+                // Nothing to report until it's confirmed.
+                String::new()
+            }
+            FormKind::IrqAffinity => {
+                let list = form.value(0).trim().to_string();
+                if queues::parse_cpu_list(&list).is_none() {
+                    return Err(std::io::Error::other(format!("invalid CPU list: {list}")).into());
+                }
+                self.form = None;
+                self.apply_irq_affinity(&list).await?
+            }
+            FormKind::NumVfs => {
+                let Ok(Some(sriov)) = &self.sriov else {
+                    return Err(std::io::Error::other("SR-IOV state not read yet").into());
+                };
+                let (total, current) = (sriov.total_vfs, sriov.num_vfs);
+                let v = form.value(0).trim();
+                let n: u32 = v
+                    .parse()
+                    .map_err(|_| std::io::Error::other(format!("invalid VF count: {v}")))?;
+                if n > total {
+                    return Err(std::io::Error::other(format!(
+                        "this NIC supports at most {total} VFs"
+                    ))
+                    .into());
+                }
+                self.form = None;
+                let iface = self.selected_iface()?;
+                if n == current {
+                    format!("{iface}: already {n} VFs")
+                } else if current == 0 {
+                    self.set_num_vfs(n).await?
+                } else {
+                    let prompt = format!(
+                        "Change the number of VFs on {iface} from {current} to {n}?\n\nThe kernel only allows this through 0, so all {current} existing VFs are removed first and VMs using them lose their network."
+                    );
+                    self.ask(ConfirmAction::SetNumVfs(n), prompt);
+                    // Nothing to report until it's confirmed.
+                    String::new()
+                }
+            }
+            FormKind::VfSettings => {
+                let vf = self.selected_vf()?;
+                let mut args: Vec<(&str, String)> = Vec::new();
+                let mac = form.value(0).trim();
+                if !mac.is_empty() {
+                    let mac = parse_mac(mac).ok_or_else(|| {
+                        std::io::Error::other(format!("invalid MAC address: {mac}"))
+                    })?;
+                    if vf.mac.as_deref() != Some(mac.as_str()) {
+                        args.push(("mac", mac));
+                    }
+                }
+                let vlan = form.value(1).trim();
+                if !vlan.is_empty() {
+                    let id = vlan
+                        .parse::<u16>()
+                        .ok()
+                        .filter(|id| *id <= 4094)
+                        .ok_or_else(|| std::io::Error::other(format!("invalid VLAN: {vlan}")))?;
+                    if id != vf.vlan() {
+                        args.push(("vlan", id.to_string()));
+                    }
+                }
+                for (i, key, current) in [(2, "spoofchk", vf.spoofchk), (3, "trust", vf.trust)] {
+                    let on = match form.value(i).trim() {
+                        "" => continue,
+                        "on" => true,
+                        "off" => false,
+                        v => {
+                            return Err(std::io::Error::other(format!(
+                                "{key} must be on/off: {v}"
+                            ))
+                            .into());
+                        }
+                    };
+                    if current != Some(on) {
+                        args.push((key, if on { "on" } else { "off" }.to_string()));
+                    }
+                }
+                let state = form.value(4).trim();
+                if !state.is_empty() {
+                    if !VF_LINK_STATES.contains(&state) {
+                        return Err(std::io::Error::other(format!(
+                            "link state must be {}: {state}",
+                            VF_LINK_STATES.join("/")
+                        ))
+                        .into());
+                    }
+                    if vf.link_state.as_deref() != Some(state) {
+                        args.push(("state", state.to_string()));
+                    }
+                }
+                self.form = None;
+                self.apply_vf_settings(vf.number, &args).await?
+            }
+            FormKind::WakeOnLan => {
+                let enable = match form.value(0).trim() {
+                    "g" => true,
+                    "d" => false,
+                    v => {
+                        return Err(
+                            std::io::Error::other(format!("mode must be g or d: {v}")).into()
+                        );
+                    }
+                };
+                let persist = match form.value(1).trim() {
+                    "yes" => true,
+                    "no" | "" => false,
+                    v => {
+                        return Err(
+                            std::io::Error::other(format!("persist must be yes/no: {v}")).into(),
+                        );
+                    }
+                };
+                self.form = None;
+                self.apply_wol(enable, persist).await?
+            }
+            FormKind::MacAddress => {
+                let mac = match form.value(0).trim() {
+                    "random" => random_local_mac()?,
+                    "permanent" => self
+                        .selected_hardware()
+                        .and_then(|hw| hw.permanent_mac.clone())
+                        .ok_or_else(|| {
+                            std::io::Error::other("permanent MAC unknown (ethtool -P)")
+                        })?,
+                    v => parse_mac(v).ok_or_else(|| {
+                        std::io::Error::other(format!("invalid MAC address: {v}"))
+                    })?,
+                };
+                self.form = None;
+                let iface = self.selected_iface()?;
+                let prompt = format!(
+                    "Set the MAC address of {iface} to {mac}?\n\nThe link may bounce, and DHCP servers or switch port security that know the old address may hand out a new lease or block the port."
+                );
+                self.ask(ConfirmAction::SetMac(mac), prompt);
+                // Nothing to report until it's confirmed.
+                String::new()
+            }
+            FormKind::Ping | FormKind::Trace => {
+                let target = form.value(0).trim();
+                if target.is_empty() || target.contains(char::is_whitespace) {
+                    return Err(std::io::Error::other(format!("invalid target: {target}")).into());
+                }
+                let target = target.to_string();
+                if form.kind == FormKind::Ping {
+                    let window = form.value(1).trim();
+                    let window = window
+                        .parse::<usize>()
+                        .map_err(|_| std::io::Error::other(format!("invalid window: {window}")))?;
+                    self.form = None;
+                    self.start_ping(&target, window)?
+                } else {
+                    self.form = None;
+                    self.start_trace(&target)?
+                }
+            }
+            FormKind::SpeedTest => {
+                let target = form.value(0).trim().to_string();
+                if target.is_empty() {
+                    return Err(std::io::Error::other(
+                        "enter an iperf3 server or a URL to download",
+                    )
+                    .into());
+                }
+                self.form = None;
+                self.start_speedtest(&target)?
+            }
+            FormKind::AddAddress => {
+                let value = form.value(0).trim();
+                let cidr = parse_cidr(value)
+                    .ok_or_else(|| std::io::Error::other(format!("invalid address: {value}")))?;
+                self.form = None;
+                self.add_address(&cidr).await?
+            }
+            FormKind::FlushAddresses => {
+                let dhcp = match form.value(0).trim() {
+                    "yes" => true,
+                    "no" | "" => false,
+                    v => {
+                        return Err(std::io::Error::other(format!(
+                            "restart DHCP must be yes/no: {v}"
+                        ))
+                        .into());
+                    }
+                };
+                self.form = None;
+                self.confirm_flush_addresses(dhcp)?;
+                String::new()
+            }
+            FormKind::Route => {
+                let iface = self.selected_iface()?;
+                let route = Route::parse(form.value(0), form.value(1), form.value(2))?;
+                self.form = None;
+                let prompt = format!(
+                    "Add the route {} on {iface}?\n\nTraffic to {} goes out of {iface} from now on; it lasts until the link is reconfigured.",
+                    route.describe(),
+                    route.dst
+                );
+                self.ask(ConfirmAction::AddRoute(route), prompt);
+                String::new()
+            }
+            FormKind::DnsServers => {
+                let iface = self.selected_iface()?;
+                let dropin = match form.value(2).trim() {
+                    "runtime" | "" => None,
+                    "persist" => Some(self.dns_dropin().ok_or_else(|| {
+                        std::io::Error::other(format!(
+                            "{iface} isn't managed by systemd-networkd; use runtime"
+                        ))
+                    })?),
+                    v => {
+                        return Err(std::io::Error::other(format!(
+                            "apply must be runtime or persist: {v}"
+                        ))
+                        .into());
+                    }
+                };
+                let change = DnsChange::parse(&iface, form.value(0), form.value(1), dropin)?;
+                self.form = None;
+                let (servers, domains) = self
+                    .selected_device()
+                    .map(|d| (d.dns.join(" "), d.dns_domains.join(" ")))
+                    .unwrap_or_default();
+                let or_none = |s: String| if s.is_empty() { "none".to_string() } else { s };
+                let prompt = format!(
+                    "Change the DNS settings of {iface}?\n\nServers: {} -> {}\nSearch domains: {} -> {}\n\n{}",
+                    or_none(servers),
+                    or_none(change.servers.join(" ")),
+                    or_none(domains),
+                    or_none(change.domains.join(" ")),
+                    change.preview()
+                );
+                self.ask(ConfirmAction::SetDns(change), prompt);
+                // Nothing to report until it's confirmed.
+                String::new()
+            }
+            FormKind::NetworkFile => {
+                let iface = self.selected_iface()?;
+                let config = NetworkConfig::parse(
+                    &iface,
+                    form.value(0),
+                    form.value(1),
+                    form.value(2),
+                    form.value(3),
+                    form.value(4),
+                    form.value(5),
+                )?;
+                self.form = None;
+                let prompt = format!(
+                    "Write a systemd-networkd configuration for {iface}?\n\n{}\n\n{}",
+                    self.network_file_note(&config),
+                    config.preview()
+                );
+                self.ask(ConfirmAction::WriteNetworkFile(config), prompt);
+                // Nothing to report until it's confirmed.
+                String::new()
+            }
+            FormKind::Netplan => match form.value(0).trim() {
+                "try" => {
+                    let timeout = match form.value(1).trim() {
+                        "" => netplan::TRY_TIMEOUT,
+                        v => v.parse().ok().filter(|t| *t > 0).ok_or_else(|| {
+                            std::io::Error::other(format!("invalid timeout: {v}"))
+                        })?,
+                    };
+                    self.start_netplan_try(timeout)?
+                }
+                "apply" => {
+                    self.form = None;
+                    let prompt = format!(
+                        "Run netplan apply?\n\nIt regenerates and applies the configuration of every interface, from:\n{}",
+                        self.netplan
+                            .as_ref()
+                            .map(|n| n.files.join("\n"))
+                            .unwrap_or_default()
+                    );
+                    self.ask(ConfirmAction::NetplanApply, prompt);
+                    // Nothing to report until it's confirmed.
+                    String::new()
+                }
+                v => {
+                    return Err(std::io::Error::other(format!(
+                        "command must be try or apply: {v}"
+                    ))
+                    .into());
+                }
+            },
+            FormKind::Profile => {
+                let name = form.value(0).trim().to_string();
+                let profile = self.profile(&name)?;
+                let d = self
+                    .selected_device()
+                    .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+                let before = SavedState::before(&profile, d, read_mtu(&d.name));
+                let prompt = format!(
+                    "Apply profile {name} to {}?\n\n{}\n\nThese are runtime changes: whatever manages {} may put its own settings back when it reconfigures the link. U rolls the profile back.",
+                    d.name,
+                    profile.changes(&before).join("\n"),
+                    d.name
+                );
+                self.form = None;
+                self.ask(ConfirmAction::ApplyProfile(name), prompt);
+                // Nothing to report until it's confirmed.
+                String::new()
+            }
+            FormKind::DnsTest => {
+                let host = form.value(0).trim().to_string();
+                if host.is_empty() || host.contains(char::is_whitespace) {
+                    return Err(std::io::Error::other(format!("invalid hostname: {host}")).into());
+                }
+                let benchmark = match form.value(1).trim() {
+                    "yes" => true,
+                    "no" | "" => false,
+                    v => {
+                        return Err(std::io::Error::other(format!(
+                            "benchmark must be yes/no: {v}"
+                        ))
+                        .into());
+                    }
+                };
+                self.form = None;
+                self.start_dns_test(&host, benchmark)?
+            }
+            FormKind::Vlan => {
+                let id = form.value(0).trim();
+                let id = id
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|id| (1..=4094).contains(id))
+                    .ok_or_else(|| {
+                        std::io::Error::other(format!("VLAN ID must be 1-4094: {id}"))
+                    })?;
+                let name = form.value(1).trim().to_string();
+                self.form = None;
+                self.add_vlan(id, &name).await?
+            }
+            FormKind::ExportJson => {
+                let path = form.value(0).trim().to_string();
+                let msg = self.export_json(&path)?;
+                self.form = None;
+                msg
+            }
+            FormKind::ExportCsv => {
+                let path = form.value(0).trim().to_string();
+                let msg = self.export_history_csv(&path)?;
+                self.form = None;
+                msg
+            }
+            FormKind::Bridge => {
+                let bridge = form.value(0).trim().to_string();
+                if bridge.is_empty() || bridge.contains(char::is_whitespace) {
+                    return Err(std::io::Error::other(format!("invalid bridge: {bridge}")).into());
+                }
+                self.form = None;
+                self.join_bridge(&bridge).await?
+            }
+        };
+
+        Ok(msg)
+    }
+
+    /// Replaces the IPv4 configuration at runtime (not persisted across reboots/reconfigures).
+    pub async fn apply_static_ipv4(&mut self, cfg: &StaticIpv4Config) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let cidr = cfg.cidr();
+        let (addrs, gateway) = self
+            .selected_device()
+            .map(|d| (d.ipv4.clone(), d.gateway_v4.clone()))
+            .unwrap_or_default();
+
+        // Saved before the flush, so `U` puts the old addresses back even when the add fails.
+        self.push_undo(Undo::Ipv4 {
+            iface: iface.clone(),
+            added: cidr.clone(),
+            addrs,
+            gateway: cfg.gateway.map(|_| gateway),
+        });
+        self.run_privileged_capture("ip", &["-4", "addr", "flush", "dev", &iface])
+            .await?;
+        self.run_privileged_capture("ip", &["addr", "add", &cidr, "dev", &iface])
+            .await?;
+
+        let mut msg = format!("{iface}: set {cidr}");
+
+        if let Some(gw) = cfg.gateway {
+            let gw = gw.to_string();
+            self.run_privileged_capture(
+                "ip",
+                &["route", "replace", "default", "via", &gw, "dev", &iface],
+            )
+            .await?;
+            msg.push_str(&format!(", gateway {gw}"));
+        }
+
+        if !cfg.dns.is_empty() {
+            let servers: Vec<String> = cfg.dns.iter().map(|s| s.to_string()).collect();
+            let mut args = vec!["dns", iface.as_str()];
+            args.extend(servers.iter().map(|s| s.as_str()));
+            // DNS is per-link in systemd-resolved; without it this is the only step that fails.
+            match self.run_privileged_capture("resolvectl", &args).await {
+                Ok(_) => msg.push_str(&format!(", DNS {}", servers.join(" "))),
+                Err(e) => msg.push_str(&format!("\nDNS not applied: {e}")),
+            }
+        }
+
+        self.last_action = Some(format!("Static IPv4 {cidr} on {iface}"));
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    /// The address under the Overview's cursor; only while the Overview is focused, so the
+    /// address acted on is the one highlighted.
+    pub fn selected_address(&self) -> Option<String> {
+        if self.focus != Focus::Details || self.tab != DetailTab::Overview {
+            return None;
+        }
+        let d = self.selected_device()?;
+        let i = self.address_state.selected()?;
+        d.ipv4.iter().chain(&d.ipv6).nth(i).cloned()
+    }
+
+    pub fn open_address_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::AddAddress,
+            title: format!(" Add address ({}) ", d.name),
+            fields: vec![FormField {
+                label: "Address",
+                hint: "IPv4 or IPv6 address/prefix, kept next to the existing ones (e.g. 192.168.0.10/24 to reach a device on its factory address)".into(),
+                value: String::new(),
+            }],
+            focus: 0,
+        });
+    }
+
+    /// Adds a secondary address at runtime; the managing backend may drop it when it
+    /// reconfigures the link.
+    pub async fn add_address(&mut self, cidr: &str) -> Result<String> {
+        let iface = self.selected_iface()?;
+        self.run_privileged_capture("ip", &["addr", "add", cidr, "dev", &iface])
+            .await?;
+        let msg = format!("{iface}: added {cidr}");
+        self.last_action = Some(msg.clone());
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    pub fn confirm_delete_address(&mut self) -> Result<()> {
+        let iface = self.selected_iface()?;
+        let cidr = self.selected_address().ok_or_else(|| {
+            std::io::Error::other("select an address in the focused Overview first (Tab, j/k)")
+        })?;
+        let prompt = format!(
+            "Delete {cidr} from {iface}?\n\nConnections using it drop, including this session when it's remote, and routes through it go with it."
+        );
+        self.ask(ConfirmAction::DeleteAddress(cidr), prompt);
+        Ok(())
+    }
+
+    pub async fn delete_address(&mut self, cidr: &str) -> Result<String> {
+        let iface = self.selected_iface()?;
+        self.run_privileged_capture("ip", &["addr", "del", cidr, "dev", &iface])
+            .await?;
+        self.push_undo(Undo::Address {
+            iface: iface.clone(),
+            cidr: cidr.to_string(),
+        });
+        let msg = format!("{iface}: deleted {cidr}");
+        self.last_action = Some(msg.clone());
+        self.address_state.select(None);
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    pub fn open_flush_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::FlushAddresses,
+            title: format!(" Flush addresses ({}) ", d.name),
+            fields: vec![FormField {
+                label: "Restart DHCP",
+                hint: "yes: renew the lease right after, so the interface comes back with a fresh address".into(),
+                value: if d.lease.is_some() { "yes" } else { "no" }.into(),
+            }],
+            focus: 0,
+        });
+    }
+
+    pub fn confirm_flush_addresses(&mut self, dhcp: bool) -> Result<()> {
+        let d = self
+            .selected_device()
+            .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+        let addrs: Vec<&str> = d.ipv4.iter().chain(&d.ipv6).map(String::as_str).collect();
+        if addrs.is_empty() {
+            return Err(std::io::Error::other(format!("{} has no addresses", d.name)).into());
+        }
+        let after = if dhcp {
+            "then asks DHCP for a new lease"
+        } else {
+            "and leaves it without an address until one is added or DHCP renews (n)"
+        };
+        let prompt = format!(
+            "Flush every address from {}?
+
+{}
+
+This drops all connections over it, including this session when it's remote, {after}.",
+            d.name,
+            addrs.join("\n")
+        );
+        self.ask(ConfirmAction::FlushAddresses(dhcp), prompt);
+        Ok(())
+    }
+
+    /// `ip addr flush`, for an interface that piled up stale addresses; with `dhcp` the backend
+    /// renews the lease right after.
+    pub async fn flush_addresses(&mut self, dhcp: bool) -> Result<String> {
+        let iface = self.selected_iface()?;
+        self.run_privileged_capture("ip", &["addr", "flush", "dev", &iface])
+            .await?;
+        self.events
+            .push(&iface, EventKind::Down, "all addresses flushed");
+        self.address_state.select(None);
+        let mut msg = format!("{iface}: addresses flushed");
+        if dhcp {
+            match self.renew_dhcp().await {
+                Ok(_) => msg.push_str(", DHCP restarted"),
+                Err(e) => msg.push_str(&format!(", but DHCP renew failed: {e}")),
+            }
+        }
+        self.last_action = Some(msg.clone());
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    /// The route under the Routes tab's cursor, while the tab is focused.
+    pub fn selected_route(&self) -> Option<Route> {
+        if self.focus != Focus::Details || self.tab != DetailTab::Routes {
+            return None;
+        }
+        let i = self.routes_state.selected()?;
+        self.selected_routes()?.get(i).cloned()
+    }
+
+    pub fn open_route_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::Route,
+            title: format!(" Add route ({}) ", d.name),
+            fields: vec![
+                FormField {
+                    label: "Destination",
+                    hint: "prefix such as 10.20.0.0/16 or fd00:20::/64, or default".into(),
+                    value: String::new(),
+                },
+                FormField {
+                    label: "Gateway",
+                    hint: format!("next hop; empty for a subnet reachable directly on {}", d.name),
+                    value: String::new(),
+                },
+                FormField {
+                    label: "Metric",
+                    hint: "lower wins between routes to the same destination; empty for the kernel's default".into(),
+                    value: String::new(),
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    pub fn confirm_delete_route(&mut self) -> Result<()> {
+        let iface = self.selected_iface()?;
+        let route = self.selected_route().ok_or_else(|| {
+            std::io::Error::other("select a route in the focused Routes tab first (Tab, j/k)")
+        })?;
+        let prompt = format!(
+            "Delete the route {} from {iface}?\n\nTraffic to {} falls back to a less specific route, or becomes unreachable.",
+            route.describe(),
+            route.dst
+        );
+        self.ask(ConfirmAction::DeleteRoute(route), prompt);
+        Ok(())
+    }
+
+    /// `ip route add` or `del` on the selected interface, then re-reads its routes.
+    pub async fn change_route(&mut self, verb: &str, route: &Route) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let args = route.ip_args(verb, &iface);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let out = self.run_privileged_capture("ip", &args).await?;
+        self.push_undo(Undo::Route {
+            iface: iface.clone(),
+            route: route.clone(),
+            deleted: verb == "del",
+        });
+        let done = if verb == "add" { "added" } else { "deleted" };
+        let mut msg = format!("{iface}: {done} route {}", route.describe());
+        if out.used_sudo {
+            msg.push_str(" (sudo)");
+        }
+        let event = format!("route {done}: {}", route.describe());
+        self.events.push(&iface, EventKind::Info, event);
+        self.last_action = Some(msg.clone());
+        if let Some(index) = self.selected_device().map(|d| d.ifindex) {
+            self.routes.remove(&index);
+        }
+        self.load_selected_routes().await;
+        Ok(msg)
+    }
+
+    /// The drop-in for the selected interface's `.network` file, when networkd manages it.
+    fn dns_dropin(&self) -> Option<String> {
+        let info = self.selected_backend_info()?;
+        if info.backend.name() != backend::Networkd.name() {
+            return None;
+        }
+        dns::dropin_path(info.config_source.as_deref()?)
+    }
+
+    pub fn open_dns_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let persist = match self.dns_dropin() {
+            Some(path) => format!("persist (writes {path})"),
+            None => "persist (systemd-networkd only)".into(),
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::DnsServers,
+            title: format!(" DNS ({}) ", d.name),
+            fields: vec![
+                FormField {
+                    label: "Servers",
+                    hint:
+                        "addresses separated by spaces, e.g. 1.1.1.1 2606:4700::1111; empty clears"
+                            .into(),
+                    value: d.dns.join(" "),
+                },
+                FormField {
+                    label: "Domains",
+                    hint:
+                        "search domains; ~example.com only routes its queries here, ~. all of them"
+                            .into(),
+                    value: d.dns_domains.join(" "),
+                },
+                FormField {
+                    label: "Apply",
+                    hint: format!("runtime (resolvectl) or {persist}"),
+                    value: "runtime".into(),
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    /// Applies a confirmed DNS change through systemd-resolved, or persists it as a networkd
+    /// drop-in and has networkd pick it up.
+    pub async fn set_dns(&mut self, change: &DnsChange) -> Result<String> {
+        let iface = &change.iface;
+        let msg = match &change.dropin {
+            Some(path) => {
+                write_privileged_file(path, &change.dropin_contents()).await?;
+                self.run_privileged_capture("networkctl", &["reload"])
+                    .await?;
+                self.run_privileged_capture("networkctl", &["reconfigure", iface])
+                    .await?;
+                format!("{iface}: DNS settings written to {path}")
+            }
+            None => {
+                for args in change.resolvectl_args() {
+                    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                    self.run_privileged_capture("resolvectl", &args).await?;
+                }
+                format!("{iface}: DNS settings applied (until the link is reconfigured)")
+            }
+        };
+        self.last_action = Some(format!("DNS of {iface} set"));
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    pub fn open_network_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::NetworkFile,
+            title: format!(" systemd-networkd config ({}) ", d.name),
+            fields: vec![
+                FormField {
+                    label: "Mode",
+                    hint: "dhcp or static".into(),
+                    value: if d.lease.is_some() || d.ipv4.is_empty() {
+                        "dhcp"
+                    } else {
+                        "static"
+                    }
+                    .into(),
+                },
+                FormField {
+                    label: "Address",
+                    hint: "static only: a.b.c.d/prefix".into(),
+                    value: d.ipv4.first().cloned().unwrap_or_default(),
+                },
+                FormField {
+                    label: "Gateway",
+                    hint: "static only, optional".into(),
+                    value: d.gateway_v4.clone().unwrap_or_default(),
+                },
+                FormField {
+                    label: "DNS",
+                    hint: "optional, IPv4 or IPv6, comma separated; with DHCP, added to the servers it hands out".into(),
+                    value: d.dns.join(", "),
+                },
+                FormField {
+                    label: "VLANs",
+                    hint: format!("optional IDs, comma separated; each becomes {}.<id> with DHCP", d.name),
+                    value: String::new(),
+                },
+                FormField {
+                    label: "Reload",
+                    hint: "yes: networkctl reload and reconfigure right after writing".into(),
+                    value: "yes".into(),
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    /// How the generated file relates to whatever configures the interface now: networkd uses
+    /// the first matching `.network` file by name, wherever it lives.
+    fn network_file_note(&self, config: &NetworkConfig) -> String {
+        let iface = &config.iface;
+        let Some(info) = self.selected_backend_info() else {
+            return format!("The file applies to {iface} once systemd-networkd manages it.");
+        };
+        if info.backend.name() != backend::Networkd.name() {
+            return format!(
+                "{iface} is managed by {} now; the file applies once systemd-networkd manages it instead.",
+                info.backend.name()
+            );
+        }
+        let ours = config.network_path();
+        let name = |p: &str| {
+            std::path::Path::new(p)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        match info.config_source.as_deref() {
+            Some(current) if current == ours => format!("It replaces the {ours} written earlier."),
+            Some(current) if name(current) < name(&ours) => format!(
+                "{current} sorts before it and keeps matching {iface}; rename or remove it for this to apply."
+            ),
+            Some(current) => format!("It takes over from {current}."),
+            None => format!("No .network file matches {iface} now."),
+        }
+    }
+
+    /// Installs the generated units through the privileged path, optionally reloading networkd.
+    pub async fn write_network_file(&mut self, config: &NetworkConfig) -> Result<String> {
+        let iface = &config.iface;
+        for (path, contents) in config.files() {
+            write_privileged_file(&path, &contents).await?;
+        }
+        let mut msg = format!("{iface}: wrote {}", config.network_path());
+        if !config.vlans.is_empty() {
+            msg.push_str(&format!(" and units for {} VLANs", config.vlans.len()));
+        }
+        if config.reload {
+            self.run_privileged_capture("networkctl", &["reload"])
+                .await?;
+            self.run_privileged_capture("networkctl", &["reconfigure", iface])
+                .await?;
+            msg.push_str(", networkd reloaded");
+        }
+        self.last_action = Some(format!("Wrote networkd config for {iface}"));
+        self.units_fetched = None;
+        if let Some(index) = self.selected_device().map(|d| d.ifindex) {
+            self.backends.remove(&index);
+        }
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    pub fn open_netplan_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let name = d.name.clone();
+        self.netplan = netplan::netplan_for(&name, d.mac.as_deref());
+        if self.netplan.is_none() {
+            self.last_error = Some("no netplan configuration on this machine".into());
+            return;
+        }
+
+        self.form = Some(Form {
+            kind: FormKind::Netplan,
+            title: format!(" netplan ({name}) "),
+            fields: vec![
+                FormField {
+                    label: "Command",
+                    hint: "try: apply, then revert unless kept in time; apply: for good".into(),
+                    value: "try".into(),
+                },
+                FormField {
+                    label: "Timeout",
+                    hint: "try only: seconds before it reverts".into(),
+                    value: netplan::TRY_TIMEOUT.to_string(),
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    pub fn start_netplan_try(&mut self, timeout: u64) -> Result<String> {
+        self.netplan_try = Some(NetplanTry::start(timeout)?);
+        Ok(format!("netplan try: reverts in {timeout}s unless kept"))
+    }
+
+    /// Enter in the popup; a no-op once netplan has finished.
+    pub fn accept_netplan_try(&mut self) {
+        if let Some(t) = &mut self.netplan_try {
+            t.accept();
+        }
+    }
+
+    /// Esc reverts a trial still running, and closes the popup once it has finished.
+    pub fn dismiss_netplan_try(&mut self) {
+        match &mut self.netplan_try {
+            Some(t) if t.exit.is_none() => t.revert(),
+            _ => self.netplan_try = None,
+        }
+    }
+
+    fn poll_netplan_try(&mut self) {
+        let Some(t) = &mut self.netplan_try else {
+            return;
+        };
+        let running = t.exit.is_none();
+        t.poll();
+        let Some(code) = t.exit.filter(|_| running) else {
+            return;
+        };
+        let msg = match (code, t.answer) {
+            (0, Some(TryAnswer::Keep)) => "netplan try: new configuration kept".to_string(),
+            (0, _) => "netplan try: configuration reverted".to_string(),
+            (code, _) => format!(
+                "netplan try failed ({code}): {}",
+                t.output.last().cloned().unwrap_or_default()
+            ),
+        };
+        if code == 0 {
+            self.last_action = Some(msg.clone());
+            self.set_toast(ToastKind::Info, msg);
+        } else {
+            self.last_error = Some(msg);
+        }
+        self.units_fetched = None;
+        self.backends.clear();
+        self.refresh_selected();
+    }
+
+    /// `netplan apply` regenerates the backend config for every interface and applies it.
+    pub async fn netplan_apply(&mut self) -> Result<String> {
+        let out = self.run_privileged_capture("netplan", &["apply"]).await?;
+        let sudo = if out.used_sudo { " (sudo)" } else { "" };
+        self.last_action = Some("netplan apply".into());
+        self.units_fetched = None;
+        self.backends.clear();
+        self.refresh_selected();
+        Ok(format!("netplan configuration applied{sudo}"))
+    }
+
+    pub fn open_profile_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let names: Vec<&str> = self
+            .config
+            .profiles
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        let Some(first) = names.first() else {
+            self.last_error = Some("no profiles configured ([[profiles]] in config.toml)".into());
+            return;
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::Profile,
+            title: format!(" Apply profile ({}) ", d.name),
+            fields: vec![FormField {
+                label: "Profile",
+                hint: names.join(", "),
+                value: first.to_string(),
+            }],
+            focus: 0,
+        });
+    }
+
+    fn profile(&self, name: &str) -> Result<Profile> {
+        self.config
+            .profiles
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| std::io::Error::other(format!("no profile named {name}")).into())
+    }
+
+    /// Applies the profile's settings to the selected interface, MTU first so addresses come up
+    /// on the right one.
+    pub async fn apply_profile(&mut self, name: &str) -> Result<String> {
+        let profile = self.profile(name)?;
+        let d = self
+            .selected_device()
+            .cloned()
+            .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+        let iface = d.name.clone();
+        // Saved before anything runs, so a step failing halfway can still be rolled back.
+        self.push_undo(Undo::Profile {
+            iface: iface.clone(),
+            name: name.to_string(),
+            previous: SavedState::before(&profile, &d, read_mtu(&iface)),
+        });
+        let applied = self.apply_profile_settings(&iface, &profile).await;
+        self.refresh_selected();
+        let applied = applied.map_err(|e| {
+            std::io::Error::other(format!(
+                "profile {name}: {e} (U puts the old settings back)"
+            ))
+        })?;
+
+        self.events
+            .push(&iface, EventKind::Info, format!("profile {name} applied"));
+        let msg = format!("{iface}: profile {name} applied ({})", applied.join(", "));
+        self.last_action = Some(msg.clone());
+        Ok(msg)
+    }
+
+    /// The steps of [`App::apply_profile`], with what each one set.
+    async fn apply_profile_settings(
+        &mut self,
+        iface: &str,
+        profile: &Profile,
+    ) -> Result<Vec<String>> {
+        let mut applied = Vec::new();
+        if let Some(mtu) = profile.mtu {
+            let mtu_s = mtu.to_string();
+            self.run_privileged_capture("ip", &["link", "set", "dev", iface, "mtu", &mtu_s])
+                .await?;
+            applied.push(format!("MTU {mtu}"));
+        }
+        if let Some(address) = &profile.address {
+            let cfg = profile.ipv4(address)?;
+            let cidr = cfg.cidr();
+            self.run_privileged_capture("ip", &["-4", "addr", "flush", "dev", iface])
+                .await?;
+            self.run_privileged_capture("ip", &["addr", "add", &cidr, "dev", iface])
+                .await?;
+            applied.push(cidr);
+            if let Some(gw) = cfg.gateway {
+                let gw = gw.to_string();
+                self.run_privileged_capture(
+                    "ip",
+                    &["route", "replace", "default", "via", &gw, "dev", iface],
+                )
+                .await?;
+                applied.push(format!("gateway {gw}"));
+            }
+        } else if profile.dhcp {
+            // Drop static addresses so only the lease is left.
+            self.run_privileged_capture("ip", &["-4", "addr", "flush", "dev", iface])
+                .await?;
+            self.renew_dhcp().await?;
+            applied.push("DHCP".into());
+        }
+        if !profile.dns.is_empty() {
+            let mut args = vec!["dns", iface];
+            args.extend(profile.dns.iter().map(String::as_str));
+            self.run_privileged_capture("resolvectl", &args).await?;
+            applied.push(format!("DNS {}", profile.dns.join(" ")));
+        }
+        Ok(applied)
+    }
+
+    pub fn open_link_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let link = self.selected_hardware().and_then(|hw| hw.link.clone());
+        let autoneg = link.as_ref().and_then(|l| l.autoneg).unwrap_or(true);
+        let speed = d.speed_mbps.map(|s| s.to_string()).unwrap_or_default();
+        let duplex = link
+            .and_then(|l| l.duplex)
+            .unwrap_or_else(|| "full".into())
+            .to_lowercase();
+
+        self.form = Some(Form {
+            kind: FormKind::LinkSettings,
+            title: format!(" Link settings ({}) ", d.name),
+            fields: vec![
+                FormField {
+                    label: "Autoneg",
+                    hint: "on re-enables autonegotiation; off forces speed/duplex".into(),
+                    value: if autoneg { "on" } else { "off" }.into(),
+                },
+                FormField {
+                    label: "Speed",
+                    hint: "Mb/s, e.g. 100 or 1000 (ignored when autoneg is on)".into(),
+                    value: speed,
+                },
+                FormField {
+                    label: "Duplex",
+                    hint: "half or full (ignored when autoneg is on)".into(),
+                    value: duplex,
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    pub async fn apply_link_settings(
+        &mut self,
+        autoneg: bool,
+        speed: &str,
+        duplex: &str,
+    ) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let msg = if autoneg {
+            self.run_privileged_capture("ethtool", &["-s", &iface, "autoneg", "on"])
+                .await?;
+            format!("{iface}: autonegotiation enabled")
+        } else {
+            self.run_privileged_capture(
+                "ethtool",
+                &[
+                    "-s", &iface, "speed", speed, "duplex", duplex, "autoneg", "off",
+                ],
+            )
+            .await?;
+            format!("{iface}: forced {speed}/{duplex}")
+        };
+
+        self.last_action = Some(msg.clone());
+        self.invalidate_selected_hardware();
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    /// Drops cached ethtool data for the selected interface so the next tick re-reads it.
+    pub fn invalidate_selected_hardware(&mut self) {
+        if let Some(index) = self.selected_device().map(|d| d.ifindex) {
+            self.hardware.remove(&index);
+        }
+    }
+
+    /// Blinks the port LED (`ethtool -p`) without blocking the UI.
+    pub fn identify_nic(&mut self, seconds: u32) -> Result<()> {
+        let iface = self.selected_iface()?;
+        self.last_action = Some(format!("Blinking {iface} LED for {seconds}s"));
+        self.spawn_job(format!("{iface} identify"), async move {
+            let secs = seconds.to_string();
+            run_privileged_capture("ethtool", &["-p", &iface, &secs]).await?;
+            Ok("LED blink finished".to_string())
+        });
+        Ok(())
+    }
+
+    pub fn run_cable_test(&mut self) -> Result<()> {
+        let iface = self.selected_iface()?;
+        self.last_action = Some(format!("Cable test on {iface}"));
+        self.spawn_popup_job(format!("Cable test ({iface})"), async move {
+            ethtool::cable_diagnostics(&iface).await
+        });
+        Ok(())
+    }
+
+    pub fn open_rings_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let Some(rings) = self.selected_hardware().and_then(|hw| hw.rings) else {
+            self.set_toast(ToastKind::Error, "Ring sizes not available (ethtool -g)");
+            return;
+        };
+        let max = |m: Option<u32>| m.map(|m| format!("max {m}")).unwrap_or_default();
+        let (rx_hint, tx_hint) = (max(rings.rx_max), max(rings.tx_max));
+
+        self.form = Some(Form {
+            kind: FormKind::Rings,
+            title: format!(" Ring sizes ({}) ", d.name),
+            fields: vec![
+                FormField {
+                    label: "RX",
+                    hint: rx_hint,
+                    value: rings.rx.map(|v| v.to_string()).unwrap_or_default(),
+                },
+                FormField {
+                    label: "TX",
+                    hint: tx_hint,
+                    value: rings.tx.map(|v| v.to_string()).unwrap_or_default(),
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    pub fn open_channels_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let Some(channels) = self.selected_hardware().and_then(|hw| hw.channels) else {
+            self.set_toast(ToastKind::Error, "Channels not available (ethtool -l)");
+            return;
+        };
+        let cpus = queues::online_cpus().len();
+        let field = |label: &'static str, cur: Option<u32>, max: Option<u32>| FormField {
+            label,
+            hint: match max {
+                Some(max) if max > 0 => {
+                    format!("max {max}, CPUs online: {cpus}; empty to leave unchanged")
+                }
+                _ => "not supported by this NIC".into(),
+            },
+            value: cur.map(|v| v.to_string()).unwrap_or_default(),
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::Channels,
+            title: format!(" Channels ({}) ", d.name),
+            fields: vec![
+                field("Combined", channels.current.combined, channels.max.combined),
+                field("RX", channels.current.rx, channels.max.rx),
+                field("TX", channels.current.tx, channels.max.tx),
+            ],
+            focus: 0,
+        });
+    }
+
+    /// Whether `counts` are what the NIC already uses.
+    fn channels_unchanged(&self, counts: &[(&str, String)]) -> bool {
+        self.selected_hardware()
+            .and_then(|hw| hw.channels)
+            .is_some_and(|c| {
+                counts.iter().all(|(k, v)| {
+                    let cur = match *k {
+                        "combined" => c.current.combined,
+                        "rx" => c.current.rx,
+                        _ => c.current.tx,
+                    };
+                    cur.map(|c| c.to_string()).as_deref() == Some(v.as_str())
+                })
+            })
+    }
+
+    /// `ethtool -L`; drivers usually reset the NIC to apply it, so the link may drop briefly.
+    pub async fn apply_channels(&mut self, counts: &[(&str, String)]) -> Result<String> {
+        let iface = self.selected_iface()?;
+        if self.channels_unchanged(counts) {
+            return Ok(format!("{iface}: channels unchanged"));
+        }
+
+        let mut args = vec!["-L", iface.as_str()];
+        for (k, v) in counts {
+            args.push(k);
+            args.push(v);
+        }
+        self.run_privileged_capture("ethtool", &args).await?;
+
+        let summary: Vec<String> = counts.iter().map(|(k, v)| format!("{k} {v}")).collect();
+        let msg = format!("{iface}: channels set ({})", summary.join(", "));
+        self.last_action = Some(msg.clone());
+        self.invalidate_selected_hardware();
+        self.queues_fetched = None;
+        Ok(msg)
+    }
+
+    /// `ethtool -G` with only the sizes that differ from the current ones, so a driver that can
+    /// resize just one ring isn't handed the other.
+    pub async fn apply_rings(&mut self, rx: u32, tx: u32) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let current = self.selected_hardware().and_then(|hw| hw.rings);
+        let mut args = vec!["-G".to_string(), iface.clone()];
+        let mut changed = Vec::new();
+        for (key, label, size, cur) in [
+            ("rx", "RX", rx, current.and_then(|r| r.rx)),
+            ("tx", "TX", tx, current.and_then(|r| r.tx)),
+        ] {
+            if cur != Some(size) {
+                args.extend([key.to_string(), size.to_string()]);
+                changed.push(format!("{label} {size}"));
+            }
+        }
+        if changed.is_empty() {
+            return Ok(format!("{iface}: rings unchanged"));
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.run_privileged_capture("ethtool", &args).await?;
+
+        let msg = format!("{iface}: rings set to {}", changed.join(" / "));
+        self.last_action = Some(msg.clone());
+        self.invalidate_selected_hardware();
+        Ok(msg)
+    }
+
+    pub fn open_coalesce_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let Some(c) = self.selected_hardware().and_then(|hw| hw.coalesce.clone()) else {
+            self.set_toast(ToastKind::Error, "Coalescing not available (ethtool -c)");
+            return;
+        };
+        let onoff = |v: Option<bool>| match v {
+            Some(true) => "on".to_string(),
+            Some(false) => "off".to_string(),
+            None => String::new(),
+        };
+        let field = |label: &'static str, hint: &str, value: String| FormField {
+            label,
+            hint: hint.into(),
+            value,
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::Coalesce,
+            title: format!(" Interrupt coalescing ({}) ", d.name),
+            fields: vec![
+                field(
+                    "adaptive-rx",
+                    "on/off, empty to leave unchanged",
+                    onoff(c.adaptive_rx),
+                ),
+                field(
+                    "adaptive-tx",
+                    "on/off, empty to leave unchanged",
+                    onoff(c.adaptive_tx),
+                ),
+                field(
+                    "rx-usecs",
+                    "microseconds, empty to leave unchanged",
+                    c.param("rx-usecs").unwrap_or_default().to_string(),
+                ),
+                field(
+                    "tx-usecs",
+                    "microseconds, empty to leave unchanged",
+                    c.param("tx-usecs").unwrap_or_default().to_string(),
+                ),
+            ],
+            focus: 0,
+        });
+    }
+
+    pub async fn apply_coalesce(&mut self, params: &[(&str, String)]) -> Result<String> {
+        let iface = self.selected_iface()?;
+        if params.is_empty() {
+            return Ok(format!("{iface}: coalescing unchanged"));
+        }
+
+        let mut args = vec!["-C", iface.as_str()];
+        for (k, v) in params {
+            args.push(k);
+            args.push(v);
+        }
+        self.run_privileged_capture("ethtool", &args).await?;
+
+        let summary: Vec<String> = params.iter().map(|(k, v)| format!("{k} {v}")).collect();
+        let msg = format!("{iface}: coalescing set ({})", summary.join(", "));
+        self.last_action = Some(msg.clone());
+        self.invalidate_selected_hardware();
+        Ok(msg)
+    }
+
+    pub fn open_fec_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let name = d.name.clone();
+        let Some(fec) = self.selected_hardware().and_then(|hw| hw.fec.clone()) else {
+            self.set_toast(ToastKind::Error, "FEC not available (ethtool --show-fec)");
+            return;
+        };
+        self.form = Some(Form {
+            kind: FormKind::Fec,
+            title: format!(
+                " FEC ({name}, active: {}) ",
+                fec.active.as_deref().unwrap_or("-")
+            ),
+            fields: vec![FormField {
+                label: "Encoding",
+                hint: format!(
+                    "{}; several allowed, like \"auto rs\"",
+                    ethtool::FEC_ENCODINGS.join(", ")
+                ),
+                value: fec.configured.join(" ").to_lowercase(),
+            }],
+            focus: 0,
+        });
+    }
+
+    pub async fn set_fec(&mut self, encoding: &str) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let mut args = vec!["--set-fec", iface.as_str(), "encoding"];
+        args.extend(encoding.split(' '));
+        self.run_privileged_capture("ethtool", &args).await?;
+
+        let msg = format!("{iface}: FEC set to {encoding}");
+        self.last_action = Some(msg.clone());
+        self.invalidate_selected_hardware();
+        Ok(msg)
+    }
+
+    /// Asks before flipping EEE, since the link renegotiates to apply it.
+    pub fn confirm_eee(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let name = d.name.clone();
+        let Some(eee) = self.selected_hardware().and_then(|hw| hw.eee.clone()) else {
+            self.set_toast(ToastKind::Error, "EEE not available (ethtool --show-eee)");
+            return;
+        };
+        if !eee.supported {
+            self.set_toast(ToastKind::Info, format!("{name} doesn't support EEE"));
+            return;
         }
+        let prompt = if eee.enabled {
+            format!(
+                "Turn Energy Efficient Ethernet off on {name}?\n\nThe link renegotiates and drops for a few seconds. The NIC then stays at full power, which avoids dropouts some PHYs have when waking from low-power idle."
+            )
+        } else {
+            format!(
+                "Turn Energy Efficient Ethernet on for {name}?\n\nThe link renegotiates and drops for a few seconds."
+            )
+        };
+        self.ask(ConfirmAction::SetEee(!eee.enabled), prompt);
+    }
+
+    pub async fn set_eee(&mut self, on: bool) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let state = if on { "on" } else { "off" };
+        self.run_privileged_capture("ethtool", &["--set-eee", &iface, "eee", state])
+            .await?;
 
-        Err(std::io::Error::other("failed to spawn command").into())
+        let msg = format!("{iface}: EEE {state}");
+        self.last_action = Some(msg.clone());
+        self.invalidate_selected_hardware();
+        Ok(msg)
     }
 
-    pub async fn renew_dhcp(&mut self) -> Result<CmdOutput> {
+    /// The queue under the cursor of the Queues tab, or the first one before anything was
+    /// selected.
+    fn selected_queue(&self) -> Result<(usize, Queue)> {
+        if self.tab != DetailTab::Queues {
+            return Err(std::io::Error::other("IRQ affinity is changed on the Queues tab").into());
+        }
+        let Ok(Some(queues)) = &self.queues else {
+            return Err(std::io::Error::other("queues not read yet").into());
+        };
+        let i = self.queues_state.selected().unwrap_or(0);
+        queues
+            .queues
+            .iter()
+            .nth(i)
+            .map(|(n, q)| (*n, q.clone()))
+            .ok_or_else(|| std::io::Error::other("no queue selected").into())
+    }
+
+    pub fn open_irq_affinity_form(&mut self) {
+        let (n, queue) = match self.selected_queue() {
+            Ok(q) => q,
+            Err(e) => {
+                self.last_error = Some(e.to_string());
+                return;
+            }
+        };
+        let Some(first) = queue.irqs.first() else {
+            self.set_toast(ToastKind::Error, format!("Queue {n} has no IRQ of its own"));
+            return;
+        };
+        let irqs: Vec<String> = queue.irqs.iter().map(|i| i.number.to_string()).collect();
+        let online = queues::online_cpus();
+        self.form = Some(Form {
+            kind: FormKind::IrqAffinity,
+            title: format!(" IRQ affinity (queue {n}: IRQ {}) ", irqs.join(",")),
+            fields: vec![FormField {
+                label: "CPUs",
+                hint: format!("list like 2 or 0-3,8 (online: {})", online.len()),
+                value: first.cpus.clone(),
+            }],
+            focus: 0,
+        });
+    }
+
+    /// Points every interrupt of the selected queue at `cpus` (`smp_affinity_list`).
+    pub async fn apply_irq_affinity(&mut self, cpus: &str) -> Result<String> {
         let iface = self.selected_iface()?;
-        // systemd-networkd environments: try `networkctl renew`, else fall back to `reconfigure`.
-        let out = Command::new("networkctl")
-            .arg("renew")
-            .arg(&iface)
-            .output()
-            .await;
-
-        if let Ok(out) = out {
-            if out.status.success() {
-                self.last_action = Some(format!("Renewed DHCP on {iface}"));
-                return Ok(CmdOutput {
-                    program: "networkctl".to_string(),
-                    args: vec!["renew".to_string(), iface.clone()],
-                    used_sudo: false,
-                    status: out.status.code().unwrap_or(0),
-                    stdout: String::from_utf8_lossy(&out.stdout).trim().to_string(),
-                    stderr: String::from_utf8_lossy(&out.stderr).trim().to_string(),
-                });
-            }
-            let err = String::from_utf8_lossy(&out.stderr);
-            if err.contains("Unknown") || err.contains("invalid") {
-                let cap = self
-                    .run_privileged_capture("networkctl", &["reconfigure", &iface])
-                    .await?;
-                self.last_action = Some(format!("Reconfigured {iface}"));
-                return Ok(cap);
+        let (n, queue) = self.selected_queue()?;
+        for irq in &queue.irqs {
+            write_kernel_file(&format!("/proc/irq/{}/smp_affinity_list", irq.number), cpus).await?;
+        }
+
+        let msg = format!("{iface}: queue {n} interrupts on CPU {cpus}");
+        self.last_action = Some(msg.clone());
+        self.queues_fetched = None;
+        Ok(msg)
+    }
+
+    pub fn confirm_spread_irqs(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let plan = match &self.queues {
+            Ok(Some(q)) if self.tab == DetailTab::Queues => {
+                queues::spread_plan(q, &queues::online_cpus())
+            }
+            _ => {
+                self.last_error = Some("IRQ affinity is changed on the Queues tab".into());
+                return;
+            }
+        };
+        if plan.is_empty() {
+            self.set_toast(
+                ToastKind::Info,
+                format!("{} has no per-queue interrupts", d.name),
+            );
+            return;
+        }
+        let mut moves: Vec<String> = plan
+            .iter()
+            .take(8)
+            .map(|(irq, cpu)| format!("IRQ {} ({}) -> CPU {cpu}", irq.number, irq.name))
+            .collect();
+        if plan.len() > moves.len() {
+            moves.push(format!("... and {} more", plan.len() - moves.len()));
+        }
+        let prompt = format!(
+            "Spread the queue interrupts of {} across all cores?\n\n{}\n\nirqbalance, if it's running, may move them again.",
+            d.name,
+            moves.join("\n")
+        );
+        self.ask(ConfirmAction::SpreadIrqs, prompt);
+    }
+
+    /// One CPU per queue, see [`queues::spread_plan`].
+    pub async fn spread_irqs(&mut self) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let plan = match &self.queues {
+            Ok(Some(q)) => queues::spread_plan(q, &queues::online_cpus()),
+            _ => Vec::new(),
+        };
+        if plan.is_empty() {
+            return Err(std::io::Error::other(format!("{iface}: no queue interrupts")).into());
+        }
+        for (irq, cpu) in &plan {
+            let path = format!("/proc/irq/{}/smp_affinity_list", irq.number);
+            write_kernel_file(&path, &cpu.to_string()).await?;
+        }
+
+        let msg = format!(
+            "{iface}: {} interrupts moved, one CPU per queue",
+            plan.len()
+        );
+        self.last_action = Some(msg.clone());
+        self.queues_fetched = None;
+        Ok(msg)
+    }
+
+    /// The selected interface's SR-IOV state, as read for the SR-IOV tab.
+    fn shown_sriov(&self) -> Result<&Sriov> {
+        if self.tab != DetailTab::Sriov {
+            return Err(std::io::Error::other("VFs are managed on the SR-IOV tab").into());
+        }
+        match &self.sriov {
+            Ok(Some(sriov)) => Ok(sriov),
+            Ok(None) => {
+                let iface = self.selected_iface()?;
+                Err(std::io::Error::other(format!("{iface} has no SR-IOV support")).into())
+            }
+            Err(e) => Err(std::io::Error::other(e.clone()).into()),
+        }
+    }
+
+    /// The VF under the cursor of the SR-IOV tab, or the first one before anything was selected.
+    fn selected_vf(&self) -> Result<Vf> {
+        let sriov = self.shown_sriov()?;
+        let i = self.sriov_state.selected().unwrap_or(0);
+        sriov
+            .vfs
+            .get(i)
+            .cloned()
+            .ok_or_else(|| std::io::Error::other("no VF selected").into())
+    }
+
+    pub fn open_num_vfs_form(&mut self) {
+        let (total, current) = match self.shown_sriov() {
+            Ok(s) => (s.total_vfs, s.num_vfs),
+            Err(e) => {
+                self.last_error = Some(e.to_string());
+                return;
+            }
+        };
+        self.form = Some(Form {
+            kind: FormKind::NumVfs,
+            title: " Number of VFs ".into(),
+            fields: vec![FormField {
+                label: "VFs",
+                hint: format!("0-{total}, 0 removes them all"),
+                value: current.to_string(),
+            }],
+            focus: 0,
+        });
+    }
+
+    /// Writes `sriov_numvfs`, going through 0 when VFs exist already (the kernel refuses to
+    /// change a non-zero count directly).
+    pub async fn set_num_vfs(&mut self, n: u32) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let path = sriov::sysfs_dir(&iface).join("sriov_numvfs");
+        let path = path.to_string_lossy();
+        let current = match &self.sriov {
+            Ok(Some(s)) => s.num_vfs,
+            _ => 0,
+        };
+        if current > 0 && n > 0 {
+            write_kernel_file(&path, "0").await?;
+        }
+        write_kernel_file(&path, &n.to_string()).await?;
+
+        let msg = format!("{iface}: {n} VFs");
+        self.last_action = Some(msg.clone());
+        self.sriov_fetched = None;
+        self.sriov_state.select(None);
+        Ok(msg)
+    }
+
+    pub fn open_vf_form(&mut self) {
+        let vf = match self.selected_vf() {
+            Ok(vf) => vf,
+            Err(e) => {
+                self.last_error = Some(e.to_string());
+                return;
             }
+        };
+        let on_off = |v: Option<bool>| match v {
+            Some(true) => "on".to_string(),
+            Some(false) => "off".to_string(),
+            None => String::new(),
+        };
+        self.form = Some(Form {
+            kind: FormKind::VfSettings,
+            title: format!(" VF {} ", vf.number),
+            fields: vec![
+                FormField {
+                    label: "MAC",
+                    hint: "aa:bb:cc:dd:ee:ff, 00:00:00:00:00:00 lets the guest pick".into(),
+                    value: vf.mac.clone().unwrap_or_default(),
+                },
+                FormField {
+                    label: "VLAN",
+                    hint: "1-4094 tagged by the NIC, 0 for none".into(),
+                    value: vf.vlan().to_string(),
+                },
+                FormField {
+                    label: "Spoof check",
+                    hint: "on/off: drop frames from other source MACs".into(),
+                    value: on_off(vf.spoofchk),
+                },
+                FormField {
+                    label: "Trust",
+                    hint: "on/off: allow promiscuous mode and MAC changes".into(),
+                    value: on_off(vf.trust),
+                },
+                FormField {
+                    label: "Link state",
+                    hint: VF_LINK_STATES.join("/"),
+                    value: vf.link_state.clone().unwrap_or_default(),
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    /// `ip link set dev <pf> vf <n> mac ... vlan ...` with only the settings that changed.
+    pub async fn apply_vf_settings(
+        &mut self,
+        vf: u32,
+        settings: &[(&str, String)],
+    ) -> Result<String> {
+        let iface = self.selected_iface()?;
+        if settings.is_empty() {
+            return Ok(format!("{iface}: VF {vf} unchanged"));
+        }
+        let number = vf.to_string();
+        let mut args = vec!["link", "set", "dev", iface.as_str(), "vf", number.as_str()];
+        for (k, v) in settings {
+            args.push(k);
+            args.push(v);
+        }
+        self.run_privileged_capture("ip", &args).await?;
+
+        let summary: Vec<String> = settings.iter().map(|(k, v)| format!("{k} {v}")).collect();
+        let msg = format!("{iface}: VF {vf} set ({})", summary.join(", "));
+        self.last_action = Some(msg.clone());
+        self.sriov_fetched = None;
+        Ok(msg)
+    }
+
+    /// Everything listed, with the throughput and routes read so far.
+    pub fn snapshot(&self) -> Snapshot<'_> {
+        Snapshot {
+            taken_at: export::now_unix(),
+            interfaces: self
+                .devices
+                .iter()
+                .map(|d| {
+                    let throughput = self.throughput.get(&d.ifindex);
+                    InterfaceSnapshot {
+                        device: d,
+                        rx_rate: throughput.and_then(|t| t.rx.back().copied()),
+                        tx_rate: throughput.and_then(|t| t.tx.back().copied()),
+                        routes: self.routes.get(&d.ifindex).map(Vec::as_slice),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn open_export_form(&mut self) {
+        self.form = Some(Form {
+            kind: FormKind::ExportJson,
+            title: " Export interfaces as JSON ".into(),
+            fields: vec![FormField {
+                label: "File",
+                hint: "written as pretty-printed JSON; ~/ is your home".into(),
+                value: format!("ethtui-{}.json", export::now_unix()),
+            }],
+            focus: 0,
+        });
+    }
+
+    pub fn export_json(&mut self, path: &str) -> Result<String> {
+        // The terminal belongs to the TUI; `-` only makes sense for --export-json.
+        if path.is_empty() || path == "-" {
+            return Err(std::io::Error::other("enter a file to write").into());
+        }
+        let snapshot = self.snapshot();
+        let count = snapshot.interfaces.len();
+        let json = snapshot.to_json()?;
+        let dest = export::write_to(path, &json)?;
+        let msg = format!("Exported {count} interfaces to {dest}");
+        self.last_action = Some(msg.clone());
+        Ok(msg)
+    }
+
+    pub fn open_history_export_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let name = d.name.clone();
+        self.form = Some(Form {
+            kind: FormKind::ExportCsv,
+            title: format!(" Export {name} history as CSV "),
+            fields: vec![FormField {
+                label: "File",
+                hint: "RX/TX rates and ping windows; ~/ is your home".into(),
+                value: format!("ethtui-{name}-{}.csv", export::now_unix()),
+            }],
+            focus: 0,
+        });
+    }
+
+    /// Writes the selected interface's throughput samples and ping windows (finished pings and
+    /// the running one) to `path`.
+    pub fn export_history_csv(&mut self, path: &str) -> Result<String> {
+        if path.is_empty() || path == "-" {
+            return Err(std::io::Error::other("enter a file to write").into());
+        }
+        let Some(d) = self.selected_device() else {
+            return Err(std::io::Error::other("no interface selected").into());
+        };
+        let name = d.name.clone();
+        let mut latency = self.latency_history.get(&name).cloned().unwrap_or_default();
+        if let Some(ping) = &self.ping
+            && ping.iface == name
+        {
+            latency.extend(ping.history.iter().copied());
         }
+        let throughput = self.throughput.get(&d.ifindex);
+        let rows = throughput.map_or(0, |t| t.at.len()) + latency.len();
+        if rows == 0 {
+            return Err(
+                std::io::Error::other(format!("no history collected for {name} yet")).into(),
+            );
+        }
+        let csv = export::history_csv(throughput, &latency);
+        let dest = export::write_to(path, &csv)?;
+        let msg = format!("Exported {rows} samples of {name} to {dest}");
+        self.last_action = Some(msg.clone());
+        Ok(msg)
+    }
+
+    pub fn open_wol_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let link = self.selected_hardware().and_then(|hw| hw.link.clone());
+        let supported = link
+            .as_ref()
+            .and_then(|l| l.wol_supported.clone())
+            .unwrap_or_else(|| "?".into());
+        // Default to flipping the current state, which is what users usually want.
+        let mode = if link.is_some_and(|l| l.magic_packet_wol()) {
+            "d"
+        } else {
+            "g"
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::WakeOnLan,
+            title: format!(" Wake-on-LAN ({}) ", d.name),
+            fields: vec![
+                FormField {
+                    label: "Mode",
+                    hint: format!("g = magic packet, d = disabled (supported: {supported})"),
+                    value: mode.into(),
+                },
+                FormField {
+                    label: "Persist",
+                    hint: "yes writes /etc/systemd/network/50-ethtui-<iface>.link".into(),
+                    value: "no".into(),
+                },
+            ],
+            focus: 0,
+        });
+    }
 
-        let cap = self
-            .run_privileged_capture("networkctl", &["renew", &iface])
+    pub async fn apply_wol(&mut self, enable: bool, persist: bool) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let mode = if enable { "g" } else { "d" };
+        self.run_privileged_capture("ethtool", &["-s", &iface, "wol", mode])
             .await?;
-        self.last_action = Some(format!("Renewed DHCP on {iface}"));
-        Ok(cap)
+
+        let mut msg = format!(
+            "{iface}: Wake-on-LAN {}",
+            if enable { "magic packet" } else { "disabled" }
+        );
+
+        if persist {
+            let mac = self
+                .selected_device()
+                .and_then(|d| d.mac.clone())
+                .ok_or_else(|| std::io::Error::other("no MAC address to match on"))?;
+            let path = format!("/etc/systemd/network/50-ethtui-{iface}.link");
+            write_privileged_file(&path, &wol_link_file(&mac, enable)).await?;
+            msg.push_str(&format!("\nPersisted in {path}"));
+        }
+
+        self.last_action = Some(msg.lines().next().unwrap_or_default().to_string());
+        self.invalidate_selected_hardware();
+        Ok(msg)
+    }
+
+    pub fn open_mac_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+        let permanent = self
+            .selected_hardware()
+            .and_then(|hw| hw.permanent_mac.clone())
+            .unwrap_or_else(|| "unknown".into());
+
+        self.form = Some(Form {
+            kind: FormKind::MacAddress,
+            title: format!(" MAC address ({}) ", d.name),
+            fields: vec![FormField {
+                label: "MAC",
+                hint: format!(
+                    "aa:bb:cc:dd:ee:ff, \"random\" (locally administered) or \"permanent\" ({permanent})"
+                ),
+                value: d.mac.clone().unwrap_or_default(),
+            }],
+            focus: 0,
+        });
+    }
+
+    pub async fn set_mac_address(&mut self, mac: &str) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let previous = self.selected_device().and_then(|d| d.mac.clone());
+        self.run_privileged_capture("ip", &["link", "set", "dev", &iface, "address", mac])
+            .await?;
+        if let Some(previous) = previous.filter(|p| !p.eq_ignore_ascii_case(mac)) {
+            self.push_undo(Undo::Mac {
+                iface: iface.clone(),
+                mac: previous,
+            });
+        }
+
+        let msg = format!("{iface}: MAC set to {mac}");
+        self.last_action = Some(msg.clone());
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    pub async fn set_mtu(&mut self, mtu: u32) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let previous = read_mtu(&iface);
+        let mtu_s = mtu.to_string();
+        self.run_privileged_capture("ip", &["link", "set", "dev", &iface, "mtu", &mtu_s])
+            .await?;
+        if let Some(previous) = previous.filter(|p| *p != mtu) {
+            self.push_undo(Undo::Mtu {
+                iface: iface.clone(),
+                mtu: previous,
+            });
+        }
+
+        let msg = format!("{iface}: MTU set to {mtu}");
+        self.last_action = Some(msg.clone());
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    pub fn open_ping_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::Ping,
+            title: format!(" Ping ({}) ", d.name),
+            fields: vec![
+                FormField {
+                    label: "Target",
+                    hint: "host or address; defaults to the IPv4 gateway".into(),
+                    value: d.gateway_v4.clone().unwrap_or_default(),
+                },
+                FormField {
+                    label: "Window",
+                    hint: "replies per rolling loss/jitter window (2-120)".into(),
+                    value: DEFAULT_PING_WINDOW.to_string(),
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    /// Starts pinging `target` out of the selected interface; the chart stays up until Esc.
+    pub fn start_ping(&mut self, target: &str, window: usize) -> Result<String> {
+        let iface = self.selected_iface()?;
+        self.ping = Some(Ping::start(&iface, target, window)?);
+        self.last_action = Some(format!("Ping {target} via {iface}"));
+        Ok(format!("Pinging {target} via {iface}"))
+    }
+
+    /// Stops the running ping (dropping it kills the process), keeping its loss/jitter windows
+    /// for the interface's details.
+    pub fn stop_ping(&mut self) {
+        if let Some(ping) = self.ping.take() {
+            self.latency_history
+                .entry(ping.iface)
+                .or_default()
+                .extend(ping.history);
+        }
+    }
+
+    pub fn open_trace_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::Trace,
+            title: format!(" Trace route ({}) ", d.name),
+            fields: vec![FormField {
+                label: "Target",
+                hint: "host or address (mtr when installed, otherwise traceroute)".into(),
+                value: DEFAULT_TRACE_TARGET.into(),
+            }],
+            focus: 0,
+        });
+    }
+
+    pub fn start_trace(&mut self, target: &str) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let trace = Trace::start(&iface, target)?;
+        let msg = format!("Tracing {target} via {iface} ({})", trace.program);
+        self.trace = Some(trace);
+        self.last_action = Some(msg.clone());
+        Ok(msg)
+    }
+
+    /// Stops the running trace; dropping it kills the process.
+    pub fn stop_trace(&mut self) {
+        self.trace = None;
+    }
+
+    pub fn open_speedtest_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::SpeedTest,
+            title: format!(" Speed test ({}) ", d.name),
+            fields: vec![FormField {
+                label: "Server",
+                hint: "iperf3 server as host[:port] ([v6]:port), or an http(s):// URL to download"
+                    .into(),
+                value: self.last_speedtest_target.clone(),
+            }],
+            focus: 0,
+        });
+    }
+
+    pub fn start_speedtest(&mut self, target: &str) -> Result<String> {
+        let d = self
+            .selected_device()
+            .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+        let source = d
+            .ipv4
+            .first()
+            .and_then(|cidr| cidr.split('/').next())
+            .ok_or_else(|| std::io::Error::other(format!("{} has no IPv4 address", d.name)))?
+            .to_string();
+        let iface = d.name.clone();
+
+        self.speedtest = Some(SpeedTest::start(&iface, &source, target)?);
+        self.last_speedtest_target = target.to_string();
+        self.last_action = Some(format!("Speed test to {target} via {iface}"));
+        Ok(format!("Speed test to {target} via {iface} started"))
+    }
+
+    /// Stops a running speed test (or closes a finished one); dropping it kills the process.
+    pub fn stop_speedtest(&mut self) {
+        self.speedtest = None;
+    }
+
+    /// Sweeps the selected interface's first IPv4 subnet; the host list stays up until Esc.
+    pub fn start_scan(&mut self) -> Result<String> {
+        let d = self
+            .selected_device()
+            .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+        let cidr = d
+            .ipv4
+            .first()
+            .ok_or_else(|| std::io::Error::other(format!("{} has no IPv4 address", d.name)))?;
+        let scan = Scan::start(&d.name, cidr)?;
+        let msg = format!("Scanning {} via {}", scan.subnet, scan.iface);
+        if self.oui.is_none() {
+            self.oui = Some(load_oui_db());
+        }
+        self.scan = Some(scan);
+        self.scan_state = TableState::default();
+        self.last_action = Some(msg.clone());
+        Ok(msg)
+    }
+
+    /// Stops a running scan (or closes a finished one); dropping it aborts the sweep.
+    pub fn stop_scan(&mut self) {
+        self.scan = None;
+    }
+
+    /// Browses mDNS services seen on the selected interface until Esc.
+    pub fn start_mdns(&mut self) -> Result<String> {
+        let iface = self.selected_iface()?;
+        self.mdns = Some(MdnsBrowser::start(&iface)?);
+        self.mdns_state = TableState::default();
+        let msg = format!("Browsing mDNS services on {iface}");
+        self.last_action = Some(msg.clone());
+        Ok(msg)
+    }
+
+    /// Stops the service browser; dropping it kills avahi-browse.
+    pub fn stop_mdns(&mut self) {
+        self.mdns = None;
+    }
+
+    /// Moves the cursor of the host or service list popup that's open.
+    pub fn scroll_popup(&mut self, down: bool) {
+        if let Some(scan) = &self.scan {
+            move_cursor(&mut self.scan_state, scan.hosts.len(), down);
+        } else if let Some(mdns) = &self.mdns {
+            move_cursor(&mut self.mdns_state, mdns.services.len(), down);
+        }
+    }
+
+    async fn poll_speedtest(&mut self) {
+        let Some(st) = &mut self.speedtest else {
+            return;
+        };
+        let rx_rate = self
+            .devices
+            .iter()
+            .find(|d| d.name == st.iface)
+            .and_then(|d| self.throughput.get(&d.ifindex))
+            .map_or(0, |tp| tp.rx_rate());
+        if !st.poll(rx_rate) {
+            return;
+        }
+
+        let (iface, result, ok) = match (&st.summary, &st.error) {
+            (Some(summary), _) => (st.iface.clone(), summary.clone(), true),
+            (None, Some(err)) => (st.iface.clone(), err.clone(), false),
+            (None, None) => (st.iface.clone(), "no result".to_string(), false),
+        };
+        let msg = format!("{iface}: speed test {result}");
+        let kind = if ok {
+            ToastKind::Success
+        } else {
+            ToastKind::Error
+        };
+        self.set_toast(kind, msg.clone());
+        self.notify(NotifyEvent::SpeedTest, msg);
+    }
+
+    pub fn open_dns_test_form(&mut self) {
+        let Some(d) = self.selected_device() else {
+            return;
+        };
+
+        self.form = Some(Form {
+            kind: FormKind::DnsTest,
+            title: format!(" DNS test ({}) ", d.name),
+            fields: vec![
+                FormField {
+                    label: "Hostname",
+                    hint: format!(
+                        "resolved by each of {}'s DNS servers, out of {}",
+                        d.name, d.name
+                    ),
+                    value: self.last_dns_test_host.clone(),
+                },
+                FormField {
+                    label: "Benchmark",
+                    hint: format!(
+                        "yes: also public resolvers, {DNS_BENCHMARK_QUERIES} queries each, ranked by median"
+                    ),
+                    value: "no".into(),
+                },
+            ],
+            focus: 0,
+        });
+    }
+
+    /// Asks every DNS server of the selected interface for `host` at once, or benchmarks them
+    /// against the public resolvers; the results stay up until Esc.
+    pub fn start_dns_test(&mut self, host: &str, benchmark: bool) -> Result<String> {
+        let d = self
+            .selected_device()
+            .ok_or_else(|| std::io::Error::other("no interface selected"))?;
+        let test = DnsTest::start(&d.name, host, &d.dns, benchmark)?;
+        let msg = if benchmark {
+            format!(
+                "Benchmarking {} DNS servers via {}",
+                test.servers.len(),
+                test.iface
+            )
+        } else {
+            format!(
+                "Resolving {host} with {} DNS servers via {}",
+                test.servers.len(),
+                test.iface
+            )
+        };
+        self.dns_test = Some(test);
+        self.last_dns_test_host = host.to_string();
+        self.last_action = Some(msg.clone());
+        Ok(msg)
+    }
+
+    /// Closes the DNS test; dropping it stops queries still waiting for an answer.
+    pub fn stop_dns_test(&mut self) {
+        self.dns_test = None;
+    }
+
+    fn poll_dns_test(&mut self) {
+        let Some(test) = &mut self.dns_test else {
+            return;
+        };
+        if test.finished {
+            return;
+        }
+        test.poll();
+        if !test.finished {
+            return;
+        }
+        if test.benchmark() {
+            let msg = match test.ranked().first().map(|s| (s, s.median_ms())) {
+                Some((fastest, Some(median))) => format!(
+                    "{}: fastest DNS server {}{} ({median:.1} ms median)",
+                    test.iface,
+                    fastest.server,
+                    fastest
+                        .operator
+                        .map(|o| format!(" ({o})"))
+                        .unwrap_or_default()
+                ),
+                _ => format!("{}: no DNS server answered", test.iface),
+            };
+            self.set_toast(ToastKind::Info, msg);
+            return;
+        }
+        let (resolved, total) = (test.resolved(), test.servers.len());
+        let msg = format!(
+            "{}: {resolved} of {total} DNS servers resolved {}",
+            test.iface, test.host
+        );
+        let kind = if resolved == total {
+            ToastKind::Success
+        } else {
+            ToastKind::Error
+        };
+        self.set_toast(kind, msg);
+    }
+
+    /// Promiscuous mode needs the key twice within a few seconds; returns `None` while armed.
+    pub async fn toggle_promisc_guarded(&mut self) -> Option<Result<String>> {
+        let d = self.selected_device()?;
+        // Armed for a different interface means the selection moved in between.
+        let armed = self
+            .promisc_armed
+            .as_ref()
+            .is_some_and(|(iface, until)| *iface == d.name && Instant::now() < *until);
+        if !armed {
+            let next = if d.promisc() { "off" } else { "on" };
+            let msg = format!(
+                "Press p again to turn promiscuous mode {next} on {}",
+                d.name
+            );
+            self.promisc_armed = Some((d.name.clone(), Instant::now() + Duration::from_secs(3)));
+            self.set_toast(ToastKind::Info, msg);
+            return None;
+        }
+
+        self.promisc_armed = None;
+        Some(self.toggle_promisc().await)
+    }
+
+    async fn toggle_promisc(&mut self) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let on = !self.selected_device().is_some_and(|d| d.promisc());
+        let state = if on { "on" } else { "off" };
+        self.run_privileged_capture("ip", &["link", "set", "dev", &iface, "promisc", state])
+            .await?;
+
+        let msg = format!("{iface}: promiscuous mode {state}");
+        self.last_action = Some(msg.clone());
+        self.refresh_selected();
+        Ok(msg)
+    }
+
+    pub fn toggle_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::Devices => Focus::Details,
+            Focus::Details => Focus::Devices,
+        };
+    }
+
+    /// Moves the cursor of the list shown in the current tab, if it has one.
+    pub fn scroll_details(&mut self, down: bool) {
+        if self.tab == DetailTab::Overview {
+            let len = self
+                .selected_device()
+                .map_or(0, |d| d.ipv4.len() + d.ipv6.len());
+            move_cursor(&mut self.address_state, len, down);
+        } else if self.tab == DetailTab::Offloads {
+            let len = self.selected_hardware().map_or(0, |hw| hw.offloads.len());
+            move_cursor(&mut self.offloads_state, len, down);
+        } else if self.tab == DetailTab::Queues {
+            let len = self
+                .queues
+                .as_ref()
+                .map_or(0, |q| q.as_ref().map_or(0, |q| q.queues.len()));
+            move_cursor(&mut self.queues_state, len, down);
+        } else if self.tab == DetailTab::Driver {
+            let len = self.shown_driver_stats().len();
+            move_cursor(&mut self.driver_stats_state, len, down);
+        } else if self.tab == DetailTab::Sriov {
+            let len = self
+                .sriov
+                .as_ref()
+                .map_or(0, |s| s.as_ref().map_or(0, |s| s.vfs.len()));
+            move_cursor(&mut self.sriov_state, len, down);
+        } else if self.tab == DetailTab::Routes {
+            let len = self.selected_routes().map_or(0, Vec::len);
+            move_cursor(&mut self.routes_state, len, down);
+        } else if self.tab == DetailTab::Connections {
+            move_cursor(&mut self.connections_state, self.connections.len(), down);
+        } else if self.tab == DetailTab::Firewall {
+            let len = self.firewall.as_ref().map_or(0, |(_, rules)| rules.len());
+            move_cursor(&mut self.firewall_state, len, down);
+        } else if self.tab == DetailTab::History {
+            let len = self.selected_device().map_or(0, |d| {
+                self.history
+                    .as_ref()
+                    .map_or(0, |h| h.for_iface(&d.name).count())
+            });
+            move_cursor(&mut self.history_state, len, down);
+        }
+    }
+
+    pub fn set_tab(&mut self, tab: DetailTab) {
+        self.tab = tab;
+        self.details_scroll = 0;
+    }
+
+    /// Moves a text tab by half a page; the renderer clamps it to the end of the text. The
+    /// Driver tab's cursor moves by a whole page instead.
+    pub fn page_details(&mut self, down: bool) {
+        if self.tab == DetailTab::Driver {
+            let len = self.shown_driver_stats().len();
+            let page = self.details_height.max(1) as usize;
+            let i = self.driver_stats_state.selected().unwrap_or(0);
+            let i = if down {
+                (i + page).min(len.saturating_sub(1))
+            } else {
+                i.saturating_sub(page)
+            };
+            self.driver_stats_state.select((len > 0).then_some(i));
+            return;
+        }
+        let step = (self.details_height / 2).max(1);
+        self.details_scroll = if down {
+            self.details_scroll.saturating_add(step)
+        } else {
+            self.details_scroll.saturating_sub(step)
+        };
+    }
+
+    pub async fn toggle_selected_offload(&mut self) -> Result<String> {
+        let iface = self.selected_iface()?;
+        let offload = self
+            .offloads_state
+            .selected()
+            .and_then(|i| self.selected_hardware()?.offloads.get(i).cloned())
+            .ok_or_else(|| std::io::Error::other("no offload selected"))?;
+        if offload.fixed {
+            return Err(
+                std::io::Error::other(format!("{} is fixed by the driver", offload.name)).into(),
+            );
+        }
+
+        let state = if offload.enabled { "off" } else { "on" };
+        self.run_privileged_capture("ethtool", &["-K", &iface, &offload.name, state])
+            .await?;
+
+        let msg = format!("{iface}: {} {state}", offload.name);
+        self.last_action = Some(msg.clone());
+        self.invalidate_selected_hardware();
+        self.load_selected_hardware().await;
+        Ok(msg)
+    }
+}
+
+/// Only the first matching `.link` file applies, so this repeats the policies from
+/// systemd's `99-default.link` to keep interface naming unchanged.
+fn wol_link_file(mac: &str, enable: bool) -> String {
+    format!(
+        "# Written by ethtui\n\
+         [Match]\n\
+         MACAddress={mac}\n\
+         \n\
+         [Link]\n\
+         NamePolicy=keep kernel database onboard slot path\n\
+         AlternativeNamesPolicy=database onboard slot path\n\
+         MACAddressPolicy=persistent\n\
+         WakeOnLan={}\n",
+        if enable { "magic" } else { "off" }
+    )
+}
+
+/// A substring, or a glob when `pattern` has `*` or `?`.
+fn filter_match(pattern: &str, name: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        glob_match(pattern, name)
+    } else {
+        name.contains(pattern)
+    }
+}
+
+fn move_cursor(state: &mut TableState, len: usize, down: bool) {
+    if len == 0 {
+        state.select(None);
+        return;
+    }
+    let i = match state.selected() {
+        Some(i) if down => (i + 1).min(len - 1),
+        Some(i) => i.saturating_sub(1),
+        None => 0,
+    };
+    state.select(Some(i));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tab_digits() {
+        let cases = [
+            ('1', Some(DetailTab::Overview)),
+            ('5', Some(DetailTab::Coalescing)),
+            ('6', Some(DetailTab::Routes)),
+            ('9', Some(DetailTab::Qos)),
+            ('0', Some(DetailTab::Firewall)),
+            ('x', None),
+        ];
+        for (c, want) in cases {
+            assert_eq!(DetailTab::from_digit(c), want, "{c}");
+        }
     }
 }
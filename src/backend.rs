@@ -0,0 +1,337 @@
+use crate::app::{CmdOutput, run_privileged_capture};
+use crate::ifupdown;
+use anyhow::Result;
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use tokio::process::Command;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Whatever owns an interface's configuration. Actions go through the owner so they don't get
+/// undone by it a moment later (e.g. NetworkManager re-upping a link set down with `ip`).
+pub trait NetworkBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Restarts or renews DHCP on the interface.
+    fn renew<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput>;
+
+    /// Gives the DHCP lease back to the server; the interface is left without an address.
+    fn release<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput>;
+
+    /// What [`release`](Self::release) costs, for its confirmation: backends without a plain
+    /// release take the whole interface down.
+    fn release_warning(&self, _iface: &str, address: &str) -> String {
+        format!("This drops {address} and any connectivity over it until the lease is renewed (n).")
+    }
+
+    /// Brings the interface administratively up or down.
+    fn set_link<'a>(&'a self, iface: &'a str, up: bool) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move {
+            let state = if up { "up" } else { "down" };
+            run_privileged_capture("ip", &["link", "set", iface, state]).await
+        })
+    }
+
+    /// Where the interface's configuration comes from (a file, a connection profile...).
+    fn config_source<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, Option<String>>;
+}
+
+pub struct Networkd;
+pub struct NetworkManager;
+pub struct Ifupdown;
+pub struct Dhcpcd;
+pub struct Dhclient;
+pub struct Iproute2;
+
+fn ifindex(iface: &str) -> Option<String> {
+    fs::read_to_string(Path::new("/sys/class/net").join(iface).join("ifindex"))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// networkd's runtime state for a link (`KEY=value`), e.g. `ADMIN_STATE` and `NETWORK_FILE`.
+fn networkd_link_state(iface: &str, key: &str) -> Option<String> {
+    let path = Path::new("/run/systemd/netif/links").join(ifindex(iface)?);
+    let content = fs::read_to_string(path).ok()?;
+    content.lines().find_map(|line| {
+        line.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix('='))
+            .map(|v| v.to_string())
+    })
+}
+
+impl NetworkBackend for Networkd {
+    fn name(&self) -> &'static str {
+        "systemd-networkd"
+    }
+
+    fn renew<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move {
+            match run_privileged_capture("networkctl", &["renew", iface]).await {
+                Ok(out) => Ok(out),
+                // Older networkd has no `renew`, and it refuses links without a DHCP client.
+                Err(e)
+                    if e.to_string().contains("Unknown") || e.to_string().contains("invalid") =>
+                {
+                    run_privileged_capture("networkctl", &["reconfigure", iface]).await
+                }
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    /// networkd has no release verb; `down` releases the lease and `u` brings the link back.
+    fn release<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move { run_privileged_capture("networkctl", &["down", iface]).await })
+    }
+
+    fn release_warning(&self, iface: &str, address: &str) -> String {
+        format!(
+            "networkd has no lease release; `networkctl down` takes {iface} down, dropping {address} and any connectivity over it until it is brought up again (u)."
+        )
+    }
+
+    fn set_link<'a>(&'a self, iface: &'a str, up: bool) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move {
+            let verb = if up { "up" } else { "down" };
+            run_privileged_capture("networkctl", &[verb, iface]).await
+        })
+    }
+
+    fn config_source<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move { Ok(networkd_link_state(iface, "NETWORK_FILE")) })
+    }
+}
+
+impl NetworkBackend for NetworkManager {
+    fn name(&self) -> &'static str {
+        "NetworkManager"
+    }
+
+    fn renew<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        // Re-activating the device restarts DHCP on its current connection.
+        Box::pin(
+            async move { run_privileged_capture("nmcli", &["device", "connect", iface]).await },
+        )
+    }
+
+    /// Disconnecting the device releases its lease.
+    fn release<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(
+            async move { run_privileged_capture("nmcli", &["device", "disconnect", iface]).await },
+        )
+    }
+
+    fn release_warning(&self, iface: &str, address: &str) -> String {
+        format!(
+            "`nmcli device disconnect` takes {iface} down, dropping {address} and any connectivity over it until it is connected again (u)."
+        )
+    }
+
+    fn set_link<'a>(&'a self, iface: &'a str, up: bool) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move {
+            let verb = if up { "connect" } else { "disconnect" };
+            run_privileged_capture("nmcli", &["device", verb, iface]).await
+        })
+    }
+
+    fn config_source<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let out = Command::new("nmcli")
+                .args(["-t", "-g", "GENERAL.CONNECTION", "device", "show", iface])
+                .output()
+                .await?;
+            let name = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            Ok((!name.is_empty()).then(|| format!("connection \"{name}\"")))
+        })
+    }
+}
+
+/// ifupdown has no renew or release of its own; taking the interface down runs its DHCP client's
+/// release, and bringing it up starts a new one.
+impl NetworkBackend for Ifupdown {
+    fn name(&self) -> &'static str {
+        "ifupdown"
+    }
+
+    fn renew<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move {
+            run_privileged_capture("ifdown", &[iface]).await?;
+            run_privileged_capture("ifup", &[iface]).await
+        })
+    }
+
+    fn release<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move { run_privileged_capture("ifdown", &[iface]).await })
+    }
+
+    fn release_warning(&self, iface: &str, address: &str) -> String {
+        format!(
+            "`ifdown` takes {iface} down, dropping {address} and any connectivity over it until it is brought up again (u)."
+        )
+    }
+
+    fn set_link<'a>(&'a self, iface: &'a str, up: bool) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move {
+            let program = if up { "ifup" } else { "ifdown" };
+            run_privileged_capture(program, &[iface]).await
+        })
+    }
+
+    fn config_source<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move { Ok(ifupdown::interfaces_for(iface).and_then(|i| i.source(iface))) })
+    }
+}
+
+impl NetworkBackend for Dhcpcd {
+    fn name(&self) -> &'static str {
+        "dhcpcd"
+    }
+
+    fn renew<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move { run_privileged_capture("dhcpcd", &["-n", iface]).await })
+    }
+
+    fn release<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move { run_privileged_capture("dhcpcd", &["-k", iface]).await })
+    }
+
+    fn config_source<'a>(&'a self, _iface: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move {
+            Ok(Path::new("/etc/dhcpcd.conf")
+                .exists()
+                .then(|| "/etc/dhcpcd.conf".to_string()))
+        })
+    }
+}
+
+/// ISC dhclient has no "renew" verb: release the lease, then request a new one.
+async fn dhclient_renew(iface: &str) -> Result<CmdOutput> {
+    run_privileged_capture("dhclient", &["-r", iface]).await?;
+    run_privileged_capture("dhclient", &[iface]).await
+}
+
+impl NetworkBackend for Dhclient {
+    fn name(&self) -> &'static str {
+        "dhclient"
+    }
+
+    fn renew<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(dhclient_renew(iface))
+    }
+
+    fn release<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move { run_privileged_capture("dhclient", &["-r", iface]).await })
+    }
+
+    fn config_source<'a>(&'a self, _iface: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move {
+            Ok(Path::new("/etc/dhcp/dhclient.conf")
+                .exists()
+                .then(|| "/etc/dhcp/dhclient.conf".to_string()))
+        })
+    }
+}
+
+fn has_program(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+}
+
+fn no_dhcp_client(iface: &str) -> anyhow::Error {
+    std::io::Error::other(format!(
+        "no DHCP client for {iface} (install dhcpcd or dhclient)"
+    ))
+    .into()
+}
+
+impl NetworkBackend for Iproute2 {
+    fn name(&self) -> &'static str {
+        "iproute2"
+    }
+
+    /// Nothing manages the link; use whichever standalone DHCP client is installed.
+    fn renew<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move {
+            if has_program("dhcpcd") {
+                run_privileged_capture("dhcpcd", &["-n", iface]).await
+            } else if has_program("dhclient") {
+                dhclient_renew(iface).await
+            } else {
+                Err(no_dhcp_client(iface))
+            }
+        })
+    }
+
+    fn release<'a>(&'a self, iface: &'a str) -> BoxFuture<'a, CmdOutput> {
+        Box::pin(async move {
+            if has_program("dhcpcd") {
+                run_privileged_capture("dhcpcd", &["-k", iface]).await
+            } else if has_program("dhclient") {
+                run_privileged_capture("dhclient", &["-r", iface]).await
+            } else {
+                Err(no_dhcp_client(iface))
+            }
+        })
+    }
+
+    fn config_source<'a>(&'a self, _iface: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move { Ok(None) })
+    }
+}
+
+/// True when NetworkManager is running and the interface isn't `unmanaged` by it.
+async fn networkmanager_manages(iface: &str) -> bool {
+    let Ok(out) = Command::new("nmcli")
+        .args(["-t", "-f", "DEVICE,STATE", "device"])
+        .output()
+        .await
+    else {
+        return false;
+    };
+    if !out.status.success() {
+        // nmcli exits non-zero when the daemon isn't running.
+        return false;
+    }
+    String::from_utf8_lossy(&out.stdout).lines().any(|line| {
+        line.split_once(':')
+            .is_some_and(|(dev, state)| dev == iface && state != "unmanaged")
+    })
+}
+
+/// ifup is installed and an `iface` stanza configures the interface.
+fn ifupdown_manages(iface: &str) -> bool {
+    has_program("ifup") && ifupdown::interfaces_for(iface).is_some_and(|i| i.configures(iface))
+}
+
+fn networkd_manages(iface: &str) -> bool {
+    networkd_link_state(iface, "ADMIN_STATE").is_some_and(|s| s != "unmanaged")
+}
+
+async fn process_running(name: &str) -> bool {
+    Command::new("pgrep")
+        .args(["-x", name])
+        .output()
+        .await
+        .is_ok_and(|o| o.status.success())
+}
+
+/// Picks the backend that owns `iface`: NetworkManager, then networkd, then ifupdown, then a
+/// running dhcpcd or dhclient, falling back to plain iproute2.
+pub async fn detect(iface: &str) -> &'static dyn NetworkBackend {
+    if networkmanager_manages(iface).await {
+        &NetworkManager
+    } else if networkd_manages(iface) {
+        &Networkd
+    } else if ifupdown_manages(iface) {
+        &Ifupdown
+    } else if process_running("dhcpcd").await {
+        &Dhcpcd
+    } else if process_running("dhclient").await {
+        &Dhclient
+    } else {
+        &Iproute2
+    }
+}
@@ -0,0 +1,181 @@
+use crate::net::iproute2_json;
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::process::Command;
+
+/// An eBPF program hooked into an interface's data path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BpfProgram {
+    /// `xdp`, or where tc runs it: `tc ingress`, `tc egress` (`tcx ...` for link-based ones).
+    pub hook: String,
+    /// XDP only: `driver`, `generic` or `offload`.
+    pub mode: Option<String>,
+    pub id: u32,
+    /// Unknown when the program can't be looked up without more privileges.
+    pub name: Option<String>,
+}
+
+impl BpfProgram {
+    /// `xdp_pass (id 42, generic)`.
+    pub fn describe(&self) -> String {
+        let mut details = vec![format!("id {}", self.id)];
+        details.extend(self.mode.clone());
+        match &self.name {
+            Some(name) => format!("{name} ({})", details.join(", ")),
+            None => format!("unnamed program ({})", details.join(", ")),
+        }
+    }
+}
+
+/// XDP and tc programs on `iface`. bpftool knows about tcx links too; without it, `ip` and
+/// `tc` still show XDP and classic tc bpf filters.
+pub async fn attached(iface: &str) -> Result<Vec<BpfProgram>> {
+    if let Some(programs) = bpftool_net(iface).await {
+        return Ok(programs);
+    }
+    let mut programs = xdp_from_ip(iface).await?;
+    for dir in ["ingress", "egress"] {
+        let filters: Vec<TcFilter> = iproute2_json("tc", &["filter", "show", "dev", iface, dir])
+            .await
+            .unwrap_or_default();
+        programs.extend(filters.into_iter().filter_map(|f| {
+            let prog = f.options?.prog?;
+            (f.kind == "bpf").then(|| BpfProgram {
+                hook: format!("tc {dir}"),
+                mode: None,
+                id: prog.id,
+                name: prog.name,
+            })
+        }));
+    }
+    Ok(programs)
+}
+
+#[derive(Debug, Deserialize)]
+struct ProgInfo {
+    id: u32,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XdpAttachment {
+    mode: u8,
+    #[serde(default)]
+    prog: Option<ProgInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XdpInfo {
+    mode: u8,
+    #[serde(default)]
+    prog: Option<ProgInfo>,
+    /// One per mode; older kernels only give the program at the top.
+    #[serde(default)]
+    attached: Vec<XdpAttachment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkXdp {
+    #[serde(default)]
+    xdp: Option<XdpInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TcFilterOptions {
+    #[serde(default)]
+    prog: Option<ProgInfo>,
+}
+
+/// One entry of `tc -j filter show`. Each filter comes twice, first without options.
+#[derive(Debug, Deserialize)]
+struct TcFilter {
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    options: Option<TcFilterOptions>,
+}
+
+/// `ip -j link` numbers the modes like the kernel's `XDP_ATTACHED_*`.
+fn xdp_mode(mode: u8) -> Option<String> {
+    let name = match mode {
+        1 => "driver",
+        2 => "generic",
+        3 => "offload",
+        _ => return None,
+    };
+    Some(name.into())
+}
+
+async fn xdp_from_ip(iface: &str) -> Result<Vec<BpfProgram>> {
+    let links: Vec<LinkXdp> = iproute2_json("ip", &["link", "show", "dev", iface]).await?;
+    let Some(xdp) = links.into_iter().next().and_then(|l| l.xdp) else {
+        return Ok(Vec::new());
+    };
+    let mut attached: Vec<(u8, ProgInfo)> = xdp
+        .attached
+        .into_iter()
+        .filter_map(|a| Some((a.mode, a.prog?)))
+        .collect();
+    if attached.is_empty() {
+        attached.extend(xdp.prog.map(|p| (xdp.mode, p)));
+    }
+    Ok(attached
+        .into_iter()
+        .map(|(mode, prog)| BpfProgram {
+            hook: "xdp".into(),
+            mode: xdp_mode(mode),
+            id: prog.id,
+            name: prog.name,
+        })
+        .collect())
+}
+
+/// None when bpftool isn't installed or can't run (it usually needs root).
+async fn bpftool_net(iface: &str) -> Option<Vec<BpfProgram>> {
+    let out = Command::new("bpftool")
+        .args(["-j", "net", "show", "dev", iface])
+        .output()
+        .await
+        .ok()
+        .filter(|o| o.status.success())?;
+    let sections: Vec<Value> = serde_json::from_slice(&out.stdout).ok()?;
+    Some(sections.iter().flat_map(parse_bpftool_net).collect())
+}
+
+/// `{"xdp": [{"mode": "generic", "id": 42}], "tc": [{"kind": "clsact/ingress", "name": "cls",
+/// "id": 43}]}`; newer versions call tcx entries' id `prog_id`.
+fn parse_bpftool_net(section: &Value) -> Vec<BpfProgram> {
+    let entries = |key: &str| {
+        section[key]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+    };
+    let program = |hook: String, mode: Option<String>, e: &Value| {
+        let id = e["id"].as_u64().or_else(|| e["prog_id"].as_u64())?;
+        Some(BpfProgram {
+            hook,
+            mode,
+            id: id as u32,
+            name: e["name"].as_str().map(str::to_string),
+        })
+    };
+    let xdp = entries("xdp").filter_map(|e| {
+        let mode = e["mode"].as_str().map(str::to_string);
+        program("xdp".into(), mode, &e)
+    });
+    let tc = entries("tc").filter_map(|e| {
+        // `clsact/ingress` or `tcx/egress`.
+        let kind = e["kind"].as_str().unwrap_or_default();
+        let hook = match kind.split_once('/') {
+            Some(("tcx", dir)) => format!("tcx {dir}"),
+            Some((_, dir)) => format!("tc {dir}"),
+            None => format!("tc {kind}"),
+        };
+        program(hook, None, &e)
+    });
+    xdp.chain(tc).collect()
+}
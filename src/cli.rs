@@ -0,0 +1,166 @@
+use crate::app::snapshot_text;
+use crate::backend;
+use crate::config::Config;
+use crate::daemon;
+use crate::export::InterfaceSnapshot;
+use crate::logfile;
+use crate::net::{EthernetDevice, fill_link_details, list_ethernet_devices, list_routes};
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+/// The TUI's options, or one of the subcommands.
+#[derive(Debug, Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Subcommand>,
+    /// Also list wireless interfaces (SSID, signal, frequency)
+    #[arg(long)]
+    pub all: bool,
+    /// How often counters are refreshed (default 250, or refresh_interval_ms from the config file)
+    #[arg(long, value_name = "MS")]
+    pub interval: Option<u64>,
+    /// Append a JSON-lines log of commands run and state changes
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+    /// Watch another machine over ssh (user@host or a Host alias) instead; it needs ethtui
+    /// installed and key authentication, and is read-only
+    #[arg(long, value_name = "HOST")]
+    pub remote: Option<String>,
+    /// Draw only ASCII: no icons, box drawing or braille charts
+    #[arg(long)]
+    pub ascii: bool,
+    /// No colors, only bold and reversed text (also with NO_COLOR set)
+    #[arg(long)]
+    pub no_color: bool,
+    /// Write every interface (state, addresses, counters, routes...) as JSON to PATH, or to stdout
+    /// with -, and exit
+    #[arg(long, value_name = "PATH", conflicts_with = "remote")]
+    pub export_json: Option<String>,
+}
+
+/// Subcommands that answer once and exit instead of starting the TUI, for scripts and status bars.
+#[derive(Debug, Clone, PartialEq, Eq, clap::Subcommand)]
+pub enum Subcommand {
+    /// Print the interfaces (name, state, carrier, speed, IPv4) and exit
+    List {
+        /// Also list wireless interfaces
+        #[arg(long = "all")]
+        include_wifi: bool,
+        /// The whole list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print one interface's addresses, gateway and DNS and exit
+    Show {
+        iface: String,
+        /// Everything, routes included, as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Renew DHCP through the backend managing the interface and exit
+    Renew { iface: String },
+    /// Serve Prometheus metrics (carrier, speed, counters, gateway ping) at /metrics, without a
+    /// terminal
+    Daemon {
+        /// Address to listen on; :PORT for every address
+        #[arg(long, value_name = "ADDR", default_value = daemon::DEFAULT_LISTEN)]
+        listen: String,
+        /// Also export wireless interfaces
+        #[arg(long = "all")]
+        include_wifi: bool,
+    },
+}
+
+impl Subcommand {
+    pub async fn run(self, config: &Config) -> Result<()> {
+        let include_wifi = config.include_wifi;
+        match self {
+            Self::List {
+                include_wifi: all,
+                json,
+            } => {
+                let mut devices = list_ethernet_devices(include_wifi || all)?;
+                fill_link_details(&mut devices).await;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&devices)?);
+                } else {
+                    print_table(&devices);
+                }
+            }
+            Self::Show { iface, json } => {
+                let d = find(&iface).await?;
+                if json {
+                    let routes = list_routes(&d.name).await.ok();
+                    let snapshot = InterfaceSnapshot {
+                        device: &d,
+                        rx_rate: None,
+                        tx_rate: None,
+                        routes: routes.as_deref(),
+                    };
+                    println!("{}", serde_json::to_string_pretty(&snapshot)?);
+                } else {
+                    println!("{}", snapshot_text(&d));
+                }
+            }
+            Self::Renew { iface } => {
+                let d = find(&iface).await?;
+                let backend = backend::detect(&d.name).await;
+                let out = backend.renew(&d.name).await?;
+                let stdout = out.stdout.trim();
+                if !stdout.is_empty() {
+                    println!("{stdout}");
+                }
+                eprintln!("renewed DHCP on {} ({})", d.name, backend.name());
+            }
+            Self::Daemon {
+                listen,
+                include_wifi: all,
+            } => {
+                // Hooks and the MQTT connection report there; nobody watches stderr.
+                if let Some(path) = &config.log_file {
+                    logfile::init(path)?;
+                }
+                daemon::run(&listen, include_wifi || all, config).await?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Any interface by name, wireless included, since it was asked for explicitly.
+async fn find(iface: &str) -> Result<EthernetDevice> {
+    let mut d = list_ethernet_devices(true)?
+        .into_iter()
+        .find(|d| d.name == iface)
+        .ok_or_else(|| std::io::Error::other(format!("no interface named {iface}")))?;
+    fill_link_details(std::slice::from_mut(&mut d)).await;
+    Ok(d)
+}
+
+fn print_table(devices: &[EthernetDevice]) {
+    let width = devices
+        .iter()
+        .map(|d| d.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!(
+        "{:width$}  {:<14}  {:<7}  {:<10}  IPV4",
+        "NAME", "STATE", "CARRIER", "SPEED"
+    );
+    for d in devices {
+        let carrier = d.carrier.map_or("?", |c| if c { "yes" } else { "no" });
+        let speed = d.speed_mbps.map_or("-".into(), |s| format!("{s} Mb/s"));
+        let ipv4 = if d.ipv4.is_empty() {
+            "-".to_string()
+        } else {
+            d.ipv4.join(", ")
+        };
+        println!(
+            "{:width$}  {:<14}  {:<7}  {:<10}  {ipv4}",
+            d.name, d.operstate, carrier, speed
+        );
+    }
+}
@@ -0,0 +1,233 @@
+/// Commands typed after `:`, with their usage. Any unique prefix works, so `:q` quits.
+pub const COMMANDS: [(&str, &str); 11] = [
+    ("quit", ":quit"),
+    ("refresh", ":refresh"),
+    ("renew", ":renew [iface]"),
+    ("release", ":release [iface]"),
+    ("up", ":up [iface]"),
+    ("down", ":down [iface]"),
+    ("mtu", ":mtu <bytes> [iface]"),
+    ("filter", ":filter [pattern]"),
+    ("select", ":select <iface>"),
+    ("qr", ":qr [text]"),
+    ("help", ":help"),
+];
+
+/// How many command lines are remembered for ↑/↓.
+const HISTORY_LEN: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Quit,
+    Refresh,
+    Renew(Option<String>),
+    Release(Option<String>),
+    Up(Option<String>),
+    Down(Option<String>),
+    Mtu(u32, Option<String>),
+    /// A substring or a glob like `enp*`; None shows every interface again.
+    Filter(Option<String>),
+    Select(String),
+    /// Text to show as a QR code; None for the selected interface's URL.
+    Qr(Option<String>),
+    Help,
+}
+
+impl std::str::FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let Some(word) = words.next() else {
+            return Err("empty command".into());
+        };
+        let matches: Vec<_> = COMMANDS
+            .iter()
+            .filter(|(name, _)| name.starts_with(word))
+            .collect();
+        let (name, usage) = match matches[..] {
+            [only] => *only,
+            [] => return Err(format!("unknown command `{word}`")),
+            _ => {
+                let names: Vec<&str> = matches.iter().map(|(n, _)| *n).collect();
+                return Err(format!("`{word}` is ambiguous: {}", names.join(", ")));
+            }
+        };
+        if name == "qr" {
+            // Everything after the command is the payload, inner spaces included.
+            let text = line.trim_start()[word.len()..].trim();
+            return Ok(Command::Qr((!text.is_empty()).then(|| text.to_string())));
+        }
+        let args: Vec<&str> = words.collect();
+        let iface = |i: usize| args.get(i).map(|s| s.to_string());
+        let max_args = usage.matches('[').count() + usage.matches('<').count();
+        if args.len() > max_args {
+            return Err(format!("usage: {usage}"));
+        }
+
+        Ok(match name {
+            "quit" => Command::Quit,
+            "refresh" => Command::Refresh,
+            "renew" => Command::Renew(iface(0)),
+            "release" => Command::Release(iface(0)),
+            "up" => Command::Up(iface(0)),
+            "down" => Command::Down(iface(0)),
+            "mtu" => {
+                let mtu = args
+                    .first()
+                    .and_then(|m| m.parse().ok())
+                    .ok_or_else(|| format!("usage: {usage}"))?;
+                Command::Mtu(mtu, iface(1))
+            }
+            "filter" => Command::Filter(iface(0)),
+            "select" => Command::Select(iface(0).ok_or_else(|| format!("usage: {usage}"))?),
+            "help" => Command::Help,
+            _ => unreachable!("every entry in COMMANDS is handled, `qr` above"),
+        })
+    }
+}
+
+/// The `:` prompt being edited.
+#[derive(Debug, Clone, Default)]
+pub struct CommandLine {
+    pub input: String,
+    /// Index into the history while browsing it with ↑/↓.
+    history_pos: Option<usize>,
+    /// What was typed before browsing the history, restored when ↓ goes past the newest entry.
+    draft: String,
+}
+
+impl CommandLine {
+    pub fn push_char(&mut self, c: char) {
+        self.input.push(c);
+        self.history_pos = None;
+    }
+
+    pub fn pop_char(&mut self) {
+        self.input.pop();
+        self.history_pos = None;
+    }
+
+    pub fn history_prev(&mut self, history: &[String]) {
+        let pos = match self.history_pos {
+            None if history.is_empty() => return,
+            None => {
+                self.draft = self.input.clone();
+                history.len() - 1
+            }
+            Some(p) => p.saturating_sub(1),
+        };
+        self.history_pos = Some(pos);
+        self.input = history[pos].clone();
+    }
+
+    pub fn history_next(&mut self, history: &[String]) {
+        let Some(pos) = self.history_pos else {
+            return;
+        };
+        if pos + 1 < history.len() {
+            self.history_pos = Some(pos + 1);
+            self.input = history[pos + 1].clone();
+        } else {
+            self.history_pos = None;
+            self.input = std::mem::take(&mut self.draft);
+        }
+    }
+
+    /// Completes the word being typed: a command name first, then interface names. With
+    /// several candidates it extends to their common prefix.
+    pub fn complete(&mut self, ifaces: &[&str]) {
+        let start = self.input.rfind(' ').map_or(0, |i| i + 1);
+        let word = &self.input[start..];
+        let first_word = start == 0;
+        let candidates: Vec<&str> = if first_word {
+            COMMANDS.iter().map(|(name, _)| *name).collect()
+        } else {
+            ifaces.to_vec()
+        };
+        let matching: Vec<&str> = candidates
+            .into_iter()
+            .filter(|c| c.starts_with(word))
+            .collect();
+        let completion = match matching[..] {
+            [] => return,
+            [only] => format!("{only} "),
+            [first, ..] => {
+                let len = matching.iter().fold(first.len(), |len, m| {
+                    first
+                        .chars()
+                        .zip(m.chars())
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                        .min(len)
+                });
+                first.chars().take(len).collect()
+            }
+        };
+        self.input.truncate(start);
+        self.input.push_str(&completion);
+        self.history_pos = None;
+    }
+}
+
+/// Appends `line` unless it repeats the previous entry, dropping the oldest past the limit.
+pub fn remember(history: &mut Vec<String>, line: &str) {
+    if line.trim().is_empty() || history.last().is_some_and(|l| l == line) {
+        return;
+    }
+    history.push(line.to_string());
+    if history.len() > HISTORY_LEN {
+        history.remove(0);
+    }
+}
+
+/// `*` matches any run of characters and `?` any one, as in shell globs.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    // Greedy with backtracking to the last `*`.
+    let (mut pi, mut ni) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        match p.get(pi) {
+            Some('*') => {
+                star = Some((pi, ni));
+                pi += 1;
+            }
+            Some(&c) if c == '?' || c == n[ni] => {
+                pi += 1;
+                ni += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    pi = sp + 1;
+                    ni = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qr_takes_the_rest_of_the_line() {
+        let cases = [
+            ("qr", None),
+            ("  qr   ", None),
+            ("qr http://192.0.2.2/", Some("http://192.0.2.2/")),
+            (
+                "qr  WIFI:S:my net;T:WPA;P:a b;;  ",
+                Some("WIFI:S:my net;T:WPA;P:a b;;"),
+            ),
+        ];
+        for (line, want) in cases {
+            let want = Command::Qr(want.map(str::to_string));
+            assert_eq!(line.parse::<Command>(), Ok(want), "{line:?}");
+        }
+    }
+}
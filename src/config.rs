@@ -0,0 +1,211 @@
+use crate::alerts::AlertRule;
+use crate::hooks::Hook;
+use crate::keymap::Keymap;
+use crate::notify::NotifyConfig;
+use crate::profiles::{self, Profile};
+use crate::ui::Theme;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Anything faster just burns CPU re-reading sysfs.
+pub const MIN_REFRESH_INTERVAL_MS: u64 = 50;
+
+/// Settings from `~/.config/ethtui/config.toml`. Every key is optional and a missing file
+/// means all defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// How often counters are re-read and running tools polled, in milliseconds.
+    pub refresh_interval_ms: u64,
+    /// Interface selected at startup, when it's listed.
+    pub default_interface: Option<String>,
+    /// List wireless NICs too, like `--all`.
+    pub include_wifi: bool,
+    /// What the Internet check fetches (expecting a 204); `ETHTUI_PROBE_URL` still wins.
+    pub probe_url: Option<String>,
+    /// Server or URL prefilled in the speed test form.
+    pub speedtest_target: Option<String>,
+    /// Hostname prefilled in the DNS test form.
+    pub dns_test_host: Option<String>,
+    /// Desktop notifications at all; which events raise one is up to `[notify]`.
+    pub notifications: bool,
+    pub notify: NotifyConfig,
+    /// Interfaces whose link went down and came back within this many minutes are flagged.
+    pub flap_window_minutes: u64,
+    /// Ask before actions that can drop connectivity (link down, DHCP release, MAC and MTU
+    /// changes...); off runs them straight away.
+    pub confirm: bool,
+    /// JSON-lines log of commands run and state changes, like `--log-file`.
+    pub log_file: Option<PathBuf>,
+    /// `--remote`: watch this ssh host instead of the local machine.
+    #[serde(skip)]
+    pub remote: Option<String>,
+    /// ssh hosts listed next to this machine, to switch between with `<`/`>`.
+    pub hosts: Vec<String>,
+    /// How ethtui is started on a remote host, when it isn't on the PATH there.
+    pub remote_command: String,
+    /// Keep addresses, gateways and leases across runs in `~/.local/share/ethtui/history.jsonl`.
+    pub history: bool,
+    pub mqtt: Mqtt,
+    /// Commands and webhooks run on link events (`[[hooks]]`).
+    pub hooks: Vec<Hook>,
+    /// Run the executables in `~/.config/ethtui/plugins/` on those events too.
+    pub plugins: bool,
+    /// Threshold rules that flag interfaces (`[[alerts]]`).
+    pub alerts: Vec<AlertRule>,
+    /// Named address/gateway/DNS/MTU settings to apply in one go (`[[profiles]]`).
+    pub profiles: Vec<Profile>,
+    pub columns: Columns,
+    pub theme: Theme,
+    /// Draw only ASCII, for the Linux console and fonts without Nerd Font icons, like
+    /// `--ascii`; guessed from `TERM` and the locale when unset.
+    pub ascii: Option<bool>,
+    pub keys: Keymap,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            refresh_interval_ms: 250,
+            default_interface: None,
+            include_wifi: false,
+            probe_url: None,
+            speedtest_target: None,
+            dns_test_host: None,
+            notifications: true,
+            notify: NotifyConfig::default(),
+            flap_window_minutes: 10,
+            confirm: true,
+            log_file: None,
+            remote: None,
+            hosts: Vec::new(),
+            remote_command: "ethtui".into(),
+            history: true,
+            mqtt: Mqtt::default(),
+            hooks: Vec::new(),
+            plugins: true,
+            alerts: Vec::new(),
+            profiles: Vec::new(),
+            columns: Columns::default(),
+            theme: Theme::default(),
+            ascii: None,
+            keys: Keymap::default(),
+        }
+    }
+}
+
+/// Publishing interface state to an MQTT broker, from the TUI and `ethtui daemon` alike.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Mqtt {
+    /// `host:port`, `[v6]:port` or a bare address (1883 when no port is given); nothing is
+    /// published without it.
+    pub broker: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Defaults to `ethtui-<hostname>`.
+    pub client_id: Option<String>,
+    /// Defaults to `ethtui/<hostname>`.
+    pub topic_prefix: Option<String>,
+    /// Announce sensors for Home Assistant's MQTT discovery.
+    pub discovery: bool,
+    pub discovery_prefix: String,
+}
+
+impl Default for Mqtt {
+    fn default() -> Self {
+        Self {
+            broker: None,
+            username: None,
+            password: None,
+            client_id: None,
+            topic_prefix: None,
+            discovery: true,
+            discovery_prefix: "homeassistant".into(),
+        }
+    }
+}
+
+/// Which optional columns the interface list shows; the name is always there.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Columns {
+    pub vid: bool,
+    pub state: bool,
+    pub carrier: bool,
+    pub speed: bool,
+    pub ipv4: bool,
+    pub lease: bool,
+    pub internet: bool,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            vid: true,
+            state: true,
+            carrier: true,
+            speed: true,
+            ipv4: true,
+            lease: true,
+            internet: true,
+        }
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/ethtui/config.toml`, falling back to `~/.config`.
+    pub fn path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        Some(base.join("ethtui").join("config.toml"))
+    }
+
+    /// Defaults when the file doesn't exist; a file that can't be read or parsed is an error
+    /// rather than being silently ignored.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+        };
+        let mut config: Self =
+            toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+        config.refresh_interval_ms = config.refresh_interval_ms.max(MIN_REFRESH_INTERVAL_MS);
+        if let Some(hook) = config
+            .hooks
+            .iter()
+            .find(|h| h.command.is_none() && h.url.is_none())
+        {
+            let events: Vec<&str> = hook.events.iter().map(|e| e.name()).collect();
+            let msg = format!(
+                "{}: the hook for {} needs a command or a url",
+                path.display(),
+                events.join(", ")
+            );
+            return Err(io::Error::other(msg).into());
+        }
+        for rule in &config.alerts {
+            rule.validate()
+                .map_err(|e| io::Error::other(format!("{}: {e}", path.display())))?;
+        }
+        for profile in &config.profiles {
+            profile
+                .validate()
+                .map_err(|e| io::Error::other(format!("{}: {e}", path.display())))?;
+        }
+        if let Some(name) = profiles::duplicate_name(&config.profiles) {
+            let msg = format!("{}: two profiles are named {name}", path.display());
+            return Err(io::Error::other(msg).into());
+        }
+        Ok(config)
+    }
+}
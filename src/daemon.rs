@@ -0,0 +1,473 @@
+use crate::config::Config;
+use crate::diag::{DEFAULT_PING_WINDOW, Ping};
+use crate::events::{EventKind, EventLog};
+use crate::history::History;
+use crate::mqtt;
+use crate::net::{EthernetDevice, LinkDetails, list_ethernet_devices, read_mtu};
+use crate::notify::Notifier;
+use crate::plugins::Plugins;
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Where `ethtui daemon` listens without `--listen`: only this machine, since the metrics include
+/// MAC addresses. 9155 isn't claimed by another exporter.
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:9155";
+
+/// How often interfaces are rescanned and the pings polled.
+const COLLECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Clients get this long to send their request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs the collection loop without a terminal and serves it as Prometheus metrics on
+/// `/metrics` until killed. Every interface with a carrier and a default gateway gets a
+/// continuous ping to that gateway, for the latency metrics and the `gateway_unreachable` hook.
+pub async fn run(listen: &str, include_wifi: bool, config: &Config) -> Result<()> {
+    // `--listen :9155` means every address, like most exporters accept.
+    let addr = match listen.strip_prefix(':') {
+        Some(port) => format!("0.0.0.0:{port}"),
+        None => listen.to_string(),
+    };
+    let listener = TcpListener::bind(&addr)
+        .await
+        .with_context(|| format!("listening on {addr}"))?;
+    eprintln!("ethtui: serving metrics on http://{addr}/metrics");
+
+    let (plugins, mut replies) = config.plugins.then(Plugins::open).flatten().unzip();
+    let mut collector = Collector {
+        include_wifi,
+        devices: list_ethernet_devices(include_wifi)?,
+        details: LinkDetails::default(),
+        pings: HashMap::new(),
+        unreachable: HashSet::new(),
+        events: EventLog::new(
+            config.hooks.clone(),
+            plugins,
+            Notifier::new(config.notifications, &config.notify),
+        ),
+        mqtt: mqtt::Publisher::start(&config.mqtt),
+        history: config.history.then(History::open).flatten(),
+    };
+    let mut tick = tokio::time::interval(COLLECT_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = tick.tick() => collector.collect().await,
+            // Without a terminal, only what plugins want logged has somewhere to go.
+            Some(reply) = async { replies.as_mut()?.recv().await } => {
+                if let Some(log) = reply.log {
+                    let iface = reply.interface.unwrap_or_default();
+                    let msg = format!("{}: {log}", reply.plugin);
+                    collector.events.push(&iface, EventKind::Info, msg);
+                }
+            }
+            accepted = listener.accept() => {
+                // A failed accept (e.g. out of file descriptors) shouldn't stop the exporter.
+                if let Ok((stream, _)) = accepted {
+                    let metrics = collector.render();
+                    tokio::spawn(serve(stream, metrics));
+                }
+            }
+        }
+    }
+}
+
+struct Collector {
+    include_wifi: bool,
+    devices: Vec<EthernetDevice>,
+    details: LinkDetails,
+    /// Gateway pings by interface name.
+    pings: HashMap<String, Ping>,
+    /// Interfaces whose gateway missed a whole window of pings.
+    unreachable: HashSet<String>,
+    /// Not shown anywhere, but it runs the hooks (and writes the log file, if any).
+    events: EventLog,
+    mqtt: Option<mqtt::Publisher>,
+    history: Option<History>,
+}
+
+impl Collector {
+    async fn collect(&mut self) {
+        // Keep the last good list when sysfs can't be read for a moment.
+        if let Ok(mut devices) = list_ethernet_devices(self.include_wifi) {
+            for d in &devices {
+                if let Some(old) = self.devices.iter().find(|o| o.ifindex == d.ifindex) {
+                    self.events.compare(old, d);
+                    // Without netlink here, a change of state or addresses stands in for the
+                    // events that tell the TUI to look again.
+                    if old.operstate != d.operstate || old.ipv4 != d.ipv4 || old.ipv6 != d.ipv6 {
+                        self.details.refresh(d);
+                    }
+                }
+            }
+            self.details.track(&devices);
+            self.details.collect().await;
+            self.details.apply(&mut devices);
+            self.devices = devices;
+        }
+        if let Some(mqtt) = &mut self.mqtt {
+            mqtt.update(&self.devices);
+        }
+        if let Some(history) = &mut self.history {
+            history.observe(&self.devices);
+        }
+        let targets: HashMap<&str, &str> = self
+            .devices
+            .iter()
+            .filter(|d| d.carrier == Some(true))
+            .filter_map(|d| Some((d.name.as_str(), d.gateway_v4.as_deref()?)))
+            .collect();
+        self.pings
+            .retain(|iface, ping| targets.get(iface.as_str()) == Some(&ping.target.as_str()));
+        self.unreachable
+            .retain(|iface| self.pings.contains_key(iface));
+        for (iface, target) in targets {
+            if let Some(ping) = self.pings.get_mut(iface) {
+                ping.poll();
+                let window = ping.rolling();
+                let down = window.sent >= ping.window && window.loss_pct >= 100.0;
+                if down && self.unreachable.insert(iface.to_string()) {
+                    self.events.gateway_unreachable(iface, target);
+                } else if !down && self.unreachable.remove(iface) {
+                    self.events.gateway_reachable(iface, target);
+                }
+            } else if let Ok(ping) = Ping::start(iface, target, DEFAULT_PING_WINDOW) {
+                self.pings.insert(iface.to_string(), ping);
+            }
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut pings: Vec<&Ping> = self.pings.values().collect();
+        pings.sort_by(|a, b| a.iface.cmp(&b.iface));
+        render(&self.devices, &pings)
+    }
+}
+
+/// The Prometheus text exposition format, version 0.0.4.
+fn render(devices: &[EthernetDevice], pings: &[&Ping]) -> String {
+    let mut out = String::new();
+    let iface = |d: &EthernetDevice| format!("interface=\"{}\"", escape(&d.name));
+    let info = |d: &EthernetDevice| {
+        let mac = d.mac.as_deref().unwrap_or_default();
+        format!(
+            "{},operstate=\"{}\",mac=\"{}\"",
+            iface(d),
+            escape(&d.operstate),
+            escape(mac)
+        )
+    };
+    let info_metric = gauge(
+        "ethtui_interface_info",
+        "Interface details; always 1.",
+        |_| Some(1.0),
+    );
+    info_metric.write(&mut out, devices, info);
+    for metric in DEVICE_METRICS {
+        metric.write(&mut out, devices, iface);
+    }
+
+    let labels = |p: &Ping| {
+        format!(
+            "interface=\"{}\",target=\"{}\"",
+            escape(&p.iface),
+            escape(&p.target)
+        )
+    };
+    for metric in PING_METRICS {
+        metric.write(&mut out, pings.iter().copied(), labels);
+    }
+    out
+}
+
+/// One metric family: a value per interface (or per ping), skipped where there's none.
+struct Metric<T: 'static> {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+    value: fn(&T) -> Option<f64>,
+}
+
+const fn gauge<T>(
+    name: &'static str,
+    help: &'static str,
+    value: fn(&T) -> Option<f64>,
+) -> Metric<T> {
+    Metric {
+        name,
+        kind: "gauge",
+        help,
+        value,
+    }
+}
+
+const fn counter<T>(
+    name: &'static str,
+    help: &'static str,
+    value: fn(&T) -> Option<f64>,
+) -> Metric<T> {
+    Metric {
+        name,
+        kind: "counter",
+        help,
+        value,
+    }
+}
+
+impl<T> Metric<T> {
+    fn write<'a>(
+        &self,
+        out: &mut String,
+        items: impl IntoIterator<Item = &'a T>,
+        labels: impl Fn(&T) -> String,
+    ) {
+        let name = self.name;
+        let _ = writeln!(
+            out,
+            "# HELP {name} {}\n# TYPE {name} {}",
+            self.help, self.kind
+        );
+        for item in items {
+            if let Some(value) = (self.value)(item) {
+                let _ = writeln!(out, "{name}{{{}}} {value}", labels(item));
+            }
+        }
+    }
+}
+
+const DEVICE_METRICS: [Metric<EthernetDevice>; 16] = [
+    gauge("ethtui_up", "Whether operstate is up.", |d| {
+        Some(f64::from(u8::from(d.operstate == "up")))
+    }),
+    gauge("ethtui_carrier", "Whether the link has a carrier.", |d| {
+        d.carrier.map(|c| f64::from(u8::from(c)))
+    }),
+    gauge("ethtui_speed_mbps", "Negotiated link speed in Mb/s.", |d| {
+        d.speed_mbps.map(f64::from)
+    }),
+    gauge("ethtui_mtu_bytes", "MTU in bytes.", |d| {
+        read_mtu(&d.name).map(f64::from)
+    }),
+    counter("ethtui_receive_bytes_total", "Bytes received.", |d| {
+        Some(d.stats.rx_bytes as f64)
+    }),
+    counter("ethtui_transmit_bytes_total", "Bytes sent.", |d| {
+        Some(d.stats.tx_bytes as f64)
+    }),
+    counter("ethtui_receive_packets_total", "Packets received.", |d| {
+        Some(d.stats.rx_packets as f64)
+    }),
+    counter("ethtui_transmit_packets_total", "Packets sent.", |d| {
+        Some(d.stats.tx_packets as f64)
+    }),
+    counter("ethtui_receive_errors_total", "Receive errors.", |d| {
+        Some(d.stats.rx_errors as f64)
+    }),
+    counter("ethtui_transmit_errors_total", "Transmit errors.", |d| {
+        Some(d.stats.tx_errors as f64)
+    }),
+    counter(
+        "ethtui_receive_dropped_total",
+        "Received packets dropped.",
+        |d| Some(d.stats.rx_dropped as f64),
+    ),
+    counter(
+        "ethtui_transmit_dropped_total",
+        "Packets dropped before sending.",
+        |d| Some(d.stats.tx_dropped as f64),
+    ),
+    counter(
+        "ethtui_receive_crc_errors_total",
+        "Frames received with a bad CRC.",
+        |d| Some(d.stats.rx_crc_errors as f64),
+    ),
+    counter(
+        "ethtui_receive_missed_errors_total",
+        "Frames the NIC had no room for.",
+        |d| Some(d.stats.rx_missed_errors as f64),
+    ),
+    counter(
+        "ethtui_multicast_total",
+        "Multicast packets received.",
+        |d| Some(d.stats.multicast as f64),
+    ),
+    counter(
+        "ethtui_collisions_total",
+        "Collisions (half duplex only).",
+        |d| Some(d.stats.collisions as f64),
+    ),
+];
+
+const PING_METRICS: [Metric<Ping>; 5] = [
+    gauge(
+        "ethtui_ping_rtt_seconds",
+        "Round trip time of the last reply from the gateway.",
+        |p| p.samples.back()?.1.map(|ms| ms / 1000.0),
+    ),
+    gauge(
+        "ethtui_ping_loss_ratio",
+        "Share of the last pings to the gateway without a reply.",
+        |p| Some(p.rolling().loss_pct / 100.0),
+    ),
+    gauge(
+        "ethtui_ping_jitter_seconds",
+        "Mean difference between consecutive round trip times of the last pings.",
+        |p| p.rolling().jitter_ms.map(|ms| ms / 1000.0),
+    ),
+    counter(
+        "ethtui_ping_sent_total",
+        "Pings sent to the gateway.",
+        |p| Some(p.sent as f64),
+    ),
+    counter(
+        "ethtui_ping_received_total",
+        "Replies received from the gateway.",
+        |p| Some(p.received as f64),
+    ),
+];
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Just enough HTTP/1.1 for a Prometheus scraper or curl: the request line decides, headers
+/// and body are ignored, and the connection is closed after the response.
+async fn serve(mut stream: TcpStream, metrics: String) {
+    let mut buf = [0u8; 4096];
+    let mut len = 0;
+    let read_head = async {
+        while len < buf.len() {
+            match stream.read(&mut buf[len..]).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => len += n,
+            }
+            if buf[..len].windows(4).any(|w| w == b"\r\n\r\n") {
+                break;
+            }
+        }
+    };
+    if tokio::time::timeout(REQUEST_TIMEOUT, read_head)
+        .await
+        .is_err()
+    {
+        return;
+    }
+    let head = String::from_utf8_lossy(&buf[..len]);
+    let mut request = head.lines().next().unwrap_or_default().split_whitespace();
+    let (method, path) = (request.next(), request.next());
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET" | "HEAD"), Some("/metrics")) => (
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            metrics,
+        ),
+        (Some("GET" | "HEAD"), Some("/")) => (
+            "200 OK",
+            "text/plain; charset=utf-8",
+            "ethtui exporter: metrics are at /metrics\n".to_string(),
+        ),
+        (Some("GET" | "HEAD"), _) => (
+            "404 Not Found",
+            "text/plain; charset=utf-8",
+            "not found\n".to_string(),
+        ),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain; charset=utf-8",
+            "method not allowed\n".to_string(),
+        ),
+    };
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    if method != Some("HEAD") {
+        response.push_str(&body);
+    }
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::test_device;
+
+    fn device(name: &str, operstate: &str, mac: &str) -> EthernetDevice {
+        let mut d = test_device(name);
+        d.operstate = operstate.into();
+        d.mac = Some(mac.into());
+        d.speed_mbps = Some(1000);
+        d.stats.rx_bytes = 1500;
+        d.stats.tx_bytes = 42;
+        d
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape("eth0"), "eth0");
+        assert_eq!(escape(r"a\b"), r"a\\b");
+        assert_eq!(escape(r#"say "hi""#), r#"say \"hi\""#);
+        assert_eq!(escape("two\nlines"), r"two\nlines");
+        // The backslash goes first, so the ones added for quotes aren't doubled.
+        assert_eq!(escape("\\\"\n"), r#"\\\"\n"#);
+    }
+
+    /// No such interface in sysfs, so there's no MTU to read and its series is left out.
+    #[test]
+    fn renders_exposition_text() {
+        let text = render(&[device("ethtuitest0", "up", "02:00:00:00:00:01")], &[]);
+        let want = "\
+# HELP ethtui_interface_info Interface details; always 1.
+# TYPE ethtui_interface_info gauge
+ethtui_interface_info{interface=\"ethtuitest0\",operstate=\"up\",mac=\"02:00:00:00:00:01\"} 1
+# HELP ethtui_up Whether operstate is up.
+# TYPE ethtui_up gauge
+ethtui_up{interface=\"ethtuitest0\"} 1
+# HELP ethtui_carrier Whether the link has a carrier.
+# TYPE ethtui_carrier gauge
+ethtui_carrier{interface=\"ethtuitest0\"} 1
+# HELP ethtui_speed_mbps Negotiated link speed in Mb/s.
+# TYPE ethtui_speed_mbps gauge
+ethtui_speed_mbps{interface=\"ethtuitest0\"} 1000
+# HELP ethtui_mtu_bytes MTU in bytes.
+# TYPE ethtui_mtu_bytes gauge
+# HELP ethtui_receive_bytes_total Bytes received.
+# TYPE ethtui_receive_bytes_total counter
+ethtui_receive_bytes_total{interface=\"ethtuitest0\"} 1500
+# HELP ethtui_transmit_bytes_total Bytes sent.
+# TYPE ethtui_transmit_bytes_total counter
+ethtui_transmit_bytes_total{interface=\"ethtuitest0\"} 42
+";
+        assert!(text.starts_with(want), "{text}");
+        // Every family is announced even without any series, pings included.
+        let families = DEVICE_METRICS.len() + PING_METRICS.len() + 1;
+        assert_eq!(text.matches("# HELP ").count(), families);
+        assert_eq!(text.matches("# TYPE ").count(), families);
+        assert!(text.ends_with("# TYPE ethtui_ping_received_total counter\n"));
+    }
+
+    #[test]
+    fn escapes_labels_in_output() {
+        let text = render(&[device("ethtuitest0", "we\"ird\\\n", "")], &[]);
+        let line = text
+            .lines()
+            .find(|l| l.starts_with("ethtui_interface_info{"))
+            .unwrap();
+        assert_eq!(
+            line,
+            r#"ethtui_interface_info{interface="ethtuitest0",operstate="we\"ird\\\n",mac=""} 1"#
+        );
+        assert_eq!(
+            text.lines().filter(|l| l.starts_with("ethtui_up{")).count(),
+            1
+        );
+        assert!(text.contains("ethtui_up{interface=\"ethtuitest0\"} 0\n"));
+    }
+}
@@ -0,0 +1,1315 @@
+use crate::dns;
+use crate::net::list_neighbors;
+use anyhow::Result;
+use futures::StreamExt;
+use std::collections::VecDeque;
+use std::net::Ipv4Addr;
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// A long-running command whose stdout and stderr lines are collected as they arrive.
+/// Dropping it kills the process, unless it was spawned detached.
+pub struct LineStream {
+    child: Child,
+    rx: mpsc::UnboundedReceiver<String>,
+}
+
+fn forward_lines<R: AsyncRead + Unpin + Send + 'static>(
+    reader: R,
+    tx: mpsc::UnboundedSender<String>,
+) {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+impl LineStream {
+    pub fn spawn(program: &str, args: &[&str]) -> Result<Self> {
+        Self::spawn_with(program, args, true)
+    }
+
+    /// Like [`LineStream::spawn`], but dropping the stream leaves the process running, for
+    /// commands that must be allowed to clean up after themselves.
+    pub fn spawn_detached(program: &str, args: &[&str]) -> Result<Self> {
+        Self::spawn_with(program, args, false)
+    }
+
+    fn spawn_with(program: &str, args: &[&str], kill_on_drop: bool) -> Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(kill_on_drop)
+            .spawn()
+            .map_err(|e| std::io::Error::other(format!("{program}: {e}")))?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        if let Some(out) = child.stdout.take() {
+            forward_lines(out, tx.clone());
+        }
+        if let Some(err) = child.stderr.take() {
+            forward_lines(err, tx);
+        }
+        Ok(Self { child, rx })
+    }
+
+    /// Lines received since the last call.
+    pub fn drain(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        while let Ok(line) = self.rx.try_recv() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// The exit code once the process has finished.
+    pub fn exit_status(&mut self) -> Option<i32> {
+        self.child
+            .try_wait()
+            .ok()
+            .flatten()
+            .map(|s| s.code().unwrap_or(1))
+    }
+
+    /// Sends `signal` to the process; false once it has exited.
+    pub fn signal(&self, signal: libc::c_int) -> bool {
+        let Some(pid) = self.child.id() else {
+            return false;
+        };
+        // SAFETY: kill has no memory-safety preconditions; the pid is our own unreaped child.
+        unsafe { libc::kill(pid as libc::pid_t, signal) == 0 }
+    }
+}
+
+/// Number of RTT samples kept for the chart.
+pub const PING_SAMPLES: usize = 120;
+
+/// Default size, in replies, of the rolling loss/jitter window.
+pub const DEFAULT_PING_WINDOW: usize = 20;
+
+/// Loss and jitter over one completed window of pings.
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyWindow {
+    pub ended: SystemTime,
+    pub sent: usize,
+    pub loss_pct: f64,
+    pub avg_ms: Option<f64>,
+    pub jitter_ms: Option<f64>,
+}
+
+impl LatencyWindow {
+    fn from_samples<'a>(samples: impl Iterator<Item = &'a (u64, Option<f64>)>) -> Self {
+        let rtts: Vec<Option<f64>> = samples.map(|(_, rtt)| *rtt).collect();
+        let replies: Vec<f64> = rtts.iter().flatten().copied().collect();
+        let sent = rtts.len();
+        let loss_pct = if sent == 0 {
+            0.0
+        } else {
+            (sent - replies.len()) as f64 * 100.0 / sent as f64
+        };
+        let avg_ms =
+            (!replies.is_empty()).then(|| replies.iter().sum::<f64>() / replies.len() as f64);
+        // Mean difference between consecutive replies (RFC 3550 style, without smoothing).
+        let jitter_ms = (replies.len() >= 2).then(|| {
+            replies.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>()
+                / (replies.len() - 1) as f64
+        });
+        Self {
+            ended: SystemTime::now(),
+            sent,
+            loss_pct,
+            avg_ms,
+            jitter_ms,
+        }
+    }
+}
+
+/// `ping -I <iface>` running in the background, with RTTs in milliseconds (`None` = no reply).
+pub struct Ping {
+    pub iface: String,
+    pub target: String,
+    pub samples: VecDeque<(u64, Option<f64>)>,
+    pub sent: u64,
+    pub received: u64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    sum: f64,
+    pub error: Option<String>,
+    pub finished: bool,
+    /// Replies per loss/jitter window, and the windows completed so far.
+    pub window: usize,
+    pub history: Vec<LatencyWindow>,
+    since_window: usize,
+    stream: LineStream,
+}
+
+impl Ping {
+    pub fn start(iface: &str, target: &str, window: usize) -> Result<Self> {
+        // -O reports unanswered requests, so losses show up as they happen.
+        let stream = LineStream::spawn("ping", &["-n", "-O", "-I", iface, target])?;
+        Ok(Self {
+            iface: iface.to_string(),
+            target: target.to_string(),
+            samples: VecDeque::with_capacity(PING_SAMPLES),
+            sent: 0,
+            received: 0,
+            min: None,
+            max: None,
+            sum: 0.0,
+            error: None,
+            finished: false,
+            window: window.clamp(2, PING_SAMPLES),
+            history: Vec::new(),
+            since_window: 0,
+            stream,
+        })
+    }
+
+    /// Picks up replies printed since the last call.
+    pub fn poll(&mut self) {
+        for line in self.stream.drain() {
+            match parse_ping_line(&line) {
+                Some((seq, rtt)) => self.record(seq, rtt),
+                None if line.starts_with("ping:") => self.error = Some(line),
+                None => {}
+            }
+        }
+        if !self.finished && self.stream.exit_status().is_some() {
+            self.finished = true;
+        }
+    }
+
+    fn record(&mut self, seq: u64, rtt: Option<f64>) {
+        self.sent = self.sent.max(seq);
+        if let Some(ms) = rtt {
+            self.received += 1;
+            self.sum += ms;
+            self.min = Some(self.min.map_or(ms, |m| m.min(ms)));
+            self.max = Some(self.max.map_or(ms, |m| m.max(ms)));
+        }
+        if self.samples.len() == PING_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((seq, rtt));
+
+        self.since_window += 1;
+        if self.since_window == self.window {
+            self.since_window = 0;
+            self.history.push(self.rolling());
+        }
+    }
+
+    /// Loss and jitter over the last `window` samples.
+    pub fn rolling(&self) -> LatencyWindow {
+        let skip = self.samples.len().saturating_sub(self.window);
+        LatencyWindow::from_samples(self.samples.iter().skip(skip))
+    }
+
+    pub fn avg(&self) -> Option<f64> {
+        (self.received > 0).then(|| self.sum / self.received as f64)
+    }
+
+    pub fn loss_pct(&self) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        self.sent.saturating_sub(self.received) as f64 * 100.0 / self.sent as f64
+    }
+}
+
+/// `64 bytes from 192.168.1.1: icmp_seq=3 ttl=64 time=0.512 ms` or
+/// `no answer yet for icmp_seq=4`.
+fn parse_ping_line(line: &str) -> Option<(u64, Option<f64>)> {
+    let field = |key: &str| {
+        line.split_whitespace()
+            .find_map(|w| w.strip_prefix(key))
+            .map(|v| v.to_string())
+    };
+    let seq = field("icmp_seq=")?.parse().ok()?;
+    if line.starts_with("no answer yet") {
+        return Some((seq, None));
+    }
+    let rtt = field("time=")?.parse().ok()?;
+    Some((seq, Some(rtt)))
+}
+
+/// Where `T` traces to unless the user types something else.
+pub const DEFAULT_TRACE_TARGET: &str = "1.1.1.1";
+
+/// One hop of a trace; latencies are in milliseconds.
+#[derive(Debug, Clone, Default)]
+pub struct Hop {
+    pub host: Option<String>,
+    pub sent: u32,
+    pub received: u32,
+    pub last: Option<f64>,
+    pub best: Option<f64>,
+    pub worst: Option<f64>,
+    sum: f64,
+}
+
+impl Hop {
+    fn reply(&mut self, ms: f64) {
+        self.received += 1;
+        self.sum += ms;
+        self.last = Some(ms);
+        self.best = Some(self.best.map_or(ms, |b| b.min(ms)));
+        self.worst = Some(self.worst.map_or(ms, |w| w.max(ms)));
+    }
+
+    pub fn avg(&self) -> Option<f64> {
+        (self.received > 0).then(|| self.sum / self.received as f64)
+    }
+
+    pub fn loss_pct(&self) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        self.sent.saturating_sub(self.received) as f64 * 100.0 / self.sent as f64
+    }
+}
+
+/// `mtr --raw` (keeps probing every hop) when installed, otherwise a one-shot `traceroute`.
+pub struct Trace {
+    pub iface: String,
+    pub target: String,
+    pub program: &'static str,
+    pub hops: Vec<Hop>,
+    pub error: Option<String>,
+    pub finished: bool,
+    stream: LineStream,
+}
+
+impl Trace {
+    pub fn start(iface: &str, target: &str) -> Result<Self> {
+        let (program, stream) =
+            match LineStream::spawn("mtr", &["--raw", "-n", "-I", iface, target]) {
+                Ok(stream) => ("mtr", stream),
+                Err(_) => (
+                    "traceroute",
+                    LineStream::spawn("traceroute", &["-n", "-i", iface, target])?,
+                ),
+            };
+        Ok(Self {
+            iface: iface.to_string(),
+            target: target.to_string(),
+            program,
+            hops: Vec::new(),
+            error: None,
+            finished: false,
+            stream,
+        })
+    }
+
+    fn hop(&mut self, index: usize) -> &mut Hop {
+        if self.hops.len() <= index {
+            self.hops.resize_with(index + 1, Hop::default);
+        }
+        &mut self.hops[index]
+    }
+
+    pub fn poll(&mut self) {
+        for line in self.stream.drain() {
+            let handled = if self.program == "mtr" {
+                self.apply_mtr_line(&line)
+            } else {
+                self.apply_traceroute_line(&line)
+            };
+            // Anything else but traceroute's banner is an error message.
+            if !handled && !line.trim().is_empty() && !line.starts_with("traceroute to") {
+                self.error = Some(line);
+            }
+        }
+        if !self.finished && self.stream.exit_status().is_some() {
+            self.finished = true;
+        }
+    }
+
+    /// `x <hop> <seq>` (probe sent), `h <hop> <ip>` (host), `p <hop> <usecs> <seq>` (reply).
+    fn apply_mtr_line(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        let (Some(kind), Some(hop)) = (words.next(), words.next().and_then(|h| h.parse().ok()))
+        else {
+            return false;
+        };
+        let value = words.next();
+        match (kind, value) {
+            ("x", _) => self.hop(hop).sent += 1,
+            ("h", Some(ip)) => self.hop(hop).host = Some(ip.to_string()),
+            ("p", Some(usecs)) => match usecs.parse::<f64>() {
+                Ok(us) => self.hop(hop).reply(us / 1000.0),
+                Err(_) => return false,
+            },
+            ("h" | "p", None) => return false,
+            // DNS names, MPLS labels, etc.
+            _ => {}
+        }
+        true
+    }
+
+    /// ` 3  10.0.0.1  1.234 ms  1.301 ms *` (a probe may also answer from another host).
+    fn apply_traceroute_line(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace().peekable();
+        let Some(hop) = words.next().and_then(|h| h.parse::<usize>().ok()) else {
+            return false;
+        };
+        let Some(index) = hop.checked_sub(1) else {
+            return false;
+        };
+        let hop = self.hop(index);
+        while let Some(word) = words.next() {
+            if word == "*" {
+                hop.sent += 1;
+            } else if let Ok(ms) = word.parse::<f64>() {
+                if words.peek() == Some(&"ms") {
+                    words.next();
+                }
+                hop.sent += 1;
+                hop.reply(ms);
+            } else if !word.starts_with('!') {
+                hop.host = Some(word.to_string());
+            }
+        }
+        true
+    }
+}
+
+/// Expects an empty `204 No Content`; anything else means something is intercepting HTTP.
+pub const DEFAULT_PROBE_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
+
+/// The probe URL: `ETHTUI_PROBE_URL`, then `probe_url` from the config file, then the default.
+pub fn probe_url(configured: Option<&str>) -> String {
+    std::env::var("ETHTUI_PROBE_URL")
+        .ok()
+        .or_else(|| configured.map(str::to_string))
+        .filter(|u| !u.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PROBE_URL.to_string())
+}
+
+/// Result of the periodic Internet check on one interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Online,
+    /// HTTP works but the probe was answered by something else (a captive portal login page).
+    Portal,
+    /// The probe host didn't resolve.
+    NoDns,
+    Offline,
+    /// No `curl` to probe with.
+    Unknown,
+}
+
+impl Connectivity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Connectivity::Online => "✓",
+            Connectivity::Portal => "portal",
+            Connectivity::NoDns => "no DNS",
+            Connectivity::Offline => "offline",
+            Connectivity::Unknown => "?",
+        }
+    }
+}
+
+fn url_host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map_or(url, |(_, r)| r);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = match authority.strip_prefix('[') {
+        Some(v6) => v6.split(']').next()?,
+        None => authority.split(':').next()?,
+    };
+    (!host.is_empty()).then_some(host)
+}
+
+/// Resolves the probe host, then fetches `url` out of `iface` with `curl --interface`.
+pub async fn probe_connectivity(iface: &str, url: &str) -> Connectivity {
+    let Some(host) = url_host(url) else {
+        return Connectivity::Unknown;
+    };
+    let resolved = Command::new("getent")
+        .args(["ahosts", host])
+        .output()
+        .await
+        .is_ok_and(|o| o.status.success());
+    if !resolved {
+        return Connectivity::NoDns;
+    }
+
+    let Ok(out) = Command::new("curl")
+        .args([
+            "--interface",
+            iface,
+            "--silent",
+            "--output",
+            "/dev/null",
+            "--write-out",
+            "%{http_code}",
+            "--max-time",
+            "5",
+            url,
+        ])
+        .output()
+        .await
+    else {
+        return Connectivity::Unknown;
+    };
+    match String::from_utf8_lossy(&out.stdout).trim() {
+        "204" => Connectivity::Online,
+        // curl prints 000 when it never got a response.
+        "000" | "" => Connectivity::Offline,
+        _ => Connectivity::Portal,
+    }
+}
+
+/// Whether an interface's IPv4 gateway answers ARP, from the periodic check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GatewayReach {
+    /// With the round trip in ms when arping measured one.
+    Reachable(Option<f64>),
+    Unreachable,
+    /// Neither arping nor the neighbor table could tell.
+    Unknown,
+}
+
+/// Asks `gateway` for its MAC out of `iface`: with arping when it's installed and allowed to
+/// run, otherwise by making the kernel resolve it and watching the neighbor table.
+pub async fn probe_gateway(iface: &str, gateway: &str) -> GatewayReach {
+    if let Some(reach) = arping(iface, gateway).await {
+        return reach;
+    }
+    let Ok(addr) = gateway.parse::<Ipv4Addr>() else {
+        return GatewayReach::Unknown;
+    };
+    // An empty datagram to the discard port still needs the gateway's MAC.
+    let Ok(socket) = tokio::net::UdpSocket::bind("0.0.0.0:0").await else {
+        return GatewayReach::Unknown;
+    };
+    if socket.send_to(&[], (addr, 9)).await.is_err() {
+        return GatewayReach::Unknown;
+    }
+    // A stale entry is only re-probed after the kernel's 5s delay_first_probe_time.
+    let mut resolving = false;
+    for _ in 0..20 {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let neighbors = list_neighbors(iface).await;
+        let Some(entry) = neighbors.iter().find(|n| n.dst == gateway) else {
+            continue;
+        };
+        let state = |s: &str| entry.state.iter().any(|e| e == s);
+        if state("REACHABLE") || state("PERMANENT") || state("NOARP") {
+            return GatewayReach::Reachable(None);
+        }
+        if state("FAILED") {
+            return GatewayReach::Unreachable;
+        }
+        resolving = state("INCOMPLETE");
+    }
+    // The kernel gives up after 3 unanswered requests, a second apart.
+    if resolving {
+        GatewayReach::Unreachable
+    } else {
+        GatewayReach::Unknown
+    }
+}
+
+/// None when arping isn't installed or can't open a raw socket.
+async fn arping(iface: &str, gateway: &str) -> Option<GatewayReach> {
+    let out = Command::new("arping")
+        .args(["-c", "1", "-w", "2", "-I", iface, gateway])
+        .output()
+        .await
+        .ok()?;
+    match out.status.code() {
+        Some(0) => Some(GatewayReach::Reachable(arping_rtt(
+            &String::from_utf8_lossy(&out.stdout),
+        ))),
+        Some(1) => Some(GatewayReach::Unreachable),
+        _ => None,
+    }
+}
+
+/// iputils prints `Unicast reply from 192.0.2.1 [..]  0.734ms`, Habets' arping
+/// `60 bytes from ... (192.0.2.1): index=0 time=1.234 msec`.
+fn arping_rtt(out: &str) -> Option<f64> {
+    let line = out
+        .lines()
+        .find(|l| l.contains("reply from") || l.contains("bytes from"))?;
+    if let Some((_, time)) = line.split_once("time=") {
+        return time.split_whitespace().next()?.parse().ok();
+    }
+    line.split_whitespace()
+        .last()?
+        .strip_suffix("ms")?
+        .parse()
+        .ok()
+}
+
+/// Looked up by the DNS test when the config doesn't name a host.
+pub const DEFAULT_DNS_TEST_HOST: &str = "example.com";
+
+/// Queries per server in a DNS benchmark.
+pub const DNS_BENCHMARK_QUERIES: usize = 10;
+
+/// One server in a DNS test or benchmark, with its answers so far.
+#[derive(Debug, Clone)]
+pub struct DnsServerResult {
+    pub server: String,
+    /// Who runs it, for the well-known public resolvers.
+    pub operator: Option<&'static str>,
+    /// One of the interface's own servers.
+    pub configured: bool,
+    /// In the order they were sent.
+    pub answers: Vec<Result<dns::Reply, String>>,
+}
+
+impl DnsServerResult {
+    /// Response times of the answers that resolved their name, in ms.
+    fn rtts(&self) -> Vec<f64> {
+        let mut rtts: Vec<f64> = self
+            .answers
+            .iter()
+            .filter_map(|a| a.as_ref().ok())
+            .filter(|r| r.resolved())
+            .map(|r| r.rtt.as_secs_f64() * 1000.0)
+            .collect();
+        rtts.sort_by(f64::total_cmp);
+        rtts
+    }
+
+    pub fn median_ms(&self) -> Option<f64> {
+        let rtts = self.rtts();
+        match rtts.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(rtts[n / 2]),
+            n => Some((rtts[n / 2 - 1] + rtts[n / 2]) / 2.0),
+        }
+    }
+
+    pub fn min_ms(&self) -> Option<f64> {
+        self.rtts().first().copied()
+    }
+
+    pub fn max_ms(&self) -> Option<f64> {
+        self.rtts().last().copied()
+    }
+
+    /// Answers that didn't resolve their name: errors, timeouts and NXDOMAIN alike.
+    pub fn failures(&self) -> usize {
+        self.answers
+            .iter()
+            .filter(|a| !a.as_ref().is_ok_and(dns::Reply::resolved))
+            .count()
+    }
+}
+
+/// Resolves a name with each of an interface's DNS servers at once, out of that interface.
+/// As a benchmark, the well-known public resolvers are asked too, each server
+/// `DNS_BENCHMARK_QUERIES` times in a row over a rotation of popular names. Dropping it stops
+/// the queries still waiting.
+pub struct DnsTest {
+    pub iface: String,
+    pub host: String,
+    /// Queries per server: 1 for a plain test.
+    pub queries: usize,
+    /// The interface's servers in its order, then the public resolvers it doesn't use already.
+    pub servers: Vec<DnsServerResult>,
+    pub finished: bool,
+    rx: mpsc::UnboundedReceiver<(usize, Result<dns::Reply, String>)>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for DnsTest {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl DnsTest {
+    pub fn start(iface: &str, host: &str, configured: &[String], benchmark: bool) -> Result<Self> {
+        if configured.is_empty() && !benchmark {
+            return Err(std::io::Error::other(format!("{iface} has no DNS servers")).into());
+        }
+        let mut servers: Vec<DnsServerResult> = configured
+            .iter()
+            .map(|s| DnsServerResult {
+                server: s.clone(),
+                operator: None,
+                configured: true,
+                answers: Vec::new(),
+            })
+            .collect();
+        let mut names = vec![host.to_string()];
+        let queries = if benchmark {
+            for (server, operator) in dns::WELL_KNOWN_RESOLVERS {
+                if let Some(known) = servers.iter_mut().find(|s| s.server == server) {
+                    known.operator = Some(operator);
+                } else {
+                    servers.push(DnsServerResult {
+                        server: server.into(),
+                        operator: Some(operator),
+                        configured: false,
+                        answers: Vec::new(),
+                    });
+                }
+            }
+            names.extend(dns::BENCHMARK_HOSTS.iter().map(|h| h.to_string()));
+            DNS_BENCHMARK_QUERIES
+        } else {
+            1
+        };
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut runs: futures::stream::FuturesUnordered<_> = servers
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let (iface, server, names, tx) = (
+                    iface.to_string(),
+                    s.server.clone(),
+                    names.clone(),
+                    tx.clone(),
+                );
+                // One query at a time per server, so queueing doesn't count as latency.
+                async move {
+                    for name in names.iter().cycle().take(queries) {
+                        let result = dns::resolve(&iface, &server, name).await;
+                        let _ = tx.send((i, result.map_err(|e| e.to_string())));
+                    }
+                }
+            })
+            .collect();
+        let task = tokio::spawn(async move { while runs.next().await.is_some() {} });
+        Ok(Self {
+            iface: iface.to_string(),
+            host: host.to_string(),
+            queries,
+            servers,
+            finished: false,
+            rx,
+            task,
+        })
+    }
+
+    pub fn benchmark(&self) -> bool {
+        self.queries > 1
+    }
+
+    pub fn poll(&mut self) {
+        // Checked before draining: once the task is done, everything it sent is queued.
+        let done = self.task.is_finished();
+        while let Ok((i, result)) = self.rx.try_recv() {
+            if let Some(s) = self.servers.get_mut(i) {
+                s.answers.push(result);
+            }
+        }
+        self.finished = done;
+    }
+
+    /// How many servers resolved the host on every query.
+    pub fn resolved(&self) -> usize {
+        self.servers
+            .iter()
+            .filter(|s| !s.answers.is_empty() && s.failures() == 0)
+            .count()
+    }
+
+    /// Servers by median latency, fastest first; the ones that never resolved anything last.
+    pub fn ranked(&self) -> Vec<&DnsServerResult> {
+        let mut ranked: Vec<_> = self.servers.iter().collect();
+        ranked.sort_by(|a, b| match (a.median_ms(), b.median_ms()) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        ranked
+    }
+}
+
+/// Rate samples kept for the speed test chart.
+pub const SPEEDTEST_SAMPLES: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedTestMode {
+    Iperf3,
+    Http,
+}
+
+/// An `iperf3` client run or an HTTP download out of one interface, with rates in Mbit/s.
+pub struct SpeedTest {
+    pub iface: String,
+    pub target: String,
+    pub mode: SpeedTestMode,
+    pub samples: VecDeque<f64>,
+    pub summary: Option<String>,
+    pub error: Option<String>,
+    pub finished: bool,
+    stream: LineStream,
+}
+
+/// `host`, `host:port`, `[v6]` or `[v6]:port`; a bare IPv6 address has several colons, so it's
+/// all host. The port defaults to iperf3's 5201.
+fn iperf3_target(target: &str) -> (&str, &str) {
+    let default = (target, "5201");
+    if let Some(bracketed) = target.strip_prefix('[') {
+        return match bracketed.split_once(']') {
+            Some((host, "")) => (host, "5201"),
+            Some((host, port)) => match port.strip_prefix(':') {
+                Some(p) if p.parse::<u16>().is_ok() => (host, p),
+                _ => default,
+            },
+            None => default,
+        };
+    }
+    match target.split_once(':') {
+        Some((h, p)) if !p.contains(':') && p.parse::<u16>().is_ok() => (h, p),
+        _ => default,
+    }
+}
+
+impl SpeedTest {
+    /// `target` is an `http(s)://` URL to download, or an iperf3 server as `host[:port]` or
+    /// `[v6]:port`.
+    /// `source` is the interface's IPv4 address, which iperf3 binds to.
+    pub fn start(iface: &str, source: &str, target: &str) -> Result<Self> {
+        let (mode, stream) = if target.starts_with("http://") || target.starts_with("https://") {
+            let stream = LineStream::spawn(
+                "curl",
+                &[
+                    "--interface",
+                    iface,
+                    "--silent",
+                    "--show-error",
+                    "--fail",
+                    "--output",
+                    "/dev/null",
+                    "--write-out",
+                    "\n%{speed_download} %{size_download} %{time_total}\n",
+                    target,
+                ],
+            )?;
+            (SpeedTestMode::Http, stream)
+        } else {
+            let (host, port) = iperf3_target(target);
+            let stream = LineStream::spawn(
+                "iperf3",
+                &[
+                    "-c",
+                    host,
+                    "-p",
+                    port,
+                    "-B",
+                    source,
+                    "-f",
+                    "m",
+                    "-t",
+                    "10",
+                    "--forceflush",
+                ],
+            )?;
+            (SpeedTestMode::Iperf3, stream)
+        };
+        Ok(Self {
+            iface: iface.to_string(),
+            target: target.to_string(),
+            mode,
+            samples: VecDeque::with_capacity(SPEEDTEST_SAMPLES),
+            summary: None,
+            error: None,
+            finished: false,
+            stream,
+        })
+    }
+
+    fn push(&mut self, mbps: f64) {
+        if self.samples.len() == SPEEDTEST_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(mbps);
+    }
+
+    pub fn current(&self) -> f64 {
+        self.samples.back().copied().unwrap_or(0.0)
+    }
+
+    /// Picks up new output; returns true once, when the test finishes. curl prints nothing
+    /// until it's done, so HTTP downloads are sampled from the interface's RX rate instead.
+    pub fn poll(&mut self, rx_bytes_per_sec: u64) -> bool {
+        if self.finished {
+            return false;
+        }
+        if self.mode == SpeedTestMode::Http {
+            self.push(rx_bytes_per_sec as f64 * 8.0 / 1e6);
+        }
+        for line in self.stream.drain() {
+            match self.mode {
+                SpeedTestMode::Iperf3 => self.apply_iperf3_line(&line),
+                SpeedTestMode::Http => self.apply_curl_line(&line),
+            }
+        }
+        if let Some(code) = self.stream.exit_status() {
+            self.finished = true;
+            if code != 0 && self.error.is_none() {
+                self.error = Some(format!("exited with status {code}"));
+            }
+        }
+        self.finished
+    }
+
+    /// `[  5]   1.00-2.00   sec   112 MBytes   941 Mbits/sec    0    404 KBytes`; the final
+    /// lines end in `sender`/`receiver`.
+    fn apply_iperf3_line(&mut self, line: &str) {
+        if line.starts_with("iperf3:") {
+            self.error = Some(line.to_string());
+            return;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some(i) = words.iter().position(|w| *w == "Mbits/sec") else {
+            return;
+        };
+        let Some(mbps) = i.checked_sub(1).and_then(|j| words[j].parse::<f64>().ok()) else {
+            return;
+        };
+        if line.trim_end().ends_with("receiver") {
+            let transferred = words
+                .iter()
+                .position(|w| *w == "sec")
+                .and_then(|s| Some(format!("{} {}", words.get(s + 1)?, words.get(s + 2)?)))
+                .unwrap_or_default();
+            self.summary = Some(format!("{mbps:.0} Mbit/s ({transferred} received)"));
+        } else if !line.trim_end().ends_with("sender") {
+            self.push(mbps);
+        }
+    }
+
+    /// The `--write-out` line: bytes/s, bytes and seconds.
+    fn apply_curl_line(&mut self, line: &str) {
+        let nums: Vec<f64> = line
+            .split_whitespace()
+            .filter_map(|w| w.parse().ok())
+            .collect();
+        match nums[..] {
+            [speed, size, secs] => {
+                self.summary = Some(format!(
+                    "{:.0} Mbit/s ({:.1} MB in {secs:.1}s)",
+                    speed * 8.0 / 1e6,
+                    size / 1e6
+                ));
+            }
+            _ if line.starts_with("curl:") => self.error = Some(line.to_string()),
+            _ => {}
+        }
+    }
+}
+
+/// Largest subnet `A` sweeps (a /22).
+pub const MAX_SCAN_ADDRESSES: u64 = 1024;
+
+/// Pings in flight at once during a sweep.
+const SWEEP_CONCURRENCY: usize = 64;
+
+/// A host that answered ARP on the scanned subnet.
+#[derive(Debug, Clone)]
+pub struct LanHost {
+    pub ip: Ipv4Addr,
+    pub mac: String,
+    pub hostname: Option<String>,
+}
+
+enum ScanEvent {
+    Method(&'static str),
+    Probed,
+    Host(LanHost),
+    Hostname(Ipv4Addr, String),
+}
+
+/// ARP sweep of an interface's IPv4 subnet: `arp-scan` when it's installed and allowed to
+/// open a raw socket, otherwise a ping to every address followed by a read of the neighbor
+/// table. Hostnames are reverse lookups done afterwards. Dropping it stops the sweep.
+pub struct Scan {
+    pub iface: String,
+    pub subnet: String,
+    pub method: Option<&'static str>,
+    pub total: usize,
+    pub probed: usize,
+    /// Sorted by address.
+    pub hosts: Vec<LanHost>,
+    pub finished: bool,
+    rx: mpsc::UnboundedReceiver<ScanEvent>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Scan {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl Scan {
+    /// `cidr` is one of the interface's addresses, e.g. `192.168.1.23/24`.
+    pub fn start(iface: &str, cidr: &str) -> Result<Self> {
+        let (subnet, targets) = subnet_targets(cidr)?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let total = targets.len();
+        let task = tokio::spawn(run_scan(iface.to_string(), subnet.clone(), targets, tx));
+        Ok(Self {
+            iface: iface.to_string(),
+            subnet,
+            method: None,
+            total,
+            probed: 0,
+            hosts: Vec::new(),
+            finished: false,
+            rx,
+            task,
+        })
+    }
+
+    pub fn poll(&mut self) {
+        // Checked before draining: once the task is done, everything it sent is queued.
+        let done = self.task.is_finished();
+        while let Ok(ev) = self.rx.try_recv() {
+            match ev {
+                ScanEvent::Method(m) => self.method = Some(m),
+                ScanEvent::Probed => self.probed += 1,
+                ScanEvent::Host(host) => {
+                    let i = self.hosts.partition_point(|h| h.ip < host.ip);
+                    self.hosts.insert(i, host);
+                }
+                ScanEvent::Hostname(ip, name) => {
+                    if let Some(h) = self.hosts.iter_mut().find(|h| h.ip == ip) {
+                        h.hostname = Some(name);
+                    }
+                }
+            }
+        }
+        self.finished = done;
+    }
+}
+
+/// The network in CIDR form and its host addresses, minus `cidr`'s own address.
+fn subnet_targets(cidr: &str) -> Result<(String, Vec<Ipv4Addr>)> {
+    let (ip, prefix) = cidr
+        .split_once('/')
+        .and_then(|(ip, p)| Some((ip.parse::<Ipv4Addr>().ok()?, p.parse::<u32>().ok()?)))
+        .filter(|(_, p)| *p <= 32)
+        .ok_or_else(|| std::io::Error::other(format!("invalid address: {cidr}")))?;
+    let size = 1u64 << (32 - prefix);
+    if size > MAX_SCAN_ADDRESSES {
+        return Err(std::io::Error::other(format!(
+            "{cidr}: subnet too large to sweep (more than {MAX_SCAN_ADDRESSES} addresses)"
+        ))
+        .into());
+    }
+    let network = u64::from(u32::from(ip) & (u32::MAX << (32 - prefix)));
+    // /31 and /32 have no network or broadcast address to skip.
+    let hosts = if size <= 2 {
+        network..network + size
+    } else {
+        network + 1..network + size - 1
+    };
+    let targets = hosts
+        .map(|a| Ipv4Addr::from(a as u32))
+        .filter(|a| *a != ip)
+        .collect();
+    Ok((
+        format!("{}/{prefix}", Ipv4Addr::from(network as u32)),
+        targets,
+    ))
+}
+
+async fn run_scan(
+    iface: String,
+    subnet: String,
+    targets: Vec<Ipv4Addr>,
+    tx: mpsc::UnboundedSender<ScanEvent>,
+) {
+    let hosts = match arp_scan(&iface, &subnet).await {
+        Some(hosts) => {
+            let _ = tx.send(ScanEvent::Method("arp-scan"));
+            hosts
+        }
+        None => {
+            let _ = tx.send(ScanEvent::Method("ping sweep"));
+            ping_sweep(&iface, &targets, &tx).await
+        }
+    };
+    for host in &hosts {
+        let _ = tx.send(ScanEvent::Host(host.clone()));
+    }
+
+    futures::stream::iter(hosts)
+        .map(|h| async move { (h.ip, reverse_lookup(h.ip).await) })
+        .buffer_unordered(SWEEP_CONCURRENCY)
+        .for_each(|(ip, name)| {
+            if let Some(name) = name {
+                let _ = tx.send(ScanEvent::Hostname(ip, name));
+            }
+            async {}
+        })
+        .await;
+}
+
+/// `arp-scan --plain` prints `<ip>\t<mac>` per reply; None if it's missing or not permitted.
+async fn arp_scan(iface: &str, subnet: &str) -> Option<Vec<LanHost>> {
+    let out = Command::new("arp-scan")
+        .args([
+            "--interface",
+            iface,
+            "--plain",
+            "--quiet",
+            "--ignoredups",
+            subnet,
+        ])
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()
+        .filter(|o| o.status.success())?;
+    let hosts = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut cols = line.split_whitespace();
+            let ip = cols.next()?.parse().ok()?;
+            let mac = cols.next()?.to_lowercase();
+            Some(LanHost {
+                ip,
+                mac,
+                hostname: None,
+            })
+        })
+        .collect();
+    Some(hosts)
+}
+
+/// Pings every target once so the kernel resolves it, then reads back what ARP found.
+async fn ping_sweep(
+    iface: &str,
+    targets: &[Ipv4Addr],
+    tx: &mpsc::UnboundedSender<ScanEvent>,
+) -> Vec<LanHost> {
+    futures::stream::iter(targets.iter().copied())
+        .map(|ip| async move {
+            let _ = Command::new("ping")
+                .args([
+                    "-n",
+                    "-q",
+                    "-c",
+                    "1",
+                    "-W",
+                    "1",
+                    "-I",
+                    iface,
+                    &ip.to_string(),
+                ])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .kill_on_drop(true)
+                .status()
+                .await;
+        })
+        .buffer_unordered(SWEEP_CONCURRENCY)
+        .for_each(|()| {
+            let _ = tx.send(ScanEvent::Probed);
+            async {}
+        })
+        .await;
+
+    list_neighbors(iface)
+        .await
+        .into_iter()
+        .filter(|n| !n.state.iter().any(|s| s == "FAILED" || s == "INCOMPLETE"))
+        .filter_map(|n| {
+            let ip = n.dst.parse().ok().filter(|ip| targets.contains(ip))?;
+            Some(LanHost {
+                ip,
+                mac: n.lladdr?,
+                hostname: None,
+            })
+        })
+        .collect()
+}
+
+/// PTR name for `ip` via NSS (`getent hosts`), so /etc/hosts and mDNS count too.
+async fn reverse_lookup(ip: Ipv4Addr) -> Option<String> {
+    let lookup = Command::new("getent")
+        .args(["hosts", &ip.to_string()])
+        .kill_on_drop(true)
+        .output();
+    let out = tokio::time::timeout(Duration::from_secs(3), lookup)
+        .await
+        .ok()?
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    stdout.split_whitespace().nth(1).map(str::to_string)
+}
+
+/// One service instance announced over mDNS; resolved fields fill in once avahi looks it up.
+#[derive(Debug, Clone, Default)]
+pub struct MdnsService {
+    /// `IPv4` or `IPv6`: avahi reports each announcement once per protocol.
+    pub protocol: String,
+    pub name: String,
+    /// Service type such as `_http._tcp`.
+    pub kind: String,
+    pub domain: String,
+    pub host: Option<String>,
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub txt: Vec<String>,
+}
+
+/// `avahi-browse -a -r -p` running in the background, restricted to one interface.
+/// Dropping it kills the process.
+pub struct MdnsBrowser {
+    pub iface: String,
+    /// Sorted by type, then name.
+    pub services: Vec<MdnsService>,
+    pub error: Option<String>,
+    pub finished: bool,
+    stream: LineStream,
+}
+
+impl MdnsBrowser {
+    pub fn start(iface: &str) -> Result<Self> {
+        // -k keeps service types raw (`_ssh._tcp`) instead of avahi's descriptions.
+        let stream = LineStream::spawn("avahi-browse", &["-a", "-r", "-p", "-k"])?;
+        Ok(Self {
+            iface: iface.to_string(),
+            services: Vec::new(),
+            error: None,
+            finished: false,
+            stream,
+        })
+    }
+
+    pub fn poll(&mut self) {
+        for line in self.stream.drain() {
+            if !self.apply_line(&line) && !line.trim().is_empty() {
+                self.error = Some(line);
+            }
+        }
+        if !self.finished && self.stream.exit_status().is_some() {
+            self.finished = true;
+        }
+    }
+
+    /// `+;eth0;IPv4;name;type;domain` (new), `-;...` (gone) and
+    /// `=;eth0;IPv4;name;type;domain;host;address;port;"txt" "txt"` (resolved).
+    fn apply_line(&mut self, line: &str) -> bool {
+        let cols: Vec<&str> = line.split(';').collect();
+        if cols.len() < 6 || !matches!(cols[0], "+" | "-" | "=") {
+            return false;
+        }
+        if cols[1] != self.iface {
+            return true;
+        }
+        let key = MdnsService {
+            protocol: cols[2].to_string(),
+            name: avahi_unescape(cols[3]),
+            kind: cols[4].to_string(),
+            domain: cols[5].to_string(),
+            ..Default::default()
+        };
+        let pos = self.services.iter().position(|s| {
+            (&s.protocol, &s.name, &s.kind, &s.domain)
+                == (&key.protocol, &key.name, &key.kind, &key.domain)
+        });
+        match (cols[0], pos) {
+            ("-", Some(i)) => {
+                self.services.remove(i);
+            }
+            ("-", None) => {}
+            (_, pos) => {
+                let i = pos.unwrap_or_else(|| {
+                    let i = self
+                        .services
+                        .partition_point(|s| (&s.kind, &s.name) < (&key.kind, &key.name));
+                    self.services.insert(i, key);
+                    i
+                });
+                if cols[0] == "=" && cols.len() >= 9 {
+                    let s = &mut self.services[i];
+                    s.host = Some(avahi_unescape(cols[6]));
+                    s.address = Some(cols[7].to_string());
+                    s.port = cols[8].parse().ok();
+                    // TXT records may themselves contain `;`.
+                    s.txt = parse_txt(&cols[9..].join(";"));
+                }
+            }
+        }
+        true
+    }
+}
+
+/// avahi escapes `.`, `\` and non-printable bytes in names as `\.`, `\\` and `\DDD` (decimal).
+fn avahi_unescape(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        let digits = rest
+            .iter()
+            .take(3)
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if digits == 3
+            && let Ok(code) = std::str::from_utf8(&rest[..3])
+                .unwrap_or_default()
+                .parse::<u8>()
+        {
+            bytes.push(code);
+            rest = &rest[3..];
+        } else if let Some((&c, tail)) = rest.split_first() {
+            bytes.push(c);
+            rest = tail;
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// `"path=/" "model=X"` into its quoted strings.
+fn parse_txt(s: &str) -> Vec<String> {
+    s.split('"')
+        .skip(1)
+        .step_by(2)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_iperf3_targets() {
+        let cases = [
+            ("iperf.example", ("iperf.example", "5201")),
+            ("192.0.2.1:5202", ("192.0.2.1", "5202")),
+            ("fe80::1", ("fe80::1", "5201")),
+            ("2001:db8::5202", ("2001:db8::5202", "5201")),
+            ("[2001:db8::1]:5202", ("2001:db8::1", "5202")),
+            ("[2001:db8::1]", ("2001:db8::1", "5201")),
+        ];
+        for (target, want) in cases {
+            assert_eq!(iperf3_target(target), want, "{target}");
+        }
+    }
+}
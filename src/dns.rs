@@ -0,0 +1,356 @@
+use anyhow::Result;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::os::fd::AsRawFd;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+
+/// How long a server gets to answer before it counts as broken.
+pub const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Public resolvers the DNS benchmark compares the configured servers with.
+pub const WELL_KNOWN_RESOLVERS: [(&str, &str); 8] = [
+    ("1.1.1.1", "Cloudflare"),
+    ("1.0.0.1", "Cloudflare"),
+    ("8.8.8.8", "Google"),
+    ("8.8.4.4", "Google"),
+    ("9.9.9.9", "Quad9"),
+    ("149.112.112.112", "Quad9"),
+    ("208.67.222.222", "OpenDNS"),
+    ("94.140.14.14", "AdGuard"),
+];
+
+/// Names the benchmark rotates through after the test host, so not every answer comes from
+/// the resolver's cache.
+pub const BENCHMARK_HOSTS: [&str; 9] = [
+    "google.com",
+    "wikipedia.org",
+    "github.com",
+    "amazon.com",
+    "debian.org",
+    "kernel.org",
+    "mozilla.org",
+    "netflix.com",
+    "bbc.co.uk",
+];
+
+/// What one server answered to an A query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    /// 0 NOERROR, 2 SERVFAIL, 3 NXDOMAIN, 5 REFUSED...
+    pub rcode: u8,
+    pub addresses: Vec<Ipv4Addr>,
+    pub rtt: Duration,
+}
+
+impl Reply {
+    /// The server resolved the name to at least one address.
+    pub fn resolved(&self) -> bool {
+        self.rcode == 0 && !self.addresses.is_empty()
+    }
+
+    /// `NOERROR`, or `NODATA` when the name exists without an A record.
+    pub fn status(&self) -> String {
+        match self.rcode {
+            0 if self.addresses.is_empty() => "NODATA".into(),
+            0 => "NOERROR".into(),
+            1 => "FORMERR".into(),
+            2 => "SERVFAIL".into(),
+            3 => "NXDOMAIN".into(),
+            4 => "NOTIMP".into(),
+            5 => "REFUSED".into(),
+            n => format!("rcode {n}"),
+        }
+    }
+}
+
+/// Parses a server as resolv.conf or resolvectl list it: `192.0.2.53`, `fe80::1%eth0`, or with a
+/// DNS-over-TLS name after `#`.
+fn server_addr(server: &str) -> Option<SocketAddr> {
+    let server = server.split('#').next()?;
+    let (ip, scope) = match server.split_once('%') {
+        Some((ip, scope)) => (ip, Some(scope)),
+        None => (server, None),
+    };
+    match ip.parse::<IpAddr>().ok()? {
+        IpAddr::V4(v4) => Some(SocketAddr::from((v4, 53))),
+        IpAddr::V6(v6) => {
+            // The zone is an interface name, or already its index.
+            let scope_id = scope.map_or(0, |scope| {
+                scope.parse().ok().unwrap_or_else(|| {
+                    fs::read_to_string(format!("/sys/class/net/{scope}/ifindex"))
+                        .ok()
+                        .and_then(|i| i.trim().parse().ok())
+                        .unwrap_or(0)
+                })
+            });
+            Some(SocketAddrV6::new(v6, 53, 0, scope_id).into())
+        }
+    }
+}
+
+/// Asks `server` for the A records of `name` out of `iface`. Loopback servers (a local stub
+/// resolver) are asked directly. Errs on timeouts and socket errors.
+pub async fn resolve(iface: &str, server: &str, name: &str) -> Result<Reply> {
+    let addr = server_addr(server)
+        .ok_or_else(|| io::Error::other(format!("invalid server address: {server}")))?;
+    let unspecified = match addr {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = std::net::UdpSocket::bind((unspecified, 0))?;
+    if !addr.ip().is_loopback() {
+        bind_to_device(&socket, iface)?;
+    }
+    socket.set_nonblocking(true)?;
+    let socket = UdpSocket::from_std(socket)?;
+    socket.connect(addr).await?;
+
+    let id = query_id();
+    let query = build_query(id, name)?;
+    let start = Instant::now();
+    socket.send(&query).await.map_err(short_error)?;
+    let mut buf = [0u8; 1500];
+    loop {
+        let left = QUERY_TIMEOUT.saturating_sub(start.elapsed());
+        let len = tokio::time::timeout(left, socket.recv(&mut buf))
+            .await
+            .map_err(|_| io::Error::other("timed out"))?
+            .map_err(short_error)?;
+        // Anything else is a late answer to an earlier query from the same port.
+        if let Some((rcode, addresses)) = parse_reply(id, &buf[..len]) {
+            return Ok(Reply {
+                rcode,
+                addresses,
+                rtt: start.elapsed(),
+            });
+        }
+    }
+}
+
+/// An ICMP error for an earlier datagram, in words that fit a table cell.
+fn short_error(err: io::Error) -> io::Error {
+    let msg = match err.kind() {
+        io::ErrorKind::ConnectionRefused => "port 53 closed",
+        io::ErrorKind::HostUnreachable => "host unreachable",
+        io::ErrorKind::NetworkUnreachable => "network unreachable",
+        _ => return err,
+    };
+    io::Error::other(msg)
+}
+
+/// SO_BINDTODEVICE, so the query leaves through `iface` whatever the routing table says.
+fn bind_to_device(socket: &std::net::UdpSocket, iface: &str) -> io::Result<()> {
+    // SAFETY: the option value is `iface.len()` readable bytes; the kernel copies them.
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_BINDTODEVICE,
+            iface.as_ptr().cast(),
+            iface.len() as libc::socklen_t,
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        let err = io::Error::last_os_error();
+        Err(io::Error::other(format!("can't bind to {iface}: {err}")))
+    }
+}
+
+fn query_id() -> u16 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    (nanos ^ std::process::id()) as u16
+}
+
+/// A recursive query for the A records of `name`.
+fn build_query(id: u16, name: &str) -> Result<Vec<u8>> {
+    let name = name.trim_end_matches('.');
+    let invalid = || io::Error::other(format!("invalid hostname: {name}"));
+    if name.is_empty() || name.len() > 253 {
+        return Err(invalid().into());
+    }
+    let mut query = Vec::with_capacity(18 + name.len());
+    query.extend(id.to_be_bytes());
+    // RD set; one question.
+    query.extend([0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(invalid().into());
+        }
+        query.push(label.len() as u8);
+        query.extend(label.as_bytes());
+    }
+    // Root label, QTYPE A, QCLASS IN.
+    query.extend([0, 0, 1, 0, 1]);
+    Ok(query)
+}
+
+/// The rcode and A records of a reply to query `id`; None for anything else.
+fn parse_reply(id: u16, msg: &[u8]) -> Option<(u8, Vec<Ipv4Addr>)> {
+    let u16_at = |at: usize| Some(u16::from_be_bytes([*msg.get(at)?, *msg.get(at + 1)?]));
+    let is_reply = msg.get(2)? & 0x80 != 0;
+    if u16_at(0)? != id || !is_reply {
+        return None;
+    }
+    let rcode = msg.get(3)? & 0x0f;
+    let questions = u16_at(4)?;
+    let answers = u16_at(6)?;
+    let mut at = 12;
+    for _ in 0..questions {
+        at = skip_name(msg, at)? + 4;
+    }
+    let mut addresses = Vec::new();
+    for _ in 0..answers {
+        at = skip_name(msg, at)?;
+        let rtype = u16_at(at)?;
+        let len = usize::from(u16_at(at + 8)?);
+        let data = msg.get(at + 10..at + 10 + len)?;
+        // CNAMEs on the way are skipped; only the addresses matter.
+        if rtype == 1 && len == 4 {
+            addresses.push(Ipv4Addr::new(data[0], data[1], data[2], data[3]));
+        }
+        at += 10 + len;
+    }
+    Some((rcode, addresses))
+}
+
+/// Offset just past the (possibly compressed) name at `at`.
+fn skip_name(msg: &[u8], mut at: usize) -> Option<usize> {
+    loop {
+        let len = *msg.get(at)?;
+        match len {
+            0 => return Some(at + 1),
+            // A pointer ends the name.
+            l if l & 0xc0 == 0xc0 => return Some(at + 2),
+            l => at += 1 + usize::from(l),
+        }
+    }
+}
+
+/// Where the DNS form writes a persistent drop-in for the `.network` file at `network_file`.
+pub fn dropin_path(network_file: &str) -> Option<String> {
+    let name = std::path::Path::new(network_file).file_name()?.to_str()?;
+    Some(format!("/etc/systemd/network/{name}.d/ethtui-dns.conf"))
+}
+
+/// New DNS servers and search domains for a link, checked and ready to preview.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsChange {
+    pub iface: String,
+    pub servers: Vec<String>,
+    pub domains: Vec<String>,
+    /// The networkd drop-in to write, or None to only change resolved's runtime state.
+    pub dropin: Option<String>,
+}
+
+impl DnsChange {
+    /// Servers and domains separated by spaces or commas, as typed in the form.
+    pub fn parse(
+        iface: &str,
+        servers: &str,
+        domains: &str,
+        dropin: Option<String>,
+    ) -> Result<Self> {
+        let split = |s: &str| {
+            s.split([' ', ','])
+                .filter(|w| !w.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let (servers, domains) = (split(servers), split(domains));
+        if let Some(bad) = servers.iter().find(|s| server_addr(s).is_none()) {
+            return Err(io::Error::other(format!("invalid DNS server: {bad}")).into());
+        }
+        if let Some(bad) = domains.iter().find(|d| !valid_domain(d)) {
+            return Err(io::Error::other(format!("invalid search domain: {bad}")).into());
+        }
+        Ok(Self {
+            iface: iface.to_string(),
+            servers,
+            domains,
+            dropin,
+        })
+    }
+
+    /// `[Network]` section replacing the `.network` file's own DNS= and Domains=; an empty
+    /// assignment clears the list first.
+    pub fn dropin_contents(&self) -> String {
+        let mut out = String::from("# Written by ethtui\n[Network]\nDNS=\n");
+        for server in &self.servers {
+            out.push_str(&format!("DNS={server}\n"));
+        }
+        out.push_str("Domains=\n");
+        if !self.domains.is_empty() {
+            out.push_str(&format!("Domains={}\n", self.domains.join(" ")));
+        }
+        out
+    }
+
+    /// The `resolvectl` argument lists; an empty string clears a list.
+    pub fn resolvectl_args(&self) -> [Vec<String>; 2] {
+        let args = |verb: &str, values: &[String]| {
+            let mut args = vec![verb.to_string(), self.iface.clone()];
+            if values.is_empty() {
+                args.push(String::new());
+            } else {
+                args.extend(values.iter().cloned());
+            }
+            args
+        };
+        [args("dns", &self.servers), args("domain", &self.domains)]
+    }
+
+    /// What will run or be written, for the confirmation.
+    pub fn preview(&self) -> String {
+        match &self.dropin {
+            Some(path) => format!(
+                "Writes {path}:\n\n{}\nthen runs networkctl reload and networkctl reconfigure {}.",
+                self.dropin_contents(),
+                self.iface
+            ),
+            None => {
+                let lines = self.resolvectl_args().map(|args| {
+                    let args: Vec<String> = args
+                        .iter()
+                        .map(|a| {
+                            if a.is_empty() {
+                                "\"\"".into()
+                            } else {
+                                a.clone()
+                            }
+                        })
+                        .collect();
+                    format!("resolvectl {}", args.join(" "))
+                });
+                format!(
+                    "Runs:\n\n{}\n\nsystemd-resolved forgets it when the link is reconfigured.",
+                    lines.join("\n")
+                )
+            }
+        }
+    }
+}
+
+/// A search domain, or with a leading `~` a routing-only domain (`~.` routes every query).
+fn valid_domain(domain: &str) -> bool {
+    let name = domain.strip_prefix('~').unwrap_or(domain);
+    if name == "." && domain.starts_with('~') {
+        return true;
+    }
+    let name = name.trim_end_matches('.');
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
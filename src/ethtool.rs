@@ -0,0 +1,672 @@
+use crate::app::run_privileged_capture;
+use crate::pci::{self, PciDevice};
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use tokio::process::Command;
+
+/// Runs `ethtool` read-only and returns stdout. Read queries don't need privileges.
+pub async fn query(args: &[&str]) -> Result<String> {
+    let out = Command::new("ethtool").args(args).output().await?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        return Err(std::io::Error::other(if stderr.is_empty() {
+            format!("ethtool {} failed", args.join(" "))
+        } else {
+            stderr
+        })
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+/// Splits `key: value` lines, skipping lines without a colon. Empty values are kept.
+fn parse_kv(out: &str) -> Vec<(String, String)> {
+    out.lines()
+        .filter_map(|line| {
+            let (k, v) = line.split_once(':')?;
+            Some((k.trim().to_string(), v.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Like [`parse_kv`], but folds continuation lines (no colon) into the previous value, as
+/// plain `ethtool <iface>` wraps long lists such as link modes over several lines.
+fn parse_settings(out: &str) -> Vec<(String, String)> {
+    let mut kv: Vec<(String, String)> = Vec::new();
+    for line in out.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("Settings for") {
+            continue;
+        }
+        match line.split_once(':') {
+            Some((k, v)) => kv.push((k.trim().to_string(), v.trim().to_string())),
+            None => {
+                if let Some((_, v)) = kv.last_mut() {
+                    if !v.is_empty() {
+                        v.push(' ');
+                    }
+                    v.push_str(line);
+                }
+            }
+        }
+    }
+    kv
+}
+
+fn non_empty(v: &str) -> Option<String> {
+    if v.is_empty() || v == "N/A" {
+        None
+    } else {
+        Some(v.to_string())
+    }
+}
+
+/// `ethtool -i` (ETHTOOL_GDRVINFO).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DriverInfo {
+    pub driver: Option<String>,
+    pub version: Option<String>,
+    pub firmware_version: Option<String>,
+    pub bus_info: Option<String>,
+}
+
+pub fn parse_driver_info(out: &str) -> DriverInfo {
+    let mut info = DriverInfo::default();
+    for (k, v) in parse_kv(out) {
+        match k.as_str() {
+            "driver" => info.driver = non_empty(&v),
+            "version" => info.version = non_empty(&v),
+            "firmware-version" => info.firmware_version = non_empty(&v),
+            "bus-info" => info.bus_info = non_empty(&v),
+            _ => {}
+        }
+    }
+    info
+}
+
+pub async fn driver_info(iface: &str) -> Result<DriverInfo> {
+    Ok(parse_driver_info(&query(&["-i", iface]).await?))
+}
+
+/// Driver name and bus address straight from sysfs, for systems without `ethtool`.
+fn sysfs_driver_info(iface: &str) -> DriverInfo {
+    let device = Path::new("/sys/class/net").join(iface).join("device");
+    let link_name = |p: &Path| {
+        fs::read_link(p)
+            .ok()
+            .and_then(|t| t.file_name().map(|n| n.to_string_lossy().to_string()))
+    };
+    let driver = link_name(&device.join("driver"));
+    let version = driver
+        .as_ref()
+        .and_then(|d| fs::read_to_string(format!("/sys/module/{d}/version")).ok())
+        .map(|v| v.trim().to_string());
+
+    DriverInfo {
+        driver,
+        version,
+        firmware_version: None,
+        bus_info: link_name(&device),
+    }
+}
+
+/// Link settings from plain `ethtool <iface>` (ETHTOOL_GLINKSETTINGS).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkSettings {
+    pub supported_modes: Vec<String>,
+    pub advertised_modes: Vec<String>,
+    pub speed: Option<String>,
+    pub duplex: Option<String>,
+    pub autoneg: Option<bool>,
+    /// Wake-on-LAN mode letters, e.g. `pumbg` supported and `g` (magic packet) active.
+    pub wol_supported: Option<String>,
+    pub wol: Option<String>,
+}
+
+impl LinkSettings {
+    pub fn magic_packet_wol(&self) -> bool {
+        self.wol.as_deref().is_some_and(|w| w.contains('g'))
+    }
+
+    /// The fastest supported mode, from names like `1000baseT/Full`.
+    pub fn max_supported_mbps(&self) -> Option<u32> {
+        self.supported_modes
+            .iter()
+            .filter_map(|m| {
+                let digits: String = m.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().ok()
+            })
+            .max()
+    }
+}
+
+fn split_modes(v: &str) -> Vec<String> {
+    if v == "Not reported" {
+        return Vec::new();
+    }
+    v.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+pub fn parse_link_settings(out: &str) -> LinkSettings {
+    let mut ls = LinkSettings::default();
+    for (k, v) in parse_settings(out) {
+        match k.as_str() {
+            "Supported link modes" => ls.supported_modes = split_modes(&v),
+            "Advertised link modes" => ls.advertised_modes = split_modes(&v),
+            "Speed" => ls.speed = non_empty(&v).filter(|s| !s.starts_with("Unknown")),
+            "Duplex" => ls.duplex = non_empty(&v).filter(|s| !s.starts_with("Unknown")),
+            "Auto-negotiation" => ls.autoneg = Some(v == "on"),
+            "Supports Wake-on" => ls.wol_supported = non_empty(&v),
+            "Wake-on" => ls.wol = non_empty(&v),
+            _ => {}
+        }
+    }
+    ls
+}
+
+pub async fn link_settings(iface: &str) -> Result<LinkSettings> {
+    Ok(parse_link_settings(&query(&[iface]).await?))
+}
+
+/// One feature from `ethtool -k`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Offload {
+    pub name: String,
+    pub enabled: bool,
+    /// `[fixed]` features can't be changed with `ethtool -K`.
+    pub fixed: bool,
+    /// Indented sub-features (e.g. `tx-checksum-ipv4` under `tx-checksumming`).
+    pub child: bool,
+}
+
+pub fn parse_offloads(out: &str) -> Vec<Offload> {
+    out.lines()
+        .filter(|l| !l.starts_with("Features for"))
+        .filter_map(|line| {
+            let child = line.starts_with(char::is_whitespace);
+            let (name, rest) = line.trim().split_once(':')?;
+            let rest = rest.trim();
+            Some(Offload {
+                name: name.to_string(),
+                enabled: rest.starts_with("on"),
+                fixed: rest.contains("[fixed]"),
+                child,
+            })
+        })
+        .collect()
+}
+
+pub async fn offloads(iface: &str) -> Result<Vec<Offload>> {
+    Ok(parse_offloads(&query(&["-k", iface]).await?))
+}
+
+/// RX/TX ring sizes from `ethtool -g`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rings {
+    pub rx_max: Option<u32>,
+    pub tx_max: Option<u32>,
+    pub rx: Option<u32>,
+    pub tx: Option<u32>,
+}
+
+impl Rings {
+    /// True when a current ring size is under a quarter of what the hardware supports.
+    pub fn far_below_max(&self) -> bool {
+        let low = |cur: Option<u32>, max: Option<u32>| match (cur, max) {
+            (Some(c), Some(m)) => c.saturating_mul(4) < m,
+            _ => false,
+        };
+        low(self.rx, self.rx_max) || low(self.tx, self.tx_max)
+    }
+}
+
+pub fn parse_rings(out: &str) -> Rings {
+    let mut rings = Rings::default();
+    let mut current = false;
+    for line in out.lines() {
+        let line = line.trim();
+        if line.starts_with("Pre-set maximums") {
+            current = false;
+            continue;
+        }
+        if line.starts_with("Current hardware settings") {
+            current = true;
+            continue;
+        }
+        let Some((k, v)) = line.split_once(':') else {
+            continue;
+        };
+        let v = v.trim().parse::<u32>().ok();
+        match (k.trim(), current) {
+            ("RX", false) => rings.rx_max = v,
+            ("TX", false) => rings.tx_max = v,
+            ("RX", true) => rings.rx = v,
+            ("TX", true) => rings.tx = v,
+            _ => {}
+        }
+    }
+    rings
+}
+
+pub async fn rings(iface: &str) -> Result<Rings> {
+    Ok(parse_rings(&query(&["-g", iface]).await?))
+}
+
+/// Forward error correction from `ethtool --show-fec`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fec {
+    /// Encodings the NIC may pick from, like `Auto RS`; `Auto` follows the module and the link
+    /// partner.
+    pub configured: Vec<String>,
+    /// In use on the link right now (`RS`, `BaseR`, `None`...).
+    pub active: Option<String>,
+}
+
+impl Fec {
+    /// The link runs without any FEC.
+    pub fn off(&self) -> bool {
+        self.active
+            .as_deref()
+            .is_some_and(|a| a.eq_ignore_ascii_case("none") || a.eq_ignore_ascii_case("off"))
+    }
+}
+
+/// Older ethtool says `Configured FEC encodings`, newer `Supported/Configured FEC encodings`.
+pub fn parse_fec(out: &str) -> Fec {
+    let mut fec = Fec::default();
+    for (k, v) in parse_settings(out) {
+        match k.as_str() {
+            "Configured FEC encodings" | "Supported/Configured FEC encodings" => {
+                fec.configured = split_modes(&v)
+            }
+            "Active FEC encoding" => fec.active = non_empty(&v),
+            _ => {}
+        }
+    }
+    fec
+}
+
+pub async fn fec(iface: &str) -> Result<Fec> {
+    Ok(parse_fec(&query(&["--show-fec", iface]).await?))
+}
+
+/// What `ethtool --set-fec <iface> encoding` takes; several may be given.
+pub const FEC_ENCODINGS: [&str; 5] = ["auto", "off", "rs", "baser", "llrs"];
+
+/// Energy Efficient Ethernet from `ethtool --show-eee`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Eee {
+    /// False when the NIC or PHY can't do it at all.
+    pub supported: bool,
+    pub enabled: bool,
+    /// Both ends agreed on it and the link uses low-power idle.
+    pub active: bool,
+    /// How long the link must be idle before TX enters low-power idle, like `17 (us)`.
+    pub tx_lpi: Option<String>,
+    pub partner_modes: Vec<String>,
+}
+
+/// `EEE status` is `not supported`, `disabled`, `enabled - inactive` or `enabled - active`.
+pub fn parse_eee(out: &str) -> Eee {
+    let mut eee = Eee::default();
+    for (k, v) in parse_settings(out) {
+        match k.as_str() {
+            "EEE status" => {
+                eee.supported = v != "not supported";
+                eee.enabled = v.starts_with("enabled");
+                eee.active = v.ends_with("- active");
+            }
+            "Tx LPI" => eee.tx_lpi = non_empty(&v).filter(|v| v != "disabled"),
+            "Link partner advertised EEE link modes" => eee.partner_modes = split_modes(&v),
+            _ => {}
+        }
+    }
+    eee
+}
+
+pub async fn eee(iface: &str) -> Result<Eee> {
+    Ok(parse_eee(&query(&["--show-eee", iface]).await?))
+}
+
+/// Channel (queue) counts from `ethtool -l`; None where the NIC has no such channel type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelCounts {
+    pub rx: Option<u32>,
+    pub tx: Option<u32>,
+    pub other: Option<u32>,
+    pub combined: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Channels {
+    pub max: ChannelCounts,
+    pub current: ChannelCounts,
+}
+
+impl Channels {
+    /// Queues RSS can spread received packets over: the combined plus RX-only channels.
+    pub fn rx_queues(&self) -> u32 {
+        self.current.combined.unwrap_or(0) + self.current.rx.unwrap_or(0)
+    }
+}
+
+/// Same layout as `ethtool -g`: `Pre-set maximums:` then `Current hardware settings:`, with
+/// `n/a` (or 0 maximum) for channel types the NIC doesn't have.
+pub fn parse_channels(out: &str) -> Channels {
+    let mut channels = Channels::default();
+    let mut counts = &mut channels.max;
+    for line in out.lines() {
+        let line = line.trim();
+        if line.starts_with("Current hardware settings") {
+            counts = &mut channels.current;
+            continue;
+        }
+        let Some((k, v)) = line.split_once(':') else {
+            continue;
+        };
+        let v = v.trim().parse::<u32>().ok();
+        match k.trim() {
+            "RX" => counts.rx = v,
+            "TX" => counts.tx = v,
+            "Other" => counts.other = v,
+            "Combined" => counts.combined = v,
+            _ => {}
+        }
+    }
+    channels
+}
+
+pub async fn channels(iface: &str) -> Result<Channels> {
+    Ok(parse_channels(&query(&["-l", iface]).await?))
+}
+
+/// Interrupt coalescing from `ethtool -c`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coalesce {
+    pub adaptive_rx: Option<bool>,
+    pub adaptive_tx: Option<bool>,
+    /// Remaining `name: value` parameters in ethtool's order (`n/a` ones are dropped).
+    pub params: Vec<(String, String)>,
+}
+
+impl Coalesce {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+pub fn parse_coalesce(out: &str) -> Coalesce {
+    let mut c = Coalesce::default();
+    for line in out.lines() {
+        let line = line.trim();
+        if line.starts_with("Coalesce parameters") || line.is_empty() {
+            continue;
+        }
+        // "Adaptive RX: off  TX: off"
+        if let Some(rest) = line.strip_prefix("Adaptive RX:") {
+            let mut it = rest.split_whitespace();
+            c.adaptive_rx = it.next().map(|v| v == "on");
+            if it.next() == Some("TX:") {
+                c.adaptive_tx = it.next().map(|v| v == "on");
+            }
+            continue;
+        }
+        if let Some((k, v)) = line.split_once(':') {
+            let v = v.trim();
+            if v != "n/a" {
+                c.params.push((k.trim().to_string(), v.to_string()));
+            }
+        }
+    }
+    c
+}
+
+pub async fn coalesce(iface: &str) -> Result<Coalesce> {
+    Ok(parse_coalesce(&query(&["-c", iface]).await?))
+}
+
+/// One counter from `ethtool -S`, with how much it grew since the previous reading.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DriverStat {
+    pub name: String,
+    pub value: u64,
+    /// None on the first reading, or for a counter the previous one didn't have.
+    pub delta: Option<u64>,
+}
+
+impl DriverStat {
+    /// Names drivers give to errors, drops and overruns (`rx_missed_errors`, `rx_fifo_errors`,
+    /// `tx_timeout_count`, `rx_no_buffer_count`...).
+    pub fn is_problem(&self) -> bool {
+        let name = self.name.to_ascii_lowercase();
+        [
+            "err", "drop", "miss", "fifo", "over", "discard", "fail", "timeout", "crc", "lost",
+            "no_buf", "nobuf", "fault",
+        ]
+        .iter()
+        .any(|word| name.contains(word))
+    }
+
+    pub fn increasing(&self) -> bool {
+        self.delta.is_some_and(|d| d > 0)
+    }
+}
+
+/// `ethtool -S`: a `NIC statistics:` header, then `name: value` lines in the driver's order.
+pub fn parse_stats(out: &str) -> Vec<DriverStat> {
+    parse_kv(out)
+        .into_iter()
+        .filter_map(|(name, value)| {
+            Some(DriverStat {
+                value: value.parse().ok()?,
+                name,
+                delta: None,
+            })
+        })
+        .collect()
+}
+
+pub async fn driver_stats(iface: &str) -> Result<Vec<DriverStat>> {
+    Ok(parse_stats(&query(&["-S", iface]).await?))
+}
+
+/// Fills in each counter's growth since `prev`. A counter that went down (driver reset) counts
+/// as unchanged.
+pub fn stats_deltas(stats: &mut [DriverStat], prev: &[DriverStat]) {
+    let prev: std::collections::HashMap<&str, u64> =
+        prev.iter().map(|s| (s.name.as_str(), s.value)).collect();
+    for stat in stats {
+        stat.delta = prev
+            .get(stat.name.as_str())
+            .map(|old| stat.value.saturating_sub(*old));
+    }
+}
+
+/// Receive power below this is treated as a weak signal when the module has no own threshold.
+pub const DEFAULT_RX_POWER_LOW_DBM: f64 = -20.0;
+
+/// SFP/QSFP module info and diagnostics from `ethtool -m`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModuleInfo {
+    pub identifier: Option<String>,
+    pub vendor: Option<String>,
+    pub part_number: Option<String>,
+    pub wavelength: Option<String>,
+    pub temperature: Option<String>,
+    /// Per channel (a single entry on SFP).
+    pub tx_power_dbm: Vec<f64>,
+    pub rx_power_dbm: Vec<f64>,
+    pub rx_power_low_warning_dbm: Option<f64>,
+}
+
+impl ModuleInfo {
+    pub fn rx_threshold_dbm(&self) -> f64 {
+        self.rx_power_low_warning_dbm
+            .unwrap_or(DEFAULT_RX_POWER_LOW_DBM)
+    }
+
+    pub fn rx_power_low(&self) -> bool {
+        let t = self.rx_threshold_dbm();
+        self.rx_power_dbm.iter().any(|p| *p < t)
+    }
+}
+
+/// Extracts the dBm figure from values like `0.4000 mW / -3.98 dBm`.
+fn parse_dbm(v: &str) -> Option<f64> {
+    v.split('/')
+        .map(str::trim)
+        .find_map(|part| part.strip_suffix("dBm"))
+        .and_then(|n| n.trim().parse().ok())
+}
+
+pub fn parse_module_info(out: &str) -> ModuleInfo {
+    let mut m = ModuleInfo::default();
+    for (k, v) in parse_kv(out) {
+        let key = k.to_lowercase();
+        match key.as_str() {
+            "identifier" => m.identifier = non_empty(&v),
+            "vendor name" => m.vendor = non_empty(&v),
+            "vendor pn" => m.part_number = non_empty(&v),
+            "laser wavelength" => m.wavelength = non_empty(&v),
+            "module temperature" => m.temperature = non_empty(&v),
+            "laser rx power low warning threshold" => m.rx_power_low_warning_dbm = parse_dbm(&v),
+            _ if key.contains("threshold") => {}
+            // SFP: "Laser output power", QSFP: "Transmit avg optical power (Channel 1)".
+            _ if key.starts_with("laser output power")
+                || key.starts_with("transmit avg optical power") =>
+            {
+                m.tx_power_dbm.extend(parse_dbm(&v))
+            }
+            // SFP: "Receiver signal average optical power", QSFP: "Rcvr signal avg optical power(Channel 1)".
+            _ if key.starts_with("receiver signal average optical power")
+                || key.starts_with("rcvr signal avg optical power") =>
+            {
+                m.rx_power_dbm.extend(parse_dbm(&v))
+            }
+            _ => {}
+        }
+    }
+    m
+}
+
+/// `None` for ports without a pluggable module (copper NICs, empty cages).
+pub async fn module_info(iface: &str) -> Option<ModuleInfo> {
+    let out = query(&["-m", iface]).await.ok()?;
+    let m = parse_module_info(&out);
+    (m.identifier.is_some() || m.vendor.is_some()).then_some(m)
+}
+
+/// Burned-in hardware address from `ethtool -P` (`Permanent address: xx:xx:...`).
+pub async fn permanent_mac(iface: &str) -> Option<String> {
+    let out = query(&["-P", iface]).await.ok()?;
+    parse_kv(&out)
+        .into_iter()
+        .find(|(k, _)| k == "Permanent address")
+        .and_then(|(_, v)| non_empty(&v))
+        .filter(|v| v != "00:00:00:00:00:00")
+}
+
+/// One twisted pair from `ethtool --cable-test`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CablePair {
+    pub pair: String,
+    pub status: String,
+    pub fault_length: Option<String>,
+}
+
+/// Parses lines like `Pair A code Open Circuit` and `Pair A, fault length: 12.00m`.
+pub fn parse_cable_test(out: &str) -> Vec<CablePair> {
+    let mut pairs: Vec<CablePair> = Vec::new();
+    for line in out.lines() {
+        let line = line.trim();
+        let Some(rest) = line.strip_prefix("Pair ") else {
+            continue;
+        };
+
+        if let Some((name, status)) = rest.split_once(" code ") {
+            pairs.push(CablePair {
+                pair: name.trim().to_string(),
+                status: status.trim().to_string(),
+                fault_length: None,
+            });
+        } else if let Some((name, len)) = rest.split_once(", fault length:") {
+            let name = name.trim();
+            if let Some(p) = pairs.iter_mut().find(|p| p.pair == name) {
+                p.fault_length = Some(len.trim().to_string());
+            }
+        }
+    }
+    pairs
+}
+
+/// Runs the PHY cable test where supported, else the driver's online self-test (`ethtool -t`).
+/// Returns a human-readable report.
+pub async fn cable_diagnostics(iface: &str) -> Result<String> {
+    match run_privileged_capture("ethtool", &["--cable-test", iface]).await {
+        Ok(out) => {
+            let pairs = parse_cable_test(&out.stdout);
+            if pairs.is_empty() {
+                return Ok(out.stdout);
+            }
+            let mut report = String::from("Cable test:");
+            for p in pairs {
+                report.push_str(&format!("\n  Pair {}: {}", p.pair, p.status));
+                if let Some(len) = p.fault_length {
+                    report.push_str(&format!(" (fault at {len})"));
+                }
+            }
+            Ok(report)
+        }
+        Err(cable_err) => {
+            let out = run_privileged_capture("ethtool", &["-t", iface, "online"])
+                .await
+                .map_err(|e| {
+                    std::io::Error::other(format!("cable test: {cable_err}\nself-test: {e}"))
+                })?;
+            Ok(format!("Self-test (online):\n{}", out.stdout))
+        }
+    }
+}
+
+/// Per-interface data fetched through ethtool. Cached by the app; none of it changes often.
+#[derive(Debug, Clone, Default)]
+pub struct Hardware {
+    pub driver: Option<DriverInfo>,
+    pub link: Option<LinkSettings>,
+    pub offloads: Vec<Offload>,
+    pub rings: Option<Rings>,
+    pub coalesce: Option<Coalesce>,
+    pub channels: Option<Channels>,
+    pub eee: Option<Eee>,
+    pub fec: Option<Fec>,
+    pub module: Option<ModuleInfo>,
+    pub pci: Option<PciDevice>,
+    pub permanent_mac: Option<String>,
+    pub error: Option<String>,
+}
+
+pub async fn load_hardware(iface: &str) -> Hardware {
+    let mut hw = Hardware::default();
+    match driver_info(iface).await {
+        Ok(d) => hw.driver = Some(d),
+        Err(e) => {
+            hw.driver = Some(sysfs_driver_info(iface));
+            hw.error = Some(e.to_string());
+        }
+    }
+    hw.link = link_settings(iface).await.ok();
+    hw.offloads = offloads(iface).await.unwrap_or_default();
+    hw.rings = rings(iface).await.ok();
+    hw.coalesce = coalesce(iface).await.ok();
+    hw.channels = channels(iface).await.ok();
+    hw.eee = eee(iface).await.ok();
+    hw.fec = fec(iface).await.ok();
+    hw.module = module_info(iface).await;
+    hw.pci = pci::device(iface);
+    hw.permanent_mac = permanent_mac(iface).await;
+    hw
+}
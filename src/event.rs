@@ -1,5 +1,6 @@
+use crate::net::NetEvent;
 use anyhow::Result;
-use crossterm::event::{Event as CrosstermEvent, KeyEvent};
+use crossterm::event::{Event as CrosstermEvent, KeyEvent, MouseEvent};
 use futures::{FutureExt, StreamExt};
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -8,7 +9,10 @@ use tokio::sync::mpsc;
 pub enum Event {
     Tick,
     Key(KeyEvent),
+    Mouse(MouseEvent),
     Resize(u16, u16),
+    Net(NetEvent),
+    NetResync,
 }
 
 #[derive(Debug)]
@@ -41,10 +45,13 @@ impl EventHandler {
                     }
                     Some(Ok(evt)) = crossterm_event => {
                         match evt {
-                            CrosstermEvent::Key(key) => {
-                                if key.kind == crossterm::event::KeyEventKind::Press {
-                                    let _ = sender_cloned.send(Event::Key(key));
-                                }
+                            CrosstermEvent::Key(key)
+                                if key.kind == crossterm::event::KeyEventKind::Press =>
+                            {
+                                let _ = sender_cloned.send(Event::Key(key));
+                            }
+                            CrosstermEvent::Mouse(mouse) => {
+                                let _ = sender_cloned.send(Event::Mouse(mouse));
                             }
                             CrosstermEvent::Resize(x, y) => {
                                 let _ = sender_cloned.send(Event::Resize(x, y));
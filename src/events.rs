@@ -0,0 +1,316 @@
+use crate::hooks::{self, Hook, HookEvent};
+use crate::logfile;
+use crate::net::EthernetDevice;
+use crate::notify::{Notifier, NotifyEvent};
+use crate::plugins::Plugins;
+use crate::ui::human_duration;
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How many events are kept; the oldest are dropped first.
+const EVENT_LOG_LEN: usize = 1000;
+
+/// Flaps remembered per interface.
+const FLAP_HISTORY_LEN: usize = 100;
+
+/// Something that changed on an interface, as it was noticed.
+#[derive(Debug, Clone)]
+pub struct LinkEvent {
+    pub at: SystemTime,
+    pub iface: String,
+    pub kind: EventKind,
+    pub msg: String,
+}
+
+impl LinkEvent {
+    pub fn clock(&self) -> String {
+        clock(self.at)
+    }
+
+    /// `14:03:22 eth0: carrier down`.
+    pub fn describe(&self) -> String {
+        format!("{} {}: {}", self.clock(), self.iface, self.msg)
+    }
+}
+
+/// `14:03:22` in local time.
+pub fn clock(at: SystemTime) -> String {
+    match local_time(at) {
+        Some(tm) => format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec),
+        None => "--:--:--".into(),
+    }
+}
+
+/// Broken-down local time, or None when the C library can't convert it.
+pub fn local_time(at: SystemTime) -> Option<libc::tm> {
+    let secs = at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as libc::time_t;
+    // SAFETY: an all-zero tm is valid, and both pointers outlive the call.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return None;
+    }
+    Some(tm)
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in local time.
+pub fn datetime(at: SystemTime) -> Option<String> {
+    let tm = local_time(at)?;
+    Some(format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    ))
+}
+
+/// Decides the color in the log: links going away stand out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Up,
+    Down,
+    Info,
+}
+
+/// Every change noticed since startup, for tracking down flaps after the fact.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    events: VecDeque<LinkEvent>,
+    /// When each interface lost its carrier and got it back, by name, oldest first.
+    flaps: HashMap<String, VecDeque<SystemTime>>,
+    /// When each interface that's down now lost its carrier, by name.
+    down_since: HashMap<String, SystemTime>,
+    /// The `[[hooks]]` from the config, run for the changes `compare` finds.
+    hooks: Vec<Hook>,
+    /// Get every event the hooks could.
+    plugins: Option<Plugins>,
+    notifier: Notifier,
+}
+
+impl EventLog {
+    pub fn new(hooks: Vec<Hook>, plugins: Option<Plugins>, notifier: Notifier) -> Self {
+        Self {
+            events: VecDeque::new(),
+            flaps: HashMap::new(),
+            down_since: HashMap::new(),
+            hooks,
+            plugins,
+            notifier,
+        }
+    }
+
+    pub fn push(&mut self, iface: &str, kind: EventKind, msg: impl Into<String>) {
+        let msg = msg.into();
+        logfile::write("event", json!({ "iface": iface, "msg": msg }));
+        if self.events.len() == EVENT_LOG_LEN {
+            self.events.pop_front();
+        }
+        self.events.push_back(LinkEvent {
+            at: SystemTime::now(),
+            iface: iface.to_string(),
+            kind,
+            msg,
+        });
+    }
+
+    /// Oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &LinkEvent> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Flaps of `iface` seen within the last `window`, oldest first.
+    pub fn recent_flaps(&self, iface: &str, window: Duration) -> Vec<SystemTime> {
+        let now = SystemTime::now();
+        self.flaps
+            .get(iface)
+            .into_iter()
+            .flatten()
+            .filter(|at| now.duration_since(**at).is_ok_and(|age| age <= window))
+            .copied()
+            .collect()
+    }
+
+    /// Sends the event just logged as a desktop notification, stamped with its time.
+    fn notify_last(&self, event: NotifyEvent) -> Option<LinkEvent> {
+        let last = self.events.back()?.clone();
+        self.notifier.send(event, last.describe());
+        Some(last)
+    }
+
+    /// Counts the link coming back from the kernel's counter, so flaps between two readings
+    /// aren't missed. True when they were too quick to be seen as carrier down and up, and
+    /// were logged as a flap instead.
+    fn count_flaps(&mut self, old: &EthernetDevice, new: &EthernetDevice) -> bool {
+        let (Some(before), Some(after)) = (old.carrier_counts, new.carrier_counts) else {
+            return false;
+        };
+        if after.changes <= before.changes || new.carrier != Some(true) {
+            return false;
+        }
+        // Losing the link and getting it back is two changes.
+        let flaps = ((after.changes - before.changes) / 2).max(1);
+        let times = self.flaps.entry(new.name.clone()).or_default();
+        for _ in 0..flaps {
+            if times.len() == FLAP_HISTORY_LEN {
+                times.pop_front();
+            }
+            times.push_back(SystemTime::now());
+        }
+        if old.carrier != new.carrier {
+            return false;
+        }
+        let msg = if flaps == 1 {
+            "carrier went down and came back".to_string()
+        } else {
+            format!("carrier flapped {flaps} times")
+        };
+        self.push(&new.name, EventKind::Down, msg);
+        true
+    }
+
+    /// Logs what differs between two readings of the same interface. Returns the carrier
+    /// going down, coming back or flapping, for the TUI to point out.
+    pub fn compare(&mut self, old: &EthernetDevice, new: &EthernetDevice) -> Option<LinkEvent> {
+        let name = &new.name;
+        if old.name != new.name {
+            self.push(name, EventKind::Info, format!("renamed from {}", old.name));
+        }
+        let mut link = None;
+        if old.carrier != new.carrier {
+            match new.carrier {
+                Some(true) => {
+                    let msg = match self.down_since.remove(name) {
+                        Some(since) => {
+                            let down = since.elapsed().map_or(0, |d| d.as_secs());
+                            format!("carrier up (down {})", human_duration(down))
+                        }
+                        None => "carrier up".to_string(),
+                    };
+                    self.push(name, EventKind::Up, msg);
+                    self.hook(HookEvent::CarrierUp, name, "carrier up", json!({}));
+                    link = self.notify_last(NotifyEvent::LinkUp);
+                }
+                Some(false) => {
+                    self.down_since.insert(name.clone(), SystemTime::now());
+                    self.push(name, EventKind::Down, "carrier down");
+                    self.hook(HookEvent::CarrierLost, name, "carrier down", json!({}));
+                    link = self.notify_last(NotifyEvent::LinkDown);
+                }
+                None => self.push(name, EventKind::Info, "carrier unknown"),
+            }
+        }
+        if self.count_flaps(old, new) {
+            link = self.notify_last(NotifyEvent::LinkDown);
+        }
+        if old.operstate != new.operstate {
+            let kind = match new.operstate.as_str() {
+                "up" => EventKind::Up,
+                "down" | "lowerlayerdown" => EventKind::Down,
+                _ => EventKind::Info,
+            };
+            let msg = format!("state {} → {}", old.operstate, new.operstate);
+            self.push(name, kind, msg);
+        }
+        if old.speed_mbps != new.speed_mbps
+            && let Some(speed) = new.speed_mbps
+        {
+            self.push(name, EventKind::Info, format!("speed {speed} Mb/s"));
+        }
+        for addr in new.ipv4.iter().chain(&new.ipv6) {
+            if !old.ipv4.contains(addr) && !old.ipv6.contains(addr) {
+                let msg = format!("address {addr} added");
+                self.hook(
+                    HookEvent::AddressAdded,
+                    name,
+                    &msg,
+                    json!({ "address": addr }),
+                );
+                self.notifier
+                    .send(NotifyEvent::IpChanged, format!("{name}: {msg}"));
+                self.push(name, EventKind::Up, msg);
+            }
+        }
+        for addr in old.ipv4.iter().chain(&old.ipv6) {
+            if !new.ipv4.contains(addr) && !new.ipv6.contains(addr) {
+                let msg = format!("address {addr} removed");
+                self.hook(
+                    HookEvent::AddressRemoved,
+                    name,
+                    &msg,
+                    json!({ "address": addr }),
+                );
+                self.notifier
+                    .send(NotifyEvent::IpChanged, format!("{name}: {msg}"));
+                self.push(name, EventKind::Down, msg);
+            }
+        }
+        self.compare_gateway(name, old.gateway_v4.as_deref(), new.gateway_v4.as_deref());
+        if let Some(lease) = &new.lease
+            && old
+                .lease
+                .as_ref()
+                .is_none_or(|l| l.acquired != lease.acquired || l.address != lease.address)
+        {
+            let address = lease.address.as_deref().unwrap_or("?");
+            let msg = format!("new DHCP lease for {address}");
+            let details = json!({ "address": lease.address, "lease": lease });
+            self.hook(HookEvent::NewLease, name, &msg, details);
+            self.push(name, EventKind::Up, msg);
+        }
+        link
+    }
+
+    /// Reported by whatever pings the gateway, once per outage.
+    pub fn gateway_unreachable(&mut self, iface: &str, gateway: &str) {
+        let msg = format!("gateway {gateway} unreachable");
+        self.hook(
+            HookEvent::GatewayUnreachable,
+            iface,
+            &msg,
+            json!({ "gateway": gateway }),
+        );
+        self.push(iface, EventKind::Down, msg);
+    }
+
+    pub fn gateway_reachable(&mut self, iface: &str, gateway: &str) {
+        self.push(
+            iface,
+            EventKind::Up,
+            format!("gateway {gateway} reachable again"),
+        );
+    }
+
+    /// Runs the hooks for `event` and every plugin.
+    pub fn hook(&self, event: HookEvent, iface: &str, message: &str, details: serde_json::Value) {
+        let body = hooks::body(event, iface, message, details);
+        hooks::fire(&self.hooks, event, iface, &body);
+        if let Some(plugins) = &self.plugins {
+            plugins.fire(&body);
+        }
+    }
+
+    pub fn compare_gateway(&mut self, iface: &str, old: Option<&str>, new: Option<&str>) {
+        let (kind, msg) = match (old, new) {
+            (Some(old), Some(new)) if old != new => {
+                (EventKind::Info, format!("gateway {old} → {new}"))
+            }
+            (None, Some(new)) => (EventKind::Up, format!("gateway {new}")),
+            (Some(old), None) => (EventKind::Down, format!("gateway {old} removed")),
+            _ => return,
+        };
+        let details = json!({ "gateway": new, "previous_gateway": old });
+        self.hook(HookEvent::GatewayChanged, iface, &msg, details);
+        self.push(iface, kind, msg);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::app::Throughput;
+use crate::diag::LatencyWindow;
+use crate::events::datetime;
+use crate::net::{EthernetDevice, Route, fill_link_details, list_ethernet_devices, list_routes};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What `e` and `--export-json` write: every listed interface as the TUI sees it.
+#[derive(Debug, Serialize)]
+pub struct Snapshot<'a> {
+    /// Unix seconds.
+    pub taken_at: u64,
+    pub interfaces: Vec<InterfaceSnapshot<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InterfaceSnapshot<'a> {
+    #[serde(flatten)]
+    pub device: &'a EthernetDevice,
+    /// Latest throughput sample in bytes per second, once there is one.
+    pub rx_rate: Option<u64>,
+    pub tx_rate: Option<u64>,
+    /// Only when they've been read (the Routes tab, or always from the command line).
+    pub routes: Option<&'a [Route]>,
+}
+
+impl Snapshot<'_> {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// A snapshot straight from sysfs and `ip`, without the TUI: no throughput yet, but routes for
+/// every interface.
+pub async fn snapshot_json(include_wifi: bool) -> Result<String> {
+    let mut devices = list_ethernet_devices(include_wifi)?;
+    fill_link_details(&mut devices).await;
+    let mut routes = Vec::new();
+    for d in &devices {
+        routes.push(list_routes(&d.name).await.ok());
+    }
+    let snapshot = Snapshot {
+        taken_at: now_unix(),
+        interfaces: devices
+            .iter()
+            .zip(&routes)
+            .map(|(device, routes)| InterfaceSnapshot {
+                device,
+                rx_rate: None,
+                tx_rate: None,
+                routes: routes.as_deref(),
+            })
+            .collect(),
+    };
+    snapshot.to_json()
+}
+
+/// One row per throughput sample and per ping window, in time order. Columns that don't apply to
+/// a row are left empty, so both series chart straight from a spreadsheet.
+pub fn history_csv(throughput: Option<&Throughput>, latency: &[LatencyWindow]) -> String {
+    let mut rows: Vec<(SystemTime, String)> = Vec::new();
+    if let Some(t) = throughput {
+        for ((at, rx), tx) in t.at.iter().zip(&t.rx).zip(&t.tx) {
+            rows.push((*at, format!("{rx},{tx},,,,")));
+        }
+    }
+    let ms = |v: Option<f64>| v.map(|v| format!("{v:.3}")).unwrap_or_default();
+    for w in latency {
+        let row = format!(
+            ",,{},{:.1},{},{}",
+            w.sent,
+            w.loss_pct,
+            ms(w.avg_ms),
+            ms(w.jitter_ms)
+        );
+        rows.push((w.ended, row));
+    }
+    rows.sort_by_key(|(at, _)| *at);
+
+    let mut out = String::from(
+        "unix_time,local_time,rx_bytes_per_sec,tx_bytes_per_sec,ping_sent,ping_loss_pct,\
+         ping_avg_ms,ping_jitter_ms\n",
+    );
+    for (at, row) in rows {
+        let unix = at
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        let local = datetime(at).unwrap_or_default();
+        let _ = writeln!(out, "{unix:.3},{local},{row}");
+    }
+    out
+}
+
+pub fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Expands a leading `~/`, since paths typed into a form don't go through a shell.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Writes `contents` to `path`, or to stdout when it's `-`. Returns where it went.
+pub fn write_to(path: &str, contents: &str) -> Result<String> {
+    if path == "-" {
+        println!("{contents}");
+        return Ok("stdout".into());
+    }
+    let path = expand_home(path);
+    std::fs::write(&path, contents).with_context(|| format!("writing {}", path.display()))?;
+    Ok(path.display().to_string())
+}
@@ -0,0 +1,212 @@
+use crate::app::run_privileged_capture;
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A firewall rule that matches on an interface, with its counters when it has any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirewallRule {
+    /// `inet filter input (policy drop)` for nftables, `filter INPUT` for iptables.
+    pub chain: String,
+    pub rule: String,
+    pub packets: Option<u64>,
+    pub bytes: Option<u64>,
+}
+
+/// Rules that mention `iface` (exactly or through a `eth*`/`eth+` wildcard), from nftables when
+/// `nft` is available, otherwise from `iptables-save`/`ip6tables-save`. Reading the ruleset
+/// needs root, so this goes through sudo when needed. Returns the tool used and the rules.
+pub async fn rules_for(iface: &str) -> Result<(&'static str, Vec<FirewallRule>)> {
+    match run_privileged_capture("nft", &["-j", "list", "ruleset"]).await {
+        Ok(out) => {
+            let json: Value = serde_json::from_str(&out.stdout)?;
+            Ok(("nft", nft_rules(&json, iface)))
+        }
+        Err(nft_err) => {
+            let Ok(v4) = run_privileged_capture("iptables-save", &["-c"]).await else {
+                return Err(nft_err);
+            };
+            let mut rules = iptables_rules(&v4.stdout, iface);
+            if let Ok(v6) = run_privileged_capture("ip6tables-save", &["-c"]).await {
+                rules.extend(iptables_rules(&v6.stdout, iface));
+            }
+            Ok(("iptables", rules))
+        }
+    }
+}
+
+fn iface_matches(pattern: &str, iface: &str) -> bool {
+    match pattern.strip_suffix(['*', '+']) {
+        Some(prefix) => iface.starts_with(prefix),
+        None => pattern == iface,
+    }
+}
+
+fn nft_rules(json: &Value, iface: &str) -> Vec<FirewallRule> {
+    let items = json["nftables"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    // Base chains carry their hook and policy; regular chains are just jump targets.
+    let mut chains: HashMap<(&str, &str, &str), String> = HashMap::new();
+    for chain in items.iter().filter_map(|i| i.get("chain")) {
+        let (Some(family), Some(table), Some(name)) = (
+            chain["family"].as_str(),
+            chain["table"].as_str(),
+            chain["name"].as_str(),
+        ) else {
+            continue;
+        };
+        let mut label = format!("{family} {table} {name}");
+        if let Some(policy) = chain["policy"].as_str() {
+            label.push_str(&format!(" (policy {policy})"));
+        }
+        chains.insert((family, table, name), label);
+    }
+
+    items
+        .iter()
+        .filter_map(|i| i.get("rule"))
+        .filter_map(|rule| {
+            let exprs = rule["expr"].as_array()?;
+            if !exprs.iter().any(|e| nft_matches_iface(e, iface)) {
+                return None;
+            }
+            let key = (
+                rule["family"].as_str()?,
+                rule["table"].as_str()?,
+                rule["chain"].as_str()?,
+            );
+            let counter = exprs.iter().find_map(|e| e.get("counter"));
+            let mut text: Vec<String> = exprs.iter().filter_map(nft_expr).collect();
+            if let Some(comment) = rule["comment"].as_str() {
+                text.push(format!("comment \"{comment}\""));
+            }
+            Some(FirewallRule {
+                chain: chains
+                    .get(&key)
+                    .cloned()
+                    .unwrap_or_else(|| format!("{} {} {}", key.0, key.1, key.2)),
+                rule: text.join(" "),
+                packets: counter.and_then(|c| c["packets"].as_u64()),
+                bytes: counter.and_then(|c| c["bytes"].as_u64()),
+            })
+        })
+        .collect()
+}
+
+/// `{"match": {"left": {"meta": {"key": "iifname"}}, "right": "eth0"}}`, where the right side
+/// may also be a set of names.
+fn nft_matches_iface(expr: &Value, iface: &str) -> bool {
+    let Some(m) = expr.get("match") else {
+        return false;
+    };
+    let is_iface_key = matches!(
+        m["left"]["meta"]["key"].as_str(),
+        Some("iifname" | "oifname" | "iif" | "oif")
+    );
+    let names = match &m["right"] {
+        Value::String(s) => vec![s.as_str()],
+        right => right["set"]
+            .as_array()
+            .map(|set| set.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default(),
+    };
+    is_iface_key && names.iter().any(|n| iface_matches(n, iface))
+}
+
+/// A compact, nft-like rendering of one rule expression; counters are shown separately.
+fn nft_expr(expr: &Value) -> Option<String> {
+    let (key, body) = expr.as_object()?.iter().next()?;
+    Some(match key.as_str() {
+        "counter" => return None,
+        "match" => {
+            let op = match body["op"].as_str() {
+                Some("==" | "in") | None => String::new(),
+                Some(op) => format!("{op} "),
+            };
+            format!(
+                "{} {op}{}",
+                nft_value(&body["left"]),
+                nft_value(&body["right"])
+            )
+        }
+        "jump" | "goto" => format!("{key} {}", body["target"].as_str().unwrap_or("?")),
+        "snat" | "dnat" => match body["addr"].as_str() {
+            Some(addr) => format!("{key} to {addr}"),
+            None => key.clone(),
+        },
+        // Verdicts and statements without arguments worth showing.
+        _ => key.clone(),
+    })
+}
+
+fn nft_value(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Array(items) => items.iter().map(nft_value).collect::<Vec<_>>().join(", "),
+        Value::Object(o) => {
+            if let Some(key) = o.get("meta").and_then(|m| m["key"].as_str()) {
+                key.to_string()
+            } else if let Some(p) = o.get("payload") {
+                format!(
+                    "{} {}",
+                    p["protocol"].as_str().unwrap_or("?"),
+                    p["field"].as_str().unwrap_or("?")
+                )
+            } else if let Some(key) = o.get("ct").and_then(|c| c["key"].as_str()) {
+                format!("ct {key}")
+            } else if let Some(set) = o.get("set") {
+                format!("{{ {} }}", nft_value(set))
+            } else if let Some(range) = o.get("range") {
+                nft_value(range).replace(", ", "-")
+            } else if let Some(prefix) = o.get("prefix") {
+                format!(
+                    "{}/{}",
+                    nft_value(&prefix["addr"]),
+                    nft_value(&prefix["len"])
+                )
+            } else {
+                "?".to_string()
+            }
+        }
+        _ => "?".to_string(),
+    }
+}
+
+/// `*filter` starts a table; rules look like `[12:3456] -A INPUT -i eth0 -j ACCEPT`.
+fn iptables_rules(save: &str, iface: &str) -> Vec<FirewallRule> {
+    let mut table = "";
+    let mut rules = Vec::new();
+    for line in save.lines() {
+        if let Some(t) = line.strip_prefix('*') {
+            table = t;
+            continue;
+        }
+        let (counters, rest) = match line.strip_prefix('[').and_then(|l| l.split_once("] ")) {
+            Some((c, rest)) => (c.split_once(':'), rest),
+            None => (None, line),
+        };
+        let Some(rest) = rest.strip_prefix("-A ") else {
+            continue;
+        };
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        let mentions = words.windows(2).any(|w| {
+            matches!(w[0], "-i" | "-o" | "--in-interface" | "--out-interface")
+                && iface_matches(w[1], iface)
+        });
+        if !mentions {
+            continue;
+        }
+        let (chain, rule) = rest.split_once(' ').unwrap_or((rest, ""));
+        rules.push(FirewallRule {
+            chain: format!("{table} {chain}"),
+            rule: rule.to_string(),
+            packets: counters.and_then(|(p, _)| p.parse().ok()),
+            bytes: counters.and_then(|(_, b)| b.parse().ok()),
+        });
+    }
+    rules
+}
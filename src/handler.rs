@@ -1,98 +1,630 @@
 use crate::{
-    app::{App, ToastKind},
-    net::EthernetDevice,
+    app::{App, ConfirmAction, DetailTab, Focus, RenewDiff, ToastKind},
+    command::{self, Command, CommandLine},
+    hooks::HookEvent,
+    keymap::Action,
+    notify::NotifyEvent,
 };
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-
-fn snap(d: &EthernetDevice) -> String {
-    let carrier = d.carrier.map(|c| if c { "1" } else { "0" }).unwrap_or("?");
-    let ip = d.ipv4.first().cloned().unwrap_or_else(|| "-".into());
-    let gw = d.gateway_v4.clone().unwrap_or_else(|| "-".into());
-    let dns = if d.dns.is_empty() {
-        "-".to_string()
-    } else {
-        d.dns.join(", ")
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use serde_json::json;
+
+async fn handle_form_keys(key_event: KeyEvent, app: &mut App) {
+    let Some(form) = app.form.as_mut() else {
+        return;
     };
-    format!(
-        "state={}; carrier={}; ip={}; gw={}; dns={}",
-        d.operstate, carrier, ip, gw, dns
-    )
+
+    match key_event.code {
+        KeyCode::Esc => app.form = None,
+        KeyCode::Tab | KeyCode::Down => form.focus_next(),
+        KeyCode::BackTab | KeyCode::Up => form.focus_prev(),
+        KeyCode::Backspace => form.pop_char(),
+        KeyCode::Char(c) => form.push_char(c),
+        KeyCode::Enter => {
+            app.clear_error();
+            match app.submit_form().await {
+                // Waiting for a confirmation instead.
+                Ok(msg) if msg.is_empty() => {}
+                Ok(msg) => app.set_toast(ToastKind::Success, msg),
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        _ => {}
+    }
 }
 
-pub async fn handle_key_events(key_event: KeyEvent, app: &mut App) -> Result<()> {
+async fn handle_command_keys(key_event: KeyEvent, app: &mut App) {
+    let Some(line) = app.command.as_mut() else {
+        return;
+    };
+
     match key_event.code {
-        KeyCode::Char('q') => app.quit(),
-        KeyCode::Esc => app.quit(),
-        KeyCode::Char('c' | 'C') if key_event.modifiers == KeyModifiers::CONTROL => app.quit(),
+        KeyCode::Esc => app.command = None,
+        // Like vim, backspacing past the `:` leaves command mode.
+        KeyCode::Backspace if line.input.is_empty() => app.command = None,
+        KeyCode::Backspace => line.pop_char(),
+        KeyCode::Up => line.history_prev(&app.command_history),
+        KeyCode::Down => line.history_next(&app.command_history),
+        KeyCode::Tab => {
+            let ifaces: Vec<&str> = app.devices.iter().map(|d| d.name.as_str()).collect();
+            line.complete(&ifaces);
+        }
+        KeyCode::Char(c) => line.push_char(c),
+        KeyCode::Enter => {
+            let input = line.input.trim().to_string();
+            app.command = None;
+            if input.is_empty() {
+                return;
+            }
+            command::remember(&mut app.command_history, &input);
+            app.clear_error();
+            match input.parse::<Command>() {
+                Ok(cmd) => run_command(cmd, app).await,
+                Err(e) => app.last_error = Some(e),
+            }
+        }
+        _ => {}
+    }
+}
 
-        KeyCode::Down | KeyCode::Char('j') => app.select_next(),
-        KeyCode::Up | KeyCode::Char('k') => app.select_prev(),
+fn handle_search_keys(key_event: KeyEvent, app: &mut App) {
+    let Some(search) = app.search.as_mut() else {
+        return;
+    };
 
-        KeyCode::Char('r') => {
-            // Force refresh now.
-            app.tick().await?;
+    match key_event.code {
+        KeyCode::Esc => app.close_search(false),
+        KeyCode::Enter => app.close_search(true),
+        KeyCode::Backspace if search.input.is_empty() => app.close_search(true),
+        KeyCode::Backspace => {
+            search.input.pop();
+            app.update_search();
         }
+        KeyCode::Char(c) => {
+            search.input.push(c);
+            app.update_search();
+        }
+        _ => {}
+    }
+}
 
-        KeyCode::Char('n') => {
-            app.clear_error();
-            let (iface, before) = match app.selected_device() {
-                Some(d) => (d.name.clone(), snap(d)),
-                None => ("-".to_string(), "no interface selected".to_string()),
+async fn run_command(cmd: Command, app: &mut App) {
+    // Commands naming an interface select it first, then act like the matching key.
+    let iface = match &cmd {
+        Command::Renew(i) | Command::Release(i) | Command::Up(i) | Command::Down(i) => i.clone(),
+        Command::Mtu(_, i) => i.clone(),
+        Command::Select(i) => Some(i.clone()),
+        _ => None,
+    };
+    if let Some(name) = iface
+        && let Err(e) = app.select_iface(&name)
+    {
+        app.last_error = Some(e.to_string());
+        return;
+    }
+    if let Some(host) = app.remote().map(|r| r.host.clone())
+        && matches!(
+            cmd,
+            Command::Renew(_)
+                | Command::Release(_)
+                | Command::Up(_)
+                | Command::Down(_)
+                | Command::Mtu(..)
+        )
+    {
+        app.last_error = Some(format!("Not available while watching {host}"));
+        return;
+    }
+
+    match cmd {
+        Command::Quit => app.quit(),
+        Command::Refresh => app.refresh(),
+        Command::Renew(_) => renew(app).await,
+        Command::Release(_) => app.confirm_release_dhcp().await,
+        Command::Up(_) => set_link(app, true).await,
+        Command::Down(_) => app.confirm_link_down(),
+        Command::Mtu(mtu, _) => {
+            if let Err(e) = app.confirm_mtu(mtu) {
+                app.last_error = Some(e.to_string());
+            }
+        }
+        Command::Filter(pattern) => {
+            let msg = match &pattern {
+                Some(p) => format!("Showing interfaces matching {p}"),
+                None => "Showing all interfaces".to_string(),
             };
+            app.set_filter(pattern);
+            app.set_toast(ToastKind::Info, msg);
+        }
+        Command::Select(_) => {}
+        Command::Qr(text) => {
+            if let Err(e) = app.show_qr(text) {
+                app.last_error = Some(e.to_string());
+            }
+        }
+        Command::Help => app.help_scroll = Some(0),
+    }
+}
 
-            match app.renew_dhcp().await {
-                Ok(out) => {
-                    // Refresh state after the command returns so UI reflects any new lease/IP.
-                    let _ = app.tick().await;
-                    let after = app
-                        .selected_device()
-                        .map(snap)
-                        .unwrap_or_else(|| "no interface selected".to_string());
-
-                    let mut msg = format!("{iface}: DHCP renew requested");
-                    if out.used_sudo {
-                        msg.push_str(" (sudo)");
-                    }
-                    if !out.stdout.is_empty() || !out.stderr.is_empty() {
-                        let mut extra = String::new();
-                        if !out.stdout.is_empty() {
-                            extra.push_str(&format!("stdout: {}", out.stdout));
-                        }
-                        if !out.stderr.is_empty() {
-                            if !extra.is_empty() {
-                                extra.push('\n');
-                            }
-                            extra.push_str(&format!("stderr: {}", out.stderr));
-                        }
-                        msg.push_str(&format!("\n{}", extra));
-                    }
-
-                    if before == after {
-                        msg.push_str("\nNo change detected (lease may still have been renewed).");
-                    }
-                    msg.push_str(&format!("\nBefore: {before}\nAfter:  {after}"));
-
-                    app.set_toast(ToastKind::Success, msg);
-                    let body = if before == after {
-                        "DHCP renew requested (no visible change)."
-                    } else {
-                        "DHCP renew requested."
-                    };
-                    app.notify("󰀂    Ethernet", &format!("{iface}: {body}"))
-                        .await;
-                }
-                Err(e) => {
-                    app.last_error = Some(e.to_string());
-                    app.set_toast(ToastKind::Error, format!("{iface}: DHCP renew failed"));
-                    app.notify("󰀂    Ethernet", &format!("{iface}: DHCP renew failed"))
-                        .await;
+/// Runs the command lines plugins asked for, each as if it had been typed after `:`.
+/// They wait while a confirmation is open, so none is replaced before it's answered.
+pub async fn run_plugin_commands(app: &mut App) {
+    if app.confirm.is_some() {
+        return;
+    }
+    for (plugin, line) in std::mem::take(&mut app.plugin_commands) {
+        match line.parse() {
+            Ok(cmd) => run_command(cmd, app).await,
+            Err(e) => app.last_error = Some(format!("Plugin {plugin}: {e}")),
+        }
+    }
+    // Same as for keys: without confirmations, a command that asks for one runs now.
+    if !app.config.confirm
+        && let Some(confirm) = app.confirm.take()
+    {
+        run_confirmed(app, confirm.action).await;
+    }
+}
+
+pub async fn handle_key_events(key_event: KeyEvent, app: &mut App) -> Result<()> {
+    handle_key(key_event, app).await?;
+    // With `confirm = false`, whatever just asked for a confirmation runs right away.
+    if !app.config.confirm
+        && let Some(confirm) = app.confirm.take()
+    {
+        run_confirmed(app, confirm.action).await;
+    }
+    Ok(())
+}
+
+async fn run_confirmed(app: &mut App, action: ConfirmAction) {
+    app.clear_error();
+    match app.run_confirmed(action).await {
+        Ok(msg) => app.set_toast(ToastKind::Success, msg),
+        Err(e) => app.last_error = Some(e.to_string()),
+    }
+}
+
+async fn handle_key(key_event: KeyEvent, app: &mut App) -> Result<()> {
+    // The error popup sits on top of everything; the first key only dismisses it.
+    if app.last_error.is_some() {
+        app.clear_error();
+        return Ok(());
+    }
+
+    if app.popup.is_some() {
+        app.popup = None;
+        return Ok(());
+    }
+
+    if app.renew_diff.is_some() {
+        app.renew_diff = None;
+        return Ok(());
+    }
+
+    if app.qr.is_some() {
+        let shown = match key_event.code {
+            KeyCode::Char('i') => app.selected_ipv4().and_then(|ip| app.show_qr(Some(ip))),
+            KeyCode::Char('u') => app.show_qr(None),
+            _ => {
+                app.qr = None;
+                Ok(())
+            }
+        };
+        if let Err(e) = shown {
+            app.last_error = Some(e.to_string());
+        }
+        return Ok(());
+    }
+
+    if let Some(confirm) = app.confirm.take() {
+        if key_event.code == KeyCode::Char('y') {
+            run_confirmed(app, confirm.action).await;
+        }
+        return Ok(());
+    }
+
+    if app.form.is_some() {
+        handle_form_keys(key_event, app).await;
+        return Ok(());
+    }
+
+    if app.command.is_some() {
+        handle_command_keys(key_event, app).await;
+        return Ok(());
+    }
+
+    if app.search.is_some() {
+        handle_search_keys(key_event, app);
+        return Ok(());
+    }
+
+    if app.yank_pending {
+        app.yank_pending = false;
+        app.toast = None;
+        if let KeyCode::Char(c) = key_event.code {
+            match app.yank(c).await {
+                Ok(msg) => app.set_toast(ToastKind::Success, msg),
+                Err(e) => app.last_error = Some(e.to_string()),
+            }
+        }
+        return Ok(());
+    }
+
+    // Actions tied to a tab win over global ones bound to the same key while that tab is shown.
+    let offloads_focused = app.focus == Focus::Details && app.tab == DetailTab::Offloads;
+    let routes_shown = app.tab == DetailTab::Routes;
+    let in_context = |a: Action| match a {
+        Action::ToggleOffload => Some(offloads_focused),
+        Action::AddRoute | Action::DeleteRoute => Some(routes_shown),
+        _ => None,
+    };
+    let action = app
+        .config
+        .keys
+        .action(&key_event, |a| in_context(a) == Some(true))
+        .or_else(|| {
+            app.config
+                .keys
+                .action(&key_event, |a| in_context(a).is_none())
+        });
+
+    if let Some(scroll) = &mut app.help_scroll {
+        match (key_event.code, action) {
+            (KeyCode::Esc, _) | (_, Some(Action::Help | Action::Quit)) => app.help_scroll = None,
+            (KeyCode::PageDown, _) => *scroll = scroll.saturating_add(10),
+            (KeyCode::PageUp, _) => *scroll = scroll.saturating_sub(10),
+            (_, Some(Action::Down)) => *scroll = scroll.saturating_add(1),
+            (_, Some(Action::Up)) => *scroll = scroll.saturating_sub(1),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if let Some(scroll) = &mut app.events_scroll {
+        match (key_event.code, action) {
+            (KeyCode::Esc, _) | (_, Some(Action::Events | Action::Quit)) => {
+                app.events_scroll = None
+            }
+            (KeyCode::PageDown, _) => *scroll = scroll.saturating_add(10),
+            (KeyCode::PageUp, _) => *scroll = scroll.saturating_sub(10),
+            (_, Some(Action::Down)) => *scroll = scroll.saturating_add(1),
+            (_, Some(Action::Up)) => *scroll = scroll.saturating_sub(1),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if app.scan.is_some() || app.mdns.is_some() {
+        match (key_event.code, action) {
+            (KeyCode::Esc, _) => {
+                app.stop_scan();
+                app.stop_mdns();
+            }
+            (_, Some(Action::Down)) => app.scroll_popup(true),
+            (_, Some(Action::Up)) => app.scroll_popup(false),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if app.netplan_try.is_some() {
+        match key_event.code {
+            KeyCode::Enter => app.accept_netplan_try(),
+            KeyCode::Esc => app.dismiss_netplan_try(),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if app.ping.is_some()
+        || app.trace.is_some()
+        || app.speedtest.is_some()
+        || app.dns_test.is_some()
+    {
+        if key_event.code == KeyCode::Esc {
+            app.stop_ping();
+            app.stop_trace();
+            app.stop_speedtest();
+            app.stop_dns_test();
+        }
+        return Ok(());
+    }
+
+    if let Some(a) = action
+        && a.runs_locally()
+        && let Some(host) = app.remote().map(|r| r.host.clone())
+    {
+        app.last_error = Some(format!(
+            "{} isn't available while watching {host}",
+            a.label()
+        ));
+        return Ok(());
+    }
+
+    if app.focus == Focus::Details {
+        match action {
+            _ if key_event.code == KeyCode::Esc || action == Some(Action::ToggleFocus) => {
+                app.toggle_focus();
+                return Ok(());
+            }
+            Some(Action::Down) => {
+                app.scroll_details(true);
+                return Ok(());
+            }
+            Some(Action::Up) => {
+                app.scroll_details(false);
+                return Ok(());
+            }
+            Some(Action::ToggleOffload) => {
+                app.clear_error();
+                match app.toggle_selected_offload().await {
+                    Ok(msg) => app.set_toast(ToastKind::Success, msg),
+                    Err(e) => app.last_error = Some(e.to_string()),
                 }
+                return Ok(());
             }
+            _ => {}
         }
+    }
 
-        _ => {}
+    let Some(action) = action else {
+        // Unbound digits jump straight to a detail tab.
+        if let KeyCode::Char(c) = key_event.code
+            && key_event.modifiers.is_empty()
+            && let Some(tab) = DetailTab::from_digit(c)
+        {
+            app.set_tab(tab);
+        }
+        return Ok(());
+    };
+    match action {
+        Action::Quit => app.quit(),
+
+        Action::ToggleFocus => app.toggle_focus(),
+        Action::NextTab => app.set_tab(app.tab.next()),
+        Action::PrevTab => app.set_tab(app.tab.prev()),
+
+        Action::Down => app.select_next(),
+        Action::Up => app.select_prev(),
+        Action::PageDown => app.page_details(true),
+        Action::PageUp => app.page_details(false),
+
+        Action::Refresh => {
+            // Force refresh now.
+            app.refresh();
+        }
+        Action::Pause => app.toggle_pause(),
+        Action::Sort => app.cycle_sort(),
+
+        Action::Renew => renew(app).await,
+        Action::Release => app.confirm_release_dhcp().await,
+        Action::AddVlan => app.open_vlan_form(),
+        Action::DeleteVlan => app.confirm_delete_vlan(),
+        Action::Bridge => app.bridge_action(),
+        Action::BondFailover => app.confirm_bond_failover(),
+
+        Action::LinkUp => set_link(app, true).await,
+        Action::LinkDown => app.confirm_link_down(),
+
+        Action::StaticIp => app.open_static_form(),
+        Action::Dns => app.open_dns_form(),
+        Action::NetworkFile => app.open_network_form(),
+        Action::Netplan => app.open_netplan_form(),
+        Action::Profile => app.open_profile_form(),
+        Action::AddAddress => app.open_address_form(),
+        Action::DeleteAddress => {
+            if let Err(e) = app.confirm_delete_address() {
+                app.last_error = Some(e.to_string());
+            }
+        }
+        Action::AddRoute => app.open_route_form(),
+        Action::DeleteRoute => {
+            if let Err(e) = app.confirm_delete_route() {
+                app.last_error = Some(e.to_string());
+            }
+        }
+        Action::FlushAddresses => app.open_flush_form(),
+        Action::LinkMode => app.open_link_form(),
+        Action::Rings => app.open_rings_form(),
+        Action::Channels => app.open_channels_form(),
+        Action::Coalescing => app.open_coalesce_form(),
+        Action::Eee => app.confirm_eee(),
+        Action::Fec => app.open_fec_form(),
+        Action::IrqAffinity => app.open_irq_affinity_form(),
+        Action::SpreadIrqs => app.confirm_spread_irqs(),
+        Action::NumVfs => app.open_num_vfs_form(),
+        Action::VfSettings => app.open_vf_form(),
+        Action::Wol => app.open_wol_form(),
+        Action::Mac => app.open_mac_form(),
+        Action::Undo => app.confirm_undo(),
+        Action::Promisc => match app.toggle_promisc_guarded().await {
+            Some(Ok(msg)) => app.set_toast(ToastKind::Success, msg),
+            Some(Err(e)) => app.last_error = Some(e.to_string()),
+            None => {}
+        },
+
+        Action::Ping => app.open_ping_form(),
+        Action::Trace => app.open_trace_form(),
+        Action::SpeedTest => app.open_speedtest_form(),
+        Action::DnsTest => app.open_dns_test_form(),
+        Action::Scan => match app.start_scan() {
+            Ok(msg) => app.set_toast(ToastKind::Info, msg),
+            Err(e) => app.last_error = Some(e.to_string()),
+        },
+        Action::Mdns => match app.start_mdns() {
+            Ok(msg) => app.set_toast(ToastKind::Info, msg),
+            Err(e) => app.last_error = Some(e.to_string()),
+        },
+
+        Action::Yank => {
+            app.yank_pending = true;
+            app.set_toast(
+                ToastKind::Info,
+                "Yank: i IP, m MAC, g gateway, d DNS, s snapshot (Esc cancels)",
+            );
+        }
+
+        Action::Export => app.open_export_form(),
+        Action::ExportCsv => app.open_history_export_form(),
+        Action::Qr => {
+            if let Err(e) = app.show_qr(None) {
+                app.last_error = Some(e.to_string());
+            }
+        }
+
+        Action::Identify => match app.identify_nic(5) {
+            Ok(()) => app.set_toast(ToastKind::Info, "Blinking port LED for 5s..."),
+            Err(e) => app.last_error = Some(e.to_string()),
+        },
+        Action::CableTest => match app.run_cable_test() {
+            Ok(()) => app.set_toast(ToastKind::Info, "Running cable test..."),
+            Err(e) => app.last_error = Some(e.to_string()),
+        },
+
+        Action::Events => app.events_scroll = Some(0),
+        Action::NextHost => app.cycle_host(true),
+        Action::PrevHost => app.cycle_host(false),
+        Action::Help => app.help_scroll = Some(0),
+        Action::Search => app.open_search(),
+        Action::Command => app.command = Some(CommandLine::default()),
+
+        // Only meaningful on the focused Offloads tab, handled above.
+        Action::ToggleOffload => {}
     }
 
     Ok(())
 }
+
+/// Clicking a column header of the interface list sorts by it. Clicks are ignored while
+/// anything is open on top of the main screen.
+pub fn handle_mouse_events(mouse_event: MouseEvent, app: &mut App) {
+    if mouse_event.kind != MouseEventKind::Down(MouseButton::Left) {
+        return;
+    }
+    let overlay = app.last_error.is_some()
+        || app.popup.is_some()
+        || app.renew_diff.is_some()
+        || app.qr.is_some()
+        || app.confirm.is_some()
+        || app.form.is_some()
+        || app.command.is_some()
+        || app.help_scroll.is_some()
+        || app.events_scroll.is_some()
+        || app.scan.is_some()
+        || app.mdns.is_some()
+        || app.ping.is_some()
+        || app.trace.is_some()
+        || app.netplan_try.is_some()
+        || app.dns_test.is_some()
+        || app.speedtest.is_some();
+    if overlay {
+        return;
+    }
+    let (x, y) = (mouse_event.column, mouse_event.row);
+    let clicked = app
+        .sort_headers
+        .iter()
+        .find(|(cell, _)| cell.contains((x, y).into()))
+        .map(|(_, key)| *key);
+    if let Some(key) = clicked {
+        app.sort_by(key);
+    }
+}
+
+async fn renew(app: &mut App) {
+    app.clear_error();
+    let before = app.selected_device().cloned();
+    let iface = before.as_ref().map_or("-".into(), |d| d.name.clone());
+
+    match app.renew_dhcp().await {
+        Ok(out) => {
+            // Refresh state after the command returns so UI reflects any new lease/IP.
+            app.refresh();
+            let after = app.selected_device().cloned();
+
+            let mut msg = format!("{iface}: DHCP renew requested");
+            if out.used_sudo {
+                msg.push_str(" (sudo)");
+            }
+            let output = [("stdout", &out.stdout), ("stderr", &out.stderr)]
+                .into_iter()
+                .filter(|(_, text)| !text.is_empty())
+                .map(|(stream, text)| format!("{stream}: {text}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let changed = match (before, after) {
+                (Some(before), Some(after)) => {
+                    let diff = RenewDiff::new(iface.clone(), &before, &after, output);
+                    let changes: Vec<_> = diff
+                        .rows
+                        .iter()
+                        .filter(|row| row.changed())
+                        .map(|row| {
+                            json!({ "field": row.field, "before": row.before, "after": row.after })
+                        })
+                        .collect();
+                    let details = json!({ "changed": changes, "output": diff.output });
+                    app.events
+                        .hook(HookEvent::RenewCompleted, &iface, "DHCP renewed", details);
+                    let changed = diff.changed();
+                    app.renew_diff = Some(diff);
+                    changed
+                }
+                _ => false,
+            };
+
+            app.set_toast(ToastKind::Success, msg);
+            let body = if changed {
+                "DHCP renew requested."
+            } else {
+                "DHCP renew requested (no visible change)."
+            };
+            app.notify(NotifyEvent::Renew, format!("{iface}: {body}"));
+        }
+        Err(e) => {
+            app.last_error = Some(e.to_string());
+            app.set_toast(ToastKind::Error, format!("{iface}: DHCP renew failed"));
+            app.notify(
+                NotifyEvent::RenewFailed,
+                format!("{iface}: DHCP renew failed"),
+            );
+        }
+    }
+}
+
+async fn set_link(app: &mut App, up: bool) {
+    app.clear_error();
+    let iface = app
+        .selected_device()
+        .map(|d| d.name.clone())
+        .unwrap_or_else(|| "-".to_string());
+    let state = if up { "up" } else { "down" };
+
+    let res = if up {
+        app.set_link_up().await
+    } else {
+        app.set_link_down().await
+    };
+
+    match res {
+        Ok(out) => {
+            let operstate = app
+                .selected_device()
+                .map(|d| d.operstate.clone())
+                .unwrap_or_else(|| "?".into());
+            let mut msg = format!("{iface}: link set {state} (operstate: {operstate})");
+            if out.used_sudo {
+                msg.push_str(" (sudo)");
+            }
+            app.set_toast(ToastKind::Success, msg);
+        }
+        Err(e) => {
+            app.last_error = Some(e.to_string());
+            app.set_toast(
+                ToastKind::Error,
+                format!("{iface}: link set {state} failed"),
+            );
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::logfile;
+use crate::net::EthernetDevice;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Entries kept, in memory and in the file; older ones are dropped when it's loaded.
+const HISTORY_LEN: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    AddressAdded,
+    AddressRemoved,
+    /// `value` is the new default gateway, empty when it went away.
+    Gateway,
+    /// `value` is the leased address; `ts` is when the lease was obtained.
+    Lease,
+}
+
+/// One line of the history file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unix seconds.
+    pub ts: u64,
+    pub iface: String,
+    pub kind: HistoryKind,
+    pub value: String,
+    /// The server and lease time of a `lease`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn at(&self) -> SystemTime {
+        UNIX_EPOCH + std::time::Duration::from_secs(self.ts)
+    }
+}
+
+/// What the last entries say an interface looked like.
+#[derive(Debug, Default)]
+struct Known {
+    addresses: BTreeSet<String>,
+    gateway: Option<String>,
+    /// When the last recorded lease was obtained.
+    lease: Option<u64>,
+}
+
+/// Addresses, default gateways and DHCP leases per interface, kept across runs in
+/// `$XDG_DATA_HOME/ethtui/history.jsonl`. The current state is compared with what the file last
+/// recorded, so changes made while ethtui wasn't running show up (stamped when first seen).
+#[derive(Debug)]
+pub struct History {
+    pub path: PathBuf,
+    entries: VecDeque<HistoryEntry>,
+    known: HashMap<String, Known>,
+}
+
+impl History {
+    /// `$XDG_DATA_HOME/ethtui/history.jsonl`, falling back to `~/.local/share`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_DATA_HOME")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("share"))
+            })?;
+        Some(base.join("ethtui").join("history.jsonl"))
+    }
+
+    /// Loads what's recorded so far; None without a home directory. Lines that don't parse are
+    /// skipped rather than losing the rest of the file.
+    pub fn open() -> Option<Self> {
+        let path = Self::default_path()?;
+        let mut entries: VecDeque<HistoryEntry> = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        if entries.len() > HISTORY_LEN {
+            entries.drain(..entries.len() - HISTORY_LEN);
+            let text: String = entries
+                .iter()
+                .filter_map(|e| serde_json::to_string(e).ok())
+                .map(|line| line + "\n")
+                .collect();
+            let tmp = path.with_extension("jsonl.tmp");
+            if fs::write(&tmp, text).is_ok() {
+                let _ = fs::rename(&tmp, &path);
+            }
+        }
+        let mut history = Self {
+            path,
+            entries: VecDeque::new(),
+            known: HashMap::new(),
+        };
+        for entry in entries {
+            history.replay(&entry);
+            history.entries.push_back(entry);
+        }
+        Some(history)
+    }
+
+    fn replay(&mut self, entry: &HistoryEntry) {
+        let known = self.known.entry(entry.iface.clone()).or_default();
+        match entry.kind {
+            HistoryKind::AddressAdded => {
+                known.addresses.insert(entry.value.clone());
+            }
+            HistoryKind::AddressRemoved => {
+                known.addresses.remove(&entry.value);
+            }
+            HistoryKind::Gateway => {
+                known.gateway = Some(entry.value.clone()).filter(|g| !g.is_empty());
+            }
+            HistoryKind::Lease => known.lease = Some(entry.ts),
+        }
+    }
+
+    /// Records whatever differs from the last known state. Link-local IPv6 addresses come and
+    /// go with the link and are left out.
+    pub fn observe(&mut self, devices: &[EthernetDevice]) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let mut new = Vec::new();
+        for d in devices {
+            let known = self.known.get(&d.name);
+            let addresses: BTreeSet<&String> = d
+                .ipv4
+                .iter()
+                .chain(d.ipv6.iter().filter(|a| !a.starts_with("fe80:")))
+                .collect();
+            let entry = |kind, value: &str| HistoryEntry {
+                ts: now,
+                iface: d.name.clone(),
+                kind,
+                value: value.to_string(),
+                detail: None,
+            };
+            if let Some(known) = known {
+                for addr in &known.addresses {
+                    if !addresses.contains(addr) {
+                        new.push(entry(HistoryKind::AddressRemoved, addr));
+                    }
+                }
+            }
+            for addr in &addresses {
+                if known.is_none_or(|k| !k.addresses.contains(*addr)) {
+                    new.push(entry(HistoryKind::AddressAdded, addr));
+                }
+            }
+            let gateway = d.gateway_v4.as_ref();
+            // A first sighting without a gateway isn't a change worth a line.
+            if known.map_or(gateway.is_some(), |k| k.gateway.as_ref() != gateway) {
+                new.push(entry(HistoryKind::Gateway, gateway.map_or("", |g| g)));
+            }
+            if let Some(lease) = &d.lease
+                && let Some(acquired) = lease.acquired
+            {
+                let ts = acquired
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                if known.is_none_or(|k| k.lease != Some(ts)) {
+                    let mut detail = Vec::new();
+                    if let Some(server) = &lease.server {
+                        detail.push(format!("from {server}"));
+                    }
+                    if let Some(time) = lease.lease_time {
+                        detail.push(format!("for {}s", time.as_secs()));
+                    }
+                    new.push(HistoryEntry {
+                        ts,
+                        detail: Some(detail.join(", ")).filter(|d| !d.is_empty()),
+                        ..entry(
+                            HistoryKind::Lease,
+                            lease.address.as_deref().unwrap_or_default(),
+                        )
+                    });
+                }
+            }
+        }
+        if !new.is_empty() {
+            self.append(new);
+        }
+    }
+
+    fn append(&mut self, new: Vec<HistoryEntry>) {
+        let written = self
+            .path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?;
+                let text: String = new
+                    .iter()
+                    .filter_map(|e| serde_json::to_string(e).ok())
+                    .map(|line| line + "\n")
+                    .collect();
+                file.write_all(text.as_bytes())
+            });
+        // Still kept in memory, so the History tab shows it this run.
+        if let Err(e) = written {
+            logfile::write(
+                "history",
+                json!({ "path": self.path, "error": e.to_string() }),
+            );
+        }
+        for entry in new {
+            self.replay(&entry);
+            if self.entries.len() == HISTORY_LEN {
+                self.entries.pop_front();
+            }
+            self.entries.push_back(entry);
+        }
+    }
+
+    /// Oldest first.
+    pub fn for_iface<'a>(
+        &'a self,
+        iface: &'a str,
+    ) -> impl DoubleEndedIterator<Item = &'a HistoryEntry> {
+        self.entries.iter().filter(move |e| e.iface == iface)
+    }
+
+    /// The last time an address was added to or removed from `iface`.
+    pub fn address_changed(&self, iface: &str) -> Option<&HistoryEntry> {
+        self.entries.iter().rev().find(|e| {
+            e.iface == iface
+                && matches!(
+                    e.kind,
+                    HistoryKind::AddressAdded | HistoryKind::AddressRemoved
+                )
+        })
+    }
+}
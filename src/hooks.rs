@@ -0,0 +1,167 @@
+use crate::command::glob_match;
+use crate::logfile;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// A hook or plugin gets this long before it's killed.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What a hook can run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    CarrierLost,
+    CarrierUp,
+    AddressAdded,
+    AddressRemoved,
+    GatewayChanged,
+    NewLease,
+    /// Only `ethtui daemon` pings gateways, so only it notices.
+    GatewayUnreachable,
+    /// Only the TUI renews leases (`n`) and scans subnets (`A`).
+    RenewCompleted,
+    ScanFinished,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::CarrierLost => "carrier_lost",
+            HookEvent::CarrierUp => "carrier_up",
+            HookEvent::AddressAdded => "address_added",
+            HookEvent::AddressRemoved => "address_removed",
+            HookEvent::GatewayChanged => "gateway_changed",
+            HookEvent::NewLease => "new_lease",
+            HookEvent::GatewayUnreachable => "gateway_unreachable",
+            HookEvent::RenewCompleted => "renew_completed",
+            HookEvent::ScanFinished => "scan_finished",
+        }
+    }
+}
+
+/// A `[[hooks]]` entry: a shell command, a webhook or both, for some events.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    pub events: Vec<HookEvent>,
+    /// A name or glob like `enp*`; every interface without one.
+    pub interface: Option<String>,
+    /// Run with `sh -c`, the details in `ETHTUI_*` variables and as JSON on stdin.
+    pub command: Option<String>,
+    /// POSTed the details as JSON with `curl`.
+    pub url: Option<String>,
+}
+
+impl Hook {
+    fn matches(&self, event: HookEvent, iface: &str) -> bool {
+        self.events.contains(&event)
+            && self
+                .interface
+                .as_deref()
+                .is_none_or(|pattern| glob_match(pattern, iface))
+    }
+}
+
+/// What hooks and plugins are given: the event, interface, message and time, with `details`
+/// (an object) merged in.
+pub fn body(event: HookEvent, iface: &str, message: &str, details: Value) -> Value {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut body = json!({
+        "event": event.name(),
+        "interface": iface,
+        "message": message,
+        "time": time,
+    });
+    if let (Some(body), Value::Object(details)) = (body.as_object_mut(), details) {
+        body.extend(details);
+    }
+    body
+}
+
+/// Starts every hook for `event` in the background. The fields of `body` also become
+/// `ETHTUI_<KEY>` variables; the outcome ends up in the log file.
+pub fn fire(hooks: &[Hook], event: HookEvent, iface: &str, body: &Value) {
+    let matching: Vec<&Hook> = hooks.iter().filter(|h| h.matches(event, iface)).collect();
+    for hook in matching {
+        if let Some(command) = &hook.command {
+            tokio::spawn(run_command(command.clone(), body.clone()));
+        }
+        if let Some(url) = &hook.url {
+            tokio::spawn(post(url.clone(), body.clone()));
+        }
+    }
+}
+
+async fn run_command(command: String, body: Value) {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(&command);
+    if let Value::Object(fields) = &body {
+        for (key, value) in fields {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            cmd.env(format!("ETHTUI_{}", key.to_uppercase()), value);
+        }
+    }
+    cmd.env("ETHTUI_JSON", body.to_string());
+    let result = pipe_json(cmd, &body).await;
+    log("command", &command, &body, result);
+}
+
+async fn post(url: String, body: Value) {
+    let mut cmd = Command::new("curl");
+    cmd.args(["-fsS", "-m", "10", "-X", "POST"])
+        .args([
+            "-H",
+            "Content-Type: application/json",
+            "--data-binary",
+            "@-",
+        ])
+        .arg(&url);
+    let result = pipe_json(cmd, &body).await;
+    log("url", &url, &body, result);
+}
+
+/// Runs `cmd` (a hook or a plugin) with `body` as one line on stdin and returns its stdout; Err
+/// says why it failed.
+pub(crate) async fn pipe_json(mut cmd: Command, body: &Value) -> Result<String, String> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores stdin closes it early; that's fine.
+        let _ = stdin.write_all(format!("{body}\n").as_bytes()).await;
+    }
+    match tokio::time::timeout(HOOK_TIMEOUT, child.wait_with_output()).await {
+        Err(_) => Err(format!("killed after {}s", HOOK_TIMEOUT.as_secs())),
+        Ok(Err(e)) => Err(e.to_string()),
+        Ok(Ok(out)) if out.status.success() => Ok(String::from_utf8_lossy(&out.stdout).into()),
+        Ok(Ok(out)) => Err(format!(
+            "exit status {}: {}",
+            out.status.code().unwrap_or(1),
+            String::from_utf8_lossy(&out.stderr).trim()
+        )),
+    }
+}
+
+fn log(target: &str, what: &str, body: &Value, result: Result<String, String>) {
+    let mut fields =
+        json!({ target: what, "event": body["event"], "interface": body["interface"] });
+    match result {
+        Ok(_) => fields["ok"] = json!(true),
+        Err(e) => fields["error"] = json!(e),
+    }
+    logfile::write("hook", fields);
+}
@@ -0,0 +1,214 @@
+use crate::command::glob_match;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// ifupdown's main configuration file; everything else is pulled in with `source`.
+pub const INTERFACES: &str = "/etc/network/interfaces";
+
+/// Includes nest; past this a `source` loop is assumed.
+const MAX_DEPTH: usize = 8;
+
+/// Options on bridges, bonds and VLANs that name the interfaces they're built on.
+const MEMBER_OPTIONS: [&str; 6] = [
+    "bridge_ports",
+    "bridge-ports",
+    "bond-slaves",
+    "slaves",
+    "vlan-raw-device",
+    "vlan_raw_device",
+];
+
+/// An `iface` block with its options, or an `auto`/`allow-*` line naming the interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stanza {
+    pub path: String,
+    /// 1-based line of the stanza's first line.
+    pub line: usize,
+    /// The interface the stanza configures: a bridge or bond the interface is a member of, for
+    /// ones that only mention it.
+    pub iface: String,
+    /// `inet dhcp`, `inet6 auto`...; empty for `auto`/`allow-*` lines.
+    pub method: String,
+    /// The lines as written, comments included.
+    pub text: String,
+}
+
+/// ifupdown's configuration as it concerns one interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interfaces {
+    /// The interfaces file and everything it includes, in the order ifupdown reads them.
+    pub files: Vec<String>,
+    pub stanzas: Vec<Stanza>,
+}
+
+impl Interfaces {
+    /// Whether an `iface` stanza is the interface's own (not a bridge it's a port of).
+    pub fn configures(&self, iface: &str) -> bool {
+        self.stanzas
+            .iter()
+            .any(|s| s.iface == iface && !s.method.is_empty())
+    }
+
+    /// `path:line` of the interface's first `iface` stanza.
+    pub fn source(&self, iface: &str) -> Option<String> {
+        self.stanzas
+            .iter()
+            .find(|s| s.iface == iface && !s.method.is_empty())
+            .map(|s| format!("{}:{}", s.path, s.line))
+    }
+}
+
+fn is_stanza_start(word: &str) -> bool {
+    matches!(
+        word,
+        "iface" | "mapping" | "auto" | "source" | "source-directory" | "source-dir"
+    ) || word.starts_with("allow-")
+}
+
+/// `source` takes a shell glob, but only in the file name is common (`interfaces.d/*`), so only
+/// that is expanded. Relative paths are relative to the including file's directory.
+fn expand(pattern: &str, base: &Path) -> Vec<PathBuf> {
+    let path = base.join(pattern);
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let name = name.to_string_lossy();
+    if !name.contains(['*', '?']) {
+        return vec![path.clone()];
+    }
+    let Ok(read) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut found: Vec<PathBuf> = read
+        .flatten()
+        .filter(|e| glob_match(&name, &e.file_name().to_string_lossy()))
+        .map(|e| e.path())
+        .collect();
+    found.sort();
+    found
+}
+
+/// `source-directory` reads files named only with letters, digits, `_` and `-`, like run-parts.
+fn directory_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(read) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut found: Vec<PathBuf> = read
+        .flatten()
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        })
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    found.sort();
+    found
+}
+
+/// Reads `path` and what it includes, depth first, appending the files to `out`.
+fn read_all(path: &Path, depth: usize, out: &mut Vec<(String, String)>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let Ok(text) = fs::read_to_string(path) else {
+        return;
+    };
+    let base = path.parent().unwrap_or(Path::new("/"));
+    out.push((path.to_string_lossy().to_string(), text.clone()));
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("source"), Some(pattern)) => {
+                for p in expand(pattern, base) {
+                    read_all(&p, depth + 1, out);
+                }
+            }
+            (Some("source-directory" | "source-dir"), Some(dir)) => {
+                for p in directory_files(&base.join(dir)) {
+                    read_all(&p, depth + 1, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The stanzas in one file that configure or mention `iface`.
+fn stanzas_in(path: &str, text: &str, iface: &str) -> Vec<Stanza> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let words: Vec<&str> = lines[i].split_whitespace().collect();
+        let Some(&keyword) = words.first() else {
+            i += 1;
+            continue;
+        };
+        if keyword == "auto" || keyword.starts_with("allow-") {
+            if words[1..].contains(&iface) {
+                found.push(Stanza {
+                    path: path.to_string(),
+                    line: i + 1,
+                    iface: iface.to_string(),
+                    method: String::new(),
+                    text: lines[i].to_string(),
+                });
+            }
+            i += 1;
+            continue;
+        }
+        if keyword != "iface" || words.len() < 2 {
+            i += 1;
+            continue;
+        }
+        // Options run until the next stanza; trailing blanks and comments belong to neither.
+        let start = i;
+        let mut last = i;
+        i += 1;
+        while i < lines.len() {
+            let t = lines[i].trim();
+            if t.split_whitespace().next().is_some_and(is_stanza_start) {
+                break;
+            }
+            if !t.is_empty() && !t.starts_with('#') {
+                last = i;
+            }
+            i += 1;
+        }
+        let options = &lines[start + 1..=last];
+        let member = options.iter().any(|l| {
+            let mut words = l.split_whitespace();
+            words.next().is_some_and(|o| MEMBER_OPTIONS.contains(&o)) && words.any(|w| w == iface)
+        });
+        if words[1] == iface || member {
+            found.push(Stanza {
+                path: path.to_string(),
+                line: start + 1,
+                iface: words[1].to_string(),
+                method: words[2..].join(" "),
+                text: lines[start..=last].join("\n"),
+            });
+        }
+    }
+    found
+}
+
+/// `None` when the machine has no ifupdown configuration.
+pub fn interfaces_for(iface: &str) -> Option<Interfaces> {
+    let mut files = Vec::new();
+    read_all(Path::new(INTERFACES), 0, &mut files);
+    if files.is_empty() {
+        return None;
+    }
+    let stanzas = files
+        .iter()
+        .flat_map(|(path, text)| stanzas_in(path, text, iface))
+        .collect();
+    Some(Interfaces {
+        files: files.into_iter().map(|(path, _)| path).collect(),
+        stanzas,
+    })
+}
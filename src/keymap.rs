@@ -0,0 +1,689 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Everything a key can be bound to outside of forms and popups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    ToggleFocus,
+    NextTab,
+    PrevTab,
+    /// Next interface, or next row when the detail tab is focused.
+    Down,
+    Up,
+    /// Scroll a text tab by half a page.
+    PageDown,
+    PageUp,
+    Refresh,
+    /// Stop and resume automatic refreshing.
+    Pause,
+    /// Cycle the column the interface list is sorted by.
+    Sort,
+    Renew,
+    Release,
+    AddVlan,
+    DeleteVlan,
+    Bridge,
+    BondFailover,
+    LinkUp,
+    LinkDown,
+    StaticIp,
+    /// DNS servers and search domains of the selected interface.
+    Dns,
+    /// Write a systemd-networkd .network file for the interface.
+    NetworkFile,
+    /// `netplan try` or `netplan apply`.
+    Netplan,
+    /// Apply one of the config's `[[profiles]]` to the selected interface.
+    Profile,
+    /// Add a route on the selected interface; only on the Routes tab.
+    AddRoute,
+    /// Delete the route under the focused Routes tab's cursor.
+    DeleteRoute,
+    /// Add a secondary IPv4 or IPv6 address.
+    AddAddress,
+    /// Delete the address under the focused Overview's cursor.
+    DeleteAddress,
+    /// Flush every address, optionally restarting DHCP.
+    FlushAddresses,
+    LinkMode,
+    Rings,
+    /// Number of RX/TX/combined channels (queues).
+    Channels,
+    Coalescing,
+    /// Turn Energy Efficient Ethernet off or on.
+    Eee,
+    /// Forward error correction encoding.
+    Fec,
+    /// CPUs of the selected queue's interrupts (Queues tab).
+    IrqAffinity,
+    /// One CPU per queue for all of the interface's queue interrupts (Queues tab).
+    SpreadIrqs,
+    /// Number of SR-IOV virtual functions (SR-IOV tab).
+    NumVfs,
+    /// MAC, VLAN, spoof check, trust and link state of the selected VF (SR-IOV tab).
+    VfSettings,
+    Wol,
+    Mac,
+    /// Revert the most recent MTU, MAC or static IPv4 change.
+    Undo,
+    Promisc,
+    Ping,
+    Trace,
+    SpeedTest,
+    /// Resolve a hostname with each of the interface's DNS servers, or benchmark them.
+    DnsTest,
+    Scan,
+    Mdns,
+    /// Copy a value of the selected interface; the next key picks which.
+    Yank,
+    /// Write every listed interface to a JSON file.
+    Export,
+    /// Write the selected interface's rate and latency history to a CSV file.
+    ExportCsv,
+    /// Show the selected interface's address as a QR code.
+    Qr,
+    Identify,
+    CableTest,
+    ToggleOffload,
+    /// Browse the log of link changes.
+    Events,
+    /// Switch between this machine and the `hosts` from the config.
+    NextHost,
+    PrevHost,
+    Help,
+    /// Narrow the interface list as a name filter is typed.
+    Search,
+    Command,
+}
+
+impl Action {
+    pub const ALL: [Action; 62] = [
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Refresh,
+        Action::Pause,
+        Action::Sort,
+        Action::Renew,
+        Action::Release,
+        Action::AddVlan,
+        Action::DeleteVlan,
+        Action::Bridge,
+        Action::BondFailover,
+        Action::LinkUp,
+        Action::LinkDown,
+        Action::StaticIp,
+        Action::Dns,
+        Action::NetworkFile,
+        Action::Netplan,
+        Action::Profile,
+        Action::AddRoute,
+        Action::DeleteRoute,
+        Action::AddAddress,
+        Action::DeleteAddress,
+        Action::FlushAddresses,
+        Action::LinkMode,
+        Action::Identify,
+        Action::CableTest,
+        Action::Rings,
+        Action::Channels,
+        Action::Coalescing,
+        Action::Eee,
+        Action::Fec,
+        Action::IrqAffinity,
+        Action::SpreadIrqs,
+        Action::NumVfs,
+        Action::VfSettings,
+        Action::Wol,
+        Action::Mac,
+        Action::Undo,
+        Action::Promisc,
+        Action::Ping,
+        Action::Trace,
+        Action::SpeedTest,
+        Action::DnsTest,
+        Action::Scan,
+        Action::Mdns,
+        Action::Yank,
+        Action::Export,
+        Action::ExportCsv,
+        Action::Qr,
+        Action::PrevTab,
+        Action::NextTab,
+        Action::PrevHost,
+        Action::NextHost,
+        Action::ToggleFocus,
+        Action::ToggleOffload,
+        Action::Events,
+        Action::Help,
+        Action::Search,
+        Action::Command,
+        Action::Quit,
+    ];
+
+    /// The name used in the `[keys]` table of the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::ToggleFocus => "toggle_focus",
+            Action::NextTab => "next_tab",
+            Action::PrevTab => "prev_tab",
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::Refresh => "refresh",
+            Action::Pause => "pause",
+            Action::Sort => "sort",
+            Action::Renew => "renew",
+            Action::Release => "release",
+            Action::AddVlan => "add_vlan",
+            Action::DeleteVlan => "delete_vlan",
+            Action::Bridge => "bridge",
+            Action::BondFailover => "bond_failover",
+            Action::LinkUp => "link_up",
+            Action::LinkDown => "link_down",
+            Action::StaticIp => "static_ip",
+            Action::Dns => "dns",
+            Action::NetworkFile => "network_file",
+            Action::Netplan => "netplan",
+            Action::Profile => "profile",
+            Action::AddRoute => "add_route",
+            Action::DeleteRoute => "delete_route",
+            Action::AddAddress => "add_address",
+            Action::DeleteAddress => "delete_address",
+            Action::FlushAddresses => "flush_addresses",
+            Action::LinkMode => "link_mode",
+            Action::Rings => "rings",
+            Action::Channels => "channels",
+            Action::Coalescing => "coalescing",
+            Action::Eee => "eee",
+            Action::Fec => "fec",
+            Action::IrqAffinity => "irq_affinity",
+            Action::SpreadIrqs => "spread_irqs",
+            Action::NumVfs => "num_vfs",
+            Action::VfSettings => "vf_settings",
+            Action::Wol => "wol",
+            Action::Mac => "mac",
+            Action::Undo => "undo",
+            Action::Promisc => "promisc",
+            Action::Ping => "ping",
+            Action::Trace => "trace",
+            Action::SpeedTest => "speed_test",
+            Action::DnsTest => "dns_test",
+            Action::Scan => "scan",
+            Action::Mdns => "mdns",
+            Action::Yank => "yank",
+            Action::Export => "export",
+            Action::ExportCsv => "export_csv",
+            Action::Qr => "qr",
+            Action::Identify => "identify",
+            Action::CableTest => "cable_test",
+            Action::ToggleOffload => "toggle_offload",
+            Action::Events => "events",
+            Action::NextHost => "next_host",
+            Action::PrevHost => "prev_host",
+            Action::Help => "help",
+            Action::Search => "search",
+            Action::Command => "command",
+        }
+    }
+
+    /// Changes something or runs a tool on this machine, so there's nothing it could do for a
+    /// host watched with `--remote`.
+    pub fn runs_locally(self) -> bool {
+        !matches!(
+            self,
+            Action::Quit
+                | Action::ToggleFocus
+                | Action::NextTab
+                | Action::PrevTab
+                | Action::Down
+                | Action::Up
+                | Action::PageDown
+                | Action::PageUp
+                | Action::Refresh
+                | Action::Pause
+                | Action::Sort
+                | Action::Yank
+                | Action::Export
+                | Action::ExportCsv
+                | Action::Qr
+                | Action::Events
+                | Action::NextHost
+                | Action::PrevHost
+                | Action::Help
+                | Action::Search
+                | Action::Command
+        )
+    }
+
+    /// Short label for the footer.
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::ToggleFocus => "focus",
+            Action::NextTab => "next tab",
+            Action::PrevTab => "prev tab",
+            Action::Down => "Down",
+            Action::Up => "Up",
+            Action::PageDown => "scroll down",
+            Action::PageUp => "scroll up",
+            Action::Refresh => "refresh",
+            Action::Pause => "pause",
+            Action::Sort => "sort",
+            Action::Renew => "renew",
+            Action::Release => "release",
+            Action::AddVlan => "add VLAN",
+            Action::DeleteVlan => "del VLAN",
+            Action::Bridge => "bridge",
+            Action::BondFailover => "bond failover",
+            Action::LinkUp => "up",
+            Action::LinkDown => "down",
+            Action::StaticIp => "static IP",
+            Action::Dns => "DNS",
+            Action::NetworkFile => "networkd",
+            Action::Netplan => "netplan",
+            Action::Profile => "profile",
+            Action::AddRoute => "add route",
+            Action::DeleteRoute => "delete route",
+            Action::AddAddress => "add IP",
+            Action::DeleteAddress => "delete IP",
+            Action::FlushAddresses => "flush IPs",
+            Action::LinkMode => "link mode",
+            Action::Rings => "rings",
+            Action::Channels => "channels",
+            Action::Coalescing => "coalescing",
+            Action::Eee => "EEE",
+            Action::Fec => "FEC",
+            Action::IrqAffinity => "IRQ affinity",
+            Action::SpreadIrqs => "spread IRQs",
+            Action::NumVfs => "VF count",
+            Action::VfSettings => "VF settings",
+            Action::Wol => "WoL",
+            Action::Mac => "MAC",
+            Action::Undo => "undo",
+            Action::Promisc => "promisc",
+            Action::Ping => "ping",
+            Action::Trace => "trace",
+            Action::SpeedTest => "speed test",
+            Action::DnsTest => "DNS test",
+            Action::Scan => "scan subnet",
+            Action::Mdns => "mDNS",
+            Action::Yank => "yank",
+            Action::Export => "export JSON",
+            Action::ExportCsv => "export CSV",
+            Action::Qr => "QR code",
+            Action::Identify => "identify",
+            Action::CableTest => "cable test",
+            Action::ToggleOffload => "toggle offload",
+            Action::Events => "events",
+            Action::NextHost => "next host",
+            Action::PrevHost => "prev host",
+            Action::Help => "help",
+            Action::Search => "filter",
+            Action::Command => "command",
+        }
+    }
+
+    /// What the action does, for the help overlay.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit (Esc leaves the detail tab first)",
+            Action::ToggleFocus => "Move focus between the interface list and the detail tab",
+            Action::NextTab => "Next detail tab",
+            Action::PrevTab => "Previous detail tab",
+            Action::Down => "Next interface, or next row in the focused detail tab",
+            Action::Up => "Previous interface, or previous row in the focused detail tab",
+            Action::PageDown => {
+                "Scroll Overview, Stats or Hardware down half a page (Driver: a page of counters)"
+            }
+            Action::PageUp => {
+                "Scroll Overview, Stats or Hardware up half a page (Driver: a page of counters)"
+            }
+            Action::Refresh => "Rescan all interfaces now",
+            Action::Pause => "Pause/resume automatic refreshing",
+            Action::Sort => "Sort interfaces by name, state, speed or IP",
+            Action::Renew => "Renew the DHCP lease through the managing backend",
+            Action::Release => "Release the DHCP lease (asks first)",
+            Action::AddVlan => "Add a VLAN subinterface",
+            Action::DeleteVlan => "Delete the selected VLAN interface (asks first)",
+            Action::Bridge => "Add the NIC to a bridge, or remove it from its bridge",
+            Action::BondFailover => "Make another bond slave active (asks first)",
+            Action::LinkUp => "Set the link up",
+            Action::LinkDown => "Set the link down",
+            Action::StaticIp => "Static IPv4 address, gateway and DNS",
+            Action::Dns => {
+                "DNS servers and search domains, at runtime or persisted (shows the change first)"
+            }
+            Action::NetworkFile => {
+                "Write a systemd-networkd .network file (DHCP or static, DNS, VLANs), shown first"
+            }
+            Action::Netplan => {
+                "Run netplan try (reverts unless kept) or netplan apply on netplan systems"
+            }
+            Action::Profile => {
+                "Apply a configured profile (address or DHCP, gateway, DNS, MTU), shown first; U rolls it back"
+            }
+            Action::AddRoute => {
+                "Add a route: destination, gateway, metric (Routes tab, asks first)"
+            }
+            Action::DeleteRoute => {
+                "Delete the route under the cursor (focused Routes tab, asks first)"
+            }
+            Action::AddAddress => "Add a secondary IPv4 or IPv6 address",
+            Action::DeleteAddress => {
+                "Delete the address under the cursor (focused Overview, asks first)"
+            }
+            Action::FlushAddresses => {
+                "Flush all addresses, then optionally restart DHCP (asks first)"
+            }
+            Action::LinkMode => "Autonegotiation or forced speed/duplex",
+            Action::Rings => "RX/TX ring sizes",
+            Action::Channels => "Channel (queue) counts for RSS",
+            Action::Coalescing => "Interrupt coalescing",
+            Action::Eee => "Turn Energy Efficient Ethernet off or on (asks first)",
+            Action::Fec => "Forward error correction: auto, off, RS or BaseR (asks first)",
+            Action::IrqAffinity => "CPUs of the selected queue's interrupts (Queues tab)",
+            Action::SpreadIrqs => {
+                "Spread queue interrupts across all cores (Queues tab, asks first)"
+            }
+            Action::NumVfs => "Number of SR-IOV virtual functions (SR-IOV tab)",
+            Action::VfSettings => {
+                "MAC, VLAN, spoof check, trust and link state of the selected VF (SR-IOV tab)"
+            }
+            Action::Wol => "Wake-on-LAN",
+            Action::Mac => "Set, randomize or restore the MAC address",
+            Action::Undo => {
+                "Revert the last MTU, MAC, static IPv4, address deletion, route or profile change"
+            }
+            Action::Promisc => "Toggle promiscuous mode (press twice)",
+            Action::Ping => "Ping out of the selected interface",
+            Action::Trace => "Trace the route out of the selected interface",
+            Action::SpeedTest => "Speed test with iperf3 or an HTTP download",
+            Action::DnsTest => {
+                "Resolve a hostname with each DNS server, or benchmark them against public resolvers"
+            }
+            Action::Scan => "Scan the IPv4 subnet for hosts",
+            Action::Mdns => "Browse mDNS/DNS-SD services",
+            Action::Yank => "Copy to the clipboard, then i IP, m MAC, g gateway, d DNS, s snapshot",
+            Action::Export => {
+                "Export every listed interface (with stats and routes) to a JSON file"
+            }
+            Action::ExportCsv => {
+                "Export the selected interface's bandwidth and latency history to CSV"
+            }
+            Action::Qr => "QR code of http://<IPv4 address> (i: the bare address, u: the URL)",
+            Action::Identify => "Blink the port LED for 5s",
+            Action::CableTest => "Cable diagnostics",
+            Action::ToggleOffload => "Toggle the selected offload (focused Offloads tab)",
+            Action::Events => "Log of carrier, address, gateway and DHCP changes",
+            Action::NextHost => "Show the next of this machine and the hosts in the config",
+            Action::PrevHost => "Show the previous of this machine and the hosts in the config",
+            Action::Help => "This help",
+            Action::Search => {
+                "Filter the interface list by name (substring, or a glob like enp*), or the \
+                 focused Driver tab's counters"
+            }
+            Action::Command => "Command line (:renew eth0, :mtu 9000, :filter enp*, :quit...)",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q", "esc", "ctrl-c"],
+            Action::ToggleFocus => &["tab"],
+            Action::NextTab => &["]"],
+            Action::PrevTab => &["[", "backtab"],
+            Action::Down => &["j", "down"],
+            Action::Up => &["k", "up"],
+            Action::PageDown => &["pgdn", "ctrl-d"],
+            Action::PageUp => &["pgup", "ctrl-u"],
+            Action::Refresh => &["r"],
+            // Shared with toggle_offload, which only applies on the focused Offloads tab.
+            Action::Pause => &["space"],
+            Action::Sort => &["o"],
+            Action::Renew => &["n"],
+            Action::Release => &["N"],
+            Action::AddVlan => &["v"],
+            Action::DeleteVlan => &["V"],
+            Action::Bridge => &["b"],
+            Action::BondFailover => &["F"],
+            Action::LinkUp => &["u"],
+            Action::LinkDown => &["d"],
+            Action::StaticIp => &["s"],
+            Action::Dns => &["R"],
+            Action::NetworkFile => &["W"],
+            Action::Netplan => &["Y"],
+            Action::Profile => &["O"],
+            // Shared with add_address and delete_address, which they take over on the Routes tab.
+            Action::AddRoute => &["+"],
+            Action::DeleteRoute => &["-", "delete"],
+            Action::AddAddress => &["+"],
+            Action::DeleteAddress => &["-", "delete"],
+            Action::FlushAddresses => &["Z"],
+            Action::LinkMode => &["l"],
+            Action::Rings => &["g"],
+            Action::Channels => &["h"],
+            Action::Coalescing => &["c"],
+            Action::Eee => &["L"],
+            Action::Fec => &["f"],
+            Action::IrqAffinity => &["a"],
+            Action::SpreadIrqs => &["I"],
+            Action::NumVfs => &["X"],
+            Action::VfSettings => &["x"],
+            Action::Wol => &["w"],
+            Action::Mac => &["m"],
+            Action::Undo => &["U"],
+            Action::Promisc => &["p"],
+            Action::Ping => &["P"],
+            Action::Trace => &["T"],
+            Action::SpeedTest => &["S"],
+            Action::DnsTest => &["D"],
+            Action::Scan => &["A"],
+            Action::Mdns => &["B"],
+            Action::Yank => &["y"],
+            Action::Export => &["e"],
+            Action::ExportCsv => &["C"],
+            Action::Qr => &["Q"],
+            Action::Identify => &["i"],
+            Action::CableTest => &["t"],
+            Action::ToggleOffload => &["space"],
+            Action::Events => &["E"],
+            Action::NextHost => &[">"],
+            Action::PrevHost => &["<"],
+            Action::Help => &["?"],
+            Action::Search => &["/"],
+            Action::Command => &[":"],
+        }
+    }
+}
+
+/// A key with its modifiers, written like `n`, `ctrl-r`, `alt-down` or `f5` in the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn matches(&self, ev: &KeyEvent) -> bool {
+        match (self.code, ev.code) {
+            // Terminals disagree on whether `N` comes with SHIFT, and on `ctrl-c` vs `ctrl-C`.
+            (KeyCode::Char(a), KeyCode::Char(b)) => {
+                let mods = ev.modifiers - KeyModifiers::SHIFT;
+                let same = if mods.is_empty() {
+                    a == b
+                } else {
+                    a.eq_ignore_ascii_case(&b)
+                };
+                same && mods == self.modifiers
+            }
+            // Shift-Tab arrives as BackTab, with or without SHIFT depending on the terminal.
+            (KeyCode::BackTab, KeyCode::BackTab) => true,
+            (a, b) => a == b && ev.modifiers == self.modifiers,
+        }
+    }
+}
+
+impl std::str::FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut rest = s;
+        let mut modifiers = KeyModifiers::NONE;
+        // `-` on its own (or `ctrl--`) is the minus key, not a separator.
+        while let Some((m, tail)) = rest.split_once('-').filter(|(_, t)| !t.is_empty()) {
+            modifiers |= match m.to_ascii_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "a" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => break,
+            };
+            rest = tail;
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                f => match f.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key `{s}`")),
+                },
+            },
+        };
+        let code = match code {
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            code => code,
+        };
+        // Shifted letters are written as the capital letter.
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers -= KeyModifiers::SHIFT;
+        }
+        Ok(Key { code, modifiers })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::BackTab => f.write_str("Shift-Tab"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Key to action bindings: the defaults, with any action listed in the `[keys]` table of the
+/// config file rebound to the keys given there.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "HashMap<String, KeyList>")]
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .flat_map(|&a| {
+                a.default_keys()
+                    .iter()
+                    .map(move |k| (k.parse().expect("valid default key"), a))
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// The action bound to `ev` among those `active` in the current context, so one key can
+    /// serve different actions in different places.
+    pub fn action(&self, ev: &KeyEvent, active: impl Fn(Action) -> bool) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(k, a)| k.matches(ev) && active(*a))
+            .map(|(_, a)| *a)
+    }
+
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = Key> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, a)| *a == action)
+            .map(|(k, _)| *k)
+    }
+}
+
+/// `renew = "ctrl-r"` or `down = ["j", "ctrl-n", "down"]`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl TryFrom<HashMap<String, KeyList>> for Keymap {
+    type Error = String;
+
+    fn try_from(table: HashMap<String, KeyList>) -> Result<Self, String> {
+        let mut rebound = Vec::new();
+        for (name, keys) in table {
+            let action = Action::ALL
+                .into_iter()
+                .find(|a| a.name() == name)
+                .ok_or_else(|| format!("unknown action `{name}` in [keys]"))?;
+            let keys = match keys {
+                KeyList::One(k) => vec![k],
+                KeyList::Many(ks) => ks,
+            };
+            for k in keys {
+                rebound.push((k.parse::<Key>()?, action));
+            }
+        }
+        // A rebound action loses its default keys, and a key taken by a rebound action is
+        // removed from whatever had it by default.
+        let mut keymap = Keymap::default();
+        keymap
+            .bindings
+            .retain(|(key, action)| !rebound.iter().any(|(k, a)| a == action || k == key));
+        keymap.bindings.splice(0..0, rebound);
+        Ok(keymap)
+    }
+}
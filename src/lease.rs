@@ -0,0 +1,237 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A DHCPv4 lease as recorded by whichever client manages the interface. Durations serialize
+/// as seconds and `acquired` as Unix seconds, which is what scripts reading an export want.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DhcpLease {
+    /// `networkd`, `dhcpcd` or `dhclient`.
+    pub source: String,
+    pub address: Option<String>,
+    pub server: Option<String>,
+    #[serde(serialize_with = "as_secs", deserialize_with = "from_secs")]
+    pub lease_time: Option<Duration>,
+    /// Renewal (T1) and rebinding (T2) timers, relative to `acquired`.
+    #[serde(serialize_with = "as_secs", deserialize_with = "from_secs")]
+    pub t1: Option<Duration>,
+    #[serde(serialize_with = "as_secs", deserialize_with = "from_secs")]
+    pub t2: Option<Duration>,
+    /// When the lease was obtained; taken from the lease file's mtime.
+    #[serde(serialize_with = "as_unix_secs", deserialize_with = "from_unix_secs")]
+    pub acquired: Option<SystemTime>,
+}
+
+fn as_secs<S: Serializer>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+    d.map(|d| d.as_secs()).serialize(s)
+}
+
+fn as_unix_secs<S: Serializer>(t: &Option<SystemTime>, s: S) -> Result<S::Ok, S::Error> {
+    t.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .serialize(s)
+}
+
+/// Reads `as_secs` back, for another host's `ethtui list --json`.
+fn from_secs<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Duration>, D::Error> {
+    Ok(Option::<u64>::deserialize(d)?.map(Duration::from_secs))
+}
+
+fn from_unix_secs<'de, D: Deserializer<'de>>(d: D) -> Result<Option<SystemTime>, D::Error> {
+    Ok(Option::<u64>::deserialize(d)?.map(|s| UNIX_EPOCH + Duration::from_secs(s)))
+}
+
+impl DhcpLease {
+    /// T1, or half the lease time when the server didn't send one (RFC 2131 default).
+    pub fn renew_after(&self) -> Option<Duration> {
+        self.t1.or_else(|| self.lease_time.map(|l| l / 2))
+    }
+
+    /// T2, or 7/8 of the lease time when the server didn't send one (RFC 2131 default).
+    pub fn rebind_after(&self) -> Option<Duration> {
+        self.t2.or_else(|| self.lease_time.map(|l| l * 7 / 8))
+    }
+
+    fn elapsed(&self, now: SystemTime) -> Option<Duration> {
+        now.duration_since(self.acquired?).ok()
+    }
+
+    /// Time left until the lease expires (zero once expired).
+    pub fn expires_in(&self, now: SystemTime) -> Option<Duration> {
+        Some(self.lease_time?.saturating_sub(self.elapsed(now)?))
+    }
+
+    /// Time left until the client should renew (zero once T1 has passed).
+    pub fn renews_in(&self, now: SystemTime) -> Option<Duration> {
+        Some(self.renew_after()?.saturating_sub(self.elapsed(now)?))
+    }
+
+    pub fn health(&self, now: SystemTime) -> LeaseHealth {
+        let Some(elapsed) = self.elapsed(now) else {
+            return LeaseHealth::Unknown;
+        };
+        match (self.renew_after(), self.rebind_after()) {
+            (_, Some(t2)) if elapsed >= t2 => LeaseHealth::Critical,
+            (Some(t1), _) if elapsed >= t1 => LeaseHealth::Warning,
+            (Some(_), _) => LeaseHealth::Ok,
+            _ => LeaseHealth::Unknown,
+        }
+    }
+}
+
+/// A lease that hasn't renewed by T1 is a warning; past T2 it's about to be lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaseHealth {
+    Ok,
+    Warning,
+    Critical,
+    Unknown,
+}
+
+fn secs(v: &str) -> Option<Duration> {
+    v.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// systemd-networkd: `/run/systemd/netif/leases/<ifindex>`, a `KEY=value` file.
+fn read_networkd_lease(ifindex: u32) -> Option<DhcpLease> {
+    let path = Path::new("/run/systemd/netif/leases").join(ifindex.to_string());
+    let content = fs::read_to_string(&path).ok()?;
+
+    let mut lease = DhcpLease {
+        source: "networkd".into(),
+        address: None,
+        server: None,
+        lease_time: None,
+        t1: None,
+        t2: None,
+        acquired: mtime(&path),
+    };
+    for line in content.lines() {
+        let Some((k, v)) = line.split_once('=') else {
+            continue;
+        };
+        match k {
+            "ADDRESS" => lease.address = Some(v.to_string()),
+            "SERVER_ADDRESS" => lease.server = Some(v.to_string()),
+            "LIFETIME" => lease.lease_time = secs(v),
+            "T1" => lease.t1 = secs(v),
+            "T2" => lease.t2 = secs(v),
+            _ => {}
+        }
+    }
+    Some(lease)
+}
+
+/// dhcpcd stores the raw DHCP ACK; pull the options we need out of the BOOTP packet.
+fn read_dhcpcd_lease(iface: &str) -> Option<DhcpLease> {
+    let path = ["/var/lib/dhcpcd", "/var/db/dhcpcd"]
+        .iter()
+        .map(|d| Path::new(d).join(format!("{iface}.lease")))
+        .find(|p| p.exists())?;
+    let pkt = fs::read(&path).ok()?;
+    parse_bootp_lease(&pkt).map(|mut lease| {
+        lease.acquired = mtime(&path);
+        lease
+    })
+}
+
+fn parse_bootp_lease(pkt: &[u8]) -> Option<DhcpLease> {
+    const MAGIC: [u8; 4] = [99, 130, 83, 99];
+    if pkt.len() < 240 || pkt[236..240] != MAGIC {
+        return None;
+    }
+
+    let ipv4 = |b: &[u8]| format!("{}.{}.{}.{}", b[0], b[1], b[2], b[3]);
+    let mut lease = DhcpLease {
+        source: "dhcpcd".into(),
+        address: Some(ipv4(&pkt[16..20])),
+        server: None,
+        lease_time: None,
+        t1: None,
+        t2: None,
+        acquired: None,
+    };
+
+    let mut i = 240;
+    while i < pkt.len() {
+        let code = pkt[i];
+        match code {
+            0 => {
+                i += 1;
+                continue;
+            }
+            255 => break,
+            _ => {}
+        }
+        let len = *pkt.get(i + 1)? as usize;
+        let data = pkt.get(i + 2..i + 2 + len)?;
+        let be32 = || {
+            (len == 4)
+                .then(|| Duration::from_secs(u32::from_be_bytes(data.try_into().unwrap()) as u64))
+        };
+        match code {
+            51 => lease.lease_time = be32(),
+            54 if len == 4 => lease.server = Some(ipv4(data)),
+            58 => lease.t1 = be32(),
+            59 => lease.t2 = be32(),
+            _ => {}
+        }
+        i += 2 + len;
+    }
+    Some(lease)
+}
+
+/// ISC dhclient: the last `lease { ... }` block for the interface in its leases file.
+fn read_dhclient_lease(iface: &str) -> Option<DhcpLease> {
+    let candidates = [
+        format!("/var/lib/dhcp/dhclient.{iface}.leases"),
+        format!("/var/lib/dhclient/dhclient-{iface}.leases"),
+        "/var/lib/dhcp/dhclient.leases".to_string(),
+        "/var/lib/dhclient/dhclient.leases".to_string(),
+    ];
+    let path = candidates.iter().map(Path::new).find(|p| p.exists())?;
+    let content = fs::read_to_string(path).ok()?;
+
+    let block = content
+        .split("lease {")
+        .skip(1)
+        .filter(|b| b.contains(&format!("interface \"{iface}\";")))
+        .last()?;
+
+    let mut lease = DhcpLease {
+        source: "dhclient".into(),
+        address: None,
+        server: None,
+        lease_time: None,
+        t1: None,
+        t2: None,
+        acquired: mtime(path),
+    };
+    for line in block.lines() {
+        let line = line.trim().trim_end_matches(';');
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some("fixed-address"), Some(a), _) => lease.address = Some(a.to_string()),
+            (Some("option"), Some("dhcp-server-identifier"), Some(v)) => {
+                lease.server = Some(v.to_string())
+            }
+            (Some("option"), Some("dhcp-lease-time"), Some(v)) => lease.lease_time = secs(v),
+            (Some("option"), Some("dhcp-renewal-time"), Some(v)) => lease.t1 = secs(v),
+            (Some("option"), Some("dhcp-rebinding-time"), Some(v)) => lease.t2 = secs(v),
+            _ => {}
+        }
+    }
+    Some(lease)
+}
+
+/// Looks for a DHCPv4 lease from networkd first, then dhcpcd, then dhclient.
+pub fn read_lease(iface: &str, ifindex: u32) -> Option<DhcpLease> {
+    read_networkd_lease(ifindex)
+        .or_else(|| read_dhcpcd_lease(iface))
+        .or_else(|| read_dhclient_lease(iface))
+}
@@ -1,6 +1,41 @@
+pub mod alerts;
 pub mod app;
+pub mod backend;
+pub mod bpf;
+pub mod cli;
+pub mod clipboard;
+pub mod command;
+pub mod config;
+pub mod daemon;
+pub mod diag;
+pub mod dns;
+pub mod ethtool;
 pub mod event;
+pub mod events;
+pub mod export;
+pub mod firewall;
 pub mod handler;
+pub mod history;
+pub mod hooks;
+pub mod ifupdown;
+pub mod keymap;
+pub mod lease;
+pub mod lldp;
+pub mod logfile;
+pub mod mqtt;
 pub mod net;
+pub mod netplan;
+pub mod networkd;
+pub mod notify;
+pub mod pci;
+pub mod plugins;
+pub mod policy;
+pub mod profiles;
+pub mod qr;
+pub mod queues;
+pub mod remote;
+pub mod sockets;
+pub mod sriov;
 pub mod tui;
 pub mod ui;
+pub mod wireguard;
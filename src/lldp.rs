@@ -0,0 +1,75 @@
+use serde_json::Value;
+use tokio::process::Command;
+
+/// What the switch (or other LLDP speaker) on the far end of the cable says about itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LldpNeighbor {
+    pub system_name: Option<String>,
+    pub chassis_id: Option<String>,
+    pub port_id: Option<String>,
+    pub port_descr: Option<String>,
+    /// VLAN IDs advertised on the port; the port's native VLAN (PVID) comes first.
+    pub vlans: Vec<String>,
+    pub mgmt_addrs: Vec<String>,
+}
+
+/// Asks lldpd what it has heard on `iface`. None when lldpd isn't installed, isn't running or
+/// won't talk to us (its socket is usually limited to root and the `_lldpd` group).
+pub async fn query(iface: &str) -> Option<Vec<LldpNeighbor>> {
+    // json0 keeps every field an array, whether there's one value or several.
+    let out = Command::new("lldpcli")
+        .args([
+            "-f",
+            "json0",
+            "show",
+            "neighbors",
+            "ports",
+            iface,
+            "details",
+        ])
+        .output()
+        .await
+        .ok()
+        .filter(|o| o.status.success())?;
+    let json: Value = serde_json::from_slice(&out.stdout).ok()?;
+    Some(parse_neighbors(&json, iface))
+}
+
+fn parse_neighbors(json: &Value, iface: &str) -> Vec<LldpNeighbor> {
+    items(json, "lldp")
+        .flat_map(|l| items(l, "interface"))
+        .filter(|i| i["name"].as_str() == Some(iface))
+        .map(|i| {
+            let chassis = items(i, "chassis").next().unwrap_or(&Value::Null);
+            let port = items(i, "port").next().unwrap_or(&Value::Null);
+            let mut vlans: Vec<(bool, String)> = items(i, "vlan")
+                .filter_map(|v| {
+                    let id = v["vlan-id"].as_str()?.to_string();
+                    Some((v["pvid"].as_bool() != Some(true), id))
+                })
+                .collect();
+            vlans.sort_by_key(|(not_pvid, _)| *not_pvid);
+            LldpNeighbor {
+                system_name: first_value(chassis, "name"),
+                chassis_id: first_value(chassis, "id"),
+                port_id: first_value(port, "id"),
+                port_descr: first_value(port, "descr"),
+                vlans: vlans.into_iter().map(|(_, id)| id).collect(),
+                mgmt_addrs: items(chassis, "mgmt-ip")
+                    .filter_map(|m| m["value"].as_str().map(str::to_string))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn items<'a>(v: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    v[key].as_array().into_iter().flatten()
+}
+
+/// `"key": [{"value": "..."}]`, the json0 shape of a single-valued field.
+fn first_value(v: &Value, key: &str) -> Option<String> {
+    items(v, key)
+        .find_map(|x| x["value"].as_str())
+        .map(str::to_string)
+}
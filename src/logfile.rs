@@ -0,0 +1,43 @@
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Set once at startup with `--log-file` or `log_file`; nothing is logged otherwise.
+static LOG: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Appends to `path`, so a log kept across runs reads as one timeline.
+pub fn init(path: &Path) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("opening log file {}", path.display()))?;
+    let _ = LOG.set(Mutex::new(file));
+    write(
+        "start",
+        json!({ "version": env!("CARGO_PKG_VERSION"), "pid": std::process::id() }),
+    );
+    Ok(())
+}
+
+/// One JSON object per line: `ts` (Unix seconds), `kind`, then `fields`.
+pub fn write(kind: &str, fields: Value) {
+    let Some(log) = LOG.get() else {
+        return;
+    };
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64());
+    let mut line = json!({ "ts": (ts * 1000.0).round() / 1000.0, "kind": kind });
+    if let (Some(line), Value::Object(fields)) = (line.as_object_mut(), fields) {
+        line.extend(fields);
+    }
+    // A full disk or a vanished file must never take the UI down.
+    if let Ok(mut file) = log.lock() {
+        let _ = writeln!(file, "{line}");
+    }
+}
@@ -1,33 +1,90 @@
 use anyhow::Result;
+use clap::Parser;
 use ethtui::{
     app::App,
+    cli::Cli,
+    config::{Config, MIN_REFRESH_INTERVAL_MS},
     event::{Event, EventHandler},
-    handler::handle_key_events,
+    export,
+    handler::{handle_key_events, handle_mouse_events, run_plugin_commands},
+    logfile,
+    net::spawn_rtnetlink_listener,
     tui::Tui,
+    ui::Theme,
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
 use std::io;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if let Some(sub) = cli.command {
+        let config = Config::load()?;
+        return sub.run(&config).await;
+    }
+
+    // Read the config before touching the terminal so a bad file is reported plainly.
+    let mut config = Config::load()?;
+    config.include_wifi |= cli.all;
+    if let Some(ms) = cli.interval {
+        config.refresh_interval_ms = ms.max(MIN_REFRESH_INTERVAL_MS);
+    }
+    config.remote = cli.remote;
+    if cli.ascii {
+        config.ascii = Some(true);
+    }
+    // https://no-color.org: any non-empty value, and it beats the config file.
+    if cli.no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        config.theme = Theme::no_color();
+    }
+    if let Some(path) = cli.export_json {
+        let json = export::snapshot_json(config.include_wifi).await?;
+        let dest = export::write_to(&path, &json)?;
+        if path != "-" {
+            eprintln!("wrote {dest}");
+        }
+        return Ok(());
+    }
+    if let Some(path) = cli.log_file.or_else(|| config.log_file.clone()) {
+        logfile::init(&path)?;
+    }
+
+    // Also before the terminal is taken over, so an unreachable --remote host is reported plainly.
+    let mut app = App::new(config).await?;
+
     let backend = CrosstermBackend::new(io::stdout());
     let terminal = Terminal::new(backend)?;
 
-    let events = EventHandler::new(250);
+    let events = EventHandler::new(app.config.refresh_interval_ms);
     let mut tui = Tui::new(terminal, events);
     tui.init()?;
 
-    let mut app = App::new().await?;
+    // Without netlink (e.g. restricted sandboxes) we still fall back to periodic rescans. With
+    // --remote this machine isn't shown at all.
+    if app.local
+        && let Err(e) = spawn_rtnetlink_listener(tui.events.sender.clone())
+    {
+        app.last_action = Some(format!("Live updates unavailable: {e}"));
+    }
 
     while app.running {
         tui.draw(&mut app)?;
 
         match tui.events.next().await? {
-            Event::Tick => app.tick().await?,
+            Event::Tick => {
+                app.tick().await?;
+                run_plugin_commands(&mut app).await;
+            }
             Event::Key(key_event) => {
                 handle_key_events(key_event, &mut app).await?;
             }
+            Event::Mouse(mouse_event) => handle_mouse_events(mouse_event, &mut app),
             Event::Resize(_, _) => {}
+            // Paused, or another host is shown: nothing changes on screen; resuming or switching
+            // back rescans everything anyway.
+            Event::Net(_) | Event::NetResync if app.paused || app.host.is_some() => {}
+            Event::Net(ev) => app.apply_net_event(ev),
+            Event::NetResync => app.refresh(),
         }
     }
 
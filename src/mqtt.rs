@@ -0,0 +1,533 @@
+use crate::config;
+use crate::logfile;
+use crate::net::{EthernetDevice, hostname};
+use anyhow::Result;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+/// PINGREQ is sent this often; the broker drops us after 1.5x without one.
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+/// Wait before reconnecting to a broker that went away or refused us.
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// The standard port for MQTT without TLS.
+const DEFAULT_PORT: u16 = 1883;
+
+struct Message {
+    topic: String,
+    payload: String,
+    retain: bool,
+}
+
+/// Publishes each interface's state (carrier, operstate, speed, addresses, gateway) to
+/// `<prefix>/<iface>/state` as retained JSON whenever it changes, plus Home Assistant discovery
+/// configs so the interfaces show up as entities on their own. The broker connection lives in a
+/// background task that reconnects and republishes on its own; problems go to the log file.
+pub struct Publisher {
+    prefix: String,
+    host: String,
+    discovery: Option<String>,
+    tx: mpsc::UnboundedSender<Message>,
+    /// Last state payload per interface, so only changes are sent.
+    last: HashMap<String, String>,
+    /// Discovery config topics per interface, to clear when it goes away.
+    discovered: HashMap<String, Vec<String>>,
+}
+
+impl Publisher {
+    /// None when no broker is configured.
+    pub fn start(config: &config::Mqtt) -> Option<Self> {
+        let broker = config.broker.clone()?;
+        let host = hostname();
+        let prefix = config
+            .topic_prefix
+            .clone()
+            .unwrap_or_else(|| format!("ethtui/{host}"));
+        let connection = Connection {
+            broker: broker_address(&broker),
+            client_id: config
+                .client_id
+                .clone()
+                .unwrap_or_else(|| format!("ethtui-{host}")),
+            username: config.username.clone(),
+            password: config.password.clone(),
+            status_topic: format!("{prefix}/status"),
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(connection.run(rx));
+        Some(Self {
+            prefix,
+            host,
+            discovery: config.discovery.then(|| config.discovery_prefix.clone()),
+            tx,
+            last: HashMap::new(),
+            discovered: HashMap::new(),
+        })
+    }
+
+    /// Publishes whatever changed since the last call, and retracts interfaces that no longer
+    /// exist. `devices` may leave some out (the TUI's filter): only ones gone from sysfs count.
+    pub fn update(&mut self, devices: &[EthernetDevice]) {
+        for d in devices {
+            if let Some(discovery) = &self.discovery
+                && !self.discovered.contains_key(&d.name)
+            {
+                let configs = self.discovery_configs(discovery, d);
+                let topics = configs.iter().map(|(topic, _)| topic.clone()).collect();
+                for (topic, config) in configs {
+                    self.send(topic, config.to_string(), true);
+                }
+                self.discovered.insert(d.name.clone(), topics);
+            }
+            let state = json!({
+                "operstate": d.operstate,
+                "carrier": d.carrier,
+                "speed_mbps": d.speed_mbps,
+                "mac": d.mac,
+                "ipv4": d.ipv4,
+                "ipv6": d.ipv6,
+                "gateway": d.gateway_v4,
+            })
+            .to_string();
+            if self.last.get(&d.name) != Some(&state) {
+                self.send(
+                    format!("{}/{}/state", self.prefix, d.name),
+                    state.clone(),
+                    true,
+                );
+                self.last.insert(d.name.clone(), state);
+            }
+        }
+
+        let gone: Vec<String> = self
+            .last
+            .keys()
+            .filter(|name| {
+                !devices.iter().any(|d| &d.name == *name)
+                    && !Path::new("/sys/class/net").join(name).exists()
+            })
+            .cloned()
+            .collect();
+        for name in gone {
+            self.retract(&name);
+        }
+    }
+
+    /// An empty retained payload deletes the retained message, and with a discovery config,
+    /// Home Assistant's entity.
+    fn retract(&mut self, name: &str) {
+        for topic in self.discovered.remove(name).unwrap_or_default() {
+            self.send(topic, String::new(), true);
+        }
+        self.send(format!("{}/{name}/state", self.prefix), String::new(), true);
+        self.last.remove(name);
+    }
+
+    fn send(&self, topic: String, payload: String, retain: bool) {
+        let _ = self.tx.send(Message {
+            topic,
+            payload,
+            retain,
+        });
+    }
+
+    /// A connectivity binary sensor for the carrier and sensors for the IPv4 address and speed,
+    /// grouped under one device per host.
+    fn discovery_configs(&self, discovery: &str, d: &EthernetDevice) -> Vec<(String, Value)> {
+        let object = |field: &str| {
+            format!("ethtui_{}_{}_{field}", self.host, d.name)
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '_' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        };
+        let entity = |field: &str, name: String, extra: Value| {
+            let mut config = json!({
+                "name": name,
+                "unique_id": object(field),
+                "state_topic": format!("{}/{}/state", self.prefix, d.name),
+                "availability_topic": format!("{}/status", self.prefix),
+                "device": {
+                    "identifiers": [format!("ethtui_{}", self.host)],
+                    "name": self.host,
+                    "manufacturer": "ethtui",
+                },
+            });
+            if let (Some(config), Value::Object(extra)) = (config.as_object_mut(), extra) {
+                config.extend(extra);
+            }
+            config
+        };
+        vec![
+            (
+                format!("{discovery}/binary_sensor/{}/config", object("link")),
+                entity(
+                    "link",
+                    format!("{} link", d.name),
+                    json!({
+                        "device_class": "connectivity",
+                        "value_template": "{{ 'ON' if value_json.carrier else 'OFF' }}",
+                    }),
+                ),
+            ),
+            (
+                format!("{discovery}/sensor/{}/config", object("ipv4")),
+                entity(
+                    "ipv4",
+                    format!("{} IPv4", d.name),
+                    json!({
+                        "value_template": "{{ (value_json.ipv4 | first | default('none')).split('/')[0] }}",
+                        "icon": "mdi:ip-network",
+                    }),
+                ),
+            ),
+            (
+                format!("{discovery}/sensor/{}/config", object("speed")),
+                entity(
+                    "speed",
+                    format!("{} speed", d.name),
+                    json!({
+                        "device_class": "data_rate",
+                        "unit_of_measurement": "Mbit/s",
+                        "value_template": "{{ value_json.speed_mbps }}",
+                    }),
+                ),
+            ),
+        ]
+    }
+}
+
+struct Connection {
+    broker: String,
+    client_id: String,
+    username: Option<String>,
+    password: Option<String>,
+    /// `online` while connected, `offline` (the will) once we're gone.
+    status_topic: String,
+}
+
+impl Connection {
+    async fn run(self, mut rx: mpsc::UnboundedReceiver<Message>) {
+        // Everything retained, so a reconnect (or a restarted broker) gets the current state.
+        let mut retained: HashMap<String, String> = HashMap::new();
+        loop {
+            match self.session(&mut rx, &mut retained).await {
+                // The publisher is gone: the app is exiting.
+                Ok(()) => return,
+                Err(e) => {
+                    logfile::write(
+                        "mqtt",
+                        json!({ "broker": self.broker, "error": e.to_string() }),
+                    );
+                }
+            }
+            // Keep up with state while waiting; non-retained messages are dropped.
+            let wait = tokio::time::sleep(RECONNECT_DELAY);
+            tokio::pin!(wait);
+            loop {
+                tokio::select! {
+                    _ = &mut wait => break,
+                    msg = rx.recv() => match msg {
+                        Some(msg) if msg.retain => {
+                            retained.insert(msg.topic, msg.payload);
+                        }
+                        Some(_) => {}
+                        None => return,
+                    },
+                }
+            }
+        }
+    }
+
+    async fn session(
+        &self,
+        rx: &mut mpsc::UnboundedReceiver<Message>,
+        retained: &mut HashMap<String, String>,
+    ) -> Result<()> {
+        let mut stream = TcpStream::connect(&self.broker).await?;
+        stream.write_all(&self.connect_packet()).await?;
+        let mut ack = [0u8; 4];
+        stream.read_exact(&mut ack).await?;
+        if ack[0] != 0x20 || ack[3] != 0 {
+            return Err(std::io::Error::other(format!(
+                "broker refused the connection (return code {})",
+                ack[3]
+            ))
+            .into());
+        }
+        logfile::write("mqtt", json!({ "broker": self.broker, "connected": true }));
+        stream
+            .write_all(&publish_packet(&self.status_topic, "online", true))
+            .await?;
+        for (topic, payload) in retained.iter() {
+            stream
+                .write_all(&publish_packet(topic, payload, true))
+                .await?;
+        }
+        // Topics cleared while disconnected are gone from the broker now too.
+        retained.retain(|_, payload| !payload.is_empty());
+
+        let (mut reader, mut writer) = stream.into_split();
+        let mut keep_alive = tokio::time::interval(KEEP_ALIVE);
+        keep_alive.tick().await;
+        let mut buf = [0u8; 256];
+        loop {
+            tokio::select! {
+                msg = rx.recv() => {
+                    let Some(msg) = msg else {
+                        // Going away on purpose: say so instead of leaving it to the will.
+                        writer
+                            .write_all(&publish_packet(&self.status_topic, "offline", true))
+                            .await?;
+                        writer.write_all(&[0xE0, 0x00]).await?;
+                        return Ok(());
+                    };
+                    writer
+                        .write_all(&publish_packet(&msg.topic, &msg.payload, msg.retain))
+                        .await?;
+                    // The broker has forgotten a cleared topic; nothing to repeat after a reconnect.
+                    if msg.retain && msg.payload.is_empty() {
+                        retained.remove(&msg.topic);
+                    } else if msg.retain {
+                        retained.insert(msg.topic, msg.payload);
+                    }
+                }
+                _ = keep_alive.tick() => writer.write_all(&[0xC0, 0x00]).await?,
+                // Only PINGRESPs arrive since nothing is subscribed; reading just notices a
+                // closed connection.
+                n = reader.read(&mut buf) => {
+                    if n? == 0 {
+                        return Err(std::io::Error::other("broker closed the connection").into());
+                    }
+                }
+            }
+        }
+    }
+
+    /// MQTT 3.1.1 CONNECT with a clean session and `offline` as the retained will.
+    fn connect_packet(&self) -> Vec<u8> {
+        let mut flags = 0x02 | 0x04 | 0x20;
+        let mut body = Vec::new();
+        push_str(&mut body, "MQTT");
+        body.push(4);
+        let flags_at = body.len();
+        body.push(0);
+        body.extend_from_slice(&(KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+        push_str(&mut body, &self.client_id);
+        push_str(&mut body, &self.status_topic);
+        push_str(&mut body, "offline");
+        if let Some(user) = &self.username {
+            flags |= 0x80;
+            push_str(&mut body, user);
+            if let Some(password) = &self.password {
+                flags |= 0x40;
+                push_str(&mut body, password);
+            }
+        }
+        body[flags_at] = flags;
+        packet(0x10, &body)
+    }
+}
+
+/// `host:port` for connecting: `broker` as configured, with the default port added when it has
+/// none. An IPv6 address needs brackets to take a port (`[fd00::1]:1883`).
+fn broker_address(broker: &str) -> String {
+    if broker.parse::<SocketAddr>().is_ok() {
+        return broker.to_string();
+    }
+    match broker.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, DEFAULT_PORT).to_string(),
+        // `[v6]` without a port, or a host name with or without one.
+        Err(_) if broker.starts_with('[') && broker.ends_with(']') => {
+            format!("{broker}:{DEFAULT_PORT}")
+        }
+        Err(_) if broker.contains(':') => broker.to_string(),
+        Err(_) => format!("{broker}:{DEFAULT_PORT}"),
+    }
+}
+
+/// QoS 0: fire and forget, which is plenty for state that is republished on every change.
+fn publish_packet(topic: &str, payload: &str, retain: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_str(&mut body, topic);
+    body.extend_from_slice(payload.as_bytes());
+    packet(0x30 | u8::from(retain), &body)
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![header];
+    // Remaining length: 7 bits per byte, least significant first.
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+fn push_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::test_device;
+
+    fn publisher(discovery: bool) -> (Publisher, mpsc::UnboundedReceiver<Message>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let publisher = Publisher {
+            prefix: "ethtui/box".into(),
+            host: "box".into(),
+            discovery: discovery.then(|| "homeassistant".into()),
+            tx,
+            last: HashMap::new(),
+            discovered: HashMap::new(),
+        };
+        (publisher, rx)
+    }
+
+    fn drain(rx: &mut mpsc::UnboundedReceiver<Message>) -> Vec<Message> {
+        std::iter::from_fn(|| rx.try_recv().ok()).collect()
+    }
+
+    #[test]
+    fn broker_ports() {
+        for (broker, want) in [
+            ("broker.lan", "broker.lan:1883"),
+            ("broker.lan:8883", "broker.lan:8883"),
+            ("192.0.2.1", "192.0.2.1:1883"),
+            ("192.0.2.1:8883", "192.0.2.1:8883"),
+            ("fd00::1", "[fd00::1]:1883"),
+            ("[fd00::1]", "[fd00::1]:1883"),
+            ("[fd00::1]:8883", "[fd00::1]:8883"),
+        ] {
+            assert_eq!(broker_address(broker), want, "{broker}");
+        }
+    }
+
+    /// The examples in MQTT 3.1.1 section 2.2.3: one byte up to 127, two from 128.
+    #[test]
+    fn remaining_length() {
+        for (len, want) in [
+            (0, vec![0x00]),
+            (127, vec![0x7F]),
+            (128, vec![0x80, 0x01]),
+            (16_383, vec![0xFF, 0x7F]),
+            (16_384, vec![0x80, 0x80, 0x01]),
+        ] {
+            let out = packet(0x30, &vec![0; len]);
+            assert_eq!(out[0], 0x30);
+            assert_eq!(out[1..1 + want.len()], want, "{len} bytes");
+            assert_eq!(out.len(), 1 + want.len() + len);
+        }
+    }
+
+    #[test]
+    fn publish_encoding() {
+        assert_eq!(
+            publish_packet("a/b", "on", true),
+            [0x31, 7, 0, 3, b'a', b'/', b'b', b'o', b'n']
+        );
+        assert_eq!(
+            publish_packet("a/b", "", false),
+            [0x30, 5, 0, 3, b'a', b'/', b'b']
+        );
+        // A topic and payload of 128 bytes together need the second length byte.
+        let out = publish_packet("t", &"x".repeat(125), true);
+        assert_eq!(out[..5], [0x31, 0x80, 0x01, 0, 1]);
+    }
+
+    #[test]
+    fn connect_encoding() {
+        let mut connection = Connection {
+            broker: "broker.lan:1883".into(),
+            client_id: "c".into(),
+            username: None,
+            password: None,
+            status_topic: "s".into(),
+        };
+        let mut want = vec![0x10, 25, 0, 4, b'M', b'Q', b'T', b'T', 4];
+        // Clean session, a will, retained.
+        want.extend([0x26, 0, 60]);
+        want.extend([0, 1, b'c', 0, 1, b's', 0, 7]);
+        want.extend(b"offline");
+        assert_eq!(connection.connect_packet(), want);
+
+        connection.username = Some("u".into());
+        connection.password = Some("p".into());
+        want[1] += 6;
+        want[9] |= 0x80 | 0x40;
+        want.extend([0, 1, b'u', 0, 1, b'p']);
+        assert_eq!(connection.connect_packet(), want);
+    }
+
+    #[test]
+    fn publishes_only_changes() {
+        let (mut publisher, mut rx) = publisher(false);
+        publisher.update(&[test_device("ethtuitest0")]);
+        let sent = drain(&mut rx);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].topic, "ethtui/box/ethtuitest0/state");
+        assert!(sent[0].retain);
+        publisher.update(&[test_device("ethtuitest0")]);
+        assert!(drain(&mut rx).is_empty());
+    }
+
+    #[test]
+    fn retracts_vanished_interfaces() {
+        let (mut publisher, mut rx) = publisher(true);
+        publisher.update(&[test_device("ethtuitest0")]);
+        let announced: Vec<String> = drain(&mut rx)
+            .into_iter()
+            .filter(|m| m.topic.starts_with("homeassistant/"))
+            .map(|m| m.topic)
+            .collect();
+        assert_eq!(announced.len(), 3);
+
+        // No such interface in sysfs, so leaving it out means it's gone.
+        publisher.update(&[]);
+        let sent = drain(&mut rx);
+        assert!(sent.iter().all(|m| m.retain && m.payload.is_empty()));
+        let mut cleared: Vec<String> = sent.into_iter().map(|m| m.topic).collect();
+        cleared.sort();
+        let mut want = announced;
+        want.push("ethtui/box/ethtuitest0/state".into());
+        want.sort();
+        assert_eq!(cleared, want);
+
+        publisher.update(&[]);
+        assert!(drain(&mut rx).is_empty());
+    }
+
+    /// Left out but still present, e.g. hidden by the TUI's filter.
+    #[test]
+    fn keeps_interfaces_that_still_exist() {
+        let (mut publisher, mut rx) = publisher(true);
+        publisher.update(&[test_device("lo")]);
+        drain(&mut rx);
+        publisher.update(&[]);
+        assert!(drain(&mut rx).is_empty());
+    }
+}
@@ -1,20 +1,175 @@
+use crate::event::Event;
+use crate::lease::{DhcpLease, read_lease};
 use anyhow::{Context, Result};
 use if_addrs::IfAddr;
+use std::collections::HashMap;
 use std::fs;
-use std::net::Ipv4Addr;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
 use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct EthernetDevice {
     pub name: String,
+    pub ifindex: u32,
     pub operstate: String,
     pub carrier: Option<bool>,
+    /// How often the carrier came and went since the interface was created.
+    pub carrier_counts: Option<CarrierCounts>,
     pub mac: Option<String>,
     pub speed_mbps: Option<u32>,
+    /// `full` or `half` while there's a link.
+    pub duplex: Option<String>,
     pub ipv4: Vec<String>,
     pub ipv6: Vec<String>,
     pub gateway_v4: Option<String>,
     pub dns: Vec<String>,
+    /// Search domains; `~`-prefixed entries are routing-only (resolved).
+    pub dns_domains: Vec<String>,
+    pub stats: IfaceStats,
+    /// Raw `IFF_*` flags from `/sys/class/net/<iface>/flags`.
+    pub flags: u32,
+    pub lease: Option<DhcpLease>,
+    /// Set for 802.1Q subinterfaces of a listed NIC.
+    pub vlan: Option<Vlan>,
+    /// Set when the interface is a port of a Linux bridge.
+    pub bridge: Option<BridgePort>,
+    /// Set when the interface is a slave of a bonding interface.
+    pub bond: Option<Bond>,
+    /// A WireGuard tunnel rather than a NIC.
+    pub wireguard: bool,
+    /// Set for wireless NICs, which are only listed with `--all`.
+    pub wifi: Option<WifiLink>,
+}
+
+/// `carrier_changes`, `carrier_up_count` and `carrier_down_count` from sysfs; the last two
+/// only exist since Linux 4.16.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CarrierCounts {
+    pub changes: u64,
+    pub up: Option<u64>,
+    pub down: Option<u64>,
+}
+
+fn read_carrier_counts(base: &Path) -> Option<CarrierCounts> {
+    Some(CarrierCounts {
+        changes: read_u64(base.join("carrier_changes"))?,
+        up: read_u64(base.join("carrier_up_count")),
+        down: read_u64(base.join("carrier_down_count")),
+    })
+}
+
+/// The association of a wireless NIC; everything but the signal is None when not connected.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WifiLink {
+    pub ssid: Option<String>,
+    pub freq_mhz: Option<u32>,
+    pub signal_dbm: Option<i32>,
+    pub tx_bitrate: Option<String>,
+}
+
+/// A bonding master's state as reported in `/proc/net/bonding/<bond>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Bond {
+    pub name: String,
+    /// e.g. `fault-tolerance (active-backup)`.
+    pub mode: String,
+    pub active_slave: Option<String>,
+    pub mii_status: String,
+    pub slaves: Vec<BondSlave>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BondSlave {
+    pub name: String,
+    pub mii_status: String,
+    pub speed: Option<String>,
+    pub link_failures: u32,
+}
+
+/// The bridge an interface is enslaved to, from its `brport` and `master` links.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BridgePort {
+    pub bridge: String,
+    /// STP port state: `forwarding`, `blocking`, `learning`...
+    pub state: String,
+    /// The bridge's other ports.
+    pub others: Vec<String>,
+}
+
+/// An 802.1Q VLAN interface's parent NIC and VLAN ID.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Vlan {
+    pub parent: String,
+    pub id: u16,
+}
+
+impl EthernetDevice {
+    /// Domains appended to unqualified hostnames.
+    pub fn search_domains(&self) -> impl Iterator<Item = &str> {
+        self.dns_domains
+            .iter()
+            .map(String::as_str)
+            .filter(|d| !d.starts_with('~'))
+    }
+
+    /// resolved routing-only domains (`~example.com`): queries for them go to this link, but
+    /// they're never used as a search suffix.
+    pub fn routing_domains(&self) -> impl Iterator<Item = &str> {
+        self.dns_domains.iter().filter_map(|d| d.strip_prefix('~'))
+    }
+
+    pub fn promisc(&self) -> bool {
+        self.flags & libc::IFF_PROMISC as u32 != 0
+    }
+
+    pub fn allmulti(&self) -> bool {
+        self.flags & libc::IFF_ALLMULTI as u32 != 0
+    }
+
+    pub fn multicast(&self) -> bool {
+        self.flags & libc::IFF_MULTICAST as u32 != 0
+    }
+}
+
+/// Kernel interface counters from `/sys/class/net/<iface>/statistics`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct IfaceStats {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+    pub multicast: u64,
+    pub collisions: u64,
+    pub rx_crc_errors: u64,
+    pub rx_missed_errors: u64,
+}
+
+pub fn read_iface_stats(iface: &str) -> IfaceStats {
+    let base = Path::new("/sys/class/net").join(iface).join("statistics");
+    let get = |f: &str| read_u64(base.join(f)).unwrap_or(0);
+    IfaceStats {
+        rx_bytes: get("rx_bytes"),
+        tx_bytes: get("tx_bytes"),
+        rx_packets: get("rx_packets"),
+        tx_packets: get("tx_packets"),
+        rx_errors: get("rx_errors"),
+        tx_errors: get("tx_errors"),
+        rx_dropped: get("rx_dropped"),
+        tx_dropped: get("tx_dropped"),
+        multicast: get("multicast"),
+        collisions: get("collisions"),
+        rx_crc_errors: get("rx_crc_errors"),
+        rx_missed_errors: get("rx_missed_errors"),
+    }
 }
 
 fn is_physical_iface(name: &str) -> bool {
@@ -45,6 +200,10 @@ fn read_u32(path: impl AsRef<Path>) -> Option<u32> {
     read_to_string(path).and_then(|s| s.parse::<u32>().ok())
 }
 
+fn read_u64(path: impl AsRef<Path>) -> Option<u64> {
+    read_to_string(path).and_then(|s| s.parse::<u64>().ok())
+}
+
 fn list_dns_servers() -> Vec<String> {
     let resolv = fs::read_to_string("/etc/resolv.conf").unwrap_or_default();
     resolv
@@ -60,6 +219,65 @@ fn list_dns_servers() -> Vec<String> {
         .collect()
 }
 
+/// `search` (or the older single `domain`) list from resolv.conf; the last one wins like libc.
+fn list_search_domains() -> Vec<String> {
+    let resolv = fs::read_to_string("/etc/resolv.conf").unwrap_or_default();
+    let mut domains = Vec::new();
+    for line in resolv.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("search") | Some("domain") => domains = words.map(|s| s.to_string()).collect(),
+            _ => {}
+        }
+    }
+    domains
+}
+
+/// True when resolv.conf only points at systemd-resolved's local stub, so it says nothing
+/// about the servers actually in use.
+fn uses_resolved_stub(servers: &[String]) -> bool {
+    !servers.is_empty()
+        && servers
+            .iter()
+            .all(|s| s == "127.0.0.53" || s == "127.0.0.54")
+}
+
+/// Runs `resolvectl <verb> <iface>` and returns the words after `Link N (iface):`.
+async fn resolvectl_link(verb: &str, iface: &str) -> Option<Vec<String>> {
+    let out = tokio::process::Command::new("resolvectl")
+        .args([verb, iface])
+        .output()
+        .await
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let (_, values) = stdout.split_once("):")?;
+    Some(values.split_whitespace().map(|s| s.to_string()).collect())
+}
+
+/// DNS servers and search domains from the global resolv.conf, or nothing when it only points
+/// at systemd-resolved's stub; those come from [`resolved_link_dns`] instead.
+fn link_dns() -> (Vec<String>, Vec<String>) {
+    let global = list_dns_servers();
+    if uses_resolved_stub(&global) {
+        return (Vec::new(), Vec::new());
+    }
+    (global, list_search_domains())
+}
+
+/// Per-link DNS servers and search domains from systemd-resolved, or `None` when resolv.conf
+/// isn't its stub and already says it all.
+pub async fn resolved_link_dns(iface: &str) -> Option<(Vec<String>, Vec<String>)> {
+    if !uses_resolved_stub(&list_dns_servers()) {
+        return None;
+    }
+    let servers = resolvectl_link("dns", iface).await.unwrap_or_default();
+    let domains = resolvectl_link("domain", iface).await.unwrap_or_default();
+    Some((servers, domains))
+}
+
 fn parse_default_gateway_v4_for_iface(iface: &str) -> Option<Ipv4Addr> {
     // /proc/net/route is stable, and avoids shelling out to `ip route`.
     let content = fs::read_to_string("/proc/net/route").ok()?;
@@ -132,48 +350,1158 @@ fn v6_netmask_to_prefix(mask: std::net::Ipv6Addr) -> u8 {
         .sum::<u16>() as u8
 }
 
-pub fn list_ethernet_devices() -> Result<Vec<EthernetDevice>> {
+fn is_ethernet_iface(name: &str) -> bool {
+    name != "lo" && is_physical_iface(name) && !is_wifi_iface(name)
+}
+
+/// `DEVTYPE` from the interface's uevent (`vlan`, `wireguard`, `bridge`...); NICs have none.
+fn devtype(name: &str) -> Option<String> {
+    let uevent = fs::read_to_string(Path::new("/sys/class/net").join(name).join("uevent")).ok()?;
+    uevent
+        .lines()
+        .find_map(|l| l.strip_prefix("DEVTYPE="))
+        .map(str::to_string)
+}
+
+/// VLAN interfaces have no `device` link; they're recognized by `DEVTYPE=vlan` and their
+/// `lower_<parent>` link, and only kept when the parent is itself a listed NIC.
+fn read_vlan(name: &str) -> Option<Vlan> {
+    if devtype(name).as_deref() != Some("vlan") {
+        return None;
+    }
+    let base = Path::new("/sys/class/net").join(name);
+    let parent = fs::read_dir(&base)
+        .ok()?
+        .flatten()
+        .find_map(|e| Some(e.file_name().to_str()?.strip_prefix("lower_")?.to_string()))
+        .filter(|p| is_ethernet_iface(p))?;
+    Some(Vlan {
+        parent,
+        id: vlan_id(name)?,
+    })
+}
+
+fn read_bridge_port(name: &str) -> Option<BridgePort> {
+    let base = Path::new("/sys/class/net").join(name);
+    if !base.join("brport").is_dir() {
+        return None;
+    }
+    let bridge = fs::read_link(base.join("master"))
+        .ok()?
+        .file_name()?
+        .to_string_lossy()
+        .to_string();
+    let state = match read_u32(base.join("brport/state")) {
+        Some(0) => "disabled",
+        Some(1) => "listening",
+        Some(2) => "learning",
+        Some(3) => "forwarding",
+        Some(4) => "blocking",
+        _ => "?",
+    };
+    let mut others: Vec<String> =
+        fs::read_dir(Path::new("/sys/class/net").join(&bridge).join("brif"))
+            .map(|ports| {
+                ports
+                    .flatten()
+                    .map(|p| p.file_name().to_string_lossy().to_string())
+                    .filter(|p| p != name)
+                    .collect()
+            })
+            .unwrap_or_default();
+    others.sort();
+    Some(BridgePort {
+        bridge,
+        state: state.to_string(),
+        others,
+    })
+}
+
+/// The bond `name` is enslaved to, if any, with its state from procfs.
+pub fn read_bond(name: &str) -> Option<Bond> {
+    let base = Path::new("/sys/class/net").join(name);
+    if !base.join("bonding_slave").is_dir() {
+        return None;
+    }
+    let bond = fs::read_link(base.join("master")).ok()?;
+    let bond = bond.file_name()?.to_string_lossy().to_string();
+    let content = fs::read_to_string(Path::new("/proc/net/bonding").join(&bond)).ok()?;
+    Some(parse_bond(bond, &content))
+}
+
+/// `Key: value` lines; everything after a `Slave Interface:` line describes that slave.
+fn parse_bond(name: String, content: &str) -> Bond {
+    let mut bond = Bond {
+        name,
+        ..Default::default()
+    };
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match (key.trim(), bond.slaves.last_mut()) {
+            ("Slave Interface", _) => bond.slaves.push(BondSlave {
+                name: value,
+                ..Default::default()
+            }),
+            ("Bonding Mode", None) => bond.mode = value,
+            ("Currently Active Slave", None) if value != "None" => bond.active_slave = Some(value),
+            ("MII Status", None) => bond.mii_status = value,
+            ("MII Status", Some(slave)) => slave.mii_status = value,
+            ("Speed", Some(slave)) => slave.speed = Some(value),
+            ("Link Failure Count", Some(slave)) => slave.link_failures = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    bond
+}
+
+/// Names of the bridges on this machine, for the join-bridge form.
+pub fn list_bridges() -> Vec<String> {
+    let mut bridges: Vec<String> = fs::read_dir("/sys/class/net")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().join("bridge").is_dir())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    bridges.sort();
+    bridges
+}
+
+/// The 802.1Q ID straight from rtnetlink: no process to wait on, and no root as
+/// `/proc/net/vlan` needs.
+fn vlan_id(name: &str) -> Option<u16> {
+    let index = read_u32(Path::new("/sys/class/net").join(name).join("ifindex"))?;
+    parse_vlan_id(&get_link(index).ok()?)
+}
+
+/// `iw dev <iface> link`: `SSID: x`, `freq: 5180`, `signal: -52 dBm`, `tx bitrate: ...`
+/// under a `Connected to` line, or just `Not connected.`.
+pub async fn read_wifi_link(name: &str) -> WifiLink {
+    let mut link = WifiLink::default();
+    if let Ok(out) = tokio::process::Command::new("iw")
+        .args(["dev", name, "link"])
+        .output()
+        .await
+    {
+        for line in String::from_utf8_lossy(&out.stdout).lines() {
+            let Some((key, value)) = line.trim().split_once(": ") else {
+                continue;
+            };
+            match key {
+                "SSID" => link.ssid = Some(value.to_string()),
+                // Newer iw prints fractional frequencies (`5180.0`).
+                "freq" => link.freq_mhz = value.split('.').next().and_then(|f| f.parse().ok()),
+                "signal" => {
+                    link.signal_dbm = value.split_whitespace().next().and_then(|s| s.parse().ok())
+                }
+                // `866.7 MBit/s VHT-MCS 9 80MHz short GI VHT-NSS 2`: keep just the rate.
+                "tx bitrate" => {
+                    let words: Vec<&str> = value.split_whitespace().take(2).collect();
+                    link.tx_bitrate = Some(words.join(" "));
+                }
+                _ => {}
+            }
+        }
+    }
+    link.signal_dbm = link.signal_dbm.or_else(|| read_wifi_signal(name));
+    link
+}
+
+/// Signal level in dBm from `/proc/net/wireless` (`wlan0: 0000   54.  -56.  -256 ...`), cheap
+/// enough to re-read every tick.
+pub fn read_wifi_signal(name: &str) -> Option<i32> {
+    let content = fs::read_to_string("/proc/net/wireless").ok()?;
+    content.lines().skip(2).find_map(|line| {
+        let mut cols = line.split_whitespace();
+        if cols.next()?.trim_end_matches(':') != name {
+            return None;
+        }
+        cols.nth(2)?.trim_end_matches('.').parse().ok()
+    })
+}
+
+pub fn read_mtu(name: &str) -> Option<u32> {
+    read_u32(Path::new("/sys/class/net").join(name).join("mtu"))
+}
+
+/// Reads the current state of a single interface, or `None` if it is gone or not Ethernet
+/// (or, with `include_wifi`, wireless).
+pub fn read_ethernet_device(name: &str, include_wifi: bool) -> Option<EthernetDevice> {
+    // WireGuard tunnels are listed too, since they usually run on top of the NIC.
+    let wireguard = devtype(name).as_deref() == Some("wireguard");
+    let wireless = include_wifi && is_physical_iface(name) && is_wifi_iface(name);
+    let vlan = if is_ethernet_iface(name) || wireguard || wireless {
+        None
+    } else {
+        Some(read_vlan(name)?)
+    };
+
+    let base = Path::new("/sys/class/net").join(name);
+
+    let ifindex = read_u32(base.join("ifindex"))?;
+    let operstate = read_to_string(base.join("operstate")).unwrap_or_else(|| "?".into());
+    let carrier = read_bool(base.join("carrier"));
+    let carrier_counts = read_carrier_counts(&base);
+    let mac = read_to_string(base.join("address"));
+    let speed_mbps = read_u32(base.join("speed"));
+    let duplex = read_to_string(base.join("duplex")).filter(|d| d != "unknown");
+
+    let (ipv4, ipv6) = list_ip_addrs_for_iface(name).unwrap_or_default();
+    let gateway_v4 = default_gateway_v4(name);
+    let (dns, dns_domains) = link_dns();
+    let stats = read_iface_stats(name);
+    let flags = read_to_string(base.join("flags"))
+        .and_then(|s| u32::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+        .unwrap_or(0);
+    let lease = read_lease(name, ifindex);
+    let bridge = read_bridge_port(name);
+    let bond = read_bond(name);
+    // The rest of what `iw` reports comes from `read_wifi_link`, which runs it.
+    let wifi = wireless.then(|| WifiLink {
+        signal_dbm: read_wifi_signal(name),
+        ..WifiLink::default()
+    });
+
+    Some(EthernetDevice {
+        name: name.to_string(),
+        ifindex,
+        operstate,
+        carrier,
+        carrier_counts,
+        mac,
+        speed_mbps,
+        duplex,
+        ipv4,
+        ipv6,
+        gateway_v4,
+        dns,
+        dns_domains,
+        stats,
+        flags,
+        lease,
+        vlan,
+        bridge,
+        bond,
+        wireguard,
+        wifi,
+    })
+}
+
+/// The interface of the IPv4 default route with the lowest metric.
+pub fn default_route_iface() -> Option<String> {
+    let content = fs::read_to_string("/proc/net/route").ok()?;
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            // Iface Destination Gateway Flags RefCnt Use Metric ...
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            (cols.len() > 6 && cols[1] == "00000000")
+                .then(|| (cols[6].parse().unwrap_or(u32::MAX), cols[0].to_string()))
+        })
+        .min()
+        .map(|(_, iface)| iface)
+}
+
+pub fn default_gateway_v4(iface: &str) -> Option<String> {
+    parse_default_gateway_v4_for_iface(iface).map(|g| g.to_string())
+}
+
+/// This machine's name, as shown to other machines (MQTT topics, the host list).
+pub fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "localhost".into())
+}
+
+pub fn list_ethernet_devices(include_wifi: bool) -> Result<Vec<EthernetDevice>> {
     let mut devices = Vec::new();
 
     for entry in fs::read_dir("/sys/class/net").context("read_dir /sys/class/net failed")? {
         let entry = entry?;
         let name = entry.file_name().to_string_lossy().to_string();
-        if name == "lo" {
-            continue;
+        if let Some(d) = read_ethernet_device(&name, include_wifi) {
+            devices.push(d);
         }
+    }
 
-        if !is_physical_iface(&name) {
-            continue;
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(devices)
+}
+
+/// Per-link DNS from resolved, as found by [`resolved_link_dns`].
+type LinkDns = Option<(Vec<String>, Vec<String>)>;
+
+/// Bitrate and SSID change without any netlink event, so wireless links are asked this often.
+const WIFI_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What [`read_ethernet_device`] leaves out because it takes a helper process, fetched in the
+/// background and kept per ifindex: DNS until a link or address event says it may have
+/// changed, wireless link details for [`WIFI_POLL_INTERVAL`].
+#[derive(Default)]
+pub struct LinkDetails {
+    dns: HashMap<u32, LinkDns>,
+    dns_fetches: Vec<(u32, JoinHandle<LinkDns>)>,
+    wifi: HashMap<u32, WifiLink>,
+    wifi_fetches: Vec<(u32, JoinHandle<WifiLink>)>,
+    wifi_polled: Option<Instant>,
+}
+
+impl LinkDetails {
+    /// Fetches again for `d`, unless a fetch is already running.
+    pub fn refresh(&mut self, d: &EthernetDevice) {
+        if !self
+            .dns_fetches
+            .iter()
+            .any(|(index, _)| *index == d.ifindex)
+        {
+            let name = d.name.clone();
+            let handle = tokio::spawn(async move { resolved_link_dns(&name).await });
+            self.dns_fetches.push((d.ifindex, handle));
+        }
+        if d.wifi.is_some() {
+            self.refresh_wifi(d);
         }
+    }
 
-        if is_wifi_iface(&name) {
-            continue;
+    fn refresh_wifi(&mut self, d: &EthernetDevice) {
+        if !self
+            .wifi_fetches
+            .iter()
+            .any(|(index, _)| *index == d.ifindex)
+        {
+            let name = d.name.clone();
+            let handle = tokio::spawn(async move { read_wifi_link(&name).await });
+            self.wifi_fetches.push((d.ifindex, handle));
+        }
+    }
+
+    /// Fetches for links seen for the first time, polls the wireless ones when due and forgets
+    /// the ones that are gone.
+    pub fn track(&mut self, devices: &[EthernetDevice]) {
+        let listed = |index: &u32| devices.iter().any(|d| d.ifindex == *index);
+        self.dns.retain(|index, _| listed(index));
+        self.wifi.retain(|index, _| listed(index));
+        for d in devices {
+            if !self.dns.contains_key(&d.ifindex) {
+                self.refresh(d);
+            }
+        }
+        let due = self
+            .wifi_polled
+            .is_none_or(|at| at.elapsed() >= WIFI_POLL_INTERVAL);
+        if due {
+            self.wifi_polled = Some(Instant::now());
+            for d in devices.iter().filter(|d| d.wifi.is_some()) {
+                self.refresh_wifi(d);
+            }
+        }
+    }
+
+    /// Collects finished fetches; true when any came back.
+    pub async fn collect(&mut self) -> bool {
+        let dns = finished(&mut self.dns_fetches).await;
+        let wifi = finished(&mut self.wifi_fetches).await;
+        let any = !dns.is_empty() || !wifi.is_empty();
+        self.dns.extend(dns);
+        self.wifi.extend(wifi);
+        any
+    }
+
+    /// Fills in what has been fetched so far.
+    pub fn apply(&self, devices: &mut [EthernetDevice]) {
+        for d in devices {
+            if let Some(Some((servers, domains))) = self.dns.get(&d.ifindex) {
+                d.dns = servers.clone();
+                d.dns_domains = domains.clone();
+            }
+            if let (Some(wifi), Some(fetched)) = (&mut d.wifi, self.wifi.get(&d.ifindex)) {
+                // The signal re-read from /proc is newer than the one `iw` printed.
+                let signal = wifi.signal_dbm.or(fetched.signal_dbm);
+                *wifi = fetched.clone();
+                wifi.signal_dbm = signal;
+            }
+        }
+    }
+}
+
+/// Takes the finished tasks out of `pending` and returns their results.
+async fn finished<T>(pending: &mut Vec<(u32, JoinHandle<T>)>) -> Vec<(u32, T)> {
+    let (done, still): (Vec<_>, Vec<_>) = pending.drain(..).partition(|(_, h)| h.is_finished());
+    *pending = still;
+    let mut results = Vec::new();
+    for (index, handle) in done {
+        if let Ok(result) = handle.await {
+            results.push((index, result));
+        }
+    }
+    results
+}
+
+/// For one-shot commands: waits for everything [`LinkDetails`] would fetch in the background.
+pub async fn fill_link_details(devices: &mut [EthernetDevice]) {
+    for d in devices {
+        if let Some((servers, domains)) = resolved_link_dns(&d.name).await {
+            d.dns = servers;
+            d.dns_domains = domains;
         }
+        if let Some(wifi) = &mut d.wifi {
+            *wifi = read_wifi_link(&d.name).await;
+        }
+    }
+}
+
+/// A runtime static IPv4 assignment as entered in the static IP form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticIpv4Config {
+    pub address: Ipv4Addr,
+    pub prefix: u8,
+    pub gateway: Option<Ipv4Addr>,
+    pub dns: Vec<Ipv4Addr>,
+}
 
-        let base = Path::new("/sys/class/net").join(&name);
+impl StaticIpv4Config {
+    /// Parses `a.b.c.d/prefix`, an optional gateway and a comma/space separated DNS list.
+    pub fn parse(address: &str, gateway: &str, dns: &str) -> Result<Self> {
+        let (ip, prefix) = address
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| io::Error::other("address must be in a.b.c.d/prefix form"))?;
+        let address: Ipv4Addr = ip
+            .parse()
+            .map_err(|_| io::Error::other(format!("invalid IPv4 address: {ip}")))?;
+        let prefix: u8 = prefix
+            .parse()
+            .ok()
+            .filter(|p| *p <= 32)
+            .ok_or_else(|| io::Error::other(format!("invalid prefix length: {prefix}")))?;
 
-        let operstate = read_to_string(base.join("operstate")).unwrap_or_else(|| "?".into());
-        let carrier = read_bool(base.join("carrier"));
-        let mac = read_to_string(base.join("address"));
-        let speed_mbps = read_u32(base.join("speed"));
+        let gateway = match gateway.trim() {
+            "" => None,
+            g => Some(
+                g.parse()
+                    .map_err(|_| io::Error::other(format!("invalid gateway: {g}")))?,
+            ),
+        };
 
-        let (ipv4, ipv6) = list_ip_addrs_for_iface(&name).unwrap_or_default();
-        let gateway_v4 = parse_default_gateway_v4_for_iface(&name).map(|g| g.to_string());
-        let dns = list_dns_servers();
+        let dns = dns
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse()
+                    .map_err(|_| io::Error::other(format!("invalid DNS server: {s}")).into())
+            })
+            .collect::<Result<Vec<Ipv4Addr>>>()?;
 
-        devices.push(EthernetDevice {
-            name,
-            operstate,
-            carrier,
-            mac,
-            speed_mbps,
-            ipv4,
-            ipv6,
-            gateway_v4,
+        Ok(Self {
+            address,
+            prefix,
+            gateway,
             dns,
-        });
+        })
     }
 
-    devices.sort_by(|a, b| a.name.cmp(&b.name));
-    Ok(devices)
+    pub fn cidr(&self) -> String {
+        format!("{}/{}", self.address, self.prefix)
+    }
+}
+
+/// Validates `aa:bb:cc:dd:ee:ff` (case-insensitive) and returns it lowercased.
+pub fn parse_mac(s: &str) -> Option<String> {
+    let parts: Vec<&str> = s.trim().split(':').collect();
+    let ok = parts.len() == 6
+        && parts
+            .iter()
+            .all(|p| p.len() == 2 && p.chars().all(|c| c.is_ascii_hexdigit()));
+    ok.then(|| parts.join(":").to_lowercase())
+}
+
+/// `address/prefix` for IPv4 or IPv6, normalized; a bare address gets a host prefix.
+pub fn parse_cidr(s: &str) -> Option<String> {
+    let (ip, prefix) = match s.trim().split_once('/') {
+        Some((ip, prefix)) => (ip.parse::<IpAddr>().ok()?, Some(prefix.parse::<u8>().ok()?)),
+        None => (s.trim().parse::<IpAddr>().ok()?, None),
+    };
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max);
+    (prefix <= max).then(|| format!("{ip}/{prefix}"))
+}
+
+/// A random unicast, locally administered MAC address.
+pub fn random_local_mac() -> Result<String> {
+    let mut b = [0u8; 6];
+    fs::File::open("/dev/urandom")
+        .and_then(|mut f| io::Read::read_exact(&mut f, &mut b))
+        .context("reading /dev/urandom failed")?;
+    b[0] = (b[0] & 0xfe) | 0x02;
+    Ok(b.iter()
+        .map(|x| format!("{x:02x}"))
+        .collect::<Vec<_>>()
+        .join(":"))
+}
+
+/// One entry from `ip -j route show dev <iface>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Route {
+    pub dst: String,
+    #[serde(default)]
+    pub gateway: Option<String>,
+    #[serde(default)]
+    pub metric: Option<u32>,
+    #[serde(default)]
+    pub protocol: Option<String>,
+    #[serde(default)]
+    pub scope: Option<String>,
+    #[serde(default)]
+    pub prefsrc: Option<String>,
+}
+
+impl Route {
+    /// A route typed into the form: `default` or a destination prefix (host bits are cleared, a
+    /// bare address is a host route), an optional gateway of the same family and an optional
+    /// metric.
+    pub fn parse(dst: &str, gateway: &str, metric: &str) -> Result<Route> {
+        let err = |msg: String| -> anyhow::Error { io::Error::other(msg).into() };
+        let dst = dst.trim();
+        let (dst, v6) = if dst == "default" {
+            ("default".to_string(), None)
+        } else {
+            let cidr = parse_cidr(dst).ok_or_else(|| err(format!("invalid destination: {dst}")))?;
+            let (ip, prefix) = cidr.split_once('/').unwrap_or((&cidr, ""));
+            let prefix: u32 = prefix.parse().unwrap_or(0);
+            let network = match ip.parse::<IpAddr>() {
+                Ok(IpAddr::V4(ip)) => {
+                    let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+                    IpAddr::from(Ipv4Addr::from(u32::from(ip) & mask))
+                }
+                Ok(IpAddr::V6(ip)) => {
+                    let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+                    IpAddr::from(Ipv6Addr::from(u128::from(ip) & mask))
+                }
+                Err(_) => return Err(err(format!("invalid destination: {dst}"))),
+            };
+            (format!("{network}/{prefix}"), Some(network.is_ipv6()))
+        };
+        let gateway = match gateway.trim() {
+            "" => None,
+            gw => {
+                let ip: IpAddr = gw
+                    .parse()
+                    .map_err(|_| err(format!("invalid gateway: {gw}")))?;
+                if v6.is_some_and(|v6| v6 != ip.is_ipv6()) {
+                    return Err(err(format!("gateway {gw} isn't the same family as {dst}")));
+                }
+                Some(ip.to_string())
+            }
+        };
+        if dst == "default" && gateway.is_none() {
+            return Err(err("a default route needs a gateway".into()));
+        }
+        let metric = match metric.trim() {
+            "" => None,
+            m => Some(m.parse().map_err(|_| err(format!("invalid metric: {m}")))?),
+        };
+        Ok(Route {
+            dst,
+            gateway,
+            metric,
+            ..Route::default()
+        })
+    }
+
+    /// Listed routes don't say their family; every address in an IPv6 route has a colon.
+    pub fn is_ipv6(&self) -> bool {
+        [
+            Some(&self.dst),
+            self.gateway.as_ref(),
+            self.prefsrc.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|a| a.contains(':'))
+    }
+
+    /// `ip route add|del` arguments that pick out this route on `iface` in the main table.
+    pub fn ip_args(&self, verb: &str, iface: &str) -> Vec<String> {
+        let family = if self.is_ipv6() { "-6" } else { "-4" };
+        let mut args = vec![family.into(), "route".into(), verb.into(), self.dst.clone()];
+        if let Some(gw) = &self.gateway {
+            args.extend(["via".into(), gw.clone()]);
+        }
+        args.extend(["dev".into(), iface.into()]);
+        if let Some(metric) = self.metric {
+            args.extend(["metric".into(), metric.to_string()]);
+        }
+        args
+    }
+
+    /// `10.0.0.0/8 via 192.0.2.1 metric 100`.
+    pub fn describe(&self) -> String {
+        let mut s = self.dst.clone();
+        if let Some(gw) = &self.gateway {
+            s.push_str(&format!(" via {gw}"));
+        }
+        if let Some(metric) = self.metric {
+            s.push_str(&format!(" metric {metric}"));
+        }
+        s
+    }
+}
+
+async fn ip_json<T: serde::de::DeserializeOwned>(args: &[&str]) -> Result<Vec<T>> {
+    iproute2_json("ip", args).await
+}
+
+/// Runs `ip` or `tc` with `-j` and parses the JSON array it prints.
+pub async fn iproute2_json<T: serde::de::DeserializeOwned>(
+    program: &str,
+    args: &[&str],
+) -> Result<Vec<T>> {
+    let out = tokio::process::Command::new(program)
+        .arg("-j")
+        .args(args)
+        .output()
+        .await
+        .with_context(|| format!("running {program} failed"))?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        return Err(io::Error::other(format!("{program} {}: {stderr}", args.join(" "))).into());
+    }
+    // `ip -j` prints nothing at all (not `[]`) for some empty results.
+    if out.stdout.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_slice(&out.stdout)?)
+}
+
+/// One entry from `tc -j -s qdisc show dev <iface>`, with its counters since it was attached.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+pub struct Qdisc {
+    pub kind: String,
+    pub handle: String,
+    #[serde(default)]
+    pub root: bool,
+    /// Parent class (`1:2`) for qdiscs below the root, or `ffff:fff1` for ingress.
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// Kind-specific settings (`target`, `interval`, `bandwidth`...).
+    #[serde(default)]
+    pub options: Option<serde_json::Value>,
+    #[serde(default)]
+    pub bytes: u64,
+    #[serde(default)]
+    pub packets: u64,
+    #[serde(default)]
+    pub drops: u64,
+    #[serde(default)]
+    pub overlimits: u64,
+    #[serde(default)]
+    pub requeues: u64,
+    /// Bytes queued right now.
+    #[serde(default)]
+    pub backlog: u64,
+    #[serde(default)]
+    pub qlen: u64,
+}
+
+pub async fn list_qdiscs(iface: &str) -> Result<Vec<Qdisc>> {
+    iproute2_json("tc", &["-s", "qdisc", "show", "dev", iface]).await
+}
+
+/// IPv4 then IPv6 routes (main table) that go out of `iface`.
+pub async fn list_routes(iface: &str) -> Result<Vec<Route>> {
+    let mut routes: Vec<Route> = ip_json(&["-4", "route", "show", "dev", iface]).await?;
+    routes.extend(ip_json::<Route>(&["-6", "route", "show", "dev", iface]).await?);
+    Ok(routes)
+}
+
+/// One ARP (IPv4) or NDP (IPv6) neighbor entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct Neighbor {
+    pub dst: String,
+    #[serde(default)]
+    pub lladdr: Option<String>,
+    /// NUD states such as `REACHABLE`, `STALE`, `FAILED` (usually a single entry).
+    #[serde(default)]
+    pub state: Vec<String>,
+}
+
+/// Fallback for systems without iproute2: IPv4 only, and the state is derived from ARP flags.
+fn read_proc_arp(iface: &str) -> Vec<Neighbor> {
+    let content = fs::read_to_string("/proc/net/arp").unwrap_or_default();
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() < 6 || cols[5] != iface {
+                return None;
+            }
+            let flags = u32::from_str_radix(cols[2].trim_start_matches("0x"), 16).unwrap_or(0);
+            let state = if flags & 0x4 != 0 {
+                "PERMANENT"
+            } else if flags & 0x2 != 0 {
+                "COMPLETE"
+            } else {
+                "INCOMPLETE"
+            };
+            Some(Neighbor {
+                dst: cols[0].to_string(),
+                lladdr: Some(cols[3].to_string()).filter(|m| m != "00:00:00:00:00:00"),
+                state: vec![state.to_string()],
+            })
+        })
+        .collect()
+}
+
+pub async fn list_neighbors(iface: &str) -> Vec<Neighbor> {
+    match ip_json(&["neigh", "show", "dev", iface]).await {
+        Ok(n) => n,
+        Err(_) => read_proc_arp(iface),
+    }
+}
+
+/// IEEE OUI registry as shipped by hwdata / ieee-data, keyed by `AABBCC`.
+pub fn load_oui_db() -> HashMap<String, String> {
+    const PATHS: [&str; 3] = [
+        "/usr/share/hwdata/oui.txt",
+        "/usr/share/ieee-data/oui.txt",
+        "/usr/share/misc/oui.txt",
+    ];
+    let Some(content) = PATHS.iter().find_map(|p| fs::read_to_string(p).ok()) else {
+        return HashMap::new();
+    };
+    // Lines look like: `00-1B-21   (hex)		Intel Corporate`
+    content
+        .lines()
+        .filter_map(|line| {
+            let (prefix, vendor) = line.split_once("(hex)")?;
+            let prefix = prefix.trim().replace('-', "");
+            (prefix.len() == 6).then(|| (prefix.to_uppercase(), vendor.trim().to_string()))
+        })
+        .collect()
+}
+
+pub fn oui_vendor<'a>(db: &'a HashMap<String, String>, mac: &str) -> Option<&'a str> {
+    let first = u8::from_str_radix(mac.get(0..2)?, 16).ok()?;
+    // Locally administered addresses (random/spoofed) have no registered vendor.
+    if first & 0x02 != 0 {
+        return Some("(locally administered)");
+    }
+    let key: String = mac.split(':').take(3).collect::<String>().to_uppercase();
+    db.get(&key).map(String::as_str)
+}
+
+/// Resolves an interface index to its current name (`if_indextoname(3)`).
+pub fn iface_name_for_index(index: u32) -> Option<String> {
+    let mut buf = [0 as libc::c_char; libc::IF_NAMESIZE];
+    // SAFETY: buf is IF_NAMESIZE bytes as required by if_indextoname.
+    let p = unsafe { libc::if_indextoname(index, buf.as_mut_ptr()) };
+    if p.is_null() {
+        return None;
+    }
+    // SAFETY: on success the buffer holds a NUL-terminated name.
+    let name = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// A change reported by the kernel over rtnetlink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetEvent {
+    /// RTM_NEWLINK / RTM_DELLINK for the given ifindex (carrier, operstate, speed, rename...).
+    Link(u32),
+    /// RTM_NEWADDR / RTM_DELADDR for the given ifindex.
+    Addr(u32),
+    /// RTM_NEWROUTE / RTM_DELROUTE (any table, any interface).
+    Route,
+}
+
+const NLMSG_HDRLEN: usize = 16;
+
+fn nlmsg_align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// The events in one `recv`. A message cut short (a datagram bigger than the buffer, or a
+/// payload too small for its type) is an error rather than a guess; the caller rescans.
+fn parse_netlink_messages(buf: &[u8]) -> io::Result<Vec<NetEvent>> {
+    let truncated = |what: &str| io::Error::new(io::ErrorKind::InvalidData, what.to_string());
+    let mut events = Vec::new();
+    let mut off = 0;
+
+    while off < buf.len() {
+        let header = buf
+            .get(off..off + NLMSG_HDRLEN)
+            .ok_or_else(|| truncated("truncated netlink header"))?;
+        let len = u32::from_ne_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = u16::from_ne_bytes([header[4], header[5]]);
+        if len < NLMSG_HDRLEN {
+            return Err(truncated("netlink message shorter than its header"));
+        }
+        let payload = buf
+            .get(off + NLMSG_HDRLEN..off + len)
+            .ok_or_else(|| truncated("truncated netlink message"))?;
+
+        // Both ifinfomsg (16 bytes) and ifaddrmsg (8) carry the interface index at offset 4.
+        let index = |min: usize| {
+            payload
+                .get(4..8)
+                .filter(|_| payload.len() >= min)
+                .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or_else(|| truncated("netlink payload too short for its type"))
+        };
+        match kind {
+            libc::RTM_NEWLINK | libc::RTM_DELLINK => events.push(NetEvent::Link(index(16)?)),
+            libc::RTM_NEWADDR | libc::RTM_DELADDR => events.push(NetEvent::Addr(index(8)?)),
+            libc::RTM_NEWROUTE | libc::RTM_DELROUTE => events.push(NetEvent::Route),
+            _ => {}
+        }
+
+        off += nlmsg_align(len);
+    }
+
+    Ok(events)
+}
+
+/// `IFLA_VLAN_ID` inside a VLAN's `IFLA_INFO_DATA` (linux/if_link.h); libc doesn't have it.
+const IFLA_VLAN_ID: u16 = 1;
+
+/// The `(type, payload)` of each rtattr in `buf`, without the nested flag.
+fn rtattrs(mut buf: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        let len = u16::from_ne_bytes([*buf.first()?, *buf.get(1)?]) as usize;
+        let kind = u16::from_ne_bytes([*buf.get(2)?, *buf.get(3)?]) & 0x3fff;
+        let payload = buf.get(4..len)?;
+        buf = buf.get(nlmsg_align(len)..).unwrap_or_default();
+        Some((kind, payload))
+    })
+}
+
+/// The ID from `IFLA_LINKINFO` → `IFLA_INFO_DATA` → `IFLA_VLAN_ID` in an RTM_NEWLINK reply,
+/// when `IFLA_INFO_KIND` says it's a VLAN.
+fn parse_vlan_id(buf: &[u8]) -> Option<u16> {
+    let len = u32::from_ne_bytes(buf.get(..4)?.try_into().ok()?) as usize;
+    let kind = u16::from_ne_bytes(buf.get(4..6)?.try_into().ok()?);
+    if kind != libc::RTM_NEWLINK {
+        return None;
+    }
+    // Attributes follow the 16-byte ifinfomsg.
+    let attrs = buf.get(NLMSG_HDRLEN + 16..len)?;
+    let (_, info) = rtattrs(attrs).find(|(t, _)| *t == libc::IFLA_LINKINFO)?;
+    let kind = rtattrs(info).find(|(t, _)| *t == libc::IFLA_INFO_KIND)?.1;
+    if kind.strip_suffix(b"\0").unwrap_or(kind) != b"vlan" {
+        return None;
+    }
+    let (_, data) = rtattrs(info).find(|(t, _)| *t == libc::IFLA_INFO_DATA)?;
+    let (_, id) = rtattrs(data).find(|(t, _)| *t == IFLA_VLAN_ID)?;
+    Some(u16::from_ne_bytes(id.get(..2)?.try_into().ok()?))
+}
+
+/// RTM_GETLINK for one ifindex; the kernel answers before `send` returns, so this never waits.
+fn get_link(index: u32) -> io::Result<Vec<u8>> {
+    // SAFETY: plain socket(2) call; the returned fd is owned immediately below.
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            libc::NETLINK_ROUTE,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd is a freshly created, valid descriptor that nothing else owns.
+    let sock = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // nlmsghdr, then an ifinfomsg that only sets the index.
+    let mut req = Vec::with_capacity(NLMSG_HDRLEN + 16);
+    req.extend(((NLMSG_HDRLEN + 16) as u32).to_ne_bytes());
+    req.extend(libc::RTM_GETLINK.to_ne_bytes());
+    req.extend((libc::NLM_F_REQUEST as u16).to_ne_bytes());
+    req.extend([0; 8]);
+    req.extend([0; 4]);
+    req.extend(index.to_ne_bytes());
+    req.extend([0; 8]);
+    // SAFETY: req is a valid buffer of the given length; unbound netlink sockets send to the
+    // kernel.
+    let sent = unsafe {
+        libc::send(
+            sock.as_raw_fd(),
+            req.as_ptr() as *const libc::c_void,
+            req.len(),
+            0,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut buf = vec![0u8; 64 * 1024];
+    // SAFETY: buf is a valid writable buffer of the given length.
+    let n = unsafe {
+        libc::recv(
+            sock.as_raw_fd(),
+            buf.as_mut_ptr() as *mut libc::c_void,
+            buf.len(),
+            0,
+        )
+    };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    buf.truncate(n as usize);
+    Ok(buf)
+}
+
+fn open_rtnetlink_socket() -> io::Result<OwnedFd> {
+    // SAFETY: plain socket(2) call; the returned fd is owned immediately below.
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            libc::NETLINK_ROUTE,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd is a freshly created, valid descriptor that nothing else owns.
+    let sock = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // SAFETY: sockaddr_nl is plain-old-data; all-zero is a valid value.
+    let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    addr.nl_groups = (libc::RTMGRP_LINK
+        | libc::RTMGRP_IPV4_IFADDR
+        | libc::RTMGRP_IPV6_IFADDR
+        | libc::RTMGRP_IPV4_ROUTE
+        | libc::RTMGRP_IPV6_ROUTE) as u32;
+
+    // SAFETY: addr is a valid sockaddr_nl and the length matches its size.
+    let rc = unsafe {
+        libc::bind(
+            sock.as_raw_fd(),
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if rc < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(sock)
+}
+
+/// Subscribes to rtnetlink link/address/route multicast groups and forwards changes as
+/// [`Event::Net`]. The listener runs on its own thread and stops once the receiver is dropped.
+pub fn spawn_rtnetlink_listener(sender: mpsc::UnboundedSender<Event>) -> Result<()> {
+    let sock = open_rtnetlink_socket().context("rtnetlink socket failed")?;
+
+    std::thread::Builder::new()
+        .name("rtnetlink".into())
+        .spawn(move || {
+            let mut buf = vec![0u8; 64 * 1024];
+            loop {
+                // SAFETY: buf is a valid writable buffer of the given length.
+                let n = unsafe {
+                    libc::recv(
+                        sock.as_raw_fd(),
+                        buf.as_mut_ptr() as *mut libc::c_void,
+                        buf.len(),
+                        0,
+                    )
+                };
+                if n < 0 {
+                    let err = io::Error::last_os_error();
+                    match err.raw_os_error() {
+                        Some(libc::EINTR) => continue,
+                        // Kernel dropped messages; ask for a full rescan instead.
+                        Some(libc::ENOBUFS) => {
+                            if sender.send(Event::NetResync).is_err() {
+                                return;
+                            }
+                            continue;
+                        }
+                        _ => return,
+                    }
+                }
+
+                // Whatever a bad datagram held is lost; a rescan catches up like after ENOBUFS.
+                let events = match parse_netlink_messages(&buf[..n as usize]) {
+                    Ok(events) => events.into_iter().map(Event::Net).collect(),
+                    Err(_) => vec![Event::NetResync],
+                };
+                for ev in events {
+                    if sender.send(ev).is_err() {
+                        return;
+                    }
+                }
+            }
+        })
+        .context("spawning rtnetlink thread failed")?;
+
+    Ok(())
+}
+
+/// An up interface with carrier and one address, for tests to adjust.
+#[cfg(test)]
+pub(crate) fn test_device(name: &str) -> EthernetDevice {
+    EthernetDevice {
+        name: name.to_string(),
+        ifindex: 99,
+        operstate: "up".into(),
+        carrier: Some(true),
+        carrier_counts: None,
+        mac: None,
+        speed_mbps: None,
+        duplex: None,
+        ipv4: vec!["192.0.2.2/24".into()],
+        ipv6: Vec::new(),
+        gateway_v4: None,
+        dns: Vec::new(),
+        dns_domains: Vec::new(),
+        stats: IfaceStats::default(),
+        flags: 0,
+        lease: None,
+        vlan: None,
+        bridge: None,
+        bond: None,
+        wireguard: false,
+        wifi: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RTM_NEWLINK for `lo` (ifindex 1) from an RTM_GETLINK dump, cut after IFLA_MTU with the
+    /// length fixed up. Little-endian, like the machines this runs on.
+    const NEWLINK_LO: [u8; 80] = [
+        0x50, 0x00, 0x00, 0x00, 0x10, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x49, 0x29, 0x00,
+        0x00, 0x00, 0x00, 0x04, 0x03, 0x01, 0x00, 0x00, 0x00, 0x49, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x07, 0x00, 0x03, 0x00, 0x6c, 0x6f, 0x00, 0x00, 0x08, 0x00, 0x0d, 0x00, 0xe8,
+        0x03, 0x00, 0x00, 0x05, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x11, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x43, 0x00, 0x01, 0x00, 0x00, 0x00, 0x08, 0x00, 0x04,
+        0x00, 0x00, 0x00, 0x01, 0x00,
+    ];
+
+    /// RTM_NEWADDR for 127.0.0.1/8 on `lo`, as an RTM_GETADDR dump returned it.
+    const NEWADDR_LO: [u8; 76] = [
+        0x4c, 0x00, 0x00, 0x00, 0x14, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x49, 0x29, 0x00,
+        0x00, 0x02, 0x08, 0x80, 0xfe, 0x01, 0x00, 0x00, 0x00, 0x08, 0x00, 0x01, 0x00, 0x7f, 0x00,
+        0x00, 0x01, 0x08, 0x00, 0x02, 0x00, 0x7f, 0x00, 0x00, 0x01, 0x07, 0x00, 0x03, 0x00, 0x6c,
+        0x6f, 0x00, 0x00, 0x08, 0x00, 0x08, 0x00, 0x80, 0x00, 0x00, 0x00, 0x14, 0x00, 0x06, 0x00,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x0b, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x00,
+        0x00,
+    ];
+
+    fn message(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let mut buf = ((NLMSG_HDRLEN + payload.len()) as u32)
+            .to_ne_bytes()
+            .to_vec();
+        buf.extend(kind.to_ne_bytes());
+        buf.extend([0; 10]);
+        buf.extend(payload);
+        buf.resize(nlmsg_align(buf.len()), 0);
+        buf
+    }
+
+    #[test]
+    fn parses_messages() {
+        let route = message(libc::RTM_NEWROUTE, &[0; 12]);
+        let done = message(libc::NLMSG_DONE as u16, &[0; 4]);
+        let both = [NEWADDR_LO.as_slice(), &NEWLINK_LO].concat();
+        let cases: [(&str, Vec<u8>, Vec<NetEvent>); 6] = [
+            ("empty", Vec::new(), vec![]),
+            ("link", NEWLINK_LO.to_vec(), vec![NetEvent::Link(1)]),
+            ("address", NEWADDR_LO.to_vec(), vec![NetEvent::Addr(1)]),
+            (
+                "several in one datagram",
+                both,
+                vec![NetEvent::Addr(1), NetEvent::Link(1)],
+            ),
+            ("route", route, vec![NetEvent::Route]),
+            ("ignored type", done, vec![]),
+        ];
+        for (name, buf, want) in cases {
+            assert_eq!(parse_netlink_messages(&buf).unwrap(), want, "{name}");
+        }
+    }
+
+    /// An unaligned length is padded to 4 before the next message.
+    #[test]
+    fn skips_padding() {
+        let first = message(libc::RTM_DELADDR, &[2, 8, 0x80, 0, 7, 0, 0, 0, 1]);
+        assert_eq!((first[0], first.len()), (25, 28));
+        let buf = [first, NEWLINK_LO.to_vec()].concat();
+        assert_eq!(
+            parse_netlink_messages(&buf).unwrap(),
+            [NetEvent::Addr(7), NetEvent::Link(1)]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated() {
+        let mut short_link = NEWLINK_LO[..NLMSG_HDRLEN + 8].to_vec();
+        short_link[0] = (NLMSG_HDRLEN + 8) as u8;
+        let mut zero_len = NEWADDR_LO.to_vec();
+        zero_len[..4].copy_from_slice(&0u32.to_ne_bytes());
+        let cases: [(&str, Vec<u8>); 5] = [
+            ("partial header", NEWLINK_LO[..10].to_vec()),
+            ("cut short", NEWLINK_LO[..60].to_vec()),
+            (
+                "second message cut short",
+                [&NEWADDR_LO[..], &NEWLINK_LO[..40]].concat(),
+            ),
+            ("ifinfomsg too short", short_link),
+            ("length under the header", zero_len),
+        ];
+        for (name, buf) in cases {
+            assert!(parse_netlink_messages(&buf).is_err(), "{name}");
+        }
+    }
+
+    /// Every prefix of a valid datagram either parses or errors; none panics.
+    #[test]
+    fn every_prefix() {
+        let buf = [NEWADDR_LO.as_slice(), &NEWLINK_LO].concat();
+        for len in 0..=buf.len() {
+            let parsed = parse_netlink_messages(&buf[..len]);
+            let whole = len == 0 || len == NEWADDR_LO.len() || len == buf.len();
+            assert_eq!(parsed.is_ok(), whole, "{len} bytes");
+        }
+    }
+
+    fn attr(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let mut buf = ((4 + payload.len()) as u16).to_ne_bytes().to_vec();
+        buf.extend(kind.to_ne_bytes());
+        buf.extend(payload);
+        buf.resize(nlmsg_align(buf.len()), 0);
+        buf
+    }
+
+    /// RTM_NEWLINK with an ifinfomsg and the given IFLA_LINKINFO contents.
+    fn newlink(linkinfo: &[u8]) -> Vec<u8> {
+        let payload = [&[0; 16][..], &attr(libc::IFLA_LINKINFO | 0x8000, linkinfo)].concat();
+        message(libc::RTM_NEWLINK, &payload)
+    }
+
+    #[test]
+    fn parses_vlan_id() {
+        let data = attr(libc::IFLA_INFO_DATA | 0x8000, &attr(IFLA_VLAN_ID, &[10, 0]));
+        let vlan = [attr(libc::IFLA_INFO_KIND, b"vlan\0"), data.clone()].concat();
+        let bridge = [attr(libc::IFLA_INFO_KIND, b"bridge\0"), data].concat();
+        assert_eq!(parse_vlan_id(&newlink(&vlan)), Some(10));
+        assert_eq!(parse_vlan_id(&newlink(&bridge)), None);
+        assert_eq!(parse_vlan_id(&NEWLINK_LO), None);
+        assert_eq!(parse_vlan_id(&newlink(&vlan)[..40]), None);
+    }
 }
@@ -0,0 +1,314 @@
+use crate::command::glob_match;
+use crate::diag::LineStream;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Where netplan reads YAML from; a file in a later directory shadows one with the same name in
+/// an earlier one.
+const DIRS: [&str; 4] = [
+    "/usr/lib/netplan",
+    "/lib/netplan",
+    "/etc/netplan",
+    "/run/netplan",
+];
+
+/// The `network:` sections that define interfaces.
+const DEVICE_TYPES: [&str; 10] = [
+    "ethernets",
+    "vlans",
+    "bonds",
+    "bridges",
+    "wifis",
+    "tunnels",
+    "vrfs",
+    "modems",
+    "dummy-devices",
+    "virtual-ethernets",
+];
+
+/// How long `netplan try` waits for the new configuration to be kept.
+pub const TRY_TIMEOUT: u64 = 120;
+
+/// One device definition, e.g. `ethernets: eth0:` and everything indented under it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stanza {
+    pub path: String,
+    /// 1-based line of the device id.
+    pub line: usize,
+    /// `ethernets`, `vlans`, `bonds`...
+    pub kind: String,
+    pub id: String,
+    /// The lines as written, comments included.
+    pub text: String,
+}
+
+/// netplan's configuration as it concerns one interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Netplan {
+    /// Every YAML file, in the order netplan merges them.
+    pub files: Vec<String>,
+    /// Definitions that apply to the interface; keys in later ones override earlier ones.
+    pub stanzas: Vec<Stanza>,
+}
+
+/// A non-blank, non-comment line: its indentation, key (if any) and value.
+struct Entry<'a> {
+    line: usize,
+    indent: usize,
+    key: Option<String>,
+    value: &'a str,
+}
+
+fn unquote(s: &str) -> String {
+    s.trim().trim_matches(|c| c == '"' || c == '\'').to_string()
+}
+
+/// Block-style YAML only, which is what netplan's own docs and generators write; flow mappings
+/// (`{...}`) are treated as plain values.
+fn entries(text: &str) -> Vec<Entry<'_>> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, raw)| {
+            let body = raw.trim_start();
+            if body.is_empty() || body.starts_with('#') {
+                return None;
+            }
+            let indent = raw.len() - body.len();
+            // A trailing comment needs whitespace before the `#`; `#` inside a value is data.
+            let body = body.split(" #").next().unwrap_or(body).trim_end();
+            let (key, value) = match body.split_once(": ") {
+                Some((k, v)) if !k.starts_with('-') => (Some(unquote(k)), v.trim()),
+                _ => match body.strip_suffix(':') {
+                    Some(k) if !k.starts_with('-') => (Some(unquote(k)), ""),
+                    _ => (None, body),
+                },
+            };
+            Some(Entry {
+                line: i,
+                indent,
+                key,
+                value,
+            })
+        })
+        .collect()
+}
+
+/// Index one past the last entry nested under `entries[at]`.
+fn block_end(entries: &[Entry], at: usize) -> usize {
+    let indent = entries[at].indent;
+    entries[at + 1..]
+        .iter()
+        .position(|e| e.indent <= indent)
+        .map_or(entries.len(), |p| at + 1 + p)
+}
+
+/// The entries directly under `entries[at]`.
+fn children(entries: &[Entry], at: usize) -> Vec<usize> {
+    let end = block_end(entries, at);
+    let Some(indent) = entries
+        .get(at + 1)
+        .filter(|_| at + 1 < end)
+        .map(|e| e.indent)
+    else {
+        return Vec::new();
+    };
+    (at + 1..end)
+        .filter(|&i| entries[i].indent == indent)
+        .collect()
+}
+
+fn child_value<'a>(entries: &'a [Entry], at: usize, key: &str) -> Option<&'a str> {
+    children(entries, at)
+        .into_iter()
+        .find(|&i| entries[i].key.as_deref() == Some(key))
+        .map(|i| entries[i].value)
+}
+
+/// netplan's rules: a definition with `match:` applies to every interface its name glob and MAC
+/// select (or the one it renames to `set-name`); without one, the id is the interface name.
+fn applies(entries: &[Entry], at: usize, iface: &str, mac: Option<&str>) -> bool {
+    if child_value(entries, at, "set-name").is_some_and(|n| unquote(n) == iface) {
+        return true;
+    }
+    let matcher = children(entries, at)
+        .into_iter()
+        .find(|&i| entries[i].key.as_deref() == Some("match"));
+    let Some(m) = matcher else {
+        return entries[at].key.as_deref() == Some(iface);
+    };
+    let name_ok = child_value(entries, m, "name").is_none_or(|p| glob_match(&unquote(p), iface));
+    let mac_ok = child_value(entries, m, "macaddress")
+        .is_none_or(|want| mac.is_some_and(|have| unquote(want).eq_ignore_ascii_case(have)));
+    name_ok && mac_ok
+}
+
+/// The definitions in one file that apply to `iface`.
+fn stanzas_in(path: &str, text: &str, iface: &str, mac: Option<&str>) -> Vec<Stanza> {
+    let entries = entries(text);
+    let lines: Vec<&str> = text.lines().collect();
+    let mut found = Vec::new();
+    let networks = (0..entries.len())
+        .filter(|&i| entries[i].indent == 0 && entries[i].key.as_deref() == Some("network"));
+    for network in networks {
+        for section in children(&entries, network) {
+            let Some(kind) = entries[section]
+                .key
+                .as_deref()
+                .filter(|k| DEVICE_TYPES.contains(k))
+            else {
+                continue;
+            };
+            for device in children(&entries, section) {
+                if !applies(&entries, device, iface, mac) {
+                    continue;
+                }
+                let end = block_end(&entries, device);
+                // Through the last nested entry, so trailing comments and blanks stay out.
+                let last = entries[end - 1].line;
+                let first = entries[device].line;
+                found.push(Stanza {
+                    path: path.to_string(),
+                    line: first + 1,
+                    kind: kind.to_string(),
+                    id: entries[device].key.clone().unwrap_or_default(),
+                    text: lines[first..=last].join("\n"),
+                });
+            }
+        }
+    }
+    found
+}
+
+/// The YAML files netplan reads, sorted by name the way it merges them.
+fn config_files() -> Vec<String> {
+    let mut by_name = BTreeMap::new();
+    for dir in DIRS {
+        let Ok(read) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in read.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "yaml") {
+                by_name.insert(entry.file_name(), path.to_string_lossy().to_string());
+            }
+        }
+    }
+    by_name.into_values().collect()
+}
+
+/// `None` when the machine has no netplan configuration at all.
+pub fn netplan_for(iface: &str, mac: Option<&str>) -> Option<Netplan> {
+    let files = config_files();
+    if files.is_empty() {
+        return None;
+    }
+    let stanzas = files
+        .iter()
+        .filter_map(|path| Some((path, fs::read_to_string(path).ok()?)))
+        .flat_map(|(path, text)| stanzas_in(path, &text, iface, mac))
+        .collect();
+    Some(Netplan { files, stanzas })
+}
+
+/// Whether a networkd unit is one netplan generated, which editing would be undone on the next
+/// `netplan generate`.
+pub fn generated(path: &str) -> bool {
+    Path::new(path)
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with("10-netplan-"))
+}
+
+/// What the user told a running `netplan try`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryAnswer {
+    Keep,
+    Revert,
+}
+
+/// `netplan try`, run without a terminal: netplan reverts on its own when the timeout passes,
+/// SIGUSR1 keeps the new configuration and SIGINT reverts it now (what netplan's D-Bus API
+/// sends).
+pub struct NetplanTry {
+    pub started: Instant,
+    pub timeout: u64,
+    pub output: Vec<String>,
+    /// Set once the user has answered, so the countdown stops.
+    pub answer: Option<TryAnswer>,
+    pub exit: Option<i32>,
+    stream: LineStream,
+}
+
+impl NetplanTry {
+    /// Through `sudo -n` unless already root, since the process must stay around to be signalled.
+    /// It isn't killed with the popup: that would leave the trial configuration in place.
+    pub fn start(timeout: u64) -> Result<Self> {
+        let timeout_arg = timeout.to_string();
+        let args = ["try", "--timeout", timeout_arg.as_str()];
+        // SAFETY: geteuid has no preconditions and can't fail.
+        let stream = if unsafe { libc::geteuid() } == 0 {
+            LineStream::spawn_detached("netplan", &args)?
+        } else {
+            let mut sudo_args = vec!["-n", "netplan"];
+            sudo_args.extend(args);
+            LineStream::spawn_detached("sudo", &sudo_args)?
+        };
+        Ok(Self {
+            started: Instant::now(),
+            timeout,
+            output: Vec::new(),
+            answer: None,
+            exit: None,
+            stream,
+        })
+    }
+
+    pub fn poll(&mut self) {
+        for line in self.stream.drain() {
+            // The countdown rewrites one line with `\r`; keep only its latest state.
+            let line = line.rsplit('\r').next().unwrap_or_default().trim_end();
+            if !line.is_empty() {
+                self.output.push(line.to_string());
+            }
+        }
+        if self.exit.is_none() {
+            self.exit = self.stream.exit_status();
+        }
+    }
+
+    /// Seconds until netplan reverts by itself.
+    pub fn remaining(&self) -> u64 {
+        let elapsed = self.started.elapsed();
+        Duration::from_secs(self.timeout)
+            .saturating_sub(elapsed)
+            .as_secs()
+    }
+
+    pub fn accept(&mut self) {
+        self.answer(libc::SIGUSR1, TryAnswer::Keep);
+    }
+
+    pub fn revert(&mut self) {
+        self.answer(libc::SIGINT, TryAnswer::Revert);
+    }
+
+    fn answer(&mut self, signal: libc::c_int, answer: TryAnswer) {
+        if self.exit.is_some() || self.answer.is_some() {
+            return;
+        }
+        if self.stream.signal(signal) {
+            self.answer = Some(answer);
+        }
+    }
+}
+
+/// Closing ethtui mid-trial reverts rather than keeps.
+impl Drop for NetplanTry {
+    fn drop(&mut self) {
+        if self.exit.is_none() {
+            self.stream.signal(libc::SIGINT);
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use crate::net::StaticIpv4Config;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Where generated units go; the `10-` prefix sorts them ahead of distribution defaults, since
+/// networkd applies the first `.network` file (by name) that matches.
+const GENERATED_DIR: &str = "/etc/systemd/network";
+
+/// A unit file networkd or udev applied to a link, with the drop-ins layered on top of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnitFile {
+    pub path: String,
+    pub dropins: Vec<String>,
+    /// Each file in the order systemd reads them, with its contents or why it couldn't be read,
+    /// the way `networkctl cat` shows them.
+    pub contents: Vec<(String, Result<String, String>)>,
+}
+
+impl UnitFile {
+    fn new(path: String, dropins: Vec<String>) -> Self {
+        let contents = std::iter::once(&path)
+            .chain(&dropins)
+            .map(|p| {
+                let text = fs::read_to_string(p).map_err(|e| e.kind().to_string());
+                (p.clone(), text)
+            })
+            .collect();
+        UnitFile {
+            path,
+            dropins,
+            contents,
+        }
+    }
+}
+
+/// Which `.network` and `.link` files configured an interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Units {
+    pub network: Option<UnitFile>,
+    pub link: Option<UnitFile>,
+}
+
+/// `KEY=value` from a state file; values may be quoted.
+fn state_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix('=')?;
+        Some(value.trim_matches('"').to_string())
+    })
+}
+
+/// Drop-in lists are `:`-separated in both networkd's and udev's state.
+fn split_dropins(value: Option<String>) -> Vec<String> {
+    value
+        .map(|v| {
+            v.split(':')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// From networkd's link state (`NETWORK_FILE`) and udev's database (`ID_NET_LINK_FILE`), or
+/// `networkctl status` when they can't be read.
+pub async fn units(iface: &str, ifindex: u32) -> Units {
+    let mut units = Units::default();
+    if let Ok(state) =
+        fs::read_to_string(Path::new("/run/systemd/netif/links").join(ifindex.to_string()))
+    {
+        units.network = state_value(&state, "NETWORK_FILE").map(|path| {
+            UnitFile::new(
+                path,
+                split_dropins(state_value(&state, "NETWORK_FILE_DROPINS")),
+            )
+        });
+    }
+    if let Ok(db) = fs::read_to_string(format!("/run/udev/data/n{ifindex}")) {
+        units.link = state_value(&db, "E:ID_NET_LINK_FILE").map(|path| {
+            UnitFile::new(
+                path,
+                split_dropins(state_value(&db, "E:ID_NET_LINK_FILE_DROPINS")),
+            )
+        });
+    }
+    if units.network.is_none() || units.link.is_none() {
+        let status = networkctl_files(iface).await;
+        units.network = units.network.or(status.network);
+        units.link = units.link.or(status.link);
+    }
+    units
+}
+
+/// The `Network File:` and `Link File:` lines of `networkctl status`; it doesn't list drop-ins.
+async fn networkctl_files(iface: &str) -> Units {
+    let Ok(out) = Command::new("networkctl")
+        .args(["status", "--no-pager", "--lines=0", iface])
+        .output()
+        .await
+    else {
+        return Units::default();
+    };
+    let text = String::from_utf8_lossy(&out.stdout);
+    let file = |label: &str| {
+        text.lines().find_map(|l| {
+            let path = l.trim().strip_prefix(label)?.trim();
+            path.starts_with('/')
+                .then(|| UnitFile::new(path.to_string(), Vec::new()))
+        })
+    };
+    Units {
+        network: file("Network File:"),
+        link: file("Link File:"),
+    }
+}
+
+/// networkd's own view of a link, from `networkctl status --json=short <iface>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LinkStatus {
+    /// The setup state: `configured`, `configuring`, `failed`, `unmanaged`, `linger`...
+    #[serde(default)]
+    pub administrative_state: Option<String>,
+    /// `online`, `partial` or `offline`, as `systemd-networkd-wait-online` judges it.
+    #[serde(default)]
+    pub online_state: Option<String>,
+    #[serde(default)]
+    pub required_for_online: Option<bool>,
+    /// The operational states that count as online, e.g. `["degraded", "routable"]`.
+    #[serde(default)]
+    pub required_operational_state_for_online: Vec<String>,
+    #[serde(default, rename = "DNS")]
+    pub dns: Vec<ConfigEntry>,
+    #[serde(default, rename = "NTP")]
+    pub ntp: Vec<ConfigEntry>,
+    #[serde(default, rename = "DHCPv4Client")]
+    pub dhcp4: Option<DhcpClient>,
+    #[serde(default, rename = "DHCPv6Client")]
+    pub dhcp6: Option<DhcpClient>,
+}
+
+impl LinkStatus {
+    /// DNS or NTP servers learned from a DHCP server or router advertisement, with the source.
+    pub fn acquired(entries: &[ConfigEntry]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .filter(|e| {
+                e.config_source
+                    .as_deref()
+                    .is_some_and(|s| s.starts_with("DHCP") || s == "NDisc")
+            })
+            .filter_map(|e| Some((e.server()?, e.config_source.clone()?)))
+            .collect()
+    }
+}
+
+/// A DNS or NTP server and where networkd got it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ConfigEntry {
+    /// `AF_INET` or `AF_INET6`, for `address`.
+    #[serde(default)]
+    pub family: Option<i32>,
+    /// The address as raw bytes.
+    #[serde(default)]
+    pub address: Option<Vec<u8>>,
+    /// NTP servers can be names instead.
+    #[serde(default)]
+    pub server: Option<String>,
+    /// `static`, `DHCPv4`, `DHCPv6`, `NDisc`...
+    #[serde(default)]
+    pub config_source: Option<String>,
+}
+
+impl ConfigEntry {
+    pub fn server(&self) -> Option<String> {
+        if let Some(name) = &self.server {
+            return Some(name.clone());
+        }
+        let bytes = self.address.as_deref()?;
+        let ip = match self.family {
+            Some(libc::AF_INET6) => IpAddr::from(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+            _ => IpAddr::from(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        };
+        Some(ip.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DhcpClient {
+    /// None while the client is still soliciting.
+    #[serde(default)]
+    pub lease: Option<DhcpClientLease>,
+}
+
+/// Timestamps are `CLOCK_BOOTTIME` microseconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct DhcpClientLease {
+    #[serde(default, rename = "LeaseTimestampUSec")]
+    pub acquired: Option<u64>,
+    /// When the client starts renewing (T1).
+    #[serde(default, rename = "Timeout1USec")]
+    pub renew_at: Option<u64>,
+}
+
+impl DhcpClientLease {
+    pub fn age(&self) -> Option<Duration> {
+        Some(Duration::from_micros(
+            boottime_usec()?.checked_sub(self.acquired?)?,
+        ))
+    }
+
+    pub fn renews_in(&self) -> Option<Duration> {
+        Some(Duration::from_micros(
+            self.renew_at?.checked_sub(boottime_usec()?)?,
+        ))
+    }
+}
+
+fn boottime_usec() -> Option<u64> {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: clock_gettime only writes to the timespec it's given.
+    let r = unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut ts) };
+    (r == 0).then(|| ts.tv_sec as u64 * 1_000_000 + ts.tv_nsec as u64 / 1_000)
+}
+
+/// networkd's description of `iface`; fails on systemd older than 250, which has no JSON output.
+pub async fn link_status(iface: &str) -> Result<LinkStatus> {
+    let out = Command::new("networkctl")
+        .args(["status", "--json=short", "--no-pager", iface])
+        .output()
+        .await
+        .context("running networkctl failed")?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        return Err(io::Error::other(format!("networkctl status: {stderr}")).into());
+    }
+    Ok(serde_json::from_slice(&out.stdout)?)
+}
+
+/// How a generated `.network` file addresses the interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Addressing {
+    Dhcp,
+    Static(StaticIpv4Config),
+}
+
+/// A `.network` file for an interface, plus a `.netdev` and `.network` pair for each VLAN on top
+/// of it, as the wizard filled them in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkConfig {
+    pub iface: String,
+    pub addressing: Addressing,
+    pub dns: Vec<IpAddr>,
+    pub vlans: Vec<u16>,
+    /// Run `networkctl reload` and `reconfigure` after writing.
+    pub reload: bool,
+}
+
+impl NetworkConfig {
+    /// The wizard's fields; lists are separated by spaces or commas.
+    pub fn parse(
+        iface: &str,
+        mode: &str,
+        address: &str,
+        gateway: &str,
+        dns: &str,
+        vlans: &str,
+        reload: &str,
+    ) -> Result<Self> {
+        let err = |msg: String| -> anyhow::Error { io::Error::other(msg).into() };
+        let split = |s: &str| {
+            s.split([' ', ','])
+                .filter(|w| !w.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let addressing = match mode.trim() {
+            "dhcp" | "" => Addressing::Dhcp,
+            "static" => Addressing::Static(StaticIpv4Config::parse(address, gateway, "")?),
+            m => return Err(err(format!("mode must be dhcp or static: {m}"))),
+        };
+        let dns = split(dns)
+            .iter()
+            .map(|s| {
+                s.parse()
+                    .map_err(|_| err(format!("invalid DNS server: {s}")))
+            })
+            .collect::<Result<Vec<IpAddr>>>()?;
+        let mut ids = Vec::new();
+        for id in split(vlans) {
+            let n = id
+                .parse::<u16>()
+                .ok()
+                .filter(|n| (1..=4094).contains(n))
+                .ok_or_else(|| err(format!("VLAN ID must be 1-4094: {id}")))?;
+            let name = format!("{iface}.{n}");
+            if name.len() >= libc::IF_NAMESIZE {
+                return Err(err(format!(
+                    "{name}: interface names are limited to {} characters",
+                    libc::IF_NAMESIZE - 1
+                )));
+            }
+            if !ids.contains(&n) {
+                ids.push(n);
+            }
+        }
+        let reload = match reload.trim() {
+            "yes" | "" => true,
+            "no" => false,
+            r => return Err(err(format!("reload must be yes/no: {r}"))),
+        };
+        Ok(Self {
+            iface: iface.to_string(),
+            addressing,
+            dns,
+            vlans: ids,
+            reload,
+        })
+    }
+
+    /// The interface's own `.network` file.
+    pub fn network_path(&self) -> String {
+        format!("{GENERATED_DIR}/10-ethtui-{}.network", self.iface)
+    }
+
+    /// Every file to write, paths first, contents second.
+    pub fn files(&self) -> Vec<(String, String)> {
+        let mut network = format!(
+            "# Written by ethtui\n[Match]\nName={}\n\n[Network]\n",
+            self.iface
+        );
+        match &self.addressing {
+            Addressing::Dhcp => network.push_str("DHCP=yes\n"),
+            Addressing::Static(cfg) => {
+                network.push_str(&format!("Address={}\n", cfg.cidr()));
+                if let Some(gw) = cfg.gateway {
+                    network.push_str(&format!("Gateway={gw}\n"));
+                }
+            }
+        }
+        for server in &self.dns {
+            network.push_str(&format!("DNS={server}\n"));
+        }
+        for id in &self.vlans {
+            network.push_str(&format!("VLAN={}.{id}\n", self.iface));
+        }
+
+        let mut files = vec![(self.network_path(), network)];
+        for id in &self.vlans {
+            let name = format!("{}.{id}", self.iface);
+            files.push((
+                format!("{GENERATED_DIR}/10-ethtui-{name}.netdev"),
+                format!(
+                    "# Written by ethtui\n[NetDev]\nName={name}\nKind=vlan\n\n[VLAN]\nId={id}\n"
+                ),
+            ));
+            files.push((
+                format!("{GENERATED_DIR}/10-ethtui-{name}.network"),
+                format!("# Written by ethtui\n[Match]\nName={name}\n\n[Network]\nDHCP=yes\n"),
+            ));
+        }
+        files
+    }
+
+    /// What will be written and run, for the confirmation.
+    pub fn preview(&self) -> String {
+        let mut out = String::new();
+        for (path, contents) in self.files() {
+            out.push_str(&format!("Writes {path}:\n\n{contents}\n"));
+        }
+        if self.reload {
+            out.push_str(&format!(
+                "then runs networkctl reload and networkctl reconfigure {}.",
+                self.iface
+            ));
+        } else {
+            out.push_str("networkd picks it up on its next reload or restart.");
+        }
+        out
+    }
+}
@@ -0,0 +1,586 @@
+use crate::logfile;
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::json;
+use std::os::linux::net::SocketAddrExt;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::process::Command;
+
+/// Summary line of every notification.
+const TITLE: &str = "󰀂    Ethernet";
+
+/// Giving up on the session bus after this long falls back to `notify-send` (with `auto`).
+const DBUS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The largest message the D-Bus specification allows (128 MiB).
+const MAX_MESSAGE_LEN: usize = 128 * 1024 * 1024;
+
+/// What can raise a desktop notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    LinkDown,
+    LinkUp,
+    /// An address added or removed.
+    IpChanged,
+    Renew,
+    RenewFailed,
+    SpeedTest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    // The byte the notification spec uses for each.
+    Low = 0,
+    Normal = 1,
+    Critical = 2,
+}
+
+impl Urgency {
+    fn name(self) -> &'static str {
+        match self {
+            Urgency::Low => "low",
+            Urgency::Normal => "normal",
+            Urgency::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotifyBackend {
+    /// The session bus, falling back to `notify-send` when that doesn't work.
+    Auto,
+    Dbus,
+    NotifySend,
+}
+
+/// The `[notify]` table; `notifications = false` still turns everything off.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    pub events: Vec<NotifyEvent>,
+    pub backend: NotifyBackend,
+    /// How long a notification stays up; 0 leaves it to the notification daemon.
+    pub timeout_ms: u32,
+    pub urgency: Urgencies,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            events: vec![
+                NotifyEvent::LinkDown,
+                NotifyEvent::LinkUp,
+                NotifyEvent::Renew,
+                NotifyEvent::RenewFailed,
+                NotifyEvent::SpeedTest,
+            ],
+            backend: NotifyBackend::Auto,
+            timeout_ms: 2000,
+            urgency: Urgencies::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Urgencies {
+    pub link_down: Urgency,
+    pub link_up: Urgency,
+    pub ip_changed: Urgency,
+    pub renew: Urgency,
+    pub renew_failed: Urgency,
+    pub speed_test: Urgency,
+    /// `[[alerts]]` rules with `notify = true`.
+    pub alert: Urgency,
+}
+
+impl Default for Urgencies {
+    fn default() -> Self {
+        Self {
+            link_down: Urgency::Critical,
+            link_up: Urgency::Normal,
+            ip_changed: Urgency::Normal,
+            renew: Urgency::Low,
+            renew_failed: Urgency::Critical,
+            speed_test: Urgency::Normal,
+            alert: Urgency::Critical,
+        }
+    }
+}
+
+impl Urgencies {
+    fn of(&self, event: NotifyEvent) -> Urgency {
+        match event {
+            NotifyEvent::LinkDown => self.link_down,
+            NotifyEvent::LinkUp => self.link_up,
+            NotifyEvent::IpChanged => self.ip_changed,
+            NotifyEvent::Renew => self.renew,
+            NotifyEvent::RenewFailed => self.renew_failed,
+            NotifyEvent::SpeedTest => self.speed_test,
+        }
+    }
+}
+
+/// Sends desktop notifications for the configured events, in the background.
+#[derive(Debug, Clone, Default)]
+pub struct Notifier {
+    /// None when notifications are off.
+    config: Option<NotifyConfig>,
+}
+
+impl Notifier {
+    pub fn new(enabled: bool, config: &NotifyConfig) -> Self {
+        Self {
+            config: enabled.then(|| config.clone()),
+        }
+    }
+
+    /// Best effort: failures only end up in the log file.
+    pub fn send(&self, event: NotifyEvent, body: impl Into<String>) {
+        let Some(config) = &self.config else {
+            return;
+        };
+        if !config.events.contains(&event) {
+            return;
+        }
+        self.deliver(config, config.urgency.of(event), body.into());
+    }
+
+    /// For an alert rule that asks for it, whatever `events` says.
+    pub fn alert(&self, body: impl Into<String>) {
+        if let Some(config) = &self.config {
+            self.deliver(config, config.urgency.alert, body.into());
+        }
+    }
+
+    fn deliver(&self, config: &NotifyConfig, urgency: Urgency, body: String) {
+        let (backend, timeout_ms) = (config.backend, config.timeout_ms);
+        tokio::spawn(async move {
+            if let Err(e) = deliver(backend, urgency, timeout_ms, &body).await {
+                logfile::write("notify", json!({ "body": body, "error": e.to_string() }));
+            }
+        });
+    }
+}
+
+async fn deliver(
+    backend: NotifyBackend,
+    urgency: Urgency,
+    timeout_ms: u32,
+    body: &str,
+) -> Result<()> {
+    if backend != NotifyBackend::NotifySend {
+        let sent = tokio::time::timeout(DBUS_TIMEOUT, dbus_notify(urgency, timeout_ms, body)).await;
+        match sent {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(e)) if backend == NotifyBackend::Dbus => return Err(e),
+            Err(_) if backend == NotifyBackend::Dbus => {
+                return Err(std::io::Error::other("session bus timed out").into());
+            }
+            _ => {}
+        }
+    }
+    let out = Command::new("notify-send")
+        .args([
+            "-u",
+            urgency.name(),
+            "-t",
+            &timeout_ms.to_string(),
+            TITLE,
+            body,
+        ])
+        .output()
+        .await?;
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        return Err(std::io::Error::other(format!("notify-send: {err}")).into());
+    }
+    Ok(())
+}
+
+/// `org.freedesktop.Notifications.Notify` over the session bus, spoken directly: SASL EXTERNAL,
+/// `Hello`, then the call, waiting for its reply so errors (no notification daemon) show up.
+async fn dbus_notify(urgency: Urgency, timeout_ms: u32, body: &str) -> Result<()> {
+    let mut stream = connect_session_bus()?;
+    // SAFETY: getuid(2) takes no arguments, always succeeds and touches no memory of ours.
+    let uid = unsafe { libc::getuid() }.to_string();
+    let hex: String = uid.bytes().map(|b| format!("{b:02x}")).collect();
+    stream
+        .write_all(format!("\0AUTH EXTERNAL {hex}\r\n").as_bytes())
+        .await?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    if !line.starts_with("OK ") {
+        return Err(std::io::Error::other(format!("bus refused auth: {}", line.trim())).into());
+    }
+    let mut hello = Message::call(
+        1,
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        "org.freedesktop.DBus",
+        "Hello",
+        "",
+    );
+    let mut notify = notify_call(2, urgency, timeout_ms, body);
+
+    let stream = reader.get_mut();
+    stream.write_all(b"BEGIN\r\n").await?;
+    stream.write_all(&hello.finish()).await?;
+    stream.write_all(&notify.finish()).await?;
+
+    // Skip the Hello reply and NameAcquired until the answer to serial 2 arrives.
+    loop {
+        let mut fixed = [0u8; 16];
+        reader.read_exact(&mut fixed).await?;
+        if fixed[0] != b'l' {
+            return Err(std::io::Error::other("big-endian bus replies aren't supported").into());
+        }
+        let body_len = u32::from_le_bytes(fixed[4..8].try_into()?) as usize;
+        let fields_len = u32::from_le_bytes(fixed[12..16].try_into()?) as usize;
+        let rest = fields_len.div_ceil(8) * 8 + body_len;
+        // Lengths come from the wire; a broken or hostile bus mustn't make us allocate gigabytes.
+        if fixed.len() + rest > MAX_MESSAGE_LEN {
+            return Err(
+                std::io::Error::other(format!("bus reply of {rest} bytes is too big")).into(),
+            );
+        }
+        let mut buf = vec![0u8; rest];
+        reader.read_exact(&mut buf).await?;
+        let fields = header_fields(&buf[..fields_len]);
+        if fields.reply_serial != Some(2) {
+            continue;
+        }
+        return match fixed[1] {
+            2 => Ok(()),
+            _ => Err(std::io::Error::other(
+                fields
+                    .error_name
+                    .unwrap_or_else(|| "Notify failed".to_string()),
+            )
+            .into()),
+        };
+    }
+}
+
+/// `Notify(app_name, replaces_id, app_icon, summary, body, actions, hints, expire_timeout)`.
+fn notify_call(serial: u32, urgency: Urgency, timeout_ms: u32, body: &str) -> Message {
+    let mut notify = Message::call(
+        serial,
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+        "Notify",
+        "susssasa{sv}i",
+    );
+    notify.string("ethtui");
+    notify.u32(0);
+    notify.string("network-wired");
+    notify.string(TITLE);
+    notify.string(body);
+    notify.u32(0); // no actions
+    // hints: {"urgency": <byte>}
+    notify.align(4);
+    let len_at = notify.body.len();
+    notify.u32(0);
+    notify.align(8);
+    let start = notify.body.len();
+    notify.string("urgency");
+    notify.signature("y");
+    notify.body.push(urgency as u8);
+    let len = (notify.body.len() - start) as u32;
+    notify.body[len_at..len_at + 4].copy_from_slice(&len.to_le_bytes());
+    notify.align(4);
+    notify
+        .body
+        .extend_from_slice(&(timeout_ms as i32).to_le_bytes());
+    notify
+}
+
+/// `unix:path=...` or `unix:abstract=...` from `DBUS_SESSION_BUS_ADDRESS`, else the usual
+/// `$XDG_RUNTIME_DIR/bus`.
+fn connect_session_bus() -> Result<UnixStream> {
+    let address = std::env::var("DBUS_SESSION_BUS_ADDRESS").ok();
+    let from_address = |key: &str| {
+        address.as_deref()?.split(';').find_map(|a| {
+            a.strip_prefix("unix:")?
+                .split(',')
+                .find_map(|kv| kv.strip_prefix(key))
+                .map(str::to_string)
+        })
+    };
+    let std_stream = if let Some(path) = from_address("path=") {
+        std::os::unix::net::UnixStream::connect(path)?
+    } else if let Some(name) = from_address("abstract=") {
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+        std::os::unix::net::UnixStream::connect_addr(&addr)?
+    } else {
+        let dir = std::env::var("XDG_RUNTIME_DIR")
+            .map_err(|_| std::io::Error::other("no session bus address"))?;
+        std::os::unix::net::UnixStream::connect(format!("{dir}/bus"))?
+    };
+    std_stream.set_nonblocking(true)?;
+    Ok(UnixStream::from_std(std_stream)?)
+}
+
+/// A little-endian method call being marshalled.
+struct Message {
+    header: Vec<u8>,
+    body: Vec<u8>,
+}
+
+impl Message {
+    fn call(serial: u32, dest: &str, path: &str, iface: &str, member: &str, sig: &str) -> Self {
+        let mut header = Vec::new();
+        header.extend_from_slice(&[b'l', 1, 0, 1]);
+        header.extend_from_slice(&0u32.to_le_bytes()); // body length, set by finish
+        header.extend_from_slice(&serial.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes()); // header fields length
+        let mut m = Self {
+            header,
+            body: Vec::new(),
+        };
+        m.field(1, "o", path);
+        m.field(2, "s", iface);
+        m.field(3, "s", member);
+        m.field(6, "s", dest);
+        if !sig.is_empty() {
+            m.field(8, "g", sig);
+        }
+        let fields_len = (m.header.len() - 16) as u32;
+        m.header[12..16].copy_from_slice(&fields_len.to_le_bytes());
+        m
+    }
+
+    /// One `(yv)` header field holding a string-like value.
+    fn field(&mut self, code: u8, sig: &str, value: &str) {
+        pad(&mut self.header, 8);
+        self.header
+            .extend_from_slice(&[code, 1, sig.as_bytes()[0], 0]);
+        if sig == "g" {
+            self.header.push(value.len() as u8);
+        } else {
+            pad(&mut self.header, 4);
+            self.header
+                .extend_from_slice(&(value.len() as u32).to_le_bytes());
+        }
+        self.header.extend_from_slice(value.as_bytes());
+        self.header.push(0);
+    }
+
+    fn align(&mut self, n: usize) {
+        pad(&mut self.body, n);
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.align(4);
+        self.body.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn string(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.body.extend_from_slice(s.as_bytes());
+        self.body.push(0);
+    }
+
+    fn signature(&mut self, s: &str) {
+        self.body.push(s.len() as u8);
+        self.body.extend_from_slice(s.as_bytes());
+        self.body.push(0);
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        let body_len = self.body.len() as u32;
+        self.header[4..8].copy_from_slice(&body_len.to_le_bytes());
+        let mut out = self.header.clone();
+        pad(&mut out, 8);
+        out.extend_from_slice(&self.body);
+        out
+    }
+}
+
+fn pad(buf: &mut Vec<u8>, n: usize) {
+    while !buf.len().is_multiple_of(n) {
+        buf.push(0);
+    }
+}
+
+#[derive(Default)]
+struct HeaderFields {
+    reply_serial: Option<u32>,
+    error_name: Option<String>,
+}
+
+/// Reads the fields after the fixed 16-byte header; `fields` starts 8-aligned at offset 16.
+fn header_fields(fields: &[u8]) -> HeaderFields {
+    let mut out = HeaderFields::default();
+    let mut i = 0;
+    let u32_at = |i: usize| {
+        fields
+            .get(i..i + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    while i + 4 <= fields.len() {
+        i = i.next_multiple_of(8);
+        let (Some(&code), Some(&sig_len)) = (fields.get(i), fields.get(i + 1)) else {
+            break;
+        };
+        let Some(&sig) = fields.get(i + 2) else {
+            break;
+        };
+        i += 2 + sig_len as usize + 1;
+        match sig {
+            b'u' => {
+                i = i.next_multiple_of(4);
+                if code == 5 {
+                    out.reply_serial = u32_at(i);
+                }
+                i += 4;
+            }
+            b's' | b'o' => {
+                i = i.next_multiple_of(4);
+                let Some(len) = u32_at(i) else {
+                    break;
+                };
+                let text = fields.get(i + 4..i + 4 + len as usize);
+                if code == 4 {
+                    out.error_name = text.map(|t| String::from_utf8_lossy(t).into_owned());
+                }
+                i += 4 + len as usize + 1;
+            }
+            b'g' => {
+                let len = fields.get(i).copied().unwrap_or(0) as usize;
+                i += 1 + len + 1;
+            }
+            _ => break,
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `Hello` every connection starts with: four string-like header fields, each 8-aligned,
+    /// no body and no signature field.
+    #[test]
+    fn hello_marshalling() {
+        let mut hello = Message::call(
+            1,
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "Hello",
+            "",
+        );
+        let want: &[u8] = b"l\x01\x00\x01\x00\x00\x00\x00\x01\x00\x00\x00\x6d\x00\x00\x00\
+            \x01\x01o\x00\x15\x00\x00\x00/org/freedesktop/DBus\x00\x00\x00\
+            \x02\x01s\x00\x14\x00\x00\x00org.freedesktop.DBus\x00\x00\x00\x00\
+            \x03\x01s\x00\x05\x00\x00\x00Hello\x00\x00\x00\
+            \x06\x01s\x00\x14\x00\x00\x00org.freedesktop.DBus\x00\x00\x00\x00";
+        assert_eq!(hello.finish(), want);
+    }
+
+    /// Captured with `dbus-monitor --binary` from a call dbus-daemon accepted.
+    #[test]
+    fn notify_body_marshalling() {
+        let mut notify = notify_call(2, Urgency::Critical, 2000, "eth0 is down");
+        let want: &[u8] = b"\x06\x00\x00\x00ethtui\x00\x00\x00\x00\x00\x00\
+            \x0d\x00\x00\x00network-wired\x00\x00\x00\
+            \x10\x00\x00\x00\xf3\xb0\x80\x82    Ethernet\x00\x00\x00\x00\
+            \x0c\x00\x00\x00eth0 is down\x00\x00\x00\x00\
+            \x00\x00\x00\x00\
+            \x10\x00\x00\x00\x07\x00\x00\x00urgency\x00\x01y\x00\x02\
+            \xd0\x07\x00\x00";
+        assert_eq!(notify.body, want);
+        let message = notify.finish();
+        assert_eq!(message.len() % 8, want.len() % 8);
+        assert!(message.ends_with(want));
+        assert_eq!(&message[4..8], &(want.len() as u32).to_le_bytes());
+    }
+
+    /// The dict entry inside the hints array starts 8-aligned whatever the body before it is.
+    #[test]
+    fn hints_padding_follows_body_length() {
+        for len in 0..16 {
+            let body = "x".repeat(len);
+            let notify = notify_call(2, Urgency::Low, 0, &body);
+            let key = notify
+                .body
+                .windows(7)
+                .position(|w| w == b"urgency")
+                .unwrap();
+            // The entry starts with the key's 4-byte length.
+            let entry = key - 4;
+            assert_eq!(entry % 8, 0, "body of {len} bytes");
+            // The array's length comes before it, 4-aligned, with at most 4 bytes of padding.
+            let at = if notify.body[entry - 4..entry] == [0; 4] {
+                entry - 8
+            } else {
+                entry - 4
+            };
+            let array_len = u32::from_le_bytes(notify.body[at..at + 4].try_into().unwrap());
+            assert_eq!(array_len, 16, "body of {len} bytes");
+            // Key, signature `y` and the byte itself; the timeout follows 4-aligned.
+            let timeout = (entry + 16).next_multiple_of(4);
+            assert_eq!(notify.body.len(), timeout + 4);
+        }
+    }
+
+    #[test]
+    fn pad_aligns() {
+        let mut buf = vec![1, 2, 3];
+        pad(&mut buf, 8);
+        assert_eq!(buf, [1, 2, 3, 0, 0, 0, 0, 0]);
+        pad(&mut buf, 8);
+        assert_eq!(buf.len(), 8);
+    }
+
+    /// Header fields of dbus-daemon's reply to `Hello`.
+    const RETURN_FIELDS: &[u8] = b"\x06\x01s\x00\x04\x00\x00\x00:1.3\x00\x00\x00\x00\
+        \x05\x01u\x00\x01\x00\x00\x00\
+        \x08\x01g\x00\x01s\x00\x00\
+        \x07\x01s\x00\x14\x00\x00\x00org.freedesktop.DBus\x00";
+
+    /// Header fields of its error for a `Notify` with no notification daemon running.
+    const ERROR_FIELDS: &[u8] = b"\x06\x01s\x00\x04\x00\x00\x00:1.3\x00\x00\x00\x00\
+        \x04\x01s\x00\x29\x00\x00\x00org.freedesktop.DBus.Error.ServiceUnknown\x00\x00\x00\
+        \x00\x00\x00\x00\
+        \x05\x01u\x00\x02\x00\x00\x00\
+        \x08\x01g\x00\x01s\x00\x00\
+        \x07\x01s\x00\x14\x00\x00\x00org.freedesktop.DBus\x00";
+
+    #[test]
+    fn method_return_header() {
+        let fields = header_fields(RETURN_FIELDS);
+        assert_eq!(fields.reply_serial, Some(1));
+        assert_eq!(fields.error_name, None);
+    }
+
+    #[test]
+    fn error_header() {
+        let fields = header_fields(ERROR_FIELDS);
+        assert_eq!(fields.reply_serial, Some(2));
+        assert_eq!(
+            fields.error_name.as_deref(),
+            Some("org.freedesktop.DBus.Error.ServiceUnknown")
+        );
+    }
+
+    #[test]
+    fn truncated_header_fields() {
+        for len in 0..ERROR_FIELDS.len() {
+            let fields = header_fields(&ERROR_FIELDS[..len]);
+            // The reply serial comes after the error name, so it can't be read from less.
+            if len < 72 {
+                assert_eq!(fields.reply_serial, None, "{len} bytes");
+            }
+        }
+    }
+}
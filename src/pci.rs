@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where distributions install the PCI ID database (`pciutils` / `hwdata`).
+const PCI_IDS: [&str; 3] = [
+    "/usr/share/hwdata/pci.ids",
+    "/usr/share/misc/pci.ids",
+    "/usr/share/pci.ids",
+];
+
+/// The PCI function behind a NIC, from sysfs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PciDevice {
+    /// `0000:01:00.0`.
+    pub address: String,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    /// From pci.ids; None when it isn't installed or doesn't know the device.
+    pub vendor_name: Option<String>,
+    pub device_name: Option<String>,
+    /// PCIe only, e.g. `8.0 GT/s PCIe`.
+    pub link_speed: Option<String>,
+    pub link_width: Option<u32>,
+    pub max_link_speed: Option<String>,
+    pub max_link_width: Option<u32>,
+    /// None on machines without NUMA (sysfs says -1).
+    pub numa_node: Option<u32>,
+}
+
+impl PciDevice {
+    /// `Intel Corporation Ethernet Controller X710 [8086:1572]`.
+    pub fn describe(&self) -> String {
+        let ids = format!("[{:04x}:{:04x}]", self.vendor_id, self.device_id);
+        match (&self.vendor_name, &self.device_name) {
+            (Some(vendor), Some(device)) => format!("{vendor} {device} {ids}"),
+            (Some(vendor), None) => format!("{vendor} {ids}"),
+            _ => ids,
+        }
+    }
+
+    /// The slot or a riser gives the card fewer lanes or a lower PCIe generation than it can use.
+    pub fn link_degraded(&self) -> bool {
+        let narrower =
+            matches!((self.link_width, self.max_link_width), (Some(w), Some(max)) if w < max);
+        let slower = match (&self.link_speed, &self.max_link_speed) {
+            (Some(s), Some(max)) => gts(s).zip(gts(max)).is_some_and(|(s, max)| s < max),
+            _ => false,
+        };
+        narrower || slower
+    }
+
+    /// Usable bandwidth of the negotiated link after line encoding, in Mb/s.
+    pub fn link_bandwidth_mbps(&self) -> Option<u32> {
+        let per_lane = gts(self.link_speed.as_deref()?)?;
+        // Gen 1 and 2 use 8b/10b, later generations 128b/130b.
+        let efficiency = if per_lane < 8.0 { 0.8 } else { 128.0 / 130.0 };
+        Some((per_lane * efficiency * 1000.0) as u32 * self.link_width?)
+    }
+}
+
+/// `8.0 GT/s PCIe` -> 8.0; `Unknown` (no link trained) -> None.
+fn gts(speed: &str) -> Option<f64> {
+    speed.split_whitespace().next()?.parse().ok()
+}
+
+/// None for virtual interfaces and NICs on other buses (USB, platform devices).
+pub fn device(iface: &str) -> Option<PciDevice> {
+    let dir = pci_dir(iface)?;
+    let uevent = fs::read_to_string(dir.join("uevent")).ok()?;
+    let field = |key: &str| {
+        uevent
+            .lines()
+            .find_map(|l| l.strip_prefix(key)?.strip_prefix('='))
+            .map(str::to_string)
+    };
+    let address = field("PCI_SLOT_NAME")?;
+    // `PCI_ID=8086:1572`.
+    let id = field("PCI_ID")?;
+    let (vendor, device) = id.split_once(':')?;
+    let vendor = u16::from_str_radix(vendor, 16).ok()?;
+    let device = u16::from_str_radix(device, 16).ok()?;
+    let read = |name: &str| {
+        fs::read_to_string(dir.join(name))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    let (vendor_name, device_name) = lookup_names(vendor, device);
+    Some(PciDevice {
+        address,
+        vendor_id: vendor,
+        device_id: device,
+        vendor_name,
+        device_name,
+        link_speed: read("current_link_speed"),
+        link_width: read("current_link_width").and_then(|w| w.parse().ok()),
+        max_link_speed: read("max_link_speed"),
+        max_link_width: read("max_link_width").and_then(|w| w.parse().ok()),
+        numa_node: read("numa_node").and_then(|n| n.parse().ok()),
+    })
+}
+
+/// The interface's device, or the nearest PCI parent of it: virtio NICs hang off a `virtioN`
+/// device below the PCI function.
+fn pci_dir(iface: &str) -> Option<PathBuf> {
+    let device = fs::canonicalize(Path::new("/sys/class/net").join(iface).join("device")).ok()?;
+    device
+        .ancestors()
+        .take_while(|d| d.starts_with("/sys/devices"))
+        .find(|d| fs::read_to_string(d.join("uevent")).is_ok_and(|u| u.contains("PCI_SLOT_NAME=")))
+        .map(Path::to_path_buf)
+}
+
+/// Vendor and device names from pci.ids: vendors at the start of a line, their devices indented by
+/// one tab.
+fn lookup_names(vendor: u16, device: u16) -> (Option<String>, Option<String>) {
+    let Some(ids) = PCI_IDS.iter().find_map(|p| fs::read_to_string(p).ok()) else {
+        return (None, None);
+    };
+    let entry = |line: &str, id: u16| {
+        let (hex, name) = line.split_once("  ")?;
+        (u16::from_str_radix(hex, 16).ok()? == id).then(|| name.trim().to_string())
+    };
+    let mut lines = ids.lines().filter(|l| !l.starts_with('#'));
+    let Some(vendor_name) = lines.find_map(|l| entry(l, vendor)) else {
+        return (None, None);
+    };
+    let device_name = lines
+        .take_while(|l| l.starts_with('\t') || l.is_empty())
+        .filter_map(|l| l.strip_prefix('\t').filter(|l| !l.starts_with('\t')))
+        .find_map(|l| entry(l, device));
+    (Some(vendor_name), device_name)
+}
@@ -0,0 +1,109 @@
+use crate::config::Config;
+use crate::hooks;
+use crate::logfile;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+/// One JSON line a plugin printed. Any combination of the fields works.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Reply {
+    /// The plugin's file name.
+    #[serde(skip)]
+    pub plugin: String,
+    /// Defaults to the interface of the event it answers.
+    pub interface: Option<String>,
+    /// Shown on the interface's Overview tab until the same plugin replaces it; empty removes it.
+    pub annotation: Option<String>,
+    /// A line for the event log.
+    pub log: Option<String>,
+    /// A `:` command line, run as if it had been typed (confirmations included).
+    pub command: Option<String>,
+}
+
+/// Every executable in the plugin directory, run once per event with it as JSON on stdin.
+/// Whatever they print back arrives as `Reply`s on the receiver `open` returns.
+#[derive(Debug, Clone)]
+pub struct Plugins {
+    pub dir: PathBuf,
+    replies: mpsc::UnboundedSender<Reply>,
+}
+
+impl Plugins {
+    /// `plugins/` next to the config file.
+    pub fn default_dir() -> Option<PathBuf> {
+        Some(Config::path()?.parent()?.join("plugins"))
+    }
+
+    /// None without a home directory; a missing directory just has no plugins yet.
+    pub fn open() -> Option<(Self, mpsc::UnboundedReceiver<Reply>)> {
+        let (replies, rx) = mpsc::unbounded_channel();
+        let plugins = Self {
+            dir: Self::default_dir()?,
+            replies,
+        };
+        Some((plugins, rx))
+    }
+
+    /// Starts every plugin in the background. The directory is listed each time, so plugins
+    /// dropped in or removed take effect without a restart.
+    pub fn fire(&self, body: &Value) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if is_plugin(&path) {
+                tokio::spawn(run(path, body.clone(), self.replies.clone()));
+            }
+        }
+    }
+}
+
+/// Executable regular files (following symlinks), skipping hidden ones like editor swap files.
+fn is_plugin(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .is_none_or(|n| n.to_string_lossy().starts_with('.'));
+    !hidden && fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+async fn run(path: PathBuf, body: Value, replies: mpsc::UnboundedSender<Reply>) {
+    let plugin = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let result = hooks::pipe_json(Command::new(&path), &body)
+        .await
+        .and_then(|stdout| {
+            stdout
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| {
+                    serde_json::from_str::<Reply>(line)
+                        .map_err(|e| format!("bad reply {line:?}: {e}"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        });
+    let mut fields =
+        json!({ "plugin": plugin, "event": body["event"], "interface": body["interface"] });
+    match result {
+        Ok(answered) => {
+            fields["replies"] = json!(answered.len());
+            for mut reply in answered {
+                reply.plugin = plugin.clone();
+                if reply.interface.is_none() {
+                    reply.interface = body["interface"].as_str().map(str::to_string);
+                }
+                let _ = replies.send(reply);
+            }
+        }
+        Err(e) => fields["error"] = json!(e),
+    }
+    logfile::write("plugin", fields);
+}
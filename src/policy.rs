@@ -0,0 +1,187 @@
+use crate::net::{Route, iproute2_json};
+use anyhow::Result;
+use serde::{Deserialize, Deserializer};
+use std::net::IpAddr;
+
+/// Tables every system has; routes in them are what the Routes tab already shows or plumbing.
+const STANDARD_TABLES: [&str; 3] = ["main", "local", "default"];
+
+/// One entry from `ip -j rule show`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Rule {
+    #[serde(default)]
+    pub priority: u32,
+    /// iproute2 prints `"not": null` for `not from ...`.
+    #[serde(default, deserialize_with = "present")]
+    pub not: bool,
+    /// `all`, or the network address with `srclen` beside it.
+    #[serde(default)]
+    pub src: Option<String>,
+    #[serde(default)]
+    pub srclen: Option<u8>,
+    #[serde(default)]
+    pub dst: Option<String>,
+    #[serde(default)]
+    pub dstlen: Option<u8>,
+    #[serde(default)]
+    pub iif: Option<String>,
+    #[serde(default)]
+    pub oif: Option<String>,
+    #[serde(default)]
+    pub fwmark: Option<String>,
+    #[serde(default)]
+    pub fwmask: Option<String>,
+    #[serde(default)]
+    pub table: Option<String>,
+    /// `unreachable`, `prohibit` or `blackhole` for rules that don't look up a table.
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+fn present<'de, D: Deserializer<'de>>(d: D) -> Result<bool, D::Error> {
+    serde::de::IgnoredAny::deserialize(d).map(|_| true)
+}
+
+impl Rule {
+    /// The lookup every system has (local, main, default), which routes by destination alone.
+    fn is_standard(&self) -> bool {
+        self.src.as_deref().is_none_or(|s| s == "all")
+            && self.dst.is_none()
+            && self.iif.is_none()
+            && self.oif.is_none()
+            && self.fwmark.is_none()
+            && self
+                .table
+                .as_deref()
+                .is_some_and(|t| STANDARD_TABLES.contains(&t))
+    }
+
+    /// Whether the rule names `iface`, matches traffic from or to one of `addrs`, or sends it to
+    /// one of `tables`.
+    fn references(&self, iface: &str, addrs: &[IpAddr], tables: &[String]) -> bool {
+        let covers = |net: &Option<String>, len: Option<u8>| {
+            let Some(net) = net.as_deref().and_then(|n| n.parse::<IpAddr>().ok()) else {
+                return false;
+            };
+            addrs.iter().any(|a| in_prefix(*a, net, len))
+        };
+        self.iif.as_deref() == Some(iface)
+            || self.oif.as_deref() == Some(iface)
+            || covers(&self.src, self.srclen)
+            || covers(&self.dst, self.dstlen)
+            || self.table.as_ref().is_some_and(|t| tables.contains(t))
+    }
+
+    /// `1000: from 192.0.2.0/24 iif eth0 lookup 100`, like `ip rule` prints it.
+    pub fn describe(&self) -> String {
+        let prefix = |net: &str, len: Option<u8>| match len {
+            Some(len) => format!("{net}/{len}"),
+            None => net.to_string(),
+        };
+        let mut s = format!("{}:", self.priority);
+        if self.not {
+            s.push_str(" not");
+        }
+        s.push_str(&format!(
+            " from {}",
+            self.src
+                .as_deref()
+                .map_or("all".into(), |n| prefix(n, self.srclen))
+        ));
+        if let Some(dst) = &self.dst {
+            s.push_str(&format!(" to {}", prefix(dst, self.dstlen)));
+        }
+        if let Some(mark) = &self.fwmark {
+            s.push_str(&format!(" fwmark {mark}"));
+            if let Some(mask) = &self.fwmask {
+                s.push_str(&format!("/{mask}"));
+            }
+        }
+        if let Some(iif) = &self.iif {
+            s.push_str(&format!(" iif {iif}"));
+        }
+        if let Some(oif) = &self.oif {
+            s.push_str(&format!(" oif {oif}"));
+        }
+        match (&self.table, &self.action) {
+            (Some(table), _) => s.push_str(&format!(" lookup {table}")),
+            (None, Some(action)) => s.push_str(&format!(" {action}")),
+            (None, None) => {}
+        }
+        s
+    }
+}
+
+fn in_prefix(addr: IpAddr, net: IpAddr, len: Option<u8>) -> bool {
+    match (addr, net) {
+        (IpAddr::V4(a), IpAddr::V4(n)) => {
+            let len = u32::from(len.unwrap_or(32).min(32));
+            let mask = u32::MAX.checked_shl(32 - len).unwrap_or(0);
+            u32::from(a) & mask == u32::from(n) & mask
+        }
+        (IpAddr::V6(a), IpAddr::V6(n)) => {
+            let len = u32::from(len.unwrap_or(128).min(128));
+            let mask = u128::MAX.checked_shl(128 - len).unwrap_or(0);
+            u128::from(a) & mask == u128::from(n) & mask
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TableRoute {
+    #[serde(flatten)]
+    route: Route,
+    /// Absent for the main table.
+    #[serde(default)]
+    table: Option<String>,
+    /// `local`, `broadcast`, `multicast`; absent for plain unicast routes.
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+}
+
+/// Source-based routing that involves an interface: the rules that pick it out, and the routes
+/// through it in tables other than main.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyRouting {
+    pub rules: Vec<Rule>,
+    /// Table name or number, with its routes via the interface.
+    pub tables: Vec<(String, Vec<Route>)>,
+}
+
+impl PolicyRouting {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.tables.is_empty()
+    }
+}
+
+/// Rules (IPv4 and IPv6) and non-main tables that reference `iface` or one of `addrs`
+/// (`address/prefix`).
+pub async fn policy_for(iface: &str, addrs: &[String]) -> Result<PolicyRouting> {
+    let routes: Vec<TableRoute> =
+        iproute2_json("ip", &["route", "show", "table", "all", "dev", iface]).await?;
+    let mut tables: Vec<(String, Vec<Route>)> = Vec::new();
+    for r in routes {
+        let Some(table) = r.table.filter(|t| !STANDARD_TABLES.contains(&t.as_str())) else {
+            continue;
+        };
+        if r.kind.is_some() {
+            continue;
+        }
+        match tables.iter_mut().find(|(t, _)| *t == table) {
+            Some((_, routes)) => routes.push(r.route),
+            None => tables.push((table, vec![r.route])),
+        }
+    }
+    let names: Vec<String> = tables.iter().map(|(t, _)| t.clone()).collect();
+
+    let addrs: Vec<IpAddr> = addrs
+        .iter()
+        .filter_map(|a| a.split('/').next()?.parse().ok())
+        .collect();
+    let mut rules: Vec<Rule> = iproute2_json("ip", &["-4", "rule", "show"]).await?;
+    rules.extend(iproute2_json::<Rule>("ip", &["-6", "rule", "show"]).await?);
+    rules.retain(|r| !r.is_standard() && r.references(iface, &addrs, &names));
+    rules.sort_by_key(|r| r.priority);
+    Ok(PolicyRouting { rules, tables })
+}
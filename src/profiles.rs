@@ -0,0 +1,138 @@
+use crate::net::{EthernetDevice, StaticIpv4Config};
+use serde::Deserialize;
+use std::net::IpAddr;
+
+/// A `[[profiles]]` entry: settings applied to the selected interface together.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub name: String,
+    /// Take the address and gateway from DHCP.
+    #[serde(default)]
+    pub dhcp: bool,
+    /// `a.b.c.d/prefix`, replacing every IPv4 address.
+    pub address: Option<String>,
+    /// Becomes the default route; needs `address`.
+    pub gateway: Option<String>,
+    /// The link's DNS servers in systemd-resolved, IPv4 or IPv6.
+    #[serde(default)]
+    pub dns: Vec<String>,
+    pub mtu: Option<u32>,
+}
+
+impl Profile {
+    /// At least one setting, and each of them well-formed.
+    pub fn validate(&self) -> Result<(), String> {
+        let name = &self.name;
+        if name.trim().is_empty() {
+            return Err("a profile needs a name".into());
+        }
+        if self.dhcp && (self.address.is_some() || self.gateway.is_some()) {
+            return Err(format!(
+                "profile {name}: dhcp can't be combined with address or gateway"
+            ));
+        }
+        if self.gateway.is_some() && self.address.is_none() {
+            return Err(format!("profile {name}: gateway needs an address"));
+        }
+        if let Some(address) = &self.address {
+            self.ipv4(address)
+                .map_err(|e| format!("profile {name}: {e}"))?;
+        }
+        if let Some(bad) = self.dns.iter().find(|s| s.parse::<IpAddr>().is_err()) {
+            return Err(format!("profile {name}: invalid DNS server: {bad}"));
+        }
+        // 68 is the IPv4 minimum; drivers enforce their own maximum.
+        if self.mtu.is_some_and(|m| !(68..=65535).contains(&m)) {
+            return Err(format!("profile {name}: MTU must be between 68 and 65535"));
+        }
+        if !self.dhcp && self.address.is_none() && self.dns.is_empty() && self.mtu.is_none() {
+            return Err(format!("profile {name} doesn't set anything"));
+        }
+        Ok(())
+    }
+
+    /// The address and gateway, parsed like the static IPv4 form's.
+    pub fn ipv4(&self, address: &str) -> anyhow::Result<StaticIpv4Config> {
+        StaticIpv4Config::parse(address, self.gateway.as_deref().unwrap_or(""), "")
+    }
+
+    /// Whether applying it replaces the IPv4 addresses.
+    pub fn sets_addresses(&self) -> bool {
+        self.dhcp || self.address.is_some()
+    }
+
+    /// `Setting: old -> new` for each setting it changes, from what `before` recorded.
+    pub fn changes(&self, before: &SavedState) -> Vec<String> {
+        let or_none = |v: Vec<String>| {
+            if v.is_empty() {
+                "none".into()
+            } else {
+                v.join(" ")
+            }
+        };
+        let mut lines = Vec::new();
+        if let Some((addrs, gateway)) = &before.ipv4 {
+            let (address, gateway_new) = if self.dhcp {
+                ("from DHCP".to_string(), "from DHCP".to_string())
+            } else {
+                (
+                    self.address.clone().unwrap_or_default(),
+                    self.gateway.clone().unwrap_or_else(|| "unchanged".into()),
+                )
+            };
+            lines.push(format!("Address: {} -> {address}", or_none(addrs.clone())));
+            lines.push(format!(
+                "Gateway: {} -> {gateway_new}",
+                gateway.as_deref().unwrap_or("none")
+            ));
+        }
+        if let Some(dns) = &before.dns {
+            lines.push(format!(
+                "DNS: {} -> {}",
+                or_none(dns.clone()),
+                self.dns.join(" ")
+            ));
+        }
+        if let Some(mtu) = self.mtu {
+            let old = before.mtu.map_or("?".to_string(), |m| m.to_string());
+            lines.push(format!("MTU: {old} -> {mtu}"));
+        }
+        lines
+    }
+}
+
+/// The settings a profile replaced, as they were before; None for the ones it left alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SavedState {
+    /// IPv4 addresses and the default gateway.
+    pub ipv4: Option<(Vec<String>, Option<String>)>,
+    pub dns: Option<Vec<String>>,
+    pub mtu: Option<u32>,
+    /// It had a DHCP lease, which is renewed after the addresses are put back.
+    pub dhcp: bool,
+}
+
+impl SavedState {
+    /// What applying `profile` to `device` (whose MTU is `mtu`) is about to replace.
+    pub fn before(profile: &Profile, device: &EthernetDevice, mtu: Option<u32>) -> Self {
+        Self {
+            ipv4: profile
+                .sets_addresses()
+                .then(|| (device.ipv4.clone(), device.gateway_v4.clone())),
+            dns: (!profile.dns.is_empty()).then(|| device.dns.clone()),
+            mtu: mtu.filter(|_| profile.mtu.is_some()),
+            dhcp: device.lease.is_some(),
+        }
+    }
+}
+
+/// The first name used by two profiles; they're picked by name, so names must be unique.
+pub fn duplicate_name(profiles: &[Profile]) -> Option<&str> {
+    profiles.iter().enumerate().find_map(|(i, p)| {
+        profiles[..i]
+            .iter()
+            .any(|q| q.name == p.name)
+            .then_some(p.name.as_str())
+    })
+}
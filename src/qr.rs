@@ -0,0 +1,469 @@
+/// A QR code symbol in byte mode with error correction level L. Only versions 1-5 (up to 106
+/// bytes, 37x37 modules) are produced: every one of them is a single Reed-Solomon block with at
+/// most one alignment pattern, and anything bigger wouldn't fit a terminal anyway.
+#[derive(Debug, Clone)]
+pub struct QrCode {
+    size: usize,
+    /// Row-major, true for dark.
+    modules: Vec<bool>,
+}
+
+/// (data codewords, error correction codewords) for versions 1-5 at level L.
+const CODEWORDS: [(usize, usize); 5] = [(19, 7), (34, 10), (55, 15), (80, 20), (108, 26)];
+
+/// Format bits for level L (`01`).
+const ECL_L: u32 = 1;
+
+impl QrCode {
+    /// None when `data` is too long for version 5.
+    pub fn encode(data: &[u8]) -> Option<Self> {
+        // Mode indicator, 8-bit length (versions 1-9), then the bytes.
+        let bits_needed = 4 + 8 + data.len() * 8;
+        let version = CODEWORDS.iter().position(|(d, _)| bits_needed <= d * 8)? + 1;
+        let (data_len, ecc_len) = CODEWORDS[version - 1];
+
+        let mut bits = BitBuffer::default();
+        bits.push(0b0100, 4);
+        bits.push(data.len() as u32, 8);
+        for &b in data {
+            bits.push(b.into(), 8);
+        }
+        let capacity = data_len * 8;
+        bits.push(0, (capacity - bits.len).min(4));
+        bits.push(0, (8 - bits.len % 8) % 8);
+        let mut codewords = bits.bytes;
+        for pad in [0xEC, 0x11].into_iter().cycle() {
+            if codewords.len() == data_len {
+                break;
+            }
+            codewords.push(pad);
+        }
+        let ecc = reed_solomon(&codewords, ecc_len);
+        codewords.extend(ecc);
+
+        let size = version * 4 + 17;
+        let mut qr = Builder {
+            size,
+            modules: vec![false; size * size],
+            function: vec![false; size * size],
+        };
+        qr.draw_function_patterns(version);
+        qr.draw_codewords(&codewords);
+
+        // Pick the mask that leaves the fewest scanner-confusing patterns.
+        let best = (0..8)
+            .min_by_key(|&mask| {
+                let mut candidate = qr.clone();
+                candidate.apply_mask(mask);
+                candidate.draw_format_bits(mask);
+                candidate.penalty()
+            })
+            .unwrap_or(0);
+        qr.apply_mask(best);
+        qr.draw_format_bits(best);
+
+        Some(Self {
+            size,
+            modules: qr.modules,
+        })
+    }
+
+    /// Width and height in modules, without a quiet zone.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module at column `x`, row `y` is dark; anything outside the symbol is light.
+    pub fn dark(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+}
+
+#[derive(Default)]
+struct BitBuffer {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitBuffer {
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                let last = self.bytes.len() - 1;
+                self.bytes[last] |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Builder {
+    size: usize,
+    modules: Vec<bool>,
+    /// Finder, timing, alignment and format modules, which data and masks skip.
+    function: Vec<bool>,
+}
+
+impl Builder {
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        for (x, y) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            self.draw_finder(x, y);
+        }
+        if version > 1 {
+            // Versions 2-6 have a single alignment pattern, near the bottom right corner.
+            let center = size - 7;
+            for y in center - 2..=center + 2 {
+                for x in center - 2..=center + 2 {
+                    let dist = x.abs_diff(center).max(y.abs_diff(center));
+                    self.set_function(x, y, dist != 1);
+                }
+            }
+        }
+        // Reserved for now; the real bits go in once the mask is chosen.
+        self.draw_format_bits(0);
+    }
+
+    /// The 7x7 finder centered on (x, y) plus its light separator.
+    fn draw_finder(&mut self, x: usize, y: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (Some(xx), Some(yy)) = (
+                    x.checked_add_signed(dx as isize),
+                    y.checked_add_signed(dy as isize),
+                ) else {
+                    continue;
+                };
+                if xx < self.size && yy < self.size {
+                    let dist = dx.abs().max(dy.abs());
+                    self.set_function(xx, yy, dist != 2 && dist != 4);
+                }
+            }
+        }
+    }
+
+    /// Level and mask, BCH-protected, in both copies next to the finders.
+    fn draw_format_bits(&mut self, mask: u32) {
+        let data = (ECL_L << 3) | mask;
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | rem) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+
+        for i in 0..6 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        let size = self.size;
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        // Always dark.
+        self.set_function(8, size - 8, true);
+    }
+
+    /// Fills the non-function modules in the standard zigzag, two columns at a time from the
+    /// bottom right, skipping the vertical timing pattern.
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let total_bits = codewords.len() * 8;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { size - 1 - vert } else { vert };
+                    if !self.function[y * size + x] && i < total_bits {
+                        self.modules[y * size + x] = (codewords[i >> 3] >> (7 - (i & 7))) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let i = y * self.size + x;
+                if invert && !self.function[i] {
+                    self.modules[i] = !self.modules[i];
+                }
+            }
+        }
+    }
+
+    /// The four penalty rules of ISO/IEC 18004: long runs, 2x2 blocks, finder-like patterns and
+    /// an unbalanced dark/light ratio.
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let at = |x: usize, y: usize| self.modules[y * size + x];
+        let mut score = 0;
+
+        let lines = (0..size).flat_map(|i| {
+            [
+                (0..size).map(|j| at(j, i)).collect::<Vec<_>>(),
+                (0..size).map(|j| at(i, j)).collect::<Vec<_>>(),
+            ]
+        });
+        for line in lines {
+            let mut run = 1;
+            for i in 1..=line.len() {
+                if i < line.len() && line[i] == line[i - 1] {
+                    run += 1;
+                    continue;
+                }
+                if run >= 5 {
+                    score += run - 2;
+                }
+                run = 1;
+            }
+
+            // Runs as (dark, length), with the quiet zone around the symbol counted as light.
+            let mut runs: Vec<(bool, usize)> = vec![(false, size)];
+            for &m in &line {
+                match runs.last_mut() {
+                    Some((dark, len)) if *dark == m => *len += 1,
+                    _ => runs.push((m, 1)),
+                }
+            }
+            match runs.last_mut() {
+                Some((false, len)) => *len += size,
+                _ => runs.push((false, size)),
+            }
+            // Dark-light-dark-light-dark at 1:1:3:1:1, with four light on one side of it.
+            for i in 1..runs.len().saturating_sub(5) {
+                let n = runs[i].1;
+                let lens = [runs[i + 1].1, runs[i + 2].1, runs[i + 3].1, runs[i + 4].1];
+                if !runs[i].0 || lens != [n, 3 * n, n, n] {
+                    continue;
+                }
+                let (before, after) = (runs[i - 1].1, runs[i + 5].1);
+                if before >= 4 * n && after >= n {
+                    score += 40;
+                }
+                if after >= 4 * n && before >= n {
+                    score += 40;
+                }
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let c = at(x, y);
+                if c == at(x + 1, y) && c == at(x, y + 1) && c == at(x + 1, y + 1) {
+                    score += 3;
+                }
+            }
+        }
+
+        let total = size * size;
+        let dark = self.modules.iter().filter(|m| **m).count();
+        let k = (dark * 20)
+            .abs_diff(total * 10)
+            .div_ceil(total)
+            .saturating_sub(1);
+        score + k * 10
+    }
+}
+
+/// Error correction codewords: the remainder of dividing `data` by the generator polynomial
+/// of degree `degree` over GF(256).
+fn reed_solomon(data: &[u8], degree: usize) -> Vec<u8> {
+    let mut divisor = vec![0u8; degree];
+    divisor[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            divisor[j] = gf_mul(divisor[j], root);
+            if j + 1 < degree {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = gf_mul(root, 0x02);
+    }
+
+    let mut result = vec![0u8; degree];
+    for &b in data {
+        let factor = b ^ result.remove(0);
+        result.push(0);
+        for (r, d) in result.iter_mut().zip(&divisor) {
+            *r ^= gf_mul(*d, factor);
+        }
+    }
+    result
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_mul(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((u32::from(y) >> i) & 1) * u32::from(x);
+    }
+    z as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference symbols from Nayuki's qrcodegen (byte mode, level L), `#` for dark.
+    const ETHTUI: [&str; 21] = [
+        "#######.#####.#######",
+        "#.....#..#.#..#.....#",
+        "#.###.#..##...#.###.#",
+        "#.###.#..###..#.###.#",
+        "#.###.#...#.#.#.###.#",
+        "#.....#....##.#.....#",
+        "#######.#.#.#.#######",
+        "........#####........",
+        "##.##.#..##.#.#.....#",
+        ".#.#.#.#.##.#.#.####.",
+        "#.#.####..#..##.#####",
+        "....#......#......##.",
+        "..#####.##....#..#..#",
+        "........##.##......#.",
+        "#######..#..#####.#..",
+        "#.....#..#.###.####..",
+        "#.###.#.#..##.###..##",
+        "#.###.#.#####.....#..",
+        "#.###.#..#....#.#####",
+        "#.....#.###..###.####",
+        "#######.####....##...",
+    ];
+
+    const REPO_URL: [&str; 29] = [
+        "#######..#..####..##..#######",
+        "#.....#.#..##..##...#.#.....#",
+        "#.###.#.#####....##...#.###.#",
+        "#.###.#..###..###.#...#.###.#",
+        "#.###.#.#....#.####...#.###.#",
+        "#.....#.##.#.#.##..#..#.....#",
+        "#######.#.#.#.#.#.#.#.#######",
+        "........##..#..#.###.........",
+        "##.#..##..#..##.#..#..###.##.",
+        "#...##....##.##.##..#.#..#..#",
+        "#.#..###.##..#..#..#.#..####.",
+        "..#.#...#....#..#...#...#.##.",
+        "#####.#.....##.###...##..#.##",
+        "...#.#....####..#..###.......",
+        "###.#.#.#.#.#....##.#.#######",
+        ".#.##...##..####.##.###..#.#.",
+        "#..#..#...##.##..#..#..#...#.",
+        "..#.##.##.#...#.#...####.#..#",
+        "#..#####..#..#....#..#.##..##",
+        "....##..##.#.#..###.##.##..##",
+        "#.##.##.##.##.###..######.#..",
+        "........#..###..#..##...#.###",
+        "#######.##.####..#..#.#.#..#.",
+        "#.....#..#.###.##...#...####.",
+        "#.###.#..#.#..####..#####....",
+        "#.###.#.####.##..#.##.######.",
+        "#.###.#...##.##..#.##...###.#",
+        "#.....#.##.#...###.###..#..#.",
+        "#######.##..##..##..######.#.",
+    ];
+
+    fn rows(qr: &QrCode) -> Vec<String> {
+        (0..qr.size())
+            .map(|y| {
+                (0..qr.size())
+                    .map(|x| if qr.dark(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_reference_version_1() {
+        let qr = QrCode::encode(b"ethtui").unwrap();
+        assert_eq!(rows(&qr), ETHTUI);
+    }
+
+    #[test]
+    fn matches_reference_version_3() {
+        let qr = QrCode::encode(b"https://github.com/skibidiandulka/ethtui").unwrap();
+        assert_eq!(rows(&qr), REPO_URL);
+    }
+
+    #[test]
+    fn version_follows_length() {
+        assert_eq!(QrCode::encode(&[b'a'; 17]).unwrap().size(), 21);
+        assert_eq!(QrCode::encode(&[b'a'; 18]).unwrap().size(), 25);
+        assert_eq!(QrCode::encode(&[b'a'; 106]).unwrap().size(), 37);
+        assert!(QrCode::encode(&[b'a'; 107]).is_none());
+    }
+
+    /// ISO/IEC 18004 Annex I: "01234567" as version 1-M.
+    #[test]
+    fn reed_solomon_iso_example() {
+        let data = [
+            0x10, 0x20, 0x0C, 0x56, 0x61, 0x80, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11,
+            0xEC, 0x11,
+        ];
+        let ecc = [0xA5, 0x24, 0xD4, 0xC1, 0xED, 0x36, 0xC7, 0x87, 0x2C, 0x55];
+        assert_eq!(reed_solomon(&data, 10), ecc);
+    }
+
+    /// "HELLO WORLD" as version 1-M, the worked example on thonky.com's QR tutorial.
+    #[test]
+    fn reed_solomon_hello_world() {
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        let ecc = [196, 35, 39, 119, 235, 215, 231, 226, 93, 23];
+        assert_eq!(reed_solomon(&data, 10), ecc);
+    }
+
+    #[test]
+    fn gf_mul_reduces_by_0x11d() {
+        assert_eq!(gf_mul(0x80, 0x02), 0x1D);
+        assert_eq!(gf_mul(0x53, 0xCA), 0x8F);
+        assert_eq!(gf_mul(0x01, 0xB7), 0xB7);
+        assert_eq!(gf_mul(0x00, 0xB7), 0x00);
+    }
+}
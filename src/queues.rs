@@ -0,0 +1,313 @@
+use crate::ethtool;
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// One interrupt line of the NIC, from `/proc/interrupts`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Irq {
+    pub number: u32,
+    /// As the driver registered it, e.g. `eth0-TxRx-3` or `mlx5_comp3@pci:0000:01:00.0`.
+    pub name: String,
+    /// CPUs it's delivered to, like `3` or `0-7`.
+    pub cpus: String,
+    /// Summed over every CPU since boot.
+    pub count: u64,
+}
+
+/// What's known about one queue (pair); counters are None when the driver doesn't report them.
+#[derive(Debug, Clone, Default)]
+pub struct Queue {
+    pub rx_packets: Option<u64>,
+    pub tx_packets: Option<u64>,
+    pub irqs: Vec<Irq>,
+    /// Per second since the previous reading; None on the first one.
+    pub rx_rate: Option<f64>,
+    pub tx_rate: Option<f64>,
+    pub irq_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Queues {
+    /// From `/sys/class/net/<iface>/queues`.
+    pub rx_count: usize,
+    pub tx_count: usize,
+    /// By queue number.
+    pub queues: BTreeMap<usize, Queue>,
+    /// The NIC's interrupts that aren't for one queue (link events, admin queue...).
+    pub other_irqs: Vec<Irq>,
+}
+
+impl Queues {
+    /// Fills in the rates from an earlier reading of the same interface.
+    pub fn rates_since(&mut self, prev: &Queues, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return;
+        }
+        let rate =
+            |now: Option<u64>, then: Option<u64>| Some(now?.saturating_sub(then?) as f64 / secs);
+        for (n, q) in &mut self.queues {
+            let Some(old) = prev.queues.get(n) else {
+                continue;
+            };
+            q.rx_rate = rate(q.rx_packets, old.rx_packets);
+            q.tx_rate = rate(q.tx_packets, old.tx_packets);
+            let irqs =
+                |q: &Queue| (!q.irqs.is_empty()).then(|| q.irqs.iter().map(|i| i.count).sum());
+            q.irq_rate = rate(irqs(q), irqs(old));
+        }
+    }
+
+    /// The busiest queue's share of all received packets per second, when there are several
+    /// queues and any traffic; an even spread over 8 queues is 12.5%.
+    pub fn rx_busiest_share(&self) -> Option<(usize, f64)> {
+        let rates: Vec<(usize, f64)> = self
+            .queues
+            .iter()
+            .filter_map(|(n, q)| Some((*n, q.rx_rate?)))
+            .collect();
+        let total: f64 = rates.iter().map(|(_, r)| r).sum();
+        if rates.len() < 2 || total <= 0.0 {
+            return None;
+        }
+        let (n, busiest) = rates.into_iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+        Some((n, busiest / total * 100.0))
+    }
+}
+
+/// Queue counts from sysfs, per-queue packets from `ethtool -S` and the NIC's interrupts with
+/// their CPU affinity. Virtual interfaces without interrupts still get their queues listed.
+pub async fn read_queues(iface: &str) -> Result<Queues> {
+    let (rx_count, tx_count) = queue_counts(iface)?;
+    let mut queues = Queues {
+        rx_count,
+        tx_count,
+        ..Queues::default()
+    };
+    for n in 0..rx_count.max(tx_count) {
+        queues.queues.insert(n, Queue::default());
+    }
+    // Not every driver has statistics, and veth or bridges have none at all.
+    for stat in ethtool::driver_stats(iface).await.unwrap_or_default() {
+        let Some((dir, n)) = queue_counter(&stat.name) else {
+            continue;
+        };
+        let q = queues.queues.entry(n).or_default();
+        match dir {
+            Dir::Rx => q.rx_packets = Some(stat.value),
+            Dir::Tx => q.tx_packets = Some(stat.value),
+        }
+    }
+    let interrupts = fs::read_to_string("/proc/interrupts").unwrap_or_default();
+    let msi = msi_irqs(iface);
+    for mut irq in parse_interrupts(&interrupts)
+        .into_iter()
+        .filter(|irq| msi.contains(&irq.number) || named_after(&irq.name, iface))
+    {
+        irq.cpus = irq_affinity(irq.number);
+        match irq_queue(&irq.name).filter(|n| queues.queues.contains_key(n)) {
+            Some(n) => queues.queues.entry(n).or_default().irqs.push(irq),
+            None => queues.other_irqs.push(irq),
+        }
+    }
+    Ok(queues)
+}
+
+fn queue_counts(iface: &str) -> Result<(usize, usize)> {
+    let dir = format!("/sys/class/net/{iface}/queues");
+    let (mut rx, mut tx) = (0, 0);
+    for entry in fs::read_dir(&dir)? {
+        let name = entry?.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with("rx-") {
+            rx += 1;
+        } else if name.starts_with("tx-") {
+            tx += 1;
+        }
+    }
+    Ok((rx, tx))
+}
+
+/// MSI/MSI-X vectors of the PCI device behind the interface; empty for anything else. A
+/// virtio NIC's interface hangs off a `virtioN` device whose PCI function has the vectors.
+fn msi_irqs(iface: &str) -> HashSet<u32> {
+    let device = Path::new("/sys/class/net").join(iface).join("device");
+    let virtio = fs::canonicalize(&device).is_ok_and(|p| {
+        p.file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with("virtio"))
+    });
+    let dir = if virtio {
+        device.join("..").join("msi_irqs")
+    } else {
+        device.join("msi_irqs")
+    };
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.file_name().to_string_lossy().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `eth0` and `eth0-rx-1`, but not `eth01`.
+fn named_after(irq: &str, iface: &str) -> bool {
+    irq.strip_prefix(iface)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+}
+
+/// Where the kernel actually delivers it, falling back to the requested mask's CPUs.
+fn irq_affinity(irq: u32) -> String {
+    ["effective_affinity_list", "smp_affinity_list"]
+        .iter()
+        .find_map(|file| {
+            fs::read_to_string(format!("/proc/irq/{irq}/{file}"))
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        })
+        .unwrap_or_else(|| "?".into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dir {
+    Rx,
+    Tx,
+}
+
+/// Per-queue packet counters in the spellings drivers use: `rx_queue_0_packets` (igb, ixgbe,
+/// virtio_net), `rx-0.packets` (i40e), `rx0_packets` (mlx5) and `queue_0_rx_cnt` (ena).
+fn queue_counter(key: &str) -> Option<(Dir, usize)> {
+    let dir = |d: &str| match d {
+        "rx" => Some(Dir::Rx),
+        "tx" => Some(Dir::Tx),
+        _ => None,
+    };
+    if let Some(rest) = key.strip_prefix("queue_") {
+        let (n, rest) = rest.split_once('_')?;
+        let (d, suffix) = rest.split_once('_')?;
+        return (suffix == "cnt").then_some((dir(d)?, n.parse().ok()?));
+    }
+    let d = dir(key.get(..2)?)?;
+    let rest = &key[2..];
+    let n = rest
+        .strip_prefix("_queue_")
+        .and_then(|r| r.strip_suffix("_packets"))
+        .or_else(|| rest.strip_prefix('-')?.strip_suffix(".packets"))
+        .or_else(|| rest.strip_suffix("_packets"))?;
+    Some((d, n.parse().ok()?))
+}
+
+/// `/proc/interrupts`: a header naming the CPUs, then `NN:` followed by one count per CPU, the
+/// chip and the handler names. Lines like `NMI:` without a number are skipped.
+fn parse_interrupts(text: &str) -> Vec<Irq> {
+    let mut lines = text.lines();
+    let cpus = lines.next().map_or(0, |h| h.split_whitespace().count());
+    lines
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let number = fields.next()?.strip_suffix(':')?.parse().ok()?;
+            let fields: Vec<&str> = fields.collect();
+            let count = fields
+                .iter()
+                .take(cpus)
+                .map_while(|c| c.parse::<u64>().ok())
+                .sum();
+            let name = fields.last()?.to_string();
+            Some(Irq {
+                number,
+                name,
+                count,
+                ..Irq::default()
+            })
+        })
+        .collect()
+}
+
+/// The queue an interrupt serves, from the number drivers end its name with: `eth0-TxRx-3`,
+/// `virtio0-input.3`, `mlx5_comp3@pci:...`. None for names without one, like `eth0` alone.
+fn irq_queue(name: &str) -> Option<usize> {
+    let name = name.split('@').next()?;
+    let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    let (head, n) = name.split_at(name.len() - digits);
+    // `eth0` is the device's own number, not a queue.
+    let separated = head.ends_with(['-', '.', '_']) || head.ends_with("comp");
+    separated.then(|| n.parse().ok()).flatten()
+}
+
+/// The kernel's own ceiling on CPU numbers (`CONFIG_NR_CPUS` tops out at 8192).
+const MAX_CPUS: usize = 8192;
+
+/// A CPU list as the kernel writes it in `*_affinity_list`, like `3` or `0-3,8`; None when it
+/// doesn't parse, is empty or names a CPU no kernel can have.
+pub fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',') {
+        match part.trim().split_once('-') {
+            Some((a, b)) => {
+                let (a, b): (usize, usize) = (a.trim().parse().ok()?, b.trim().parse().ok()?);
+                if a > b || b >= MAX_CPUS {
+                    return None;
+                }
+                cpus.extend(a..=b);
+            }
+            None => cpus.push(part.trim().parse().ok().filter(|&cpu| cpu < MAX_CPUS)?),
+        }
+    }
+    (!cpus.is_empty()).then_some(cpus)
+}
+
+/// From `/sys/devices/system/cpu/online`; just CPU 0 if that can't be read.
+pub fn online_cpus() -> Vec<usize> {
+    fs::read_to_string("/sys/devices/system/cpu/online")
+        .ok()
+        .and_then(|s| parse_cpu_list(&s))
+        .unwrap_or_else(|| vec![0])
+}
+
+/// One CPU per queue, round-robin over `cpus`: every interrupt of queue N goes to the Nth CPU,
+/// so RX and TX vectors of a queue (virtio) stay together.
+pub fn spread_plan(queues: &Queues, cpus: &[usize]) -> Vec<(Irq, usize)> {
+    if cpus.is_empty() {
+        return Vec::new();
+    }
+    queues
+        .queues
+        .values()
+        .filter(|q| !q.irqs.is_empty())
+        .enumerate()
+        .flat_map(|(i, q)| {
+            q.irqs
+                .iter()
+                .map(move |irq| (irq.clone(), cpus[i % cpus.len()]))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cpu_lists() {
+        let cases: [(&str, Option<Vec<usize>>); 7] = [
+            ("3", Some(vec![3])),
+            ("0-3,8\n", Some(vec![0, 1, 2, 3, 8])),
+            (" 1 - 2 ", Some(vec![1, 2])),
+            ("", None),
+            ("3-1", None),
+            ("0-4000000000", None),
+            ("8192", None),
+        ];
+        for (list, want) in cases {
+            assert_eq!(parse_cpu_list(list), want, "{list:?}");
+        }
+    }
+}
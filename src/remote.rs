@@ -0,0 +1,123 @@
+use crate::net::EthernetDevice;
+use anyhow::{Context, Result};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+
+/// How often the host is asked again; every poll is a new ssh connection.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Another machine's interfaces, read by running `ethtui list --json` there over ssh. Only
+/// what that prints is known: no routes, neighbors, hardware details or live netlink updates,
+/// and nothing can be changed.
+pub struct Remote {
+    /// Anything ssh accepts: `user@host`, a `Host` alias from ~/.ssh/config...
+    pub host: String,
+    command: String,
+    include_wifi: bool,
+    /// Everything the last good poll returned, before the `/` filter.
+    pub devices: Vec<EthernetDevice>,
+    /// Why the last poll failed; cleared by the next good one.
+    pub error: Option<String>,
+    /// A poll has succeeded at least once.
+    pub fetched: bool,
+    pending: Option<JoinHandle<Result<Vec<EthernetDevice>>>>,
+    last_poll: Instant,
+}
+
+impl Remote {
+    /// Starts polling in the background; `devices` stays empty until the first answer.
+    pub fn new(host: String, command: String, include_wifi: bool) -> Self {
+        let mut remote = Self {
+            host,
+            command,
+            include_wifi,
+            devices: Vec::new(),
+            error: None,
+            fetched: false,
+            pending: None,
+            last_poll: Instant::now(),
+        };
+        remote.poll_now();
+        remote
+    }
+
+    /// Fails when the first poll does, so a wrong host or a missing ethtui on it is reported
+    /// before the TUI starts.
+    pub async fn connect(host: String, command: String, include_wifi: bool) -> Result<Self> {
+        let devices = fetch(&host, &command, include_wifi).await?;
+        Ok(Self {
+            host,
+            command,
+            include_wifi,
+            devices,
+            error: None,
+            fetched: true,
+            pending: None,
+            last_poll: Instant::now(),
+        })
+    }
+
+    /// Starts a poll when one is due and collects a finished one; true when `devices` changed.
+    pub async fn poll(&mut self) -> bool {
+        if let Some(handle) = self.pending.take_if(|h| h.is_finished()) {
+            match handle.await {
+                Ok(Ok(devices)) => {
+                    self.devices = devices;
+                    self.error = None;
+                    self.fetched = true;
+                    return true;
+                }
+                Ok(Err(e)) => self.error = Some(format!("{e:#}")),
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        } else if self.last_poll.elapsed() >= POLL_INTERVAL {
+            self.poll_now();
+        }
+        false
+    }
+
+    /// Asks right away unless a poll is already running.
+    pub fn poll_now(&mut self) {
+        if self.pending.is_some() {
+            return;
+        }
+        self.last_poll = Instant::now();
+        let (host, command) = (self.host.clone(), self.command.clone());
+        let include_wifi = self.include_wifi;
+        self.pending = Some(tokio::spawn(async move {
+            fetch(&host, &command, include_wifi).await
+        }));
+    }
+}
+
+/// `BatchMode` keeps ssh from prompting for a password or a host key on the TUI's terminal, so
+/// the host needs key (or agent) authentication and to be in known_hosts already.
+pub async fn fetch(host: &str, command: &str, include_wifi: bool) -> Result<Vec<EthernetDevice>> {
+    let mut cmd = Command::new("ssh");
+    cmd.args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=5", "--", host])
+        .arg(format!("{command} list --json"));
+    if include_wifi {
+        cmd.arg("--all");
+    }
+    let out = cmd
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("running ssh")?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let msg = match stderr.trim().lines().last() {
+            Some(line) => format!("{host}: {line}"),
+            None => format!(
+                "ssh {host}: exit status {}",
+                out.status.code().unwrap_or(-1)
+            ),
+        };
+        return Err(std::io::Error::other(msg).into());
+    }
+    serde_json::from_slice(&out.stdout)
+        .with_context(|| format!("reading `{command} list --json` from {host}"))
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// A TCP or UDP socket from `/proc/net/{tcp,udp}{,6}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Socket {
+    /// `tcp`, `tcp6`, `udp` or `udp6`.
+    pub proto: &'static str,
+    pub local: (IpAddr, u16),
+    pub peer: (IpAddr, u16),
+    pub state: &'static str,
+    /// `comm` and PID of a process holding the socket, when its `/proc/<pid>/fd` is readable.
+    pub process: Option<(String, u32)>,
+    inode: u64,
+}
+
+const TABLES: [&str; 4] = ["tcp", "tcp6", "udp", "udp6"];
+
+/// Sockets whose local address is one of `addrs`; sockets bound to the wildcard address are
+/// left out since they don't belong to any one interface.
+pub fn list_sockets(addrs: &[IpAddr]) -> Vec<Socket> {
+    let mut sockets: Vec<Socket> = TABLES
+        .iter()
+        .flat_map(|proto| {
+            let content = fs::read_to_string(format!("/proc/net/{proto}")).unwrap_or_default();
+            content
+                .lines()
+                .skip(1)
+                .filter_map(|line| parse_socket_line(proto, line))
+                .collect::<Vec<_>>()
+        })
+        .filter(|s| addrs.contains(&s.local.0))
+        .collect();
+    if sockets.is_empty() {
+        return sockets;
+    }
+
+    let owners = socket_owners();
+    for s in &mut sockets {
+        s.process = owners.get(&s.inode).cloned();
+    }
+    sockets.sort_by(|a, b| (a.proto, a.local, a.peer).cmp(&(b.proto, b.local, b.peer)));
+    sockets
+}
+
+/// `sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode`
+fn parse_socket_line(proto: &'static str, line: &str) -> Option<Socket> {
+    let cols: Vec<&str> = line.split_whitespace().collect();
+    if cols.len() < 10 {
+        return None;
+    }
+    let state = u8::from_str_radix(cols[3], 16).ok()?;
+    let state = if proto.starts_with("udp") {
+        // UDP only uses ESTABLISHED (connected) and CLOSE (unconnected).
+        if state == 0x01 {
+            "ESTABLISHED"
+        } else {
+            "UNCONN"
+        }
+    } else {
+        tcp_state(state)
+    };
+    Some(Socket {
+        proto,
+        local: parse_endpoint(cols[1])?,
+        peer: parse_endpoint(cols[2])?,
+        state,
+        process: None,
+        inode: cols[9].parse().ok()?,
+    })
+}
+
+/// `0100007F:0277`: the address is printed as native-endian 32-bit words, the port as a number.
+fn parse_endpoint(s: &str) -> Option<(IpAddr, u16)> {
+    let (addr, port) = s.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let words: Vec<[u8; 4]> = (0..addr.len() / 8)
+        .map(|i| {
+            let word = u32::from_str_radix(addr.get(i * 8..i * 8 + 8)?, 16).ok()?;
+            Some(word.to_ne_bytes())
+        })
+        .collect::<Option<_>>()?;
+    let ip = match words[..] {
+        [w] => IpAddr::V4(Ipv4Addr::from(w)),
+        [a, b, c, d] => {
+            let mut bytes = [0u8; 16];
+            for (chunk, w) in bytes.chunks_mut(4).zip([a, b, c, d]) {
+                chunk.copy_from_slice(&w);
+            }
+            let v6 = Ipv6Addr::from(bytes);
+            // Dual-stack sockets report IPv4 peers as ::ffff:a.b.c.d.
+            v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4)
+        }
+        _ => return None,
+    };
+    Some((ip, port))
+}
+
+fn tcp_state(state: u8) -> &'static str {
+    match state {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        0x0C => "NEW_SYN_RECV",
+        _ => "?",
+    }
+}
+
+/// Socket inode to (`comm`, PID), from the `socket:[inode]` links in `/proc/<pid>/fd`.
+/// Without root only our own processes are readable.
+fn socket_owners() -> HashMap<u64, (String, u32)> {
+    let mut owners = HashMap::new();
+    let Ok(procs) = fs::read_dir("/proc") else {
+        return owners;
+    };
+    for entry in procs.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|p| p.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let mut comm = None;
+        for fd in fds.flatten() {
+            let Some(inode) = fs::read_link(fd.path()).ok().and_then(|target| {
+                let target = target.to_str()?;
+                target
+                    .strip_prefix("socket:[")?
+                    .strip_suffix(']')?
+                    .parse::<u64>()
+                    .ok()
+            }) else {
+                continue;
+            };
+            let comm = comm.get_or_insert_with(|| {
+                fs::read_to_string(entry.path().join("comm"))
+                    .map(|c| c.trim().to_string())
+                    .unwrap_or_default()
+            });
+            owners.entry(inode).or_insert_with(|| (comm.clone(), pid));
+        }
+    }
+    owners
+}
@@ -0,0 +1,97 @@
+use crate::net::iproute2_json;
+use anyhow::Result;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Link states a VF can be forced into (`ip link set ... vf N state`).
+pub const VF_LINK_STATES: [&str; 3] = ["auto", "enable", "disable"];
+
+/// One virtual function as the PF driver reports it in `ip -j link show`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Vf {
+    #[serde(rename = "vf")]
+    pub number: u32,
+    #[serde(default, rename = "address")]
+    pub mac: Option<String>,
+    /// Newer kernels report a list (for 802.1ad); older ones a single `vlan`.
+    #[serde(default)]
+    vlan_list: Vec<VfVlan>,
+    #[serde(default)]
+    vlan: Option<u16>,
+    #[serde(default)]
+    pub spoofchk: Option<bool>,
+    #[serde(default)]
+    pub trust: Option<bool>,
+    /// `auto`, `enable` or `disable`.
+    #[serde(default)]
+    pub link_state: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+struct VfVlan {
+    #[serde(default)]
+    vlan: u16,
+}
+
+impl Vf {
+    /// The VLAN the PF tags the VF's traffic with; 0 means none.
+    pub fn vlan(&self) -> u16 {
+        self.vlan_list
+            .first()
+            .map(|v| v.vlan)
+            .or(self.vlan)
+            .unwrap_or(0)
+    }
+
+    /// The PF lets the VF send from any MAC, so a guest can impersonate others.
+    pub fn spoofable(&self) -> bool {
+        self.spoofchk == Some(false)
+    }
+}
+
+/// SR-IOV state of a physical function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sriov {
+    /// `sriov_totalvfs`: how many VFs the device can have.
+    pub total_vfs: u32,
+    /// `sriov_numvfs`: how many are enabled now.
+    pub num_vfs: u32,
+    pub vfs: Vec<Vf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkVfs {
+    #[serde(default)]
+    vfinfo_list: Vec<Vf>,
+}
+
+/// Where `sriov_totalvfs` and `sriov_numvfs` live for `iface`.
+pub fn sysfs_dir(iface: &str) -> PathBuf {
+    Path::new("/sys/class/net").join(iface).join("device")
+}
+
+fn read_count(dir: &Path, name: &str) -> Option<u32> {
+    fs::read_to_string(dir.join(name)).ok()?.trim().parse().ok()
+}
+
+/// None when `iface` isn't an SR-IOV capable physical function.
+pub async fn read_sriov(iface: &str) -> Result<Option<Sriov>> {
+    let dir = sysfs_dir(iface);
+    let Some(total_vfs) = read_count(&dir, "sriov_totalvfs").filter(|n| *n > 0) else {
+        return Ok(None);
+    };
+    Ok(Some(Sriov {
+        total_vfs,
+        num_vfs: read_count(&dir, "sriov_numvfs").unwrap_or(0),
+        vfs: vfs(iface).await?,
+    }))
+}
+
+/// The VFs of `iface` with their MAC, VLAN and policy, from `ip -j link show`.
+pub async fn vfs(iface: &str) -> Result<Vec<Vf>> {
+    let links: Vec<LinkVfs> = iproute2_json("ip", &["link", "show", "dev", iface]).await?;
+    let mut vfs: Vec<Vf> = links.into_iter().flat_map(|l| l.vfinfo_list).collect();
+    vfs.sort_by_key(|v| v.number);
+    Ok(vfs)
+}
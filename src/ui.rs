@@ -1,25 +1,338 @@
-use crate::app::App;
+use crate::app::{
+    App, Confirm, DetailTab, Focus, Form, Popup, QrPopup, RenewDiff, Search, SortKey,
+    THROUGHPUT_SAMPLES,
+};
+use crate::command::CommandLine;
+use crate::diag::{
+    Connectivity, DnsTest, GatewayReach, LatencyWindow, MdnsBrowser, PING_SAMPLES, Ping, Scan,
+    SpeedTest, SpeedTestMode, Trace,
+};
+use crate::events::{self, EventKind, EventLog, datetime};
+use crate::history::HistoryKind;
+use crate::ifupdown;
+use crate::keymap::{Action, Keymap};
+use crate::lease::{DhcpLease, LeaseHealth};
+use crate::net::{EthernetDevice, Qdisc, oui_vendor};
+use crate::netplan::{self, NetplanTry, TryAnswer};
+use crate::networkd::LinkStatus;
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Margin, Rect},
     style::{Color, Style, Stylize},
+    symbols::Marker,
     text::{Line, Span, Text},
-    widgets::{Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table},
+    widgets::{
+        Axis, Block, BorderType, Borders, Cell, Chart, Clear, Dataset, GraphType, Paragraph,
+        RenderDirection, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Sparkline, Table,
+        TableState, Tabs,
+    },
 };
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Colors and border style used throughout the UI, from the `[theme]` table of the config file:
+/// a preset, optionally with individual colors overridden.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "ThemeConfig")]
+pub struct Theme {
+    /// Borders of the main panes and forms.
+    pub accent: Color,
+    /// Table headers, the selected tab and the field being edited.
+    pub header: Color,
+    pub text: Color,
+    /// Hints, placeholders and inactive tabs.
+    pub muted: Color,
+    pub good: Color,
+    pub warn: Color,
+    pub bad: Color,
+    /// Footer and tool popups (ping, trace, scan...).
+    pub info: Color,
+    pub rx: Color,
+    pub tx: Color,
+    /// The selected row of a focused table.
+    pub highlight: Style,
+    pub border_type: BorderType,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            accent: Color::Green,
+            header: Color::Yellow,
+            text: Color::White,
+            muted: Color::DarkGray,
+            good: Color::Green,
+            warn: Color::Yellow,
+            bad: Color::Red,
+            info: Color::Cyan,
+            rx: Color::Green,
+            tx: Color::Blue,
+            highlight: Style::new().bg(Color::DarkGray).fg(Color::White),
+            border_type: BorderType::Thick,
+        }
+    }
+}
+
+impl Theme {
+    pub const PRESETS: [&str; 6] = [
+        "default",
+        "gruvbox",
+        "nord",
+        "monochrome",
+        "colorblind",
+        "high-contrast",
+    ];
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let rgb = |hex: u32| Color::from_u32(hex);
+        Some(match name {
+            "default" => Self::default(),
+            "gruvbox" => Self {
+                accent: rgb(0xb8bb26),
+                header: rgb(0xfabd2f),
+                text: rgb(0xebdbb2),
+                muted: rgb(0x928374),
+                good: rgb(0xb8bb26),
+                warn: rgb(0xfe8019),
+                bad: rgb(0xfb4934),
+                info: rgb(0x8ec07c),
+                rx: rgb(0xb8bb26),
+                tx: rgb(0x83a598),
+                highlight: Style::new().bg(rgb(0x504945)).fg(rgb(0xfbf1c7)),
+                border_type: BorderType::Thick,
+            },
+            "nord" => Self {
+                accent: rgb(0x88c0d0),
+                header: rgb(0xebcb8b),
+                text: rgb(0xeceff4),
+                muted: rgb(0x4c566a),
+                good: rgb(0xa3be8c),
+                warn: rgb(0xebcb8b),
+                bad: rgb(0xbf616a),
+                info: rgb(0x81a1c1),
+                rx: rgb(0xa3be8c),
+                tx: rgb(0x5e81ac),
+                highlight: Style::new().bg(rgb(0x434c5e)).fg(rgb(0xeceff4)),
+                border_type: BorderType::Rounded,
+            },
+            // Terminal default colors only; selection is shown by reversing video.
+            "monochrome" => Self {
+                accent: Color::Reset,
+                header: Color::Reset,
+                text: Color::Reset,
+                muted: Color::DarkGray,
+                good: Color::Reset,
+                warn: Color::Reset,
+                bad: Color::Reset,
+                info: Color::Reset,
+                rx: Color::Reset,
+                tx: Color::Reset,
+                highlight: Style::new().reversed(),
+                border_type: BorderType::Plain,
+            },
+            // Okabe-Ito colors: good and bad are blue and vermillion, which stay apart with any
+            // kind of color blindness, and warnings are told from both by brightness.
+            "colorblind" => Self {
+                accent: rgb(0x56b4e9),
+                header: rgb(0xf0e442),
+                text: Color::White,
+                muted: Color::DarkGray,
+                good: rgb(0x56b4e9),
+                warn: rgb(0xf0e442),
+                bad: rgb(0xd55e00),
+                info: rgb(0x56b4e9),
+                rx: rgb(0x009e73),
+                tx: rgb(0xcc79a7),
+                highlight: Style::new().bg(Color::DarkGray).fg(Color::White),
+                border_type: BorderType::Thick,
+            },
+            // Bright colors only, no dark gray text, and a reversed bold selection.
+            "high-contrast" => Self {
+                accent: Color::White,
+                header: Color::LightYellow,
+                text: Color::White,
+                muted: Color::Gray,
+                good: Color::LightCyan,
+                warn: Color::LightYellow,
+                bad: Color::LightRed,
+                info: Color::White,
+                rx: Color::LightCyan,
+                tx: Color::LightMagenta,
+                highlight: Style::new().reversed().bold(),
+                border_type: BorderType::Thick,
+            },
+            _ => return None,
+        })
+    }
+
+    /// For `--no-color` and `NO_COLOR`: `monochrome` without its dark gray, so nothing but bold
+    /// and reversed video is sent. The QR code stays black on white to remain scannable.
+    pub fn no_color() -> Self {
+        Self {
+            muted: Color::Reset,
+            ..Self::preset("monochrome").unwrap_or_default()
+        }
+    }
+}
+
+/// The `[theme]` table as written: colors are names (`green`, `light-red`), `#rrggbb` or 256-color
+/// indices, as understood by ratatui.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeConfig {
+    preset: Option<String>,
+    accent: Option<String>,
+    header: Option<String>,
+    text: Option<String>,
+    muted: Option<String>,
+    good: Option<String>,
+    warn: Option<String>,
+    bad: Option<String>,
+    info: Option<String>,
+    rx: Option<String>,
+    tx: Option<String>,
+    highlight_fg: Option<String>,
+    highlight_bg: Option<String>,
+    /// `plain`, `rounded`, `double` or `thick`.
+    border_type: Option<String>,
+}
+
+impl TryFrom<ThemeConfig> for Theme {
+    type Error = String;
+
+    fn try_from(c: ThemeConfig) -> Result<Self, String> {
+        let preset = c.preset.as_deref().unwrap_or("default");
+        let mut theme = Theme::preset(preset).ok_or_else(|| {
+            format!(
+                "unknown theme preset `{preset}` (expected one of {})",
+                Theme::PRESETS.join(", ")
+            )
+        })?;
+        let parse = |v: &str| {
+            v.parse::<Color>()
+                .map_err(|_| format!("invalid color `{v}`"))
+        };
+        let color = |value: &Option<String>, slot: &mut Color| -> Result<(), String> {
+            if let Some(v) = value {
+                *slot = parse(v)?;
+            }
+            Ok(())
+        };
+        color(&c.accent, &mut theme.accent)?;
+        color(&c.header, &mut theme.header)?;
+        color(&c.text, &mut theme.text)?;
+        color(&c.muted, &mut theme.muted)?;
+        color(&c.good, &mut theme.good)?;
+        color(&c.warn, &mut theme.warn)?;
+        color(&c.bad, &mut theme.bad)?;
+        color(&c.info, &mut theme.info)?;
+        color(&c.rx, &mut theme.rx)?;
+        color(&c.tx, &mut theme.tx)?;
+        // Only the given half of the preset's highlight is replaced.
+        if let Some(v) = &c.highlight_fg {
+            theme.highlight.fg = Some(parse(v)?);
+        }
+        if let Some(v) = &c.highlight_bg {
+            theme.highlight.bg = Some(parse(v)?);
+        }
+        if let Some(b) = &c.border_type {
+            theme.border_type = match b.as_str() {
+                "plain" => BorderType::Plain,
+                "rounded" => BorderType::Rounded,
+                "double" => BorderType::Double,
+                "thick" => BorderType::Thick,
+                _ => return Err(format!("invalid border_type `{b}`")),
+            };
+        }
+        Ok(theme)
+    }
+}
 
 pub fn render(app: &mut App, frame: &mut Frame) {
+    draw(app, frame);
+    if app.ascii {
+        asciify(frame.buffer_mut());
+    }
+}
+
+/// Terminals that likely can't show box drawing, braille or icons: the Linux console, old
+/// hardware terminals and locales with a charset other than UTF-8 (`C` says nothing about the
+/// terminal, so it doesn't count).
+pub fn plain_terminal() -> bool {
+    let term = std::env::var("TERM").unwrap_or_default();
+    if matches!(
+        term.as_str(),
+        "linux" | "dumb" | "vt100" | "vt102" | "vt220"
+    ) {
+        return true;
+    }
+    // The first one set wins, as in setlocale(3).
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_default()
+        .to_lowercase();
+    locale
+        .split_once('.')
+        .is_some_and(|(_, charset)| !charset.starts_with("utf-8") && !charset.starts_with("utf8"))
+}
+
+/// Replaces the glyphs widgets and icons draw with ASCII look-alikes, leaving text alone (an
+/// SSID or hostname with accents stays as it is). The QR code's half blocks are kept: it can't
+/// be drawn square without them, and every console font has them.
+fn asciify(buf: &mut Buffer) {
+    for cell in &mut buf.content {
+        let Some(c) = cell.symbol().chars().next() else {
+            continue;
+        };
+        let ascii = match c {
+            '─' | '━' | '═' | '╌' | '╍' | '┄' | '┅' => "-",
+            '│' | '┃' | '║' | '╎' | '╏' | '┆' | '┇' => "|",
+            '\u{2500}'..='\u{257f}' => "+",
+            '▀' => continue,
+            '▁' | '▂' | '▃' => "_",
+            '▄' | '▅' | '▆' => "=",
+            '\u{2580}'..='\u{259f}' => "#",
+            '↑' | '▲' | '△' | '▴' => "^",
+            '↓' | '▼' | '▽' | '▾' => "v",
+            '←' | '◀' | '◁' => "<",
+            '→' | '▶' | '▷' | '▸' => ">",
+            '↯' => "!",
+            '\u{2190}'..='\u{21ff}' => "-",
+            '•' | '●' | '◆' | '■' => "*",
+            '○' | '◇' | '□' => "o",
+            '…' => "~",
+            '·' => ".",
+            '✓' | '✔' => "+",
+            '✗' | '✘' => "x",
+            // An empty braille pattern is a blank; any dots become one.
+            '\u{2800}' => " ",
+            '\u{2801}'..='\u{28ff}' => ".",
+            // Nerd Font icons live in the private use areas.
+            '\u{e000}'..='\u{f8ff}' | '\u{f0000}'..='\u{10ffff}' => "*",
+            _ => continue,
+        };
+        cell.set_symbol(ascii);
+    }
+}
+
+fn draw(app: &mut App, frame: &mut Frame) {
+    let theme = app.config.theme;
     const MIN_W: u16 = 80;
     const MIN_H: u16 = 24;
 
     let area = frame.area();
     if area.width < MIN_W || area.height < MIN_H {
-        render_too_small(frame, area, MIN_W, MIN_H);
+        render_too_small(frame, area, MIN_W, MIN_H, &theme);
         return;
     }
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
+            Constraint::Length(1), // status bar
             Constraint::Min(10),   // details
             Constraint::Length(8), // interfaces
             Constraint::Length(2), // footer
@@ -27,21 +340,122 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         .margin(1)
         .split(frame.area());
 
-    render_details(app, frame, chunks[0]);
-    render_devices(app, frame, chunks[1]);
-    render_footer(frame, chunks[2]);
+    render_status_bar(app, frame, chunks[0]);
+    let details = if app.host_choices().len() > 1 {
+        let cols =
+            Layout::horizontal([Constraint::Length(26), Constraint::Min(0)]).split(chunks[1]);
+        render_hosts(app, frame, cols[0]);
+        cols[1]
+    } else {
+        chunks[1]
+    };
+    render_details(app, frame, details);
+    render_devices(app, frame, chunks[2]);
+    match (&app.command, &app.search) {
+        (Some(line), _) => render_command_line(frame, chunks[3], line, &theme),
+        (None, Some(search)) => render_search_line(frame, chunks[3], search, &theme),
+        (None, None) => render_footer(frame, chunks[3], &app.config.keys, &theme),
+    }
+
+    if let Some(ping) = &app.ping {
+        render_ping_popup(frame, ping, &theme);
+    }
+
+    if let Some(trace) = &app.trace {
+        render_trace_popup(frame, trace, &theme);
+    }
+
+    if let Some(t) = &app.netplan_try {
+        render_netplan_try_popup(frame, t, &theme);
+    }
+
+    if let Some(st) = &app.speedtest {
+        render_speedtest_popup(frame, st, &theme);
+    }
+
+    if let Some(test) = &app.dns_test {
+        render_dns_test_popup(frame, test, &theme);
+    }
+
+    if let Some(scan) = &app.scan {
+        render_scan_popup(frame, scan, app.oui.as_ref(), &mut app.scan_state, &theme);
+    }
+
+    if let Some(mdns) = &app.mdns {
+        render_mdns_popup(frame, mdns, &mut app.mdns_state, &theme);
+    }
+
+    if let Some(form) = &app.form {
+        render_form_popup(frame, form, &theme);
+    }
+
+    if let Some(popup) = &app.popup {
+        render_result_popup(frame, popup, &theme);
+    }
+
+    if let Some(diff) = &app.renew_diff {
+        render_renew_popup(frame, diff, &theme);
+    }
+
+    if let Some(qr) = &app.qr {
+        render_qr_popup(frame, qr, &theme);
+    }
+
+    if let Some(scroll) = &mut app.events_scroll {
+        render_events_popup(frame, &app.events, scroll, &theme);
+    }
+
+    if let Some(scroll) = &mut app.help_scroll {
+        render_help_popup(frame, &app.config.keys, scroll, &theme);
+    }
+
+    if let Some(confirm) = &app.confirm {
+        render_confirm_popup(frame, confirm, &theme);
+    }
 
     if let Some(err) = &app.last_error {
-        render_error_popup(frame, err);
+        render_error_popup(frame, err, &theme);
         return;
     }
 
     if let Some(t) = &app.toast {
-        render_toast_popup(frame, t.kind, &t.msg);
+        render_toast_popup(frame, t.kind, &t.msg, &theme);
     }
 }
 
 fn render_devices(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let cols = &app.config.columns;
+    let columns = [
+        (true, "", Constraint::Length(2), None),
+        (true, "Iface", Constraint::Length(10), Some(SortKey::Name)),
+        (cols.vid, "VID", Constraint::Length(4), None),
+        (
+            cols.state,
+            "State",
+            Constraint::Length(9),
+            Some(SortKey::State),
+        ),
+        (cols.carrier, "Carrier", Constraint::Length(7), None),
+        (
+            cols.speed,
+            "Speed",
+            Constraint::Length(7),
+            Some(SortKey::Speed),
+        ),
+        (cols.ipv4, "IPv4", Constraint::Min(10), Some(SortKey::Ipv4)),
+        (cols.lease, "Lease", Constraint::Length(10), None),
+        (cols.internet, "Internet", Constraint::Length(8), None),
+    ];
+    let shown = |cells: Vec<Cell<'static>>| -> Vec<Cell<'static>> {
+        cells
+            .into_iter()
+            .zip(&columns)
+            .filter(|(_, (show, ..))| *show)
+            .map(|(cell, _)| cell)
+            .collect()
+    };
+
     let rows: Vec<Row> = app
         .devices
         .iter()
@@ -51,81 +465,342 @@ fn render_devices(app: &mut App, frame: &mut Frame, area: Rect) {
                 .speed_mbps
                 .map(|s| format!("{s}"))
                 .unwrap_or_else(|| "-".into());
+            let speed = if app.degraded_link(d).is_some() {
+                Cell::from(Span::from(speed + "!").fg(theme.warn).bold())
+            } else {
+                Cell::from(speed)
+            };
+            let internet = app.connectivity.get(&d.ifindex).copied();
             let connected = if d.carrier == Some(true) && !d.ipv4.is_empty() {
-                "󰀂".to_string()
+                Span::from("󰀂").fg(connectivity_color(internet, &theme))
             } else {
-                "".to_string()
+                Span::from("")
+            };
+            let internet = match internet {
+                Some(c) => Span::from(c.label()).fg(connectivity_color(Some(c), &theme)),
+                None => Span::from("-"),
             };
 
-            Row::new(vec![
+            let vid = d
+                .vlan
+                .as_ref()
+                .map_or_else(|| "-".to_string(), |v| v.id.to_string());
+
+            // A badge for links that went down and came back recently.
+            let flaps = app.recent_flaps(&d.name).len();
+            let carrier = if flaps > 0 {
+                Cell::from(Line::from(vec![
+                    Span::from(carrier),
+                    Span::from(format!(" ↯{flaps}")).fg(theme.warn).bold(),
+                ]))
+            } else {
+                Cell::from(carrier)
+            };
+
+            let alerted = !app.alerts.active(&d.name).is_empty();
+            let row = Row::new(shown(vec![
                 Cell::from(connected),
                 Cell::from(d.name.clone()),
+                Cell::from(vid),
                 Cell::from(d.operstate.clone()),
-                Cell::from(carrier),
-                Cell::from(speed),
+                carrier,
+                speed,
                 Cell::from(d.ipv4.first().cloned().unwrap_or_else(|| "-".into())),
-            ])
+                Cell::from(lease_countdown(d, &theme)),
+                Cell::from(internet),
+            ]));
+            if alerted {
+                row.style(Style::default().fg(theme.bad).bold())
+            } else {
+                row
+            }
         })
         .collect();
 
-    let widths = [
-        Constraint::Length(2),
-        Constraint::Length(10),
-        Constraint::Length(9),
-        Constraint::Length(7),
-        Constraint::Length(7),
-        Constraint::Min(10),
-    ];
+    let widths: Vec<Constraint> = columns
+        .iter()
+        .filter(|(show, ..)| *show)
+        .map(|(_, _, width, _)| *width)
+        .collect();
+    let header: Vec<Cell> = columns
+        .iter()
+        .filter(|(show, ..)| *show)
+        .map(|(_, title, _, sort)| {
+            let arrow = match sort {
+                Some(key) if *key == app.sort && app.sort_descending => "▼",
+                Some(key) if *key == app.sort => "▲",
+                _ => "",
+            };
+            Cell::from(format!("{title}{arrow}")).style(Style::default().fg(theme.header))
+        })
+        .collect();
+
+    // Where the Table will put each header cell (it starts columns flush left with one space
+    // between them), so a click can be mapped back to a column.
+    let inner = area.inner(Margin::new(1, 1));
+    let cells = Layout::horizontal(widths.clone())
+        .flex(Flex::Start)
+        .spacing(1)
+        .split(inner);
+    app.sort_headers = cells
+        .iter()
+        .zip(columns.iter().filter(|(show, ..)| *show))
+        .filter_map(|(cell, (_, _, _, sort))| Some((Rect { height: 1, ..*cell }, (*sort)?)))
+        .collect();
+
+    let mut title = vec![Span::from(" Interfaces ")];
+    if let Some(remote) = app.remote() {
+        title.push(Span::from(format!("on {} ", remote.host)).fg(theme.header));
+        if let Some(e) = &remote.error {
+            title.push(Span::from(format!("({e}) ")).fg(theme.bad));
+        }
+    }
+    if let Some(pattern) = &app.filter {
+        title.push(Span::from(format!("[{pattern}] ")).fg(theme.header));
+    }
+    if app.paused {
+        title.push(Span::from("PAUSED ").fg(theme.warn).bold());
+    }
 
     let table = Table::new(rows, widths)
-        .header(
-            Row::new(vec![
-                Cell::from("").style(Style::default().fg(Color::Yellow)),
-                Cell::from("Iface").style(Style::default().fg(Color::Yellow)),
-                Cell::from("State").style(Style::default().fg(Color::Yellow)),
-                Cell::from("Carrier").style(Style::default().fg(Color::Yellow)),
-                Cell::from("Speed").style(Style::default().fg(Color::Yellow)),
-                Cell::from("IPv4").style(Style::default().fg(Color::Yellow)),
-            ])
-            .style(Style::new().bold())
-            .bottom_margin(1),
-        )
+        .header(Row::new(header).style(Style::new().bold()).bottom_margin(1))
         .block(
             Block::default()
-                .title(" Interfaces ")
+                .title(Line::from(title))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Green))
-                .border_type(BorderType::Thick),
+                .border_style(Style::default().fg(theme.accent))
+                .border_type(theme.border_type),
         )
-        .row_highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White));
+        .row_highlight_style(theme.highlight);
 
     frame.render_stateful_widget(table, area, &mut app.devices_state);
 }
 
+/// Host, backend, default route and whether it reaches the Internet, with the time on the right.
+fn render_status_bar(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let host = match app.remote() {
+        Some(remote) => remote.host.as_str(),
+        None => app.hostname.as_str(),
+    };
+    // Backends and connectivity probes only exist for this machine.
+    let local = app.host.is_none();
+    let sep = || Span::from(" | ").fg(theme.muted);
+    let mut spans = vec![Span::from(host).bold()];
+    let default = app.default_device();
+    if let Some(info) = default
+        .filter(|_| local)
+        .and_then(|d| app.backends.get(&d.ifindex))
+    {
+        spans.push(sep());
+        spans.push(Span::from(info.backend.name()));
+    }
+    spans.push(sep());
+    match (&app.default_iface, default) {
+        (Some(iface), Some(d)) => {
+            let via = d
+                .gateway_v4
+                .as_deref()
+                .map_or(String::new(), |g| format!(" via {g}"));
+            spans.push(Span::from(format!("default {iface}{via}")));
+            if local {
+                let internet = app.connectivity.get(&d.ifindex).copied();
+                spans.push(sep());
+                spans.push(Span::from("Internet: "));
+                spans.push(match internet {
+                    Some(c) => Span::from(c.label()).fg(connectivity_color(Some(c), &theme)),
+                    None => Span::from("checking").fg(theme.muted),
+                });
+            }
+        }
+        // Filtered out of the list, so its details aren't at hand.
+        (Some(iface), None) => spans.push(Span::from(format!("default {iface}"))),
+        (None, _) => spans.push(Span::from("no default route").fg(theme.bad)),
+    }
+    let clock = events::clock(std::time::SystemTime::now());
+    let cols = Layout::horizontal([Constraint::Min(0), Constraint::Length(clock.len() as u16)])
+        .split(area);
+    frame.render_widget(Paragraph::new(Line::from(spans)).fg(theme.info), cols[0]);
+    frame.render_widget(Paragraph::new(clock).fg(theme.info), cols[1]);
+}
+
+/// The host selector of the multi-host dashboard: every host with how many of its interfaces
+/// have a carrier. This machine is only counted while it's the one shown.
+fn render_hosts(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let summary = |devices: &[EthernetDevice]| {
+        let up = devices.iter().filter(|d| d.carrier == Some(true)).count();
+        let color = match up {
+            0 => theme.bad,
+            n if n == devices.len() => theme.good,
+            _ => theme.warn,
+        };
+        Span::from(format!("{up}/{} up", devices.len())).fg(color)
+    };
+    let lines: Vec<Line> = app
+        .host_choices()
+        .into_iter()
+        .map(|choice| {
+            let shown = choice == app.host;
+            let (name, status) = match choice.and_then(|i| app.hosts.get(i)) {
+                None => (
+                    app.hostname.clone(),
+                    if shown {
+                        summary(&app.devices)
+                    } else {
+                        Span::from("local").fg(theme.muted)
+                    },
+                ),
+                Some(remote) => (
+                    remote.host.clone(),
+                    if remote.error.is_some() {
+                        Span::from("unreachable").fg(theme.bad)
+                    } else if !remote.fetched {
+                        Span::from("connecting").fg(theme.muted)
+                    } else {
+                        summary(&remote.devices)
+                    },
+                ),
+            };
+            let marker = if shown { "▶ " } else { "  " };
+            let name = Span::from(format!("{marker}{name} "));
+            Line::from(vec![if shown { name.bold() } else { name }, status])
+        })
+        .collect();
+    let bound = |action| {
+        app.config
+            .keys
+            .keys(action)
+            .next()
+            .map(|k| k.to_string())
+            .unwrap_or_default()
+    };
+    let title = format!(
+        " Hosts {}/{} ",
+        bound(Action::PrevHost),
+        bound(Action::NextHost)
+    );
+    let p = Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.accent))
+            .border_type(theme.border_type),
+    );
+    frame.render_widget(p, area);
+}
+
 fn render_details(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
     let title = if let Some(d) = app.selected_device() {
         format!(" Details ({}) ", d.name)
     } else {
         " Details ".to_string()
     };
 
+    let border_style = if app.focus == Focus::Details {
+        Style::default().fg(theme.accent)
+    } else {
+        Style::default()
+    };
+
     let block = Block::default()
         .title(title)
         .borders(Borders::ALL)
-        .border_style(Style::default())
+        .border_style(border_style)
         .border_type(BorderType::default());
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0)])
+        .split(inner);
+
+    let titles: Vec<Line> = DetailTab::ALL
+        .iter()
+        .map(|t| Line::from(format!(" {} ", t.title())))
+        .collect();
+    let selected = DetailTab::ALL
+        .iter()
+        .position(|t| *t == app.tab)
+        .unwrap_or(0);
+    let tabs = Tabs::new(titles)
+        .select(selected)
+        .style(Style::default().fg(theme.muted))
+        .highlight_style(Style::default().fg(theme.header).bold())
+        .divider("|");
+    frame.render_widget(tabs, parts[0]);
+
+    // Only what `ethtui list --json` prints comes back from a remote host.
+    if let Some(remote) = app.remote()
+        && !matches!(app.tab, DetailTab::Overview | DetailTab::Stats)
+    {
+        frame.render_widget(
+            Paragraph::new(format!("Not available for {}.", remote.host)).fg(theme.muted),
+            parts[1],
+        );
+        return;
+    }
+
+    match app.tab {
+        DetailTab::Overview => render_overview(app, frame, parts[1]),
+        DetailTab::Stats => render_stats(app, frame, parts[1]),
+        DetailTab::Hardware => render_hardware(app, frame, parts[1]),
+        DetailTab::Offloads => render_offloads(app, frame, parts[1]),
+        DetailTab::Coalescing => render_coalescing(app, frame, parts[1]),
+        DetailTab::Queues => render_queues(app, frame, parts[1]),
+        DetailTab::Driver => render_driver_stats(app, frame, parts[1]),
+        DetailTab::Sriov => render_sriov(app, frame, parts[1]),
+        DetailTab::Routes => render_routes(app, frame, parts[1]),
+        DetailTab::Neighbors => render_neighbors(app, frame, parts[1]),
+        DetailTab::Connections => render_connections(app, frame, parts[1]),
+        DetailTab::Qos => render_qos(app, frame, parts[1]),
+        DetailTab::Firewall => render_firewall(app, frame, parts[1]),
+        DetailTab::History => render_history(app, frame, parts[1]),
+        DetailTab::Config => render_config(app, frame, parts[1]),
+    }
+}
+
+fn render_overview(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+
     let text = if let Some(d) = app.selected_device() {
         let mut lines = Vec::new();
 
+        for msg in app.alerts.active(&d.name) {
+            lines.push(Line::from(vec![
+                Span::from("Alert: ").bold().fg(theme.bad),
+                Span::from(msg.to_string()).fg(theme.bad),
+            ]));
+        }
+        for (plugin, text) in app.annotations.get(&d.name).into_iter().flatten() {
+            lines.push(Line::from(vec![
+                Span::from(format!("{plugin}: ")).bold().fg(theme.info),
+                Span::from(text.clone()).fg(theme.info),
+            ]));
+        }
+        let programs = app.selected_bpf();
+        for p in programs {
+            lines.push(Line::from(vec![
+                Span::from(format!("eBPF {}: ", p.hook))
+                    .bold()
+                    .fg(theme.warn),
+                Span::from(p.describe()).fg(theme.warn),
+            ]));
+        }
+        if !programs.is_empty() {
+            lines.push(Line::from(
+                Span::from("  Attached programs can drop, redirect or rewrite packets.")
+                    .fg(theme.muted),
+            ));
+        }
         lines.push(Line::from(vec![
             Span::from("State: ").bold(),
             Span::from(d.operstate.clone()),
         ]));
-        lines.push(Line::from(vec![
+        let mut carrier = vec![
             Span::from("Carrier: ").bold(),
             Span::from(
                 d.carrier
@@ -133,31 +808,185 @@ fn render_details(app: &mut App, frame: &mut Frame, area: Rect) {
                     .unwrap_or("?")
                     .to_string(),
             ),
-        ]));
+        ];
+        if let Some(counts) = d.carrier_counts {
+            let mut text = format!(" ({} changes", counts.changes);
+            if let (Some(up), Some(down)) = (counts.up, counts.down) {
+                text.push_str(&format!(": {up} up, {down} down"));
+            }
+            carrier.push(Span::from(text + ")").fg(theme.muted));
+        }
+        lines.push(Line::from(carrier));
         lines.push(Line::from(
             Span::from("  Carrier is 1 when link is detected (cable plugged / switch port up).")
-                .fg(Color::DarkGray),
+                .fg(theme.muted),
         ));
+        let flaps = app.recent_flaps(&d.name);
+        if let Some(last) = flaps.last() {
+            let times = if flaps.len() == 1 {
+                "once".to_string()
+            } else {
+                format!("{} times", flaps.len())
+            };
+            lines.push(Line::from(
+                Span::from(format!(
+                    "  Link went down and came back {times} in the last {} min (last at {}): check the cable, transceiver and switch port",
+                    app.config.flap_window_minutes,
+                    events::clock(*last)
+                ))
+                .fg(theme.warn),
+            ));
+        }
+        let mut speed = d
+            .speed_mbps
+            .map(|s| format!("{s} Mb/s"))
+            .unwrap_or_else(|| "-".into());
+        if let Some(duplex) = &d.duplex {
+            speed.push_str(&format!(", {duplex} duplex"));
+        }
         lines.push(Line::from(vec![
             Span::from("Speed: ").bold(),
-            Span::from(
-                d.speed_mbps
-                    .map(|s| format!("{s} Mb/s"))
-                    .unwrap_or_else(|| "-".into()),
-            ),
+            Span::from(speed),
         ]));
+        if let Some(why) = app.degraded_link(d) {
+            lines.push(Line::from(Span::from(format!("  {why}")).fg(theme.warn)));
+        }
+        if let Some(info) = app.selected_backend_info() {
+            let mut managed = info.backend.name().to_string();
+            if let Some(src) = &info.config_source {
+                managed.push_str(&format!(" ({src})"));
+            }
+            lines.push(Line::from(vec![
+                Span::from("Managed by: ").bold(),
+                Span::from(managed),
+            ]));
+        }
+        match app.selected_networkd_status() {
+            Some(Ok(status)) => lines.extend(networkd_lines(status, &theme)),
+            Some(Err(e)) => lines.push(Line::from(
+                Span::from(format!("  networkctl status: {e}")).fg(theme.muted),
+            )),
+            None => {}
+        }
+        if let Some(wifi) = &d.wifi {
+            let mut spans = vec![Span::from("Wi-Fi: ").bold()];
+            match &wifi.ssid {
+                Some(ssid) => {
+                    spans.push(Span::from(ssid.clone()));
+                    if let Some(freq) = wifi.freq_mhz {
+                        let band = if freq >= 5925 {
+                            "6 GHz"
+                        } else if freq >= 5000 {
+                            "5 GHz"
+                        } else {
+                            "2.4 GHz"
+                        };
+                        spans.push(Span::from(format!(" | {freq} MHz ({band})")));
+                    }
+                    if let Some(rate) = &wifi.tx_bitrate {
+                        spans.push(Span::from(format!(" | {rate}")));
+                    }
+                }
+                None => spans.push(Span::from("not connected").fg(theme.muted)),
+            }
+            if let Some(dbm) = wifi.signal_dbm {
+                let color = if dbm >= -60 {
+                    theme.good
+                } else if dbm >= -70 {
+                    theme.warn
+                } else {
+                    theme.bad
+                };
+                spans.push(Span::from(" | "));
+                spans.push(Span::from(format!("{dbm} dBm")).fg(color));
+            }
+            lines.push(Line::from(spans));
+        }
+        if let Some(port) = &d.bridge {
+            let others = if port.others.is_empty() {
+                "no other ports".to_string()
+            } else {
+                format!("with {}", port.others.join(", "))
+            };
+            lines.push(Line::from(vec![
+                Span::from("Bridge: ").bold(),
+                Span::from(format!("{} ({}), {others}", port.bridge, port.state)),
+            ]));
+        }
+        if let Some(bond) = &d.bond {
+            lines.push(Line::from(vec![
+                Span::from("Bond: ").bold(),
+                Span::from(format!(
+                    "{} ({}), MII {}, active {}",
+                    bond.name,
+                    bond.mode,
+                    bond.mii_status,
+                    bond.active_slave.as_deref().unwrap_or("-")
+                )),
+            ]));
+            for s in &bond.slaves {
+                let color = if s.mii_status != "up" {
+                    theme.bad
+                } else if s.link_failures > 0 {
+                    theme.warn
+                } else {
+                    Color::Reset
+                };
+                let active = if bond.active_slave.as_ref() == Some(&s.name) {
+                    " (active)"
+                } else {
+                    ""
+                };
+                lines.push(Line::from(
+                    Span::from(format!(
+                        "  {}{active}: MII {}, {}, {} link failures",
+                        s.name,
+                        s.mii_status,
+                        s.speed.as_deref().unwrap_or("-"),
+                        s.link_failures
+                    ))
+                    .fg(color),
+                ));
+            }
+        }
         lines.push(Line::from(vec![
             Span::from("MAC: ").bold(),
             Span::from(d.mac.clone().unwrap_or_else(|| "-".into())),
         ]));
+        if let Some(perm) = app
+            .selected_hardware()
+            .and_then(|hw| hw.permanent_mac.as_ref())
+        {
+            let mut spans = vec![
+                Span::from("Permanent MAC: ").bold(),
+                Span::from(perm.clone()),
+            ];
+            if d.mac.as_ref() != Some(perm) {
+                spans.push(Span::from(" (overridden)").fg(theme.warn));
+            }
+            lines.push(Line::from(spans));
+        }
         lines.push(Line::from(""));
 
+        // The cursor `-` deletes at, over IPv4 then IPv6, while the Overview is focused.
+        let cursor = app
+            .address_state
+            .selected()
+            .filter(|_| app.focus == Focus::Details);
+        let address = |i: usize, ip: &String| {
+            let line = Line::from(format!("  {ip}"));
+            if cursor == Some(i) {
+                line.reversed()
+            } else {
+                line
+            }
+        };
         lines.push(Line::from(Span::from("IPv4: ").bold()));
         if d.ipv4.is_empty() {
             lines.push(Line::from("  -"));
         } else {
-            for ip in &d.ipv4 {
-                lines.push(Line::from(format!("  {ip}")));
+            for (i, ip) in d.ipv4.iter().enumerate() {
+                lines.push(address(i, ip));
             }
         }
 
@@ -166,8 +995,8 @@ fn render_details(app: &mut App, frame: &mut Frame, area: Rect) {
         if d.ipv6.is_empty() {
             lines.push(Line::from("  -"));
         } else {
-            for ip in &d.ipv6 {
-                lines.push(Line::from(format!("  {ip}")));
+            for (i, ip) in d.ipv6.iter().enumerate() {
+                lines.push(address(d.ipv4.len() + i, ip));
             }
         }
 
@@ -175,8 +1004,50 @@ fn render_details(app: &mut App, frame: &mut Frame, area: Rect) {
         lines.push(Line::from(vec![
             Span::from("Gateway v4: ").bold(),
             Span::from(d.gateway_v4.clone().unwrap_or_else(|| "-".into())),
+            match app.selected_gateway_reach() {
+                Some(GatewayReach::Reachable(Some(rtt))) => {
+                    Span::from(format!(" (answers ARP, {rtt:.1} ms)")).fg(theme.good)
+                }
+                Some(GatewayReach::Reachable(None)) => Span::from(" (answers ARP)").fg(theme.good),
+                Some(GatewayReach::Unreachable) => {
+                    Span::from(" (no ARP reply: link up, router silent)").fg(theme.bad)
+                }
+                Some(GatewayReach::Unknown) => Span::from(" (ARP state unknown)").fg(theme.muted),
+                None => Span::from(""),
+            },
         ]));
 
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::from("DHCP: ").bold()));
+        match &d.lease {
+            Some(l) => {
+                let dur = |v: Option<std::time::Duration>| {
+                    v.map(|d| human_duration(d.as_secs()))
+                        .unwrap_or_else(|| "-".into())
+                };
+                let now = std::time::SystemTime::now();
+                lines.push(Line::from(format!(
+                    "  Server: {} (via {})",
+                    l.server.clone().unwrap_or_else(|| "-".into()),
+                    l.source
+                )));
+                lines.push(Line::from(format!(
+                    "  Lease: {} | T1: {} | T2: {}",
+                    dur(l.lease_time),
+                    dur(l.renew_after()),
+                    dur(l.rebind_after())
+                )));
+                lines.push(Line::from(vec![
+                    Span::from("  Renews in: "),
+                    Span::from(dur(l.renews_in(now))),
+                    Span::from(" | Expires in: "),
+                    Span::from(dur(l.expires_in(now)) + lease_mark(l)).fg(lease_color(l, &theme)),
+                ]));
+            }
+            None if d.ipv4.is_empty() => lines.push(Line::from("  -")),
+            None => lines.push(Line::from("  static (no DHCP lease found)")),
+        }
+
         lines.push(Line::from(""));
         lines.push(Line::from(Span::from("DNS: ").bold()));
         if d.dns.is_empty() {
@@ -186,12 +1057,127 @@ fn render_details(app: &mut App, frame: &mut Frame, area: Rect) {
                 lines.push(Line::from(format!("  {s}")));
             }
         }
+        let search: Vec<&str> = d.search_domains().collect();
+        lines.push(Line::from(vec![
+            Span::from("  Search: ").bold(),
+            Span::from(if search.is_empty() {
+                "-".to_string()
+            } else {
+                search.join(" ")
+            }),
+        ]));
+        if !search.is_empty() {
+            lines.push(Line::from(
+                Span::from(format!(
+                    "  Unqualified names like \"host\" are tried as host.{}",
+                    search[0]
+                ))
+                .fg(theme.muted),
+            ));
+        }
+        let routing: Vec<&str> = d.routing_domains().collect();
+        if !routing.is_empty() {
+            let routing: Vec<&str> = routing
+                .into_iter()
+                .map(|r| if r == "." { "(all)" } else { r })
+                .collect();
+            lines.push(Line::from(vec![
+                Span::from("  Routing-only: ").bold(),
+                Span::from(routing.join(" ")),
+            ]));
+        }
+
+        match app.selected_wireguard() {
+            Some(Ok(wg)) => {
+                let now = std::time::SystemTime::now();
+                lines.push(Line::from(""));
+                lines.push(Line::from(vec![
+                    Span::from("WireGuard: ").bold(),
+                    Span::from(format!(
+                        "key {} | port {}",
+                        short_key(&wg.public_key),
+                        wg.listen_port
+                            .map_or_else(|| "-".to_string(), |p| p.to_string())
+                    )),
+                ]));
+                if wg.peers.is_empty() {
+                    lines.push(Line::from(Span::from("  No peers").fg(theme.muted)));
+                }
+                for peer in &wg.peers {
+                    // Sessions are renegotiated every 2 minutes and expire after 3.
+                    let (handshake, color) = match peer.handshake_age(now) {
+                        Some(age) if age.as_secs() <= 180 => {
+                            (format!("{} ago", human_duration(age.as_secs())), theme.good)
+                        }
+                        Some(age) => (format!("{} ago", human_duration(age.as_secs())), theme.warn),
+                        None => ("never".to_string(), theme.bad),
+                    };
+                    lines.push(Line::from(vec![
+                        Span::from(format!("  Peer {}", short_key(&peer.public_key))),
+                        Span::from(" @ "),
+                        Span::from(peer.endpoint.clone().unwrap_or_else(|| "-".into())),
+                    ]));
+                    lines.push(Line::from(format!(
+                        "    Allowed IPs: {}",
+                        if peer.allowed_ips.is_empty() {
+                            "-".to_string()
+                        } else {
+                            peer.allowed_ips.join(", ")
+                        }
+                    )));
+                    lines.push(Line::from(vec![
+                        Span::from("    Handshake: "),
+                        Span::from(handshake).fg(color),
+                        Span::from(format!(
+                            " | RX {} | TX {}",
+                            human_bytes(peer.rx_bytes),
+                            human_bytes(peer.tx_bytes)
+                        )),
+                    ]));
+                }
+            }
+            Some(Err(e)) => {
+                lines.push(Line::from(""));
+                lines.push(Line::from(vec![
+                    Span::from("WireGuard: ").bold(),
+                    Span::from(e.clone()).fg(theme.bad),
+                ]));
+            }
+            None => {}
+        }
+
+        for n in app.selected_lldp() {
+            let field = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".into());
+            let list = |v: &[String]| {
+                if v.is_empty() {
+                    "-".to_string()
+                } else {
+                    v.join(", ")
+                }
+            };
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::from("Switch (LLDP): ").bold()));
+            lines.push(Line::from(format!(
+                "  {} ({})",
+                field(&n.system_name),
+                field(&n.chassis_id)
+            )));
+            let port = match (&n.port_id, &n.port_descr) {
+                (Some(id), Some(descr)) if id != descr => format!("{id} ({descr})"),
+                (id, descr) => field(&id.clone().or_else(|| descr.clone())),
+            };
+            lines.push(Line::from(format!(
+                "  Port: {port} | VLAN: {}",
+                list(&n.vlans)
+            )));
+            lines.push(Line::from(format!("  Management: {}", list(&n.mgmt_addrs))));
+        }
 
         if let Some(msg) = &app.last_action {
             lines.push(Line::from(""));
             lines.push(Line::from(vec![
                 Span::from("Last: ").bold(),
-                Span::from(msg.clone()).fg(Color::Cyan),
+                Span::from(msg.clone()).fg(theme.info),
             ]));
         }
 
@@ -204,103 +1190,2740 @@ fn render_details(app: &mut App, frame: &mut Frame, area: Rect) {
         ])
     };
 
-    let p = Paragraph::new(text)
-        .alignment(Alignment::Left)
-        .wrap(ratatui::widgets::Wrap { trim: true });
-    frame.render_widget(p, inner);
+    render_text_tab(app, frame, area, text, true);
 }
 
-fn render_footer(frame: &mut Frame, area: Rect) {
-    let text = Line::from(vec![
-        Span::from("k").bold(),
-        Span::from(","),
-        Span::from("↑").bold(),
-        Span::from(" Up"),
-        Span::from(" | "),
-        Span::from("j").bold(),
-        Span::from(","),
-        Span::from("↓").bold(),
-        Span::from(" Down"),
-        Span::from(" | "),
-        Span::from("r").bold(),
-        Span::from(" refresh"),
-        Span::from(" | "),
-        Span::from("n").bold(),
-        Span::from(" renew"),
-        Span::from(" | "),
-        Span::from("q").bold(),
-        Span::from(" quit"),
-    ]);
-
-    let p = Paragraph::new(text)
-        .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::Cyan));
-    frame.render_widget(p, area);
-}
+fn render_hardware(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let Some(link_speed) = app.selected_device().map(|d| d.speed_mbps) else {
+        return;
+    };
+    let mut lines = Vec::new();
+    lines.push(Line::from(Span::from("Hardware: ").bold()));
+    match app.selected_hardware().and_then(|hw| hw.driver.as_ref()) {
+        Some(drv) => {
+            let field = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".into());
+            lines.push(Line::from(format!(
+                "  Driver: {} {}",
+                field(&drv.driver),
+                drv.version.clone().unwrap_or_default()
+            )));
+            lines.push(Line::from(format!(
+                "  Firmware: {}",
+                field(&drv.firmware_version)
+            )));
+            lines.push(Line::from(format!("  Bus: {}", field(&drv.bus_info))));
+            if let Some(err) = app.selected_hardware().and_then(|hw| hw.error.as_ref()) {
+                lines.push(Line::from(
+                    Span::from(format!("  sysfs fallback (ethtool: {err})")).fg(theme.muted),
+                ));
+            }
+        }
+        None => lines.push(Line::from("  loading...")),
+    }
 
-fn render_too_small(frame: &mut Frame, area: Rect, min_w: u16, min_h: u16) {
-    let block = Block::default()
-        .title(" ethtui ")
-        .borders(Borders::ALL)
-        .border_type(BorderType::Thick)
-        .border_style(Style::default().fg(Color::Yellow));
-    let inner = block.inner(area);
-    frame.render_widget(block, area);
+    if let Some(pci) = app.selected_hardware().and_then(|hw| hw.pci.as_ref()) {
+        lines.push(Line::from(format!(
+            "  PCI: {} {}",
+            pci.address,
+            pci.describe()
+        )));
+        let mut placement = Vec::new();
+        if let (Some(speed), Some(width)) = (&pci.link_speed, pci.link_width) {
+            let max = match (&pci.max_link_speed, pci.max_link_width) {
+                (Some(s), Some(w)) => format!(" (max {s} x{w})"),
+                _ => String::new(),
+            };
+            placement.push(format!("PCIe link: {speed} x{width}{max}"));
+        }
+        if let Some(node) = pci.numa_node {
+            placement.push(format!("NUMA node: {node}"));
+        }
+        if !placement.is_empty() {
+            lines.push(Line::from(format!("  {}", placement.join(" | "))));
+        }
+        let bandwidth = pci.link_bandwidth_mbps();
+        if let (Some(pcie), Some(speed)) = (bandwidth, link_speed)
+            && pcie < speed
+        {
+            lines.push(Line::from(
+                Span::from(format!(
+                    "  The PCIe link carries about {} Mb/s, less than the {speed} Mb/s port: move the card to a wider slot",
+                    pcie
+                ))
+                .fg(theme.bad),
+            ));
+        } else if pci.link_degraded() {
+            lines.push(Line::from(
+                Span::from(
+                    "  PCIe link is slower or narrower than the card supports (check the slot)",
+                )
+                .fg(theme.warn),
+            ));
+        }
+    }
 
-    let msg = format!(
-        "Terminal is too small.\n\nMinimum size: {}x{}\nCurrent size:  {}x{}",
-        min_w, min_h, area.width, area.height
-    );
-    let p = Paragraph::new(msg)
-        .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::White))
-        .wrap(ratatui::widgets::Wrap { trim: true });
-    frame.render_widget(p, inner);
-}
+    if let Some(rings) = app.selected_hardware().and_then(|hw| hw.rings) {
+        let ring = |cur: Option<u32>, max: Option<u32>| {
+            let v = |x: Option<u32>| x.map(|x| x.to_string()).unwrap_or_else(|| "-".into());
+            format!("{}/{}", v(cur), v(max))
+        };
+        lines.push(Line::from(format!(
+            "  Rings (current/max): RX {} | TX {}",
+            ring(rings.rx, rings.rx_max),
+            ring(rings.tx, rings.tx_max)
+        )));
+        if rings.far_below_max() {
+            lines.push(Line::from(
+                Span::from("  Ring sizes are far below the hardware maximum (g to edit)")
+                    .fg(theme.warn),
+            ));
+        }
+    }
 
-fn render_error_popup(frame: &mut Frame, msg: &str) {
-    let area = centered_rect(80, 40, frame.area());
-    frame.render_widget(Clear, area);
+    if let Some(ch) = app.selected_hardware().and_then(|hw| hw.channels) {
+        let kinds = [
+            ("combined", ch.current.combined, ch.max.combined),
+            ("RX", ch.current.rx, ch.max.rx),
+            ("TX", ch.current.tx, ch.max.tx),
+            ("other", ch.current.other, ch.max.other),
+        ];
+        let counts: Vec<String> = kinds
+            .iter()
+            .filter(|(_, _, max)| max.is_some_and(|m| m > 0))
+            .map(|(kind, cur, max)| {
+                let cur = cur.map_or_else(|| "-".to_string(), |c| c.to_string());
+                format!("{kind} {cur}/{}", max.unwrap_or_default())
+            })
+            .collect();
+        if !counts.is_empty() {
+            lines.push(Line::from(format!(
+                "  Channels (current/max): {}",
+                counts.join(" | ")
+            )));
+        }
+        // RSS can't use more cores than there are receive queues.
+        let cpus = crate::queues::online_cpus().len() as u32;
+        let possible = ch.max.combined.unwrap_or(0) + ch.max.rx.unwrap_or(0);
+        let rx = ch.rx_queues();
+        if rx > 0 && rx < cpus && possible > rx {
+            lines.push(Line::from(
+                Span::from(format!(
+                    "  {rx} receive queues for {cpus} CPUs: RSS spreads over only {rx} cores (h to edit)"
+                ))
+                .fg(theme.warn),
+            ));
+        }
+    }
 
-    let block = Block::default()
-        .title(" Error ")
-        .borders(Borders::ALL)
-        .border_type(BorderType::Thick)
-        .border_style(Style::default().fg(Color::Red));
+    if let Some(m) = app.selected_hardware().and_then(|hw| hw.module.as_ref()) {
+        let field = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".into());
+        let dbm = |v: &[f64]| {
+            if v.is_empty() {
+                "-".to_string()
+            } else {
+                v.iter()
+                    .map(|p| format!("{p:.2}"))
+                    .collect::<Vec<_>>()
+                    .join(" / ")
+                    + " dBm"
+            }
+        };
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::from("Module: ").bold()));
+        lines.push(Line::from(format!(
+            "  {} {} ({})",
+            field(&m.vendor),
+            field(&m.part_number),
+            field(&m.identifier)
+        )));
+        lines.push(Line::from(format!(
+            "  Wavelength: {} | Temp: {}",
+            field(&m.wavelength),
+            field(&m.temperature)
+        )));
+        lines.push(Line::from(format!("  TX power: {}", dbm(&m.tx_power_dbm))));
+        let rx = Span::from(format!("  RX power: {}", dbm(&m.rx_power_dbm)));
+        if m.rx_power_low() {
+            lines.push(Line::from(rx.fg(theme.bad)));
+            lines.push(Line::from(
+                Span::from(format!(
+                    "  RX power below {:.1} dBm: check fiber, connectors and the far end",
+                    m.rx_threshold_dbm()
+                ))
+                .fg(theme.bad),
+            ));
+        } else {
+            lines.push(Line::from(rx));
+        }
+    }
+
+    if let Some(link) = app.selected_hardware().and_then(|hw| hw.link.as_ref()) {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::from("Link settings: ").bold()));
+        let autoneg = match link.autoneg {
+            Some(true) => "on",
+            Some(false) => "off",
+            None => "-",
+        };
+        lines.push(Line::from(format!(
+            "  Autoneg: {autoneg} | Duplex: {}",
+            link.duplex.clone().unwrap_or_else(|| "-".into())
+        )));
+        if let Some(fec) = app.selected_hardware().and_then(|hw| hw.fec.as_ref()) {
+            let configured = if fec.configured.is_empty() {
+                "-".to_string()
+            } else {
+                fec.configured.join(" ")
+            };
+            lines.push(Line::from(format!(
+                "  FEC: {} (configured: {configured})",
+                fec.active.as_deref().unwrap_or("-")
+            )));
+            // 25G and faster lanes are specified with FEC; without it they see bit errors.
+            if fec.off()
+                && let Some(speed) = link_speed.filter(|s| *s >= 25_000)
+            {
+                lines.push(Line::from(
+                    Span::from(format!(
+                        "  No FEC at {speed} Mb/s: links this fast usually need RS or BaseR"
+                    ))
+                    .fg(theme.warn),
+                ));
+            }
+        }
+        lines.push(Line::from(format!(
+            "  Wake-on: {} (supports {})",
+            link.wol.clone().unwrap_or_else(|| "-".into()),
+            link.wol_supported.clone().unwrap_or_else(|| "-".into())
+        )));
+        let modes = |m: &[String]| {
+            if m.is_empty() {
+                "-".to_string()
+            } else {
+                m.join(" ")
+            }
+        };
+        lines.push(Line::from(format!(
+            "  Supported: {}",
+            modes(&link.supported_modes)
+        )));
+        lines.push(Line::from(format!(
+            "  Advertised: {}",
+            modes(&link.advertised_modes)
+        )));
+    }
+
+    if let Some(eee) = app.selected_hardware().and_then(|hw| hw.eee.as_ref()) {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::from("Energy Efficient Ethernet: ").bold()));
+        let status = match (eee.supported, eee.enabled, eee.active) {
+            (false, _, _) => Span::from("not supported").fg(theme.muted),
+            (_, false, _) => Span::from("disabled"),
+            (_, true, false) => Span::from("enabled, inactive (the link partner doesn't use it)"),
+            (_, true, true) => Span::from("enabled, active").fg(theme.warn),
+        };
+        lines.push(Line::from(vec![Span::from("  Status: "), status]));
+        if let Some(lpi) = &eee.tx_lpi {
+            lines.push(Line::from(format!("  TX low-power idle after: {lpi}")));
+        }
+        if !eee.partner_modes.is_empty() {
+            lines.push(Line::from(format!(
+                "  Link partner: {}",
+                eee.partner_modes.join(" ")
+            )));
+        }
+        if eee.active {
+            let mut hint =
+                "  Waking from low-power idle causes brief dropouts on some NICs".to_string();
+            if let Some(key) = app.config.keys.keys(Action::Eee).next() {
+                hint.push_str(&format!("; {key} turns it off"));
+            }
+            lines.push(Line::from(Span::from(hint).fg(theme.muted)));
+        }
+    }
+
+    render_text_tab(app, frame, area, Text::from(lines), true);
+}
+
+fn render_stats(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let Some(d) = app.selected_device() else {
+        return;
+    };
+    let mut lines = Vec::new();
+    lines.push(Line::from(Span::from("Statistics: ").bold()));
+    let st = &d.stats;
+    lines.push(Line::from(format!(
+        "  RX: {} ({} packets, {} errors, {} dropped)",
+        human_bytes(st.rx_bytes),
+        st.rx_packets,
+        st.rx_errors,
+        st.rx_dropped
+    )));
+    lines.push(Line::from(format!(
+        "  TX: {} ({} packets, {} errors, {} dropped)",
+        human_bytes(st.tx_bytes),
+        st.tx_packets,
+        st.tx_errors,
+        st.tx_dropped
+    )));
+    // CRC errors usually mean a bad cable or port; missed means the RX ring overflowed.
+    let problem = |n: u64| {
+        if n > 0 {
+            Span::from(n.to_string()).fg(theme.warn)
+        } else {
+            Span::from("0")
+        }
+    };
+    lines.push(Line::from(vec![
+        Span::from("  CRC errors: "),
+        problem(st.rx_crc_errors),
+        Span::from(" | Missed: "),
+        problem(st.rx_missed_errors),
+        Span::from(" | Collisions: "),
+        problem(st.collisions),
+        Span::from(format!(" | Multicast RX: {}", st.multicast)),
+    ]));
+
+    if let Some(history) = app.latency_history.get(&d.name)
+        && !history.is_empty()
+    {
+        lines.push(Line::from(""));
+        lines.push(Line::from(
+            Span::from("Latency (ping windows: age / loss / jitter): ").bold(),
+        ));
+        for w in history.iter().rev().take(5) {
+            lines.push(latency_window_line(w, &theme));
+        }
+    }
+
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(8), Constraint::Min(0)])
+        .split(area);
+    render_throughput(app, frame, parts[0]);
+    render_text_tab(app, frame, parts[1], Text::from(lines), true);
+}
+
+/// networkd's setup and online state, its DHCP clients and the servers it learned from them.
+fn networkd_lines(status: &LinkStatus, theme: &Theme) -> Vec<Line<'static>> {
+    let setup = status
+        .administrative_state
+        .clone()
+        .unwrap_or_else(|| "?".into());
+    let color = match setup.as_str() {
+        "configured" => theme.good,
+        "failed" => theme.bad,
+        "configuring" | "pending" => theme.warn,
+        _ => theme.muted,
+    };
+    let mut spans = vec![
+        Span::from("  Setup: ").bold(),
+        Span::from(setup.clone()).fg(color),
+    ];
+    if let Some(online) = &status.online_state {
+        spans.push(Span::from(", online state "));
+        spans.push(Span::from(online.clone()));
+    }
+    match status.required_for_online {
+        Some(true) if !status.required_operational_state_for_online.is_empty() => {
+            spans.push(Span::from(format!(
+                " (required for online: {})",
+                status.required_operational_state_for_online.join("/")
+            )))
+        }
+        Some(true) => spans.push(Span::from(" (required for online)")),
+        Some(false) => spans.push(Span::from(" (not required for online)").fg(theme.muted)),
+        None => {}
+    }
+    let mut lines = vec![Line::from(spans)];
+    if setup == "failed" {
+        lines.push(Line::from(
+            Span::from(
+                "    networkd couldn't apply its config; see journalctl -u systemd-networkd",
+            )
+            .fg(theme.bad),
+        ));
+    }
+
+    for (name, client) in [("DHCPv4", &status.dhcp4), ("DHCPv6", &status.dhcp6)] {
+        let Some(client) = client else {
+            continue;
+        };
+        let state = match &client.lease {
+            Some(lease) => {
+                let mut s = "bound".to_string();
+                if let Some(age) = lease.age() {
+                    s.push_str(&format!(", leased {} ago", human_duration(age.as_secs())));
+                }
+                if let Some(t1) = lease.renews_in() {
+                    s.push_str(&format!(", renews in {}", human_duration(t1.as_secs())));
+                }
+                Span::from(s)
+            }
+            None => Span::from("no lease yet").fg(theme.warn),
+        };
+        lines.push(Line::from(vec![
+            Span::from(format!("  {name} client: ")).bold(),
+            state,
+        ]));
+    }
+
+    for (label, entries) in [("DNS", &status.dns), ("NTP", &status.ntp)] {
+        let acquired = LinkStatus::acquired(entries);
+        if acquired.is_empty() {
+            continue;
+        }
+        let servers: Vec<String> = acquired
+            .iter()
+            .map(|(server, source)| format!("{server} ({source})"))
+            .collect();
+        lines.push(Line::from(vec![
+            Span::from(format!("  {label} acquired: ")).bold(),
+            Span::from(servers.join(", ")),
+        ]));
+    }
+    lines
+}
+
+/// Renders a tab that's plain text, scrolled by `details_scroll` with a scrollbar when it
+/// doesn't fit.
+/// `trim` drops leading whitespace, except where the indentation is the content (YAML).
+fn render_text_tab(app: &mut App, frame: &mut Frame, area: Rect, text: Text, trim: bool) {
+    let theme = app.config.theme;
+    // Leave a column for the scrollbar. Lines are counted as wrapped at this width, which can
+    // be off by a line where word wrapping breaks early; close enough to clamp the scroll.
+    let text_area = Rect {
+        width: area.width.saturating_sub(1),
+        ..area
+    };
+    let wrapped: usize = text
+        .lines
+        .iter()
+        .map(|l| l.width().div_ceil(text_area.width.max(1) as usize).max(1))
+        .sum();
+    let max_scroll = wrapped.saturating_sub(text_area.height as usize) as u16;
+    app.details_scroll = app.details_scroll.min(max_scroll);
+    app.details_height = text_area.height;
+
+    let p = Paragraph::new(text)
+        .alignment(Alignment::Left)
+        .wrap(ratatui::widgets::Wrap { trim })
+        .scroll((app.details_scroll, 0));
+    frame.render_widget(p, text_area);
+
+    if max_scroll > 0 {
+        let mut state = ScrollbarState::new(max_scroll as usize + 1)
+            .position(app.details_scroll as usize)
+            .viewport_content_length(text_area.height as usize);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .style(Style::default().fg(theme.muted)),
+            area,
+            &mut state,
+        );
+    }
+}
+
+fn render_offloads(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let Some(hw) = app.selected_hardware() else {
+        frame.render_widget(Paragraph::new("loading..."), area);
+        return;
+    };
+    if hw.offloads.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No offload information (is ethtool installed?)").fg(theme.muted),
+            area,
+        );
+        return;
+    }
+
+    let rows: Vec<Row> = hw
+        .offloads
+        .iter()
+        .map(|o| {
+            let name = if o.child {
+                format!("  {}", o.name)
+            } else {
+                o.name.clone()
+            };
+            let state = if o.enabled {
+                Span::from("on").fg(theme.good)
+            } else {
+                Span::from("off").fg(theme.bad)
+            };
+            let fixed = if o.fixed { "fixed" } else { "" };
+            Row::new(vec![
+                Cell::from(name),
+                Cell::from(state),
+                Cell::from(Span::from(fixed).fg(theme.muted)),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Min(30),
+        Constraint::Length(5),
+        Constraint::Length(6),
+    ];
+
+    let highlight = if app.focus == Focus::Details {
+        theme.highlight
+    } else {
+        Style::default()
+    };
+
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["Feature", "State", ""]).style(Style::default().fg(theme.header).bold()),
+        )
+        .row_highlight_style(highlight);
+
+    frame.render_stateful_widget(table, area, &mut app.offloads_state);
+}
+
+fn render_coalescing(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let Some(c) = app.selected_hardware().and_then(|hw| hw.coalesce.as_ref()) else {
+        frame.render_widget(
+            Paragraph::new("No coalescing information (ethtool -c)").fg(theme.muted),
+            area,
+        );
+        return;
+    };
+
+    let onoff = |v: Option<bool>| match v {
+        Some(true) => "on",
+        Some(false) => "off",
+        None => "-",
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            Span::from("Adaptive: ").bold(),
+            Span::from(format!(
+                "RX {} | TX {}",
+                onoff(c.adaptive_rx),
+                onoff(c.adaptive_tx)
+            )),
+        ]),
+        Line::from(""),
+    ];
+    for (k, v) in &c.params {
+        lines.push(Line::from(vec![
+            Span::from(format!("{k}: ")).bold(),
+            Span::from(v.clone()),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(
+        Span::from("Press c to edit (ethtool -C).").fg(theme.muted),
+    ));
+
+    frame.render_widget(Paragraph::new(Text::from(lines)), area);
+}
+
+fn render_routes(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let policy = policy_lines(app);
+    let area = if policy.is_empty() {
+        area
+    } else {
+        let height = (policy.len() as u16).min(area.height / 2);
+        let parts = Layout::vertical([Constraint::Min(3), Constraint::Length(height)]).split(area);
+        frame.render_widget(Paragraph::new(Text::from(policy)), parts[1]);
+        parts[0]
+    };
+    let Some(routes) = app.selected_routes() else {
+        frame.render_widget(Paragraph::new("loading..."), area);
+        return;
+    };
+    if routes.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No routes via this interface.").fg(theme.muted),
+            area,
+        );
+        return;
+    }
+
+    let dash = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".into());
+    let rows: Vec<Row> = routes
+        .iter()
+        .map(|r| {
+            Row::new(vec![
+                Cell::from(r.dst.clone()),
+                Cell::from(dash(&r.gateway)),
+                Cell::from(
+                    r.metric
+                        .map(|m| m.to_string())
+                        .unwrap_or_else(|| "-".into()),
+                ),
+                Cell::from(dash(&r.protocol)),
+                Cell::from(r.scope.clone().unwrap_or_else(|| "global".into())),
+                Cell::from(dash(&r.prefsrc)),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Min(18),
+        Constraint::Length(16),
+        Constraint::Length(7),
+        Constraint::Length(8),
+        Constraint::Length(7),
+        Constraint::Length(16),
+    ];
+
+    let highlight = if app.focus == Focus::Details {
+        theme.highlight
+    } else {
+        Style::default()
+    };
+
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec![
+                "Destination",
+                "Gateway",
+                "Metric",
+                "Proto",
+                "Scope",
+                "Src",
+            ])
+            .style(Style::default().fg(theme.header).bold()),
+        )
+        .row_highlight_style(highlight);
+    frame.render_stateful_widget(table, area, &mut app.routes_state);
+}
+
+/// Rules and non-main tables that route some of this interface's traffic differently from what
+/// the main table above says.
+fn policy_lines(app: &App) -> Vec<Line<'static>> {
+    let theme = app.config.theme;
+    let policy = match app.selected_policy() {
+        Some(Ok(p)) if !p.is_empty() => p,
+        Some(Err(e)) => {
+            return vec![Line::from(
+                Span::from(format!("Policy routing: {e}")).fg(theme.muted),
+            )];
+        }
+        _ => return Vec::new(),
+    };
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::from("Policy routing").fg(theme.header).bold(),
+            Span::from("  rules and tables besides main that involve this interface")
+                .fg(theme.muted),
+        ]),
+    ];
+    for rule in &policy.rules {
+        lines.push(Line::from(
+            Span::from(format!("  {}", rule.describe())).fg(theme.warn),
+        ));
+    }
+    for (table, routes) in &policy.tables {
+        let routes: Vec<String> = routes.iter().map(|r| r.describe()).collect();
+        lines.push(Line::from(vec![
+            Span::from(format!("  table {table}: ")).bold(),
+            Span::from(routes.join(", ")),
+        ]));
+    }
+    lines
+}
+
+fn render_neighbors(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    if app.neighbors.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No neighbors on this interface.").fg(theme.muted),
+            area,
+        );
+        return;
+    }
+
+    let empty = Default::default();
+    let oui = app.oui.as_ref().unwrap_or(&empty);
+    let rows: Vec<Row> = app
+        .neighbors
+        .iter()
+        .map(|n| {
+            let state = n.state.join(",");
+            let color = match state.as_str() {
+                "REACHABLE" | "PERMANENT" | "COMPLETE" => theme.good,
+                "STALE" | "DELAY" | "PROBE" => theme.warn,
+                "FAILED" | "INCOMPLETE" => theme.bad,
+                _ => theme.text,
+            };
+            let mac = n.lladdr.clone().unwrap_or_else(|| "-".into());
+            let vendor = n
+                .lladdr
+                .as_deref()
+                .and_then(|m| oui_vendor(oui, m))
+                .unwrap_or("-")
+                .to_string();
+            Row::new(vec![
+                Cell::from(n.dst.clone()),
+                Cell::from(mac),
+                Cell::from(Span::from(state).fg(color)),
+                Cell::from(vendor),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(28),
+        Constraint::Length(18),
+        Constraint::Length(11),
+        Constraint::Min(10),
+    ];
+
+    let table = Table::new(rows, widths).header(
+        Row::new(vec!["IP", "MAC", "State", "Vendor"])
+            .style(Style::default().fg(theme.header).bold()),
+    );
+    frame.render_widget(table, area);
+}
+
+fn render_connections(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    if app.connections.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No sockets bound to this interface's addresses.").fg(theme.muted),
+            area,
+        );
+        return;
+    }
+
+    let endpoint = |(ip, port): (IpAddr, u16)| match ip {
+        IpAddr::V4(v4) => format!("{v4}:{port}"),
+        IpAddr::V6(v6) => format!("[{v6}]:{port}"),
+    };
+    let rows: Vec<Row> = app
+        .connections
+        .iter()
+        .map(|s| {
+            let color = match s.state {
+                "ESTABLISHED" => theme.good,
+                "LISTEN" | "UNCONN" => theme.info,
+                "SYN_SENT" | "SYN_RECV" => theme.warn,
+                _ => theme.muted,
+            };
+            // Listening and unconnected sockets have no peer yet.
+            let peer = if s.peer.0.is_unspecified() {
+                "*".to_string()
+            } else {
+                endpoint(s.peer)
+            };
+            Row::new(vec![
+                Cell::from(s.proto),
+                Cell::from(endpoint(s.local)),
+                Cell::from(peer),
+                Cell::from(Span::from(s.state).fg(color)),
+                Cell::from(
+                    s.process
+                        .as_ref()
+                        .map_or_else(|| "-".to_string(), |(comm, pid)| format!("{comm} ({pid})")),
+                ),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(5),
+        Constraint::Min(22),
+        Constraint::Min(22),
+        Constraint::Length(12),
+        Constraint::Min(12),
+    ];
+
+    let highlight = if app.focus == Focus::Details {
+        theme.highlight
+    } else {
+        Style::default()
+    };
+
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["Proto", "Local", "Peer", "State", "Process"])
+                .style(Style::default().fg(theme.header).bold()),
+        )
+        .row_highlight_style(highlight);
+    frame.render_stateful_widget(table, area, &mut app.connections_state);
+}
+
+fn render_qos(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let qdiscs = match &app.qdiscs {
+        Ok(q) if q.is_empty() => {
+            frame.render_widget(Paragraph::new("loading...").fg(theme.muted), area);
+            return;
+        }
+        Ok(q) => q,
+        Err(e) => {
+            frame.render_widget(Paragraph::new(e.as_str()).fg(theme.bad), area);
+            return;
+        }
+    };
+
+    let rows: Vec<Row> = qdiscs
+        .iter()
+        .map(|q| {
+            let parent = if q.root {
+                "root".to_string()
+            } else {
+                q.parent.clone().unwrap_or_else(|| "-".into())
+            };
+            let drops = Span::from(q.drops.to_string());
+            let drops = if q.drops > 0 {
+                drops.fg(theme.warn)
+            } else {
+                drops
+            };
+            Row::new(vec![
+                Cell::from(q.kind.clone()),
+                Cell::from(q.handle.clone()),
+                Cell::from(parent),
+                Cell::from(human_bytes(q.bytes)),
+                Cell::from(drops),
+                Cell::from(q.overlimits.to_string()),
+                Cell::from(q.requeues.to_string()),
+                Cell::from(format!("{} / {}p", human_bytes(q.backlog), q.qlen)),
+                Cell::from(qdisc_options(q)).style(Style::default().fg(theme.muted)),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Length(6),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Length(12),
+        Constraint::Min(10),
+    ];
+
+    let table = Table::new(rows, widths).header(
+        Row::new(vec![
+            "Qdisc",
+            "Handle",
+            "Parent",
+            "Sent",
+            "Drops",
+            "Overlimits",
+            "Requeues",
+            "Backlog",
+            "Options",
+        ])
+        .style(Style::default().fg(theme.header).bold()),
+    );
+    frame.render_widget(table, area);
+}
+
+/// One row per queue pair with its packet rates and interrupts; the busiest RX queue is
+/// flagged when it gets more than twice its fair share, as RSS isn't spreading the load.
+fn render_queues(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let queues = match &app.queues {
+        Ok(Some(q)) => q,
+        Ok(None) => {
+            frame.render_widget(Paragraph::new("loading...").fg(theme.muted), area);
+            return;
+        }
+        Err(e) => {
+            frame.render_widget(Paragraph::new(e.as_str()).fg(theme.bad), area);
+            return;
+        }
+    };
+
+    let mut summary = vec![Span::from(format!(
+        "{} RX / {} TX queues",
+        queues.rx_count, queues.tx_count
+    ))];
+    let busiest = queues.rx_busiest_share();
+    let fair = 100.0 / queues.queues.len().max(1) as f64;
+    let unbalanced = busiest
+        .filter(|(_, share)| *share > 2.0 * fair)
+        .map(|(n, _)| n);
+    if let Some((n, share)) = busiest {
+        let text = format!(" | busiest RX queue {n}: {share:.0}% of packets");
+        summary.push(if unbalanced.is_some() {
+            Span::from(text + " (unbalanced)").fg(theme.warn)
+        } else {
+            Span::from(text)
+        });
+    }
+    let mut lines = vec![Line::from(summary)];
+    if !queues.other_irqs.is_empty() {
+        let other: Vec<String> = queues
+            .other_irqs
+            .iter()
+            .map(|irq| format!("{} {} (CPU {})", irq.number, irq.name, irq.cpus))
+            .collect();
+        lines.push(Line::from(format!("Other IRQs: {}", other.join(", "))).fg(theme.muted));
+    }
+
+    let rate = |r: Option<f64>| r.map_or("-".to_string(), human_count);
+    let total = |t: Option<u64>| t.map_or("-".to_string(), |t| human_count(t as f64));
+    let rows: Vec<Row> = queues
+        .queues
+        .iter()
+        .map(|(n, q)| {
+            let irqs: Vec<String> = q.irqs.iter().map(|i| i.number.to_string()).collect();
+            let mut cpus: Vec<&str> = q.irqs.iter().map(|i| i.cpus.as_str()).collect();
+            // Separate RX and TX vectors (virtio) are often on the same CPU.
+            cpus.dedup();
+            let row = Row::new(vec![
+                n.to_string(),
+                rate(q.rx_rate),
+                rate(q.tx_rate),
+                total(q.rx_packets),
+                total(q.tx_packets),
+                if irqs.is_empty() {
+                    "-".into()
+                } else {
+                    irqs.join(",")
+                },
+                if cpus.is_empty() {
+                    "-".into()
+                } else {
+                    cpus.join(",")
+                },
+                rate(q.irq_rate),
+            ]);
+            if unbalanced == Some(*n) {
+                row.style(Style::default().fg(theme.warn))
+            } else {
+                row
+            }
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(5),
+        Constraint::Length(9),
+        Constraint::Length(9),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Min(8),
+    ];
+    let highlight = if app.focus == Focus::Details {
+        theme.highlight
+    } else {
+        Style::default()
+    };
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec![
+                "Queue", "RX pkt/s", "TX pkt/s", "RX pkts", "TX pkts", "IRQ", "CPU", "IRQ/s",
+            ])
+            .style(Style::default().fg(theme.header).bold()),
+        )
+        .row_highlight_style(highlight);
+    let parts = Layout::vertical([
+        Constraint::Length(lines.len() as u16 + 1),
+        Constraint::Min(0),
+    ])
+    .split(area);
+    frame.render_widget(Paragraph::new(lines), parts[0]);
+    frame.render_stateful_widget(table, parts[1], &mut app.queues_state);
+}
+
+fn render_driver_stats(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    if let Err(e) = &app.driver_stats {
+        frame.render_widget(Paragraph::new(e.as_str()).fg(theme.bad), area);
+        return;
+    }
+    let stats = app.shown_driver_stats();
+    let total = app.driver_stats.as_ref().map_or(0, |s| s.len());
+    if total == 0 {
+        frame.render_widget(
+            Paragraph::new("No driver statistics (ethtool -S)").fg(theme.muted),
+            area,
+        );
+        return;
+    }
+
+    let changing = stats.iter().filter(|s| s.increasing()).count();
+    let rising: Vec<&str> = stats
+        .iter()
+        .filter(|s| s.increasing() && s.is_problem())
+        .map(|s| s.name.as_str())
+        .collect();
+    let mut summary = vec![Span::from(match &app.stats_filter {
+        Some(f) => format!(
+            "{} of {total} counters match {f:?} | {changing} changing",
+            stats.len()
+        ),
+        None => format!("{total} counters | {changing} changing"),
+    })];
+    if !rising.is_empty() {
+        summary.push(
+            Span::from(format!(" | increasing: {}", rising.join(", ")))
+                .fg(theme.bad)
+                .bold(),
+        );
+    }
+
+    let rows: Vec<Row> = stats
+        .iter()
+        .map(|s| {
+            let delta = match s.delta {
+                Some(0) | None => String::new(),
+                Some(d) => format!("+{d}"),
+            };
+            let style = if s.increasing() && s.is_problem() {
+                Style::default().fg(theme.bad).bold()
+            } else if s.increasing() {
+                Style::default()
+            } else {
+                Style::default().fg(theme.muted)
+            };
+            Row::new(vec![s.name.clone(), s.value.to_string(), delta]).style(style)
+        })
+        .collect();
+
+    let highlight = if app.focus == Focus::Details {
+        theme.highlight
+    } else {
+        Style::default()
+    };
+    let widths = [
+        Constraint::Min(30),
+        Constraint::Length(20),
+        Constraint::Length(12),
+    ];
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["Counter", "Value", "Change"])
+                .style(Style::default().fg(theme.header).bold()),
+        )
+        .row_highlight_style(highlight);
+    let parts = Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).split(area);
+    frame.render_widget(
+        Paragraph::new(Line::from(summary)).wrap(ratatui::widgets::Wrap { trim: true }),
+        parts[0],
+    );
+    // The header row isn't part of a page.
+    app.details_height = parts[1].height.saturating_sub(1);
+    frame.render_stateful_widget(table, parts[1], &mut app.driver_stats_state);
+}
+
+fn render_sriov(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let sriov = match &app.sriov {
+        Ok(Some(s)) => s,
+        Ok(None) => {
+            frame.render_widget(
+                Paragraph::new("Not an SR-IOV physical function (no sriov_totalvfs)")
+                    .fg(theme.muted),
+                area,
+            );
+            return;
+        }
+        Err(e) => {
+            frame.render_widget(Paragraph::new(e.as_str()).fg(theme.bad), area);
+            return;
+        }
+    };
+
+    let bound = |action| {
+        app.config
+            .keys
+            .keys(action)
+            .next()
+            .map(|k| k.to_string())
+            .unwrap_or_default()
+    };
+    let summary = Line::from(vec![
+        Span::from(format!(
+            "{} of {} VFs enabled",
+            sriov.num_vfs, sriov.total_vfs
+        )),
+        Span::from(format!(
+            " | {} changes the count, {} edits the selected VF",
+            bound(Action::NumVfs),
+            bound(Action::VfSettings)
+        ))
+        .fg(theme.muted),
+    ]);
+
+    let on_off = |v: Option<bool>| match v {
+        Some(true) => "on",
+        Some(false) => "off",
+        None => "-",
+    };
+    let rows: Vec<Row> = sriov
+        .vfs
+        .iter()
+        .map(|vf| {
+            let vlan = match vf.vlan() {
+                0 => "-".to_string(),
+                id => id.to_string(),
+            };
+            let row = Row::new(vec![
+                vf.number.to_string(),
+                vf.mac.clone().unwrap_or_else(|| "-".into()),
+                vlan,
+                on_off(vf.spoofchk).to_string(),
+                on_off(vf.trust).to_string(),
+                vf.link_state.clone().unwrap_or_else(|| "-".into()),
+            ]);
+            // Without spoof checking a guest can send as any MAC.
+            if vf.spoofable() {
+                row.style(Style::default().fg(theme.warn))
+            } else {
+                row
+            }
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(4),
+        Constraint::Length(19),
+        Constraint::Length(6),
+        Constraint::Length(12),
+        Constraint::Length(6),
+        Constraint::Min(8),
+    ];
+    let highlight = if app.focus == Focus::Details {
+        theme.highlight
+    } else {
+        Style::default()
+    };
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["VF", "MAC", "VLAN", "Spoof check", "Trust", "Link"])
+                .style(Style::default().fg(theme.header).bold()),
+        )
+        .row_highlight_style(highlight);
+    let parts = Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).split(area);
+    frame.render_widget(Paragraph::new(summary), parts[0]);
+    frame.render_stateful_widget(table, parts[1], &mut app.sriov_state);
+}
+
+/// Packet and interrupt counts: `950`, `12.3k`, `4.1M`.
+fn human_count(n: f64) -> String {
+    if n < 1_000.0 {
+        format!("{n:.0}")
+    } else if n < 1_000_000.0 {
+        format!("{:.1}k", n / 1_000.0)
+    } else if n < 1_000_000_000.0 {
+        format!("{:.1}M", n / 1_000_000.0)
+    } else {
+        format!("{:.1}G", n / 1_000_000_000.0)
+    }
+}
+
+/// Scalar options as `key=value`; nested ones (cake's tin stats, priomaps...) are skipped.
+fn qdisc_options(q: &Qdisc) -> String {
+    let Some(serde_json::Value::Object(options)) = &q.options else {
+        return String::new();
+    };
+    options
+        .iter()
+        .filter_map(|(k, v)| match v {
+            serde_json::Value::String(s) => Some(format!("{k}={s}")),
+            serde_json::Value::Number(n) => Some(format!("{k}={n}")),
+            serde_json::Value::Bool(true) => Some(k.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn render_firewall(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let (tool, rules) = match &app.firewall {
+        Ok((tool, rules)) => (*tool, rules),
+        Err(e) => {
+            frame.render_widget(Paragraph::new(e.as_str()).fg(theme.bad), area);
+            return;
+        }
+    };
+    if tool.is_empty() {
+        frame.render_widget(Paragraph::new("loading...").fg(theme.muted), area);
+        return;
+    }
+    if rules.is_empty() {
+        frame.render_widget(
+            Paragraph::new(format!("No {tool} rules mention this interface.")).fg(theme.muted),
+            area,
+        );
+        return;
+    }
+
+    let count = |v: Option<u64>| v.map_or_else(|| "-".to_string(), |v| v.to_string());
+    let rows: Vec<Row> = rules
+        .iter()
+        .map(|r| {
+            // Rules that drop traffic and have actually hit something are what people look for.
+            let blocking = ["drop", "reject", "DROP", "REJECT"]
+                .iter()
+                .any(|v| r.rule.split_whitespace().any(|w| w == *v));
+            let style = if blocking && r.packets.is_some_and(|p| p > 0) {
+                Style::default().fg(theme.bad)
+            } else {
+                Style::default()
+            };
+            Row::new(vec![
+                Cell::from(r.chain.clone()),
+                Cell::from(r.rule.clone()),
+                Cell::from(count(r.packets)),
+                Cell::from(r.bytes.map_or_else(|| "-".to_string(), human_bytes)),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(28),
+        Constraint::Min(30),
+        Constraint::Length(10),
+        Constraint::Length(10),
+    ];
+
+    let highlight = if app.focus == Focus::Details {
+        theme.highlight
+    } else {
+        Style::default()
+    };
+
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["Chain", "Rule", "Packets", "Bytes"])
+                .style(Style::default().fg(theme.header).bold()),
+        )
+        .row_highlight_style(highlight);
+    frame.render_stateful_widget(table, area, &mut app.firewall_state);
+}
+
+fn render_history(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let Some(history) = &app.history else {
+        frame.render_widget(
+            Paragraph::new("History is off (history = false in the config).").fg(theme.muted),
+            area,
+        );
+        return;
+    };
+    let Some(d) = app.selected_device() else {
+        return;
+    };
+
+    let now = std::time::SystemTime::now();
+    let when = |at: std::time::SystemTime| {
+        let ago = now.duration_since(at).map_or("just now".into(), |d| {
+            format!("{} ago", human_duration(d.as_secs()))
+        });
+        format!("{} ({ago})", datetime(at).unwrap_or_else(|| "?".into()))
+    };
+    let summary = match history.address_changed(&d.name) {
+        Some(e) => Line::from(vec![
+            Span::from("Address last changed: ").bold(),
+            Span::from(when(e.at())),
+        ]),
+        None => Line::from("No address changes recorded yet.").fg(theme.muted),
+    };
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(1)])
+        .split(area);
+    frame.render_widget(
+        Paragraph::new(vec![
+            summary,
+            Line::from(format!("Stored in {}", history.path.display())).fg(theme.muted),
+        ]),
+        parts[0],
+    );
+
+    let rows: Vec<Row> = history
+        .for_iface(&d.name)
+        .rev()
+        .map(|e| {
+            let (change, color) = match e.kind {
+                HistoryKind::AddressAdded => ("address added", theme.good),
+                HistoryKind::AddressRemoved => ("address removed", theme.bad),
+                HistoryKind::Gateway if e.value.is_empty() => ("gateway removed", theme.bad),
+                HistoryKind::Gateway => ("gateway", theme.info),
+                HistoryKind::Lease => ("new lease", theme.info),
+            };
+            Row::new(vec![
+                Cell::from(datetime(e.at()).unwrap_or_default()),
+                Cell::from(change).fg(color),
+                Cell::from(e.value.clone()),
+                Cell::from(e.detail.clone().unwrap_or_default()).fg(theme.muted),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(19),
+        Constraint::Length(15),
+        Constraint::Length(28),
+        Constraint::Min(10),
+    ];
+
+    let highlight = if app.focus == Focus::Details {
+        theme.highlight
+    } else {
+        Style::default()
+    };
+
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["Time", "Change", "Value", "Details"])
+                .style(Style::default().fg(theme.header).bold()),
+        )
+        .row_highlight_style(highlight);
+    frame.render_stateful_widget(table, parts[1], &mut app.history_state);
+}
+
+/// The `.network` and `.link` files behind the interface, as systemd layers them, so it's clear
+/// where each setting comes from.
+fn render_config(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let Some(units) = &app.units else {
+        frame.render_widget(Paragraph::new("loading..."), area);
+        return;
+    };
+    let name = app.selected_device().map_or("-".into(), |d| d.name.clone());
+
+    let mut lines = Vec::new();
+    if let Some(info) = app.selected_backend_info() {
+        let mut managed = info.backend.name().to_string();
+        if let Some(src) = &info.config_source {
+            managed.push_str(&format!(" ({src})"));
+        }
+        lines.push(Line::from(vec![
+            Span::from("Managed by: ").bold(),
+            Span::from(managed),
+        ]));
+    }
+    if let Some(plan) = &app.netplan {
+        lines.push(Line::from(""));
+        lines.extend(netplan_lines(plan, &name, &theme));
+    }
+    if let Some(interfaces) = &app.ifupdown {
+        lines.push(Line::from(""));
+        lines.extend(ifupdown_lines(interfaces, &name, &theme));
+    }
+    let sections = [("Network file", &units.network), ("Link file", &units.link)];
+    for (title, unit) in sections {
+        lines.push(Line::from(""));
+        let Some(unit) = unit else {
+            lines.push(Line::from(vec![
+                Span::from(format!("{title}: ")).bold(),
+                Span::from("none").fg(theme.muted),
+            ]));
+            continue;
+        };
+        lines.push(Line::from(vec![
+            Span::from(format!("{title}: ")).bold(),
+            Span::from(unit.path.clone()),
+        ]));
+        if netplan::generated(&unit.path) {
+            lines.push(
+                Line::from("generated by netplan: edit the YAML above, not this file")
+                    .fg(theme.muted),
+            );
+        }
+        if !unit.dropins.is_empty() {
+            lines
+                .push(Line::from("drop-ins below override it, later ones winning").fg(theme.muted));
+        }
+        for (i, (path, text)) in unit.contents.iter().enumerate() {
+            if i > 0 {
+                lines.push(Line::from(""));
+            }
+            lines.push(Line::from(Span::from(format!("# {path}")).fg(theme.accent)));
+            match text {
+                Ok(text) => lines.extend(text.lines().map(|l| {
+                    let t = l.trim_start();
+                    let line = Line::from(l.to_string());
+                    if t.starts_with('[') {
+                        line.fg(theme.header).bold()
+                    } else if t.starts_with('#') || t.starts_with(';') {
+                        line.fg(theme.muted)
+                    } else {
+                        line
+                    }
+                })),
+                Err(e) => lines.push(Line::from(format!("can't read it: {e}")).fg(theme.bad)),
+            }
+        }
+    }
+    if units.network.is_none() && units.link.is_none() {
+        lines.push(Line::from(""));
+        lines.push(
+            Line::from(format!(
+                "systemd-networkd doesn't manage {name}, and udev didn't record a .link file for it."
+            ))
+            .fg(theme.muted),
+        );
+    }
+
+    render_text_tab(app, frame, area, Text::from(lines), false);
+}
+
+/// The netplan definitions for `name`, each headed by its file and line.
+fn netplan_lines(plan: &netplan::Netplan, name: &str, theme: &Theme) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if plan.stanzas.is_empty() {
+        lines.push(Line::from(vec![
+            Span::from("Netplan: ").bold(),
+            Span::from(format!("no definition matches {name}")).fg(theme.muted),
+        ]));
+        lines.push(Line::from(format!("read {}", plan.files.join(", "))).fg(theme.muted));
+        return lines;
+    }
+    lines.push(Line::from(vec![
+        Span::from("Netplan: ").bold(),
+        Span::from(
+            plan.stanzas
+                .iter()
+                .map(|s| format!("{}: {}", s.kind, s.id))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    ]));
+    if plan.stanzas.len() > 1 {
+        lines.push(Line::from("netplan merges these, later ones winning").fg(theme.muted));
+    }
+    let excerpts = plan
+        .stanzas
+        .iter()
+        .map(|s| (s.path.as_str(), s.line, s.text.as_str()));
+    lines.extend(excerpt_lines(excerpts, theme));
+    lines
+}
+
+/// The ifupdown stanzas for `name`: its own `auto` and `iface` ones, and bridges or bonds it's a
+/// port of.
+fn ifupdown_lines(
+    interfaces: &ifupdown::Interfaces,
+    name: &str,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if interfaces.stanzas.is_empty() {
+        lines.push(Line::from(vec![
+            Span::from("ifupdown: ").bold(),
+            Span::from(format!("no stanza mentions {name}")).fg(theme.muted),
+        ]));
+        lines.push(Line::from(format!("read {}", interfaces.files.join(", "))).fg(theme.muted));
+        return lines;
+    }
+    let summary: Vec<String> = interfaces
+        .stanzas
+        .iter()
+        .filter(|s| !s.method.is_empty())
+        .map(|s| {
+            if s.iface == name {
+                format!("iface {} {}", s.iface, s.method)
+            } else {
+                format!("port of {}", s.iface)
+            }
+        })
+        .collect();
+    let summary = if summary.is_empty() {
+        Span::from(format!("only auto/allow lines, no iface stanza for {name}")).fg(theme.muted)
+    } else {
+        Span::from(summary.join(", "))
+    };
+    lines.push(Line::from(vec![Span::from("ifupdown: ").bold(), summary]));
+    let excerpts = interfaces
+        .stanzas
+        .iter()
+        .map(|s| (s.path.as_str(), s.line, s.text.as_str()));
+    lines.extend(excerpt_lines(excerpts, theme));
+    lines
+}
+
+/// Config file excerpts as `(path, first line, text)`, each headed by `# path:line` unless it
+/// picks up right where the previous one ended.
+fn excerpt_lines<'a>(
+    excerpts: impl Iterator<Item = (&'a str, usize, &'a str)>,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut next: Option<(&str, usize)> = None;
+    for (path, line, text) in excerpts {
+        if next != Some((path, line)) {
+            if next.is_some() {
+                lines.push(Line::from(""));
+            }
+            lines.push(Line::from(
+                Span::from(format!("# {path}:{line}")).fg(theme.accent),
+            ));
+        }
+        next = Some((path, line + text.lines().count()));
+        lines.extend(text.lines().map(|l| {
+            let line = Line::from(l.to_string());
+            if l.trim_start().starts_with('#') {
+                line.fg(theme.muted)
+            } else {
+                line
+            }
+        }));
+    }
+    lines
+}
+
+fn render_throughput(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = app.config.theme;
+    let Some(tp) = app.selected_throughput() else {
+        return;
+    };
+
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let series = [
+        ("RX", &tp.rx, tp.rx_rate(), theme.rx),
+        ("TX", &tp.tx, tp.tx_rate(), theme.tx),
+    ];
+
+    for ((label, samples, rate, color), area) in series.into_iter().zip(halves.iter()) {
+        let block = Block::default()
+            .title(format!(" {label} {}/s ", human_bytes(rate)))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+
+        // Newest sample on the right; only as many as fit the width.
+        let width = block.inner(*area).width as usize;
+        let data: Vec<u64> = samples
+            .iter()
+            .rev()
+            .take(width.min(THROUGHPUT_SAMPLES))
+            .copied()
+            .collect();
+
+        let sparkline = Sparkline::default()
+            .block(block)
+            .data(&data)
+            .direction(RenderDirection::RightToLeft)
+            .style(Style::default().fg(color));
+        frame.render_widget(sparkline, *area);
+    }
+}
+
+fn connectivity_color(c: Option<Connectivity>, theme: &Theme) -> Color {
+    match c {
+        Some(Connectivity::Online) => theme.good,
+        Some(Connectivity::Portal) => theme.warn,
+        Some(Connectivity::NoDns | Connectivity::Offline) => theme.bad,
+        Some(Connectivity::Unknown) | None => Color::Reset,
+    }
+}
+
+/// Says what the lease color does, for themes (or eyes) where it doesn't show.
+fn lease_mark(l: &DhcpLease) -> &'static str {
+    match l.health(std::time::SystemTime::now()) {
+        LeaseHealth::Ok | LeaseHealth::Unknown => "",
+        LeaseHealth::Warning => "!",
+        LeaseHealth::Critical => "!!",
+    }
+}
+
+fn lease_color(l: &DhcpLease, theme: &Theme) -> Color {
+    match l.health(std::time::SystemTime::now()) {
+        LeaseHealth::Ok | LeaseHealth::Unknown => theme.text,
+        LeaseHealth::Warning => theme.warn,
+        LeaseHealth::Critical => theme.bad,
+    }
+}
+
+fn lease_countdown(d: &EthernetDevice, theme: &Theme) -> Span<'static> {
+    match &d.lease {
+        Some(l) => {
+            let left = l
+                .expires_in(std::time::SystemTime::now())
+                .map(|d| human_duration(d.as_secs()))
+                .unwrap_or_else(|| "dhcp".into());
+            Span::from(left + lease_mark(l)).fg(lease_color(l, theme))
+        }
+        None if d.ipv4.is_empty() => Span::from("-"),
+        None => Span::from("static").fg(theme.muted),
+    }
+}
+
+pub fn human_duration(secs: u64) -> String {
+    let (d, h, m, s) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if d > 0 {
+        format!("{d}d {h}h")
+    } else if h > 0 {
+        format!("{h}h {m:02}m")
+    } else if m > 0 {
+        format!("{m}m {s:02}s")
+    } else {
+        format!("{s}s")
+    }
+}
+
+/// First characters of a base64 WireGuard key, as `wg` users recognize them.
+fn short_key(key: &str) -> String {
+    match key.get(..8) {
+        Some(prefix) if key.len() > 8 => format!("{prefix}…"),
+        _ => key.to_string(),
+    }
+}
+
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+fn render_footer(frame: &mut Frame, area: Rect, keys: &Keymap, theme: &Theme) {
+    let mut spans = Vec::new();
+    // Toggling an offload and editing routes only work on their tabs, where they're
+    // self-explanatory, and share keys with actions listed here.
+    for action in Action::ALL.into_iter().filter(|a| {
+        !matches!(
+            a,
+            Action::ToggleOffload | Action::AddRoute | Action::DeleteRoute
+        )
+    }) {
+        let mut bound = keys.keys(action).peekable();
+        if bound.peek().is_none() {
+            continue;
+        }
+        if !spans.is_empty() {
+            spans.push(Span::from(" | "));
+        }
+        for (i, key) in bound.enumerate() {
+            if i > 0 {
+                spans.push(Span::from(","));
+            }
+            spans.push(Span::from(key.to_string()).bold());
+        }
+        spans.push(Span::from(format!(" {}", action.label())));
+    }
+    let text = Line::from(spans);
+
+    let p = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(theme.info))
+        .wrap(ratatui::widgets::Wrap { trim: true });
+    frame.render_widget(p, area);
+}
+
+fn render_command_line(frame: &mut Frame, area: Rect, line: &CommandLine, theme: &Theme) {
+    let text = Line::from(vec![
+        Span::from(":").fg(theme.header).bold(),
+        Span::from(format!("{}█", line.input)),
+    ]);
+    let hint = Line::from(
+        Span::from("Tab complete | ↑↓ history | Enter run | Esc cancel | :help lists keys")
+            .fg(theme.muted),
+    );
+    frame.render_widget(Paragraph::new(vec![text, hint]), area);
+}
+
+fn render_search_line(frame: &mut Frame, area: Rect, search: &Search, theme: &Theme) {
+    let text = Line::from(vec![
+        Span::from("/").fg(theme.header).bold(),
+        Span::from(format!("{}█", search.input)),
+    ]);
+    let hint = if search.counters {
+        "Filters counters as you type (substring, or a glob like *err*) | Enter keep | Esc cancel"
+    } else {
+        "Filters as you type (substring, or a glob like enp*) | Enter keep | Esc cancel"
+    };
+    let hint = Line::from(Span::from(hint).fg(theme.muted));
+    frame.render_widget(Paragraph::new(vec![text, hint]), area);
+}
+
+fn render_too_small(frame: &mut Frame, area: Rect, min_w: u16, min_h: u16, theme: &Theme) {
+    let block = Block::default()
+        .title(" ethtui ")
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.warn));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let msg = format!(
+        "Terminal is too small.\n\nMinimum size: {}x{}\nCurrent size:  {}x{}",
+        min_w, min_h, area.width, area.height
+    );
+    let p = Paragraph::new(msg)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(theme.text))
+        .wrap(ratatui::widgets::Wrap { trim: true });
+    frame.render_widget(p, inner);
+}
+
+fn render_error_popup(frame: &mut Frame, msg: &str, theme: &Theme) {
+    let area = centered_rect(80, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(" Error ")
+        .title_bottom(Line::from(" any key to dismiss ").right_aligned())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.bad));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let p = Paragraph::new(msg)
+        .alignment(Alignment::Left)
+        .style(Style::default().fg(theme.text))
+        .wrap(ratatui::widgets::Wrap { trim: true });
+
+    frame.render_widget(p, inner);
+}
+
+fn render_toast_popup(frame: &mut Frame, kind: crate::app::ToastKind, msg: &str, theme: &Theme) {
+    let (title, color) = match kind {
+        crate::app::ToastKind::Success => (" Success ", theme.good),
+        crate::app::ToastKind::Error => (" Error ", theme.bad),
+        crate::app::ToastKind::Info => (" Info ", theme.info),
+    };
+
+    let area = centered_rect(80, 28, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(color));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let p = Paragraph::new(msg)
+        .alignment(Alignment::Left)
+        .style(Style::default().fg(theme.text))
+        .wrap(ratatui::widgets::Wrap { trim: false });
+    frame.render_widget(p, inner);
+}
+
+fn render_ping_popup(frame: &mut Frame, ping: &Ping, theme: &Theme) {
+    let area = centered_rect(80, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let status = if ping.finished { " (stopped)" } else { "" };
+    let block = Block::default()
+        .title(format!(" Ping {} via {}{status} ", ping.target, ping.iface))
+        .title_bottom(Line::from(" Esc to stop ").right_aligned())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(5)])
+        .split(inner);
+
+    let ms = |v: Option<f64>| v.map_or_else(|| "-".to_string(), |v| format!("{v:.2}"));
+    let loss = ping.loss_pct();
+    let mut summary = vec![Line::from(vec![
+        Span::from("Sent: ").bold(),
+        Span::from(ping.sent.to_string()),
+        Span::from("  Received: ").bold(),
+        Span::from(ping.received.to_string()),
+        Span::from("  Loss: ").bold(),
+        Span::from(format!("{loss:.1}%")).fg(loss_pct_color(loss, theme)),
+        Span::from("  min/avg/max: ").bold(),
+        Span::from(format!(
+            "{}/{}/{} ms",
+            ms(ping.min),
+            ms(ping.avg()),
+            ms(ping.max)
+        )),
+    ])];
+    let rolling = ping.rolling();
+    summary.push(Line::from(vec![
+        Span::from(format!("Last {}: ", ping.window)).bold(),
+        Span::from(format!("loss {:.1}%", rolling.loss_pct))
+            .fg(loss_pct_color(rolling.loss_pct, theme)),
+        Span::from(format!("  jitter {} ms", ms(rolling.jitter_ms))),
+    ]));
+    if let Some(err) = &ping.error {
+        summary.push(Line::from(Span::from(err.clone()).fg(theme.bad)));
+    }
+    frame.render_widget(Paragraph::new(summary), chunks[0]);
+
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(30), Constraint::Length(30)])
+        .split(chunks[1]);
+    let history: Vec<Line> = std::iter::once(Line::from(Span::from(" age / loss / jitter").bold()))
+        .chain(
+            ping.history
+                .iter()
+                .rev()
+                .map(|w| latency_window_line(w, theme)),
+        )
+        .collect();
+    frame.render_widget(
+        Paragraph::new(history).block(Block::default().borders(Borders::LEFT)),
+        panes[1],
+    );
+
+    let replies: Vec<(f64, f64)> = ping
+        .samples
+        .iter()
+        .filter_map(|(seq, rtt)| rtt.map(|ms| (*seq as f64, ms)))
+        .collect();
+    let y_max = ping.max.unwrap_or(1.0).max(1.0) * 1.2;
+    // Lost requests are drawn along the top edge so they stand out from the RTT line.
+    let lost: Vec<(f64, f64)> = ping
+        .samples
+        .iter()
+        .filter(|(_, rtt)| rtt.is_none())
+        .map(|(seq, _)| (*seq as f64, y_max))
+        .collect();
+
+    let x_max = ping.sent.max(PING_SAMPLES as u64) as f64;
+    let x_min = x_max - PING_SAMPLES as f64;
+    let datasets = vec![
+        Dataset::default()
+            .name("RTT")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(theme.good))
+            .data(&replies),
+        Dataset::default()
+            .name("lost")
+            .marker(Marker::Dot)
+            .graph_type(GraphType::Scatter)
+            .style(Style::default().fg(theme.bad))
+            .data(&lost),
+    ];
+    let chart = Chart::new(datasets)
+        .x_axis(
+            Axis::default()
+                .title("icmp_seq")
+                .bounds([x_min, x_max])
+                .style(Style::default().fg(theme.muted)),
+        )
+        .y_axis(
+            Axis::default()
+                .title("ms")
+                .bounds([0.0, y_max])
+                .labels([
+                    Span::from("0"),
+                    Span::from(format!("{:.1}", y_max / 2.0)),
+                    Span::from(format!("{y_max:.1}")),
+                ])
+                .style(Style::default().fg(theme.muted)),
+        );
+    frame.render_widget(chart, panes[0]);
+}
+
+fn loss_pct_color(loss: f64, theme: &Theme) -> Color {
+    if loss == 0.0 {
+        theme.good
+    } else if loss < 5.0 {
+        theme.warn
+    } else {
+        theme.bad
+    }
+}
+
+/// `   3m 02s   5.0%    1.23 ms`: age, loss and jitter of one window.
+fn latency_window_line(w: &LatencyWindow, theme: &Theme) -> Line<'static> {
+    let ago = std::time::SystemTime::now()
+        .duration_since(w.ended)
+        .map(|d| human_duration(d.as_secs()))
+        .unwrap_or_default();
+    let jitter = w
+        .jitter_ms
+        .map_or_else(|| "-".to_string(), |j| format!("{j:.2}"));
+    Line::from(vec![
+        Span::from(format!("  {ago:>7}  ")),
+        Span::from(format!("{:>5.1}%", w.loss_pct)).fg(loss_pct_color(w.loss_pct, theme)),
+        Span::from(format!("  {jitter:>6} ms")),
+    ])
+}
+
+fn render_trace_popup(frame: &mut Frame, trace: &Trace, theme: &Theme) {
+    let area = centered_rect(80, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let status = if trace.finished { " (done)" } else { "" };
+    let block = Block::default()
+        .title(format!(
+            " {} {} via {}{status} ",
+            trace.program, trace.target, trace.iface
+        ))
+        .title_bottom(Line::from(" Esc to stop ").right_aligned())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let ms = |v: Option<f64>| v.map_or_else(|| "-".to_string(), |v| format!("{v:.1}"));
+    let rows: Vec<Row> = trace
+        .hops
+        .iter()
+        .enumerate()
+        .map(|(i, hop)| {
+            let loss = hop.loss_pct();
+            let loss_color = if hop.sent == 0 || loss == 0.0 {
+                Color::Reset
+            } else if loss < 50.0 {
+                theme.warn
+            } else {
+                theme.bad
+            };
+            Row::new(vec![
+                Cell::from((i + 1).to_string()),
+                Cell::from(hop.host.clone().unwrap_or_else(|| "???".into())),
+                Cell::from(format!("{loss:.0}%")).style(Style::default().fg(loss_color)),
+                Cell::from(hop.sent.to_string()),
+                Cell::from(ms(hop.last)),
+                Cell::from(ms(hop.avg())),
+                Cell::from(ms(hop.best)),
+                Cell::from(ms(hop.worst)),
+            ])
+        })
+        .collect();
+
+    let header = ["#", "Host", "Loss", "Sent", "Last", "Avg", "Best", "Worst"]
+        .into_iter()
+        .map(|h| Cell::from(h).style(Style::default().fg(theme.header)));
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Min(16),
+        Constraint::Length(6),
+        Constraint::Length(5),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+    ];
+    let table = Table::new(rows, widths).header(Row::new(header).style(Style::new().bold()));
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(inner);
+    frame.render_widget(table, chunks[0]);
+    if let Some(err) = &trace.error {
+        frame.render_widget(
+            Paragraph::new(Span::from(err.clone()).fg(theme.bad)),
+            chunks[1],
+        );
+    }
+}
+
+fn render_netplan_try_popup(frame: &mut Frame, t: &NetplanTry, theme: &Theme) {
+    let area = centered_rect(70, 50, frame.area());
+    frame.render_widget(Clear, area);
+
+    let (status, color, keys) = match (t.exit, t.answer) {
+        (Some(0), Some(TryAnswer::Keep)) => (
+            "new configuration kept".to_string(),
+            theme.good,
+            " Esc to close ",
+        ),
+        (Some(0), _) => (
+            "configuration reverted".to_string(),
+            theme.warn,
+            " Esc to close ",
+        ),
+        (Some(code), _) => (format!("failed ({code})"), theme.bad, " Esc to close "),
+        (None, Some(TryAnswer::Keep)) => ("keeping it...".to_string(), theme.info, ""),
+        (None, Some(TryAnswer::Revert)) => ("reverting...".to_string(), theme.warn, ""),
+        (None, None) => (
+            format!("reverts in {}s", t.remaining()),
+            theme.warn,
+            " Enter to keep, Esc to revert now ",
+        ),
+    };
+    let block = Block::default()
+        .title(" netplan try ")
+        .title_bottom(Line::from(keys).right_aligned())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(color));
+
+    let mut lines = vec![Line::from(Span::from(status).fg(color).bold())];
+    if t.exit.is_none() {
+        lines.push(
+            Line::from(
+                "The new configuration is live on every interface netplan manages. If it cut you \
+                 off, just wait: netplan puts the old one back by itself.",
+            )
+            .fg(theme.muted),
+        );
+    }
+    lines.push(Line::from(""));
+    lines.extend(t.output.iter().map(|l| Line::from(l.clone())));
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(ratatui::widgets::Wrap { trim: false }),
+        area,
+    );
+}
+
+fn render_speedtest_popup(frame: &mut Frame, st: &SpeedTest, theme: &Theme) {
+    let area = centered_rect(80, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let tool = match st.mode {
+        SpeedTestMode::Iperf3 => "iperf3",
+        SpeedTestMode::Http => "HTTP download",
+    };
+    let status = if st.finished { " (done)" } else { "" };
+    let block = Block::default()
+        .title(format!(
+            " Speed test: {tool} {} via {}{status} ",
+            st.target, st.iface
+        ))
+        .title_bottom(
+            Line::from(if st.finished {
+                " Esc to close "
+            } else {
+                " Esc to stop "
+            })
+            .right_aligned(),
+        )
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let p = Paragraph::new(msg)
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(5)])
+        .split(inner);
+
+    let mut summary = vec![Line::from(vec![
+        Span::from("Now: ").bold(),
+        Span::from(format!("{:.1} Mbit/s", st.current())),
+    ])];
+    if let Some(result) = &st.summary {
+        summary.push(Line::from(vec![
+            Span::from("Result: ").bold(),
+            Span::from(result.clone()).fg(theme.good),
+        ]));
+    }
+    if let Some(err) = &st.error {
+        summary.push(Line::from(Span::from(err.clone()).fg(theme.bad)));
+    }
+    frame.render_widget(Paragraph::new(summary), chunks[0]);
+
+    let data: Vec<(f64, f64)> = st
+        .samples
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i as f64, *v))
+        .collect();
+    let y_max = st.samples.iter().copied().fold(1.0, f64::max) * 1.2;
+    let x_max = (data.len().max(2) - 1) as f64;
+    let chart = Chart::new(vec![
+        Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(theme.good))
+            .data(&data),
+    ])
+    .x_axis(
+        Axis::default()
+            .bounds([0.0, x_max])
+            .style(Style::default().fg(theme.muted)),
+    )
+    .y_axis(
+        Axis::default()
+            .title("Mbit/s")
+            .bounds([0.0, y_max])
+            .labels([
+                Span::from("0"),
+                Span::from(format!("{:.0}", y_max / 2.0)),
+                Span::from(format!("{y_max:.0}")),
+            ])
+            .style(Style::default().fg(theme.muted)),
+    );
+    frame.render_widget(chart, chunks[1]);
+}
+
+fn render_dns_test_popup(frame: &mut Frame, test: &DnsTest, theme: &Theme) {
+    let area = centered_rect(80, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let status = if test.finished { " (done)" } else { "" };
+    let block = Block::default()
+        .title(if test.benchmark() {
+            format!(
+                " DNS benchmark: {} queries per server via {}{status} ",
+                test.queries, test.iface
+            )
+        } else {
+            format!(" DNS test: {} via {}{status} ", test.host, test.iface)
+        })
+        .title_bottom(
+            Line::from(if test.finished {
+                " Esc to close "
+            } else {
+                " Esc to stop "
+            })
+            .right_aligned(),
+        )
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(inner);
+    if test.benchmark() {
+        render_dns_benchmark(frame, chunks[0], test, theme);
+    } else {
+        render_dns_results(frame, chunks[0], test, theme);
+    }
+    if test.finished && !test.benchmark() {
+        let (resolved, total) = (test.resolved(), test.servers.len());
+        let color = if resolved == total {
+            theme.good
+        } else {
+            theme.bad
+        };
+        frame.render_widget(
+            Paragraph::new(
+                Span::from(format!("{resolved} of {total} servers resolved it")).fg(color),
+            ),
+            chunks[1],
+        );
+    }
+}
+
+/// One row per server with its single answer.
+fn render_dns_results(frame: &mut Frame, area: Rect, test: &DnsTest, theme: &Theme) {
+    let rows: Vec<Row> = test
+        .servers
+        .iter()
+        .map(|s| {
+            let (status, time, addresses) = match s.answers.first() {
+                None => (
+                    Span::from("waiting").fg(theme.muted),
+                    "-".into(),
+                    String::new(),
+                ),
+                Some(Ok(reply)) => {
+                    let color = if reply.resolved() {
+                        theme.good
+                    } else {
+                        theme.bad
+                    };
+                    let addresses = reply.addresses.iter().map(|a| a.to_string());
+                    (
+                        Span::from(reply.status()).fg(color),
+                        format!("{:.1}", reply.rtt.as_secs_f64() * 1000.0),
+                        addresses.collect::<Vec<_>>().join(", "),
+                    )
+                }
+                Some(Err(err)) => (
+                    Span::from(err.clone()).fg(theme.bad),
+                    "-".into(),
+                    String::new(),
+                ),
+            };
+            Row::new(vec![
+                Cell::from(s.server.clone()),
+                Cell::from(status),
+                Cell::from(time),
+                Cell::from(addresses),
+            ])
+        })
+        .collect();
+
+    let header = ["Server", "Result", "ms", "Addresses"]
+        .into_iter()
+        .map(|h| Cell::from(h).style(Style::default().fg(theme.header)));
+    let widths = [
+        Constraint::Length(28),
+        Constraint::Length(24),
+        Constraint::Length(8),
+        Constraint::Min(16),
+    ];
+    let table = Table::new(rows, widths).header(Row::new(header).style(Style::new().bold()));
+    frame.render_widget(table, area);
+}
+
+/// Servers ranked by median latency, the interface's own ones highlighted.
+fn render_dns_benchmark(frame: &mut Frame, area: Rect, test: &DnsTest, theme: &Theme) {
+    let ms = |v: Option<f64>| v.map_or_else(|| "-".to_string(), |v| format!("{v:.1}"));
+    let rows: Vec<Row> = test
+        .ranked()
+        .into_iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let who = match (s.operator, s.configured) {
+                (Some(op), true) => format!("{op} (in use)"),
+                (Some(op), false) => op.to_string(),
+                (None, _) => "in use".into(),
+            };
+            let failures = s.failures();
+            let failed_color = if failures == 0 {
+                Color::Reset
+            } else {
+                theme.bad
+            };
+            let last_error = s
+                .answers
+                .iter()
+                .rev()
+                .find_map(|a| match a {
+                    Ok(reply) if !reply.resolved() => Some(reply.status()),
+                    Err(err) => Some(err.clone()),
+                    Ok(_) => None,
+                })
+                .unwrap_or_default();
+            let row = Row::new(vec![
+                Cell::from((i + 1).to_string()),
+                Cell::from(s.server.clone()),
+                Cell::from(who),
+                Cell::from(ms(s.median_ms())),
+                Cell::from(ms(s.min_ms())),
+                Cell::from(ms(s.max_ms())),
+                Cell::from(format!("{}/{}", s.answers.len(), test.queries)),
+                Cell::from(failures.to_string()).style(Style::default().fg(failed_color)),
+                Cell::from(last_error).style(Style::default().fg(theme.bad)),
+            ]);
+            if s.configured {
+                row.style(Style::new().bold())
+            } else {
+                row
+            }
+        })
+        .collect();
+
+    let header = [
+        "#", "Server", "Who", "Median", "Min", "Max", "Done", "Failed", "Error",
+    ]
+    .into_iter()
+    .map(|h| Cell::from(h).style(Style::default().fg(theme.header)));
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Length(24),
+        Constraint::Length(20),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Min(10),
+    ];
+    let table = Table::new(rows, widths).header(Row::new(header).style(Style::new().bold()));
+    frame.render_widget(table, area);
+}
+
+fn render_scan_popup(
+    frame: &mut Frame,
+    scan: &Scan,
+    oui: Option<&HashMap<String, String>>,
+    state: &mut TableState,
+    theme: &Theme,
+) {
+    let area = centered_rect(80, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let method = scan.method.map(|m| format!(" ({m})")).unwrap_or_default();
+    let status = if scan.finished { " (done)" } else { "" };
+    let block = Block::default()
+        .title(format!(
+            " Scan {} via {}{method}{status} ",
+            scan.subnet, scan.iface
+        ))
+        .title_bottom(
+            Line::from(if scan.finished {
+                " j/k scroll, Esc to close "
+            } else {
+                " j/k scroll, Esc to stop "
+            })
+            .right_aligned(),
+        )
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(3)])
+        .split(inner);
+
+    // arp-scan reports nothing until it's done, so only the ping sweep shows progress.
+    let mut summary = vec![
+        Span::from("Hosts: ").bold(),
+        Span::from(scan.hosts.len().to_string()),
+    ];
+    if scan.method == Some("ping sweep") {
+        summary.push(Span::from("  Probed: ").bold());
+        summary.push(Span::from(format!("{}/{}", scan.probed, scan.total)));
+    }
+    frame.render_widget(Paragraph::new(Line::from(summary)), chunks[0]);
+
+    let empty = Default::default();
+    let oui = oui.unwrap_or(&empty);
+    let rows: Vec<Row> = scan
+        .hosts
+        .iter()
+        .map(|h| {
+            Row::new(vec![
+                Cell::from(h.ip.to_string()),
+                Cell::from(h.mac.clone()),
+                Cell::from(oui_vendor(oui, &h.mac).unwrap_or("-").to_string()),
+                Cell::from(h.hostname.clone().unwrap_or_else(|| "-".into())),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(16),
+        Constraint::Length(18),
+        Constraint::Min(16),
+        Constraint::Min(16),
+    ];
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["IP", "MAC", "Vendor", "Hostname"])
+                .style(Style::default().fg(theme.header).bold()),
+        )
+        .row_highlight_style(theme.highlight);
+    frame.render_stateful_widget(table, chunks[1], state);
+}
+
+fn render_mdns_popup(frame: &mut Frame, mdns: &MdnsBrowser, state: &mut TableState, theme: &Theme) {
+    let area = centered_rect(80, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let status = if mdns.finished { " (done)" } else { "" };
+    let block = Block::default()
+        .title(format!(" mDNS services on {}{status} ", mdns.iface))
+        .title_bottom(Line::from(" j/k scroll, Esc to close ").right_aligned())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(inner);
+
+    let dash = || "-".to_string();
+    let rows: Vec<Row> = mdns
+        .services
+        .iter()
+        .map(|s| {
+            Row::new(vec![
+                Cell::from(s.kind.clone()),
+                Cell::from(s.name.clone()),
+                Cell::from(s.protocol.clone()),
+                Cell::from(s.host.clone().unwrap_or_else(dash)),
+                Cell::from(s.address.clone().unwrap_or_else(dash)),
+                Cell::from(s.port.map_or_else(dash, |p| p.to_string())),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(20),
+        Constraint::Min(20),
+        Constraint::Length(5),
+        Constraint::Min(16),
+        Constraint::Length(26),
+        Constraint::Length(6),
+    ];
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["Type", "Name", "IP", "Host", "Address", "Port"])
+                .style(Style::default().fg(theme.header).bold()),
+        )
+        .row_highlight_style(theme.highlight);
+    frame.render_stateful_widget(table, chunks[0], state);
+
+    // The selected service's TXT records, or why nothing shows up.
+    let footer = if let Some(err) = &mdns.error {
+        Span::from(err.clone()).fg(theme.bad)
+    } else if let Some(s) = state.selected().and_then(|i| mdns.services.get(i)) {
+        Span::from(format!("TXT: {}", s.txt.join("  "))).fg(theme.muted)
+    } else if mdns.services.is_empty() {
+        Span::from("Waiting for announcements...").fg(theme.muted)
+    } else {
+        Span::from("")
+    };
+    frame.render_widget(Paragraph::new(footer), chunks[1]);
+}
+
+fn render_confirm_popup(frame: &mut Frame, confirm: &Confirm, theme: &Theme) {
+    // Tall enough for previews of what will run (a drop-in file, a list of commands).
+    let lines = confirm.prompt.lines().count() as u16 + 3;
+    let percent = (lines * 100 / frame.area().height.max(1)).clamp(30, 90);
+    let area = centered_rect(60, percent, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(" Confirm ")
+        .title_bottom(Line::from(" y to confirm, any other key cancels ").right_aligned())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.warn));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let p = Paragraph::new(confirm.prompt.as_str())
         .alignment(Alignment::Left)
-        .style(Style::default().fg(Color::White))
-        .wrap(ratatui::widgets::Wrap { trim: true });
+        .style(Style::default().fg(theme.text))
+        .wrap(ratatui::widgets::Wrap { trim: false });
+    frame.render_widget(p, inner);
+}
+
+fn render_result_popup(frame: &mut Frame, popup: &Popup, theme: &Theme) {
+    let area = centered_rect(80, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(popup.title.clone())
+        .title_bottom(Line::from(" any key to close ").right_aligned())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
 
+    let p = Paragraph::new(popup.body.as_str())
+        .alignment(Alignment::Left)
+        .style(Style::default().fg(theme.text))
+        .wrap(ratatui::widgets::Wrap { trim: false });
     frame.render_widget(p, inner);
 }
 
-fn render_toast_popup(frame: &mut Frame, kind: crate::app::ToastKind, msg: &str) {
-    let (title, color) = match kind {
-        crate::app::ToastKind::Success => (" Success ", Color::Green),
-        crate::app::ToastKind::Error => (" Error ", Color::Red),
-        crate::app::ToastKind::Info => (" Info ", Color::Cyan),
+/// Changed fields have the old value in red and the new one in green.
+fn render_renew_popup(frame: &mut Frame, diff: &RenewDiff, theme: &Theme) {
+    let area = centered_rect(80, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let summary = if diff.changed() {
+        " changes highlighted "
+    } else {
+        " no visible change (the lease may still have been renewed) "
     };
+    let block = Block::default()
+        .title(format!(" DHCP renew ({}) ", diff.iface))
+        .title_bottom(Line::from(summary).left_aligned())
+        .title_bottom(Line::from(" any key to close ").right_aligned())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
 
-    let area = centered_rect(80, 28, frame.area());
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let output_lines = if diff.output.is_empty() {
+        0
+    } else {
+        diff.output.lines().count() as u16 + 1
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(output_lines)])
+        .split(inner);
+
+    let rows: Vec<Row> = diff
+        .rows
+        .iter()
+        .map(|row| {
+            let (before, after) = if row.changed() {
+                (
+                    Style::default().fg(theme.bad),
+                    Style::default().fg(theme.good).bold(),
+                )
+            } else {
+                let plain = Style::default().fg(theme.text);
+                (plain, plain)
+            };
+            Row::new(vec![
+                // The marker carries the change without relying on the colors.
+                Cell::from(format!(
+                    "{}{}",
+                    if row.changed() { "* " } else { "  " },
+                    row.field
+                ))
+                .style(Style::default().fg(theme.muted)),
+                Cell::from(row.before.clone()).style(before),
+                Cell::from(row.after.clone()).style(after),
+            ])
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(17),
+            Constraint::Percentage(50),
+            Constraint::Percentage(50),
+        ],
+    )
+    .header(Row::new(vec!["", "Before", "After"]).style(Style::default().bold()))
+    .column_spacing(2);
+    frame.render_widget(table, chunks[0]);
+
+    if !diff.output.is_empty() {
+        let p = Paragraph::new(diff.output.as_str())
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_style(Style::default().fg(theme.muted)),
+            )
+            .style(Style::default().fg(theme.muted));
+        frame.render_widget(p, chunks[1]);
+    }
+}
+
+/// Two modules per cell with half blocks. Always black on white whatever the theme, since
+/// scanners expect dark modules on a light background, with a 2-module quiet zone.
+fn render_qr_popup(frame: &mut Frame, qr: &QrPopup, theme: &Theme) {
+    const QUIET: usize = 2;
+    let modules = qr.code.size() + 2 * QUIET;
+    let dark = |x: usize, y: usize| x >= QUIET && y >= QUIET && qr.code.dark(x - QUIET, y - QUIET);
+    let color = |d: bool| if d { Color::Black } else { Color::White };
+    let mut lines: Vec<Line> = (0..modules.div_ceil(2))
+        .map(|row| {
+            let spans: Vec<Span> = (0..modules)
+                .map(|x| {
+                    let top = dark(x, row * 2);
+                    let bottom = row * 2 + 1 < modules && dark(x, row * 2 + 1);
+                    Span::from("▀").fg(color(top)).bg(color(bottom))
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    lines.push(Line::from(qr.text.clone()).fg(theme.text).centered());
+
+    let screen = frame.area();
+    let width = (modules as u16 + 2)
+        .max(qr.text.len() as u16 + 2)
+        .min(screen.width);
+    let height = (lines.len() as u16 + 2).min(screen.height);
+    let area = Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y: screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    };
     frame.render_widget(Clear, area);
 
     let block = Block::default()
-        .title(title)
+        .title(" QR code ")
+        .title_bottom(Line::from(" i address | u URL | any key closes ").right_aligned())
         .borders(Borders::ALL)
-        .border_type(BorderType::Thick)
-        .border_style(Style::default().fg(color));
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
+    let p = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(block);
+    frame.render_widget(p, area);
+}
+
+/// What the interface list columns mean, for the help overlay.
+const COLUMN_HELP: [(&str, &str); 8] = [
+    (
+        "Iface",
+        "Interface name; the icon shows it has carrier and an address",
+    ),
+    ("VID", "802.1Q VLAN ID, for VLAN subinterfaces"),
+    (
+        "State",
+        "Kernel operstate: up, down, dormant, lowerlayerdown (parent or cable down), unknown",
+    ),
+    (
+        "Carrier",
+        "1 = link partner detected on the cable, 0 = no link, ? = not known (e.g. admin down)",
+    ),
+    (
+        "Speed",
+        "Negotiated or forced link speed in Mb/s; ! = 100 Mb/s or half duplex on a gigabit NIC",
+    ),
+    ("IPv4", "First IPv4 address"),
+    (
+        "Lease",
+        "Time left on the DHCP lease (yellow past T1, red past T2), or static",
+    ),
+    (
+        "Internet",
+        "Last connectivity probe: ✓, portal (captive portal), no DNS or offline",
+    ),
+];
 
+fn render_help_popup(frame: &mut Frame, keys: &Keymap, scroll: &mut u16, theme: &Theme) {
+    let area = centered_rect(80, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(" Help ")
+        .title_bottom(Line::from(" ↑↓/PgUp/PgDn scroll | Esc or ? to close ").right_aligned())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let p = Paragraph::new(msg)
+    let mut lines = vec![Line::from(Span::from("Keys").fg(theme.header).bold())];
+    for action in Action::ALL {
+        let bound: Vec<String> = keys.keys(action).map(|k| k.to_string()).collect();
+        let bound = if bound.is_empty() {
+            "(unbound)".to_string()
+        } else {
+            bound.join(", ")
+        };
+        lines.push(Line::from(vec![
+            Span::from(format!("  {bound:<16} ")).bold(),
+            Span::from(action.description()),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::from(format!("  {:<16} ", "1-9, 0")).bold(),
+        Span::from("Jump to one of the first ten detail tabs (unless rebound)"),
+    ]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(
+        Span::from("Popups and forms").fg(theme.header).bold(),
+    ));
+    lines.push(Line::from(
+        "  Esc closes or stops them; in forms Tab/↑↓ move between fields and Enter applies.",
+    ));
+    lines.push(Line::from(""));
+    lines.push(Line::from(
+        Span::from("Interface list columns").fg(theme.header).bold(),
+    ));
+    for (column, meaning) in COLUMN_HELP {
+        lines.push(Line::from(vec![
+            Span::from(format!("  {column:<16} ")).bold(),
+            Span::from(meaning),
+        ]));
+    }
+
+    // Keep the last page in view instead of scrolling into blank space.
+    let max = (lines.len() as u16).saturating_sub(inner.height);
+    *scroll = (*scroll).min(max);
+    let p = Paragraph::new(Text::from(lines))
+        .style(Style::default().fg(theme.text))
+        .scroll((*scroll, 0));
+    frame.render_widget(p, inner);
+}
+
+/// Newest first, so a flap that just happened is on top.
+fn render_events_popup(frame: &mut Frame, events: &EventLog, scroll: &mut u16, theme: &Theme) {
+    let area = centered_rect(80, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!(" Events ({}) ", events.len()))
+        .title_bottom(Line::from(" ↑↓/PgUp/PgDn scroll | Esc or E to close ").right_aligned())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.info));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if events.is_empty() {
+        let p = Paragraph::new("No changes seen yet.").style(Style::default().fg(theme.muted));
+        frame.render_widget(p, inner);
+        return;
+    }
+    let lines: Vec<Line> = events
+        .iter()
+        .rev()
+        .map(|e| {
+            let color = match e.kind {
+                EventKind::Up => theme.good,
+                EventKind::Down => theme.bad,
+                EventKind::Info => theme.text,
+            };
+            Line::from(vec![
+                Span::from(format!("{}  ", e.clock())).fg(theme.muted),
+                Span::from(format!("{:<12} ", e.iface)).bold(),
+                Span::from(e.msg.clone()).fg(color),
+            ])
+        })
+        .collect();
+
+    let max = (lines.len() as u16).saturating_sub(inner.height);
+    *scroll = (*scroll).min(max);
+    let p = Paragraph::new(Text::from(lines))
+        .style(Style::default().fg(theme.text))
+        .scroll((*scroll, 0));
+    frame.render_widget(p, inner);
+}
+
+fn render_form_popup(frame: &mut Frame, form: &Form, theme: &Theme) {
+    let area = centered_rect(70, 50, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(form.title.clone())
+        .title_bottom(Line::from(" Tab/↑↓ field | Enter apply | Esc cancel ").centered())
+        .borders(Borders::ALL)
+        .border_type(theme.border_type)
+        .border_style(Style::default().fg(theme.accent));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let mut lines = Vec::new();
+    for (i, f) in form.fields.iter().enumerate() {
+        let focused = i == form.focus;
+        let label = Span::from(format!("{}: ", f.label)).bold();
+        let value = if focused {
+            Span::from(format!("{}█", f.value)).fg(theme.header)
+        } else {
+            Span::from(f.value.clone())
+        };
+        lines.push(Line::from(vec![label, value]));
+        lines.push(Line::from(
+            Span::from(format!("  {}", f.hint)).fg(theme.muted),
+        ));
+        lines.push(Line::from(""));
+    }
+
+    let p = Paragraph::new(Text::from(lines))
         .alignment(Alignment::Left)
-        .style(Style::default().fg(Color::White))
         .wrap(ratatui::widgets::Wrap { trim: false });
     frame.render_widget(p, inner);
 }
@@ -0,0 +1,71 @@
+use crate::app::run_privileged_capture;
+use anyhow::Result;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A WireGuard interface and its peers, as reported by `wg show <iface> dump`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WgInterface {
+    pub public_key: String,
+    pub listen_port: Option<u16>,
+    pub peers: Vec<WgPeer>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WgPeer {
+    pub public_key: String,
+    pub endpoint: Option<String>,
+    pub allowed_ips: Vec<String>,
+    /// None until the first handshake.
+    pub latest_handshake: Option<SystemTime>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub keepalive: Option<u16>,
+}
+
+impl WgPeer {
+    /// Time since the last handshake; over ~3 minutes means the session has expired.
+    pub fn handshake_age(&self, now: SystemTime) -> Option<Duration> {
+        now.duration_since(self.latest_handshake?).ok()
+    }
+}
+
+/// Reading keys and peers needs CAP_NET_ADMIN, so this goes through sudo when needed.
+pub async fn show(iface: &str) -> Result<WgInterface> {
+    let out = run_privileged_capture("wg", &["show", iface, "dump"]).await?;
+    Ok(parse_dump(&out.stdout))
+}
+
+/// Tab-separated: first the interface (`private-key public-key listen-port fwmark`), then one
+/// line per peer (`public-key preshared-key endpoint allowed-ips latest-handshake rx tx
+/// keepalive`). Unset values are `(none)` or `off`, and a handshake of 0 means never.
+fn parse_dump(dump: &str) -> WgInterface {
+    let set = |v: &str| (v != "(none)" && v != "off" && !v.is_empty()).then(|| v.to_string());
+    let mut lines = dump.lines().map(|l| l.split('\t').collect::<Vec<_>>());
+    let mut wg = match lines.next() {
+        Some(cols) if cols.len() >= 3 => WgInterface {
+            public_key: cols[1].to_string(),
+            listen_port: cols[2].parse().ok().filter(|p| *p != 0),
+            peers: Vec::new(),
+        },
+        _ => return WgInterface::default(),
+    };
+    wg.peers = lines
+        .filter(|cols| cols.len() >= 8)
+        .map(|cols| WgPeer {
+            public_key: cols[0].to_string(),
+            endpoint: set(cols[2]),
+            allowed_ips: set(cols[3])
+                .map(|ips| ips.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            latest_handshake: cols[4]
+                .parse::<u64>()
+                .ok()
+                .filter(|t| *t != 0)
+                .map(|t| UNIX_EPOCH + Duration::from_secs(t)),
+            rx_bytes: cols[5].parse().unwrap_or(0),
+            tx_bytes: cols[6].parse().unwrap_or(0),
+            keepalive: cols[7].parse().ok(),
+        })
+        .collect();
+    wg
+}